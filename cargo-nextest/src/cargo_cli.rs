@@ -292,16 +292,7 @@ impl<'a> CargoCli<'a> {
             self.add_arg("--all-targets");
         }
 
-        // ---
-        // Feature selection
-        // ---
-        self.add_args(options.features.iter().flat_map(|s| ["--features", s]));
-        if options.all_features {
-            self.add_arg("--all-features");
-        }
-        if options.no_default_features {
-            self.add_arg("--no-default-features");
-        }
+        self.add_feature_options(options);
 
         // ---
         // Compilation options
@@ -364,6 +355,24 @@ impl<'a> CargoCli<'a> {
         self
     }
 
+    /// Add Cargo feature selection options.
+    pub(crate) fn add_feature_options(&mut self, options: &'a CargoOptions) -> &mut Self {
+        self.add_args(
+            options
+                .features
+                .iter()
+                .flat_map(|s| ["--features", s.as_str()]),
+        );
+        if options.all_features {
+            self.add_arg("--all-features");
+        }
+        if options.no_default_features {
+            self.add_arg("--no-default-features");
+        }
+
+        self
+    }
+
     /// Add Cargo options that are common to all commands.
     pub(crate) fn add_generic_cargo_options(&mut self, options: &CargoOptions) -> &mut Self {
         // ---
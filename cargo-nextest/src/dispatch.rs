@@ -12,30 +12,33 @@ use clap::{builder::BoolishValueParser, ArgAction, Args, Parser, Subcommand, Val
 use guppy::graph::PackageGraph;
 use itertools::Itertools;
 use log::warn;
-use nextest_filtering::{EvalContext, Filterset, FiltersetKind, ParseContext};
+use nextest_filtering::{Filterset, FiltersetKind, ParseContext};
 use nextest_metadata::BuildPlatform;
 use nextest_runner::{
     cargo_config::{CargoConfigs, EnvironmentMap, TargetTriple},
     config::{
-        get_num_cpus, ConfigExperimental, NextestConfig, NextestProfile, NextestVersionConfig,
-        NextestVersionEval, PreBuildPlatform, RetryPolicy, TestGroup, TestThreads, ToolConfigFile,
-        VersionOnlyConfig,
+        get_num_cpus, ConfigExperimental, ExtraDylibPathsContext, NextestConfig, NextestProfile,
+        NextestVersionConfig, NextestVersionEval, PreBuildPlatform, RetryPolicy, TestGroup,
+        TestThreads, ToolConfigFile, VersionOnlyConfig,
     },
     double_spawn::DoubleSpawnInfo,
-    errors::WriteTestListError,
+    errors::{ListCacheError, WriteTestListError},
     list::{
-        BinaryList, OutputFormat, RustTestArtifact, SerializableFormat, TestExecuteContext,
-        TestList,
+        BinaryList, DiagnosticSeverity, ListCache, OutputFormat, RustTestArtifact,
+        SerializableFormat, TestExecuteContext, TestList,
     },
-    partition::PartitionerBuilder,
-    platform::{BuildPlatforms, HostPlatform, PlatformLibdir, TargetPlatform},
+    partition::{PartitionTimings, PartitionerBuilder},
+    platform::{detect_libdir, BuildPlatforms, HostPlatform, PlatformLibdir, TargetPlatform},
     redact::Redactor,
     reporter::{
         heuristic_extract_description, highlight_end, structured, DescriptionKind,
         FinalStatusLevel, StatusLevel, TestOutputDisplay, TestOutputDisplayStreams,
         TestReporterBuilder,
     },
-    reuse_build::{archive_to_file, ArchiveReporter, PathMapper, ReuseBuildInfo},
+    reuse_build::{
+        archive_to_file, ArchiveReporter, MetadataKind, PathMapper, ReuseBuildInfo,
+        ReusedBinaryList,
+    },
     runner::{
         configure_handle_inheritance, ExecutionResult, FinalRunStats, RunStatsFailureKind,
         TestRunnerBuilder,
@@ -52,12 +55,14 @@ use owo_colors::{OwoColorize, Stream, Style};
 use quick_junit::XmlString;
 use semver::Version;
 use std::{
-    collections::BTreeSet,
+    collections::{BTreeMap, BTreeSet},
     env::VarError,
+    error::Error as _,
     fmt,
     io::{Cursor, Write},
     str::FromStr,
     sync::Arc,
+    time::Duration,
 };
 use swrite::{swrite, SWrite};
 
@@ -234,18 +239,31 @@ struct ConfigOpts {
     #[arg(long, global = true)]
     pub override_version_check: bool,
 
-    /// The nextest profile to use.
+    /// Reject platforms with no known dynamic library path variable.
     ///
-    /// Nextest's configuration supports multiple profiles, which can be used to set up different
-    /// configurations for different purposes. (For example, a configuration for local runs and one
-    /// for CI.) This option selects the profile to use.
+    /// Nextest sets an environment variable (e.g. LD_LIBRARY_PATH) so that test binaries can
+    /// find dynamic libraries, falling back to LD_LIBRARY_PATH for operating systems it doesn't
+    /// recognize. With this option, listing fails instead if the host or target OS isn't one
+    /// nextest has an explicit mapping for.
     #[arg(
         long,
-        short = 'P',
-        env = "NEXTEST_PROFILE",
         global = true,
-        help_heading = "Config options"
+        env = "NEXTEST_STRICT_PLATFORMS",
+        value_parser = BoolishValueParser::new()
     )]
+    pub strict_platforms: bool,
+
+    /// The nextest profile to use.
+    ///
+    /// Nextest's configuration supports multiple profiles, which can be used to set up different
+    /// configurations for different purposes. (For example, a configuration for local runs and one
+    /// for CI.) This option selects the profile to use.
+    ///
+    /// If not specified, the `NEXTEST_PROFILE` environment variable is used, and if that isn't set
+    /// either, the default profile.
+    // The environment variable is read in `resolve_profile_name` rather than by clap, so that the
+    // source of the profile can be logged.
+    #[arg(long, short = 'P', global = true, help_heading = "Config options")]
     profile: Option<String>,
 }
 
@@ -525,10 +543,28 @@ struct TestBuildFilter {
     #[arg(long, value_enum, value_name = "WHICH")]
     run_ignored: Option<RunIgnoredOpt>,
 
-    /// Test partition, e.g. hash:1/2 or count:2/3
+    /// Test partition, e.g. hash:1/2, count:2/3 or timing:1/4
     #[arg(long)]
     partition: Option<PartitionerBuilder>,
 
+    /// Timing report to balance shards with, for --partition timing:M/N
+    ///
+    /// This is a timing report written by a previous run, as configured by `profile.<name>.timing`.
+    #[arg(long, value_name = "PATH", requires = "partition")]
+    partition_timing_file: Option<Utf8PathBuf>,
+
+    /// Seconds that tests missing from the timing report are expected to take, for --partition
+    /// timing:M/N
+    ///
+    /// Defaults to the average duration of the tests in the timing report.
+    #[arg(
+        long,
+        value_name = "SECS",
+        requires = "partition_timing_file",
+        value_parser = parse_duration_secs
+    )]
+    partition_default_secs: Option<Duration>,
+
     /// Filter test binaries by build platform (DEPRECATED)
     ///
     /// Instead, use -E with 'platform(host)' or 'platform(target)'.
@@ -551,6 +587,18 @@ struct TestBuildFilter {
     )]
     filterset: Vec<String>,
 
+    /// Read test filtersets from a file, one per line
+    ///
+    /// Blank lines and lines starting with `#` are ignored. Each remaining line is treated as if
+    /// it had been passed in with `-E`, and can be combined with `-E` on the command line.
+    #[arg(
+        long,
+        alias = "filter-expr-file",
+        value_name = "PATH",
+        action(ArgAction::Append)
+    )]
+    filterset_file: Vec<Utf8PathBuf>,
+
     /// Ignore the default filter configured in the profile.
     ///
     /// By default, all filtersets are intersected with the default filter configured in the
@@ -560,6 +608,37 @@ struct TestBuildFilter {
     #[arg(long)]
     ignore_default_filter: bool,
 
+    /// Also add the parent directory of each linked path to the dynamic library path
+    ///
+    /// This is a workaround for a few native libraries that expect both the directory passed to
+    /// `cargo::rustc-link-search` and its parent to be on the search path.
+    #[arg(long, hide_short_help = true)]
+    include_linked_path_parents: bool,
+
+    /// Also add subdirectories of linked paths that contain shared libraries to the dynamic
+    /// library path
+    ///
+    /// Each linked path is scanned one level deep. This is a workaround for vendored SDKs that
+    /// place libraries one directory below the path passed to `cargo::rustc-link-search`. It can
+    /// slow down listing, and can change which libraries are resolved at runtime.
+    #[arg(long, hide_short_help = true)]
+    scan_linked_path_subdirs: bool,
+
+    /// On macOS, set DYLD_LIBRARY_PATH rather than DYLD_FALLBACK_LIBRARY_PATH
+    ///
+    /// Libraries in DYLD_LIBRARY_PATH take precedence over the ones test binaries were linked
+    /// against, including system libraries, so this can break tests in unexpected ways. Use this
+    /// only if tests need to override libraries. This has no effect on other platforms.
+    #[arg(long, hide_short_help = true)]
+    use_dyld_library_path: bool,
+
+    /// Don't use or update the test list cache
+    ///
+    /// By default, the test list of each binary is cached in the target directory and reused if
+    /// the binary's size and modification time are unchanged.
+    #[arg(long, hide_short_help = true)]
+    no_list_cache: bool,
+
     /// Test name filters
     #[arg(help_heading = None, name = "FILTERS")]
     pre_double_dash_filters: Vec<String>,
@@ -579,7 +658,7 @@ impl TestBuildFilter {
         binary_list: Arc<BinaryList>,
         test_filter_builder: TestFilterBuilder,
         env: EnvironmentMap,
-        ecx: &EvalContext<'_>,
+        profile: &NextestProfile<'_, PreBuildPlatform>,
         reuse_build: &ReuseBuildInfo,
     ) -> Result<TestList<'g>> {
         let path_mapper = make_path_mapper(
@@ -588,7 +667,34 @@ impl TestBuildFilter {
             &binary_list.rust_build_meta.target_directory,
         )?;
 
-        let rust_build_meta = binary_list.rust_build_meta.map_paths(&path_mapper);
+        let mut rust_build_meta = binary_list.rust_build_meta.map_paths(&path_mapper);
+        rust_build_meta.include_linked_path_parents = self.include_linked_path_parents;
+        rust_build_meta.scan_linked_path_subdirs = self.scan_linked_path_subdirs;
+        rust_build_meta.use_dyld_library_path = self.use_dyld_library_path;
+        let extra_dylib_paths = profile
+            .extra_dylib_paths(ExtraDylibPathsContext {
+                target_dir: &rust_build_meta.target_directory,
+                workspace_root: &workspace_root,
+            })
+            .map_err(|err| ExpectedError::ExtraDylibPathsError { err })?;
+        for path in extra_dylib_paths {
+            // Configured paths aren't owned by any package.
+            rust_build_meta
+                .absolute_linked_paths
+                .entry(path)
+                .or_default();
+        }
+        let list_cache = if self.no_list_cache {
+            None
+        } else {
+            match ListCache::load(&rust_build_meta.target_directory) {
+                Ok(list_cache) => Some(list_cache),
+                Err(err) => {
+                    warn_on_list_cache_err(&err);
+                    None
+                }
+            }
+        };
         let test_artifacts = RustTestArtifact::from_binary_list(
             graph,
             binary_list,
@@ -596,14 +702,14 @@ impl TestBuildFilter {
             &path_mapper,
             self.platform_filter.into(),
         )?;
-        TestList::new(
+        let test_list = TestList::new(
             ctx,
             test_artifacts,
             rust_build_meta,
             &test_filter_builder,
             workspace_root,
             env,
-            ecx,
+            &profile.filterset_ecx(),
             if self.ignore_default_filter {
                 FilterBound::All
             } else {
@@ -611,8 +717,16 @@ impl TestBuildFilter {
             },
             // TODO: do we need to allow customizing this?
             get_num_cpus(),
+            list_cache.as_ref(),
         )
-        .map_err(|err| ExpectedError::CreateTestListError { err })
+        .map_err(|err| ExpectedError::CreateTestListError { err })?;
+
+        if let Some(list_cache) = &list_cache {
+            if let Err(err) = list_cache.save() {
+                warn_on_list_cache_err(&err);
+            }
+        }
+        Ok(test_list)
     }
 
     fn make_test_filter_builder(&self, filter_exprs: Vec<Filterset>) -> Result<TestFilterBuilder> {
@@ -623,12 +737,32 @@ impl TestBuildFilter {
 
         Ok(TestFilterBuilder::new(
             run_ignored.unwrap_or_default(),
-            self.partition.clone(),
+            self.make_partitioner_builder()?,
             &patterns,
             filter_exprs,
         )?)
     }
 
+    fn make_partitioner_builder(&self) -> Result<Option<PartitionerBuilder>> {
+        let Some(partition) = &self.partition else {
+            return Ok(None);
+        };
+        match (partition.timing_plan(), &self.partition_timing_file) {
+            (Some(_), Some(path)) => {
+                let timings = PartitionTimings::read_report(path)
+                    .map_err(|err| ExpectedError::PartitionTimingsReadError { err })?;
+                Ok(Some(
+                    partition
+                        .clone()
+                        .with_timings(&timings, self.partition_default_secs),
+                ))
+            }
+            (Some(_), None) => Err(ExpectedError::PartitionTimingFileMissing),
+            (None, Some(_)) => Err(ExpectedError::PartitionTimingFileUnused),
+            (None, None) => Ok(Some(partition.clone())),
+        }
+    }
+
     fn merge_test_binary_args(
         &self,
         run_ignored: &mut Option<RunIgnored>,
@@ -753,10 +887,78 @@ impl CargoOptions {
             ));
         }
 
-        let test_binaries =
+        let mut test_binaries =
             BinaryList::from_messages(Cursor::new(output.stdout), graph, build_platforms)?;
+        test_binaries.rust_build_meta.record_host_os_version();
+        test_binaries.rust_build_meta.record_rust_version();
+        test_binaries
+            .rust_build_meta
+            .record_list_command(std::env::args_os().map(|arg| arg.to_string_lossy().into_owned()));
+        test_binaries.rust_build_meta.record_built_at();
+        test_binaries.rust_build_meta.record_incremental();
+        // Recorded right after the build, so that binaries metadata and archives can warn about
+        // search directories that went missing before tests are run.
+        test_binaries.rust_build_meta.record_resolved_search_dirs();
         Ok(test_binaries)
     }
+
+    /// Returns true if any feature selection options were passed in.
+    fn has_feature_options(&self) -> bool {
+        !self.features.is_empty() || self.all_features || self.no_default_features
+    }
+
+    /// Resolves the features Cargo would enable for each package with the feature selection
+    /// options passed in, keyed by package ID.
+    fn compute_requested_features(
+        &self,
+        manifest_path: Option<&Utf8Path>,
+        output: OutputContext,
+        build_platforms: &BuildPlatforms,
+    ) -> Result<BTreeMap<String, BTreeSet<String>>> {
+        let cargo_target_arg = build_platforms.to_cargo_target_arg()?;
+        let cargo_target_arg_str = cargo_target_arg.to_string();
+
+        let mut cargo_cli = CargoCli::new("metadata", manifest_path, output);
+        cargo_cli
+            .add_args(["--format-version=1"])
+            .add_args(["--filter-platform", &cargo_target_arg_str])
+            .add_feature_options(self)
+            .add_generic_cargo_options(self);
+
+        let output = cargo_cli
+            .to_expression()
+            .stdout_capture()
+            .unchecked()
+            .run()
+            .map_err(|err| ExpectedError::cargo_metadata_exec_failed(cargo_cli.all_args(), err))?;
+        if !output.status.success() {
+            return Err(ExpectedError::cargo_metadata_failed(cargo_cli.all_args()));
+        }
+
+        let metadata: serde_json::Value =
+            serde_json::from_slice(&output.stdout).map_err(|error| {
+                let io_error = std::io::Error::new(std::io::ErrorKind::InvalidData, error);
+                ExpectedError::cargo_metadata_exec_failed(cargo_cli.all_args(), io_error)
+            })?;
+
+        // Cargo reports the features enabled for each package in the resolve graph.
+        let nodes = metadata["resolve"]["nodes"].as_array();
+        let requested = nodes
+            .into_iter()
+            .flatten()
+            .filter_map(|node| {
+                let package_id = node["id"].as_str()?;
+                let features = node["features"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|feature| feature.as_str().map(str::to_owned))
+                    .collect();
+                Some((package_id.to_owned(), features))
+            })
+            .collect();
+        Ok(requested)
+    }
 }
 
 /// Test runner options.
@@ -767,6 +969,14 @@ pub struct TestRunnerOpts {
     #[arg(long, name = "no-run")]
     no_run: bool,
 
+    /// Print the command that would be run for each test, without running any tests
+    ///
+    /// Each command includes the working directory and environment variables nextest would set,
+    /// preceded by comments showing the retry policy, slow timeout and test group. Setup scripts
+    /// aren't run, so variables they set aren't shown.
+    #[arg(long, conflicts_with = "no-run")]
+    print_command: bool,
+
     /// Number of tests to run simultaneously [possible values: integer or "num-cpus"]
     /// [default: from profile]
     #[arg(
@@ -868,6 +1078,8 @@ enum MessageFormat {
     /// Output test information in the same format as libtest, with a `nextest` subobject that
     /// includes additional metadata.
     LibtestJsonPlus,
+    /// Output one JSON object per line for each test event, as it occurs.
+    JsonLines,
 }
 
 #[derive(Debug, Default, Args)]
@@ -924,6 +1136,10 @@ struct TestReporterOpts {
     #[arg(long, env = "NEXTEST_HIDE_PROGRESS_BAR", value_parser = BoolishValueParser::new())]
     hide_progress_bar: bool,
 
+    /// Print the N slowest tests at the end of the run
+    #[arg(long, conflicts_with = "no-run", value_name = "N")]
+    slowest: Option<usize>,
+
     /// Format to use for test results (experimental).
     #[arg(
         long,
@@ -967,6 +1183,7 @@ impl TestReporterOpts {
             builder.set_final_status_level(final_status_level.into());
         }
         builder.set_hide_progress_bar(self.hide_progress_bar);
+        builder.set_slowest(self.slowest);
         builder
     }
 }
@@ -1146,22 +1363,29 @@ impl BaseApp {
         let build_platforms = match reuse_build.binaries_metadata() {
             Some(kind) => kind.binary_list.rust_build_meta.build_platforms.clone(),
             None => {
-                let host = HostPlatform::current(PlatformLibdir::from_rustc_stdout(
-                    RustcCli::print_host_libdir().read(),
-                ))?;
+                let host = HostPlatform::current(detect_libdir(None))?;
                 let target = if let Some(triple) =
                     discover_target_triple(&cargo_configs, cargo_opts.target.as_deref())
                 {
-                    let libdir = PlatformLibdir::from_rustc_stdout(
-                        RustcCli::print_target_libdir(&triple).read(),
-                    );
-                    Some(TargetPlatform::new(triple, libdir))
+                    let libdir = detect_libdir(Some(&triple));
+                    // The sysroot is printed in the same format as the libdir.
+                    let sysroot = PlatformLibdir::from_rustc_stdout(
+                        RustcCli::print_target_sysroot(&triple).read(),
+                    )
+                    .as_path()
+                    .map(|sysroot| sysroot.to_path_buf());
+                    Some(TargetPlatform::new(triple, libdir).with_sysroot(sysroot))
                 } else {
                     None
                 };
-                BuildPlatforms { host, target }
+                let mut build_platforms = BuildPlatforms { host, target };
+                build_platforms.fill_libdirs_from_sysroot();
+                build_platforms
             }
         };
+        if config_opts.strict_platforms {
+            build_platforms.check_dylib_path_envvar()?;
+        }
 
         // Read the Cargo metadata.
         let (cargo_metadata_json, package_graph) = match reuse_build.cargo_metadata() {
@@ -1471,7 +1695,30 @@ impl BaseApp {
 
     fn build_binary_list(&self) -> Result<Arc<BinaryList>> {
         let binary_list = match self.reuse_build.binaries_metadata() {
-            Some(m) => m.binary_list.clone(),
+            Some(m) => {
+                let rust_build_meta = &m.binary_list.rust_build_meta;
+                rust_build_meta.check_host_os_version();
+                rust_build_meta.check_rust_version();
+                // Archives ship their own standard library, so the toolchain on this machine
+                // doesn't matter.
+                if !self.reuse_build.libdir_mapper.is_remapping() {
+                    rust_build_meta.libdir_matches_current();
+                }
+                // Only check features if they were asked for: resolving them requires Cargo,
+                // which might not be available where a build is reused.
+                if self.cargo_opts.has_feature_options() {
+                    let requested = self.cargo_opts.compute_requested_features(
+                        self.manifest_path.as_deref(),
+                        self.output,
+                        &self.build_platforms,
+                    )?;
+                    rust_build_meta.check_enabled_features(&requested);
+                }
+                if let Some(list_command) = &rust_build_meta.list_command {
+                    log::debug!("reusing build produced by `{}`", list_command.join(" "));
+                }
+                m.binary_list.clone()
+            }
             None => Arc::new(self.cargo_opts.compute_binary_list(
                 self.graph(),
                 self.manifest_path.as_deref(),
@@ -1491,15 +1738,17 @@ impl BaseApp {
         &self,
         config: &'cfg NextestConfig,
     ) -> Result<NextestProfile<'cfg, PreBuildPlatform>> {
-        let profile_name = self.config_opts.profile.as_deref().unwrap_or_else(|| {
-            // The "official" way to detect a miri environment is with MIRI_SYSROOT.
-            // https://github.com/rust-lang/miri/pull/2398#issuecomment-1190747685
-            if std::env::var_os("MIRI_SYSROOT").is_some() {
-                NextestConfig::DEFAULT_MIRI_PROFILE
-            } else {
-                NextestConfig::DEFAULT_PROFILE
-            }
-        });
+        let env_profile = std::env::var("NEXTEST_PROFILE").ok();
+        // The "official" way to detect a miri environment is with MIRI_SYSROOT.
+        // https://github.com/rust-lang/miri/pull/2398#issuecomment-1190747685
+        let is_miri = std::env::var_os("MIRI_SYSROOT").is_some();
+        let (profile_name, source) = resolve_profile_name(
+            self.config_opts.profile.as_deref(),
+            env_profile.as_deref(),
+            is_miri,
+        );
+        log::debug!("using nextest profile `{profile_name}` ({source})");
+
         let profile = config
             .profile(profile_name)
             .map_err(ExpectedError::profile_not_found)?;
@@ -1512,6 +1761,50 @@ impl BaseApp {
     }
 }
 
+/// Where the nextest profile in use was selected.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum ProfileSource {
+    /// The `--profile` command-line option.
+    CommandLine,
+
+    /// The `NEXTEST_PROFILE` environment variable.
+    Environment,
+
+    /// The default profile, since neither of the above were specified.
+    Default,
+}
+
+impl fmt::Display for ProfileSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CommandLine => write!(f, "from --profile"),
+            Self::Environment => write!(f, "from NEXTEST_PROFILE"),
+            Self::Default => write!(f, "default"),
+        }
+    }
+}
+
+/// Resolves the nextest profile to use.
+///
+/// `--profile` on the command line always takes precedence over the `NEXTEST_PROFILE`
+/// environment variable, which in turn takes precedence over the default profile. An empty
+/// environment variable is treated as unset.
+fn resolve_profile_name<'a>(
+    cli_profile: Option<&'a str>,
+    env_profile: Option<&'a str>,
+    is_miri: bool,
+) -> (&'a str, ProfileSource) {
+    if let Some(profile) = cli_profile {
+        (profile, ProfileSource::CommandLine)
+    } else if let Some(profile) = env_profile.filter(|profile| !profile.is_empty()) {
+        (profile, ProfileSource::Environment)
+    } else if is_miri {
+        (NextestConfig::DEFAULT_MIRI_PROFILE, ProfileSource::Default)
+    } else {
+        (NextestConfig::DEFAULT_PROFILE, ProfileSource::Default)
+    }
+}
+
 fn current_version() -> Version {
     // This is a test-only, not part of the public API.
     match std::env::var("__NEXTEST_TEST_VERSION") {
@@ -1533,6 +1826,28 @@ struct App {
     build_filter: TestBuildFilter,
 }
 
+/// Parses a non-negative number of seconds, possibly fractional, into a [`Duration`].
+fn parse_duration_secs(input: &str) -> Result<Duration, String> {
+    let secs: f64 = input
+        .parse()
+        .map_err(|err| format!("failed to parse '{input}' as seconds: {err}"))?;
+    Duration::try_from_secs_f64(secs).map_err(|err| format!("invalid duration '{input}': {err}"))
+}
+
+/// Splits the contents of a filterset file into `(path:line, filterset)` pairs, skipping blank
+/// lines and comments.
+fn parse_filterset_file(path: &Utf8Path, contents: &str) -> Vec<(String, String)> {
+    contents
+        .lines()
+        .enumerate()
+        .filter_map(|(idx, line)| {
+            let line = line.trim();
+            (!line.is_empty() && !line.starts_with('#'))
+                .then(|| (format!("{path}:{}", idx + 1), line.to_owned()))
+        })
+        .collect()
+}
+
 // (_output is not used, but must be passed in to ensure that the output is properly initialized
 // before calling this method)
 fn check_experimental_filtering(_output: OutputContext) {
@@ -1554,11 +1869,29 @@ impl App {
             graph: self.base.graph(),
             kind: FiltersetKind::Test,
         };
-        let (exprs, all_errors): (Vec<_>, Vec<_>) = self
+        let mut inputs: Vec<(Option<String>, String)> = self
             .build_filter
             .filterset
             .iter()
-            .map(|input| Filterset::parse(input.clone(), &pcx))
+            .map(|input| (None, input.clone()))
+            .collect();
+        for path in &self.build_filter.filterset_file {
+            let contents = std::fs::read_to_string(path).map_err(|err| {
+                ExpectedError::FiltersetFileReadError {
+                    path: path.clone(),
+                    err,
+                }
+            })?;
+            inputs.extend(
+                parse_filterset_file(path, &contents)
+                    .into_iter()
+                    .map(|(source, input)| (Some(source), input)),
+            );
+        }
+
+        let (exprs, all_errors): (Vec<_>, Vec<_>) = inputs
+            .into_iter()
+            .map(|(source, input)| Filterset::parse(input, &pcx).map_err(|errors| (source, errors)))
             .partition_result();
 
         if !all_errors.is_empty() {
@@ -1573,19 +1906,55 @@ impl App {
         ctx: &TestExecuteContext<'_>,
         binary_list: Arc<BinaryList>,
         test_filter_builder: TestFilterBuilder,
-        ecx: &EvalContext<'_>,
+        profile: &NextestProfile<'_, PreBuildPlatform>,
     ) -> Result<TestList> {
         let env = EnvironmentMap::new(&self.base.cargo_configs);
-        self.build_filter.compute_test_list(
+        let partitioner_builder = test_filter_builder.partitioner_builder().cloned();
+        let test_list = self.build_filter.compute_test_list(
             ctx,
             self.base.graph(),
             self.base.workspace_root.clone(),
             binary_list,
             test_filter_builder,
             env,
-            ecx,
+            profile,
             &self.base.reuse_build,
-        )
+        )?;
+        if self.base.output.verbose {
+            log::info!(
+                "dynamic library path candidates:\n{}",
+                test_list
+                    .rust_build_meta()
+                    .dylib_paths_with_status()
+                    .iter()
+                    .map(|candidate| format!("  {candidate}"))
+                    .join("\n"),
+            );
+            if let Some(PartitionerBuilder::Timing {
+                shard,
+                total_shards,
+                plan,
+            }) = &partitioner_builder
+            {
+                log::info!(
+                    "predicted shard durations \
+                     (tests missing from the timing report are assumed to take {:.3}s):\n{}",
+                    plan.fallback_duration().as_secs_f64(),
+                    plan.predicted_durations()
+                        .iter()
+                        .zip(1..)
+                        .map(|(duration, n)| {
+                            let current = if n == *shard { " (current)" } else { "" };
+                            format!(
+                                "  shard {n}/{total_shards}: {:.3}s{current}",
+                                duration.as_secs_f64(),
+                            )
+                        })
+                        .join("\n"),
+                );
+            }
+        }
+        Ok(test_list)
     }
 
     fn exec_list(
@@ -1623,10 +1992,9 @@ impl App {
                     double_spawn,
                     target_runner,
                 };
-                let ecx = profile.filterset_ecx();
 
                 let test_list =
-                    self.build_test_list(&ctx, binary_list, test_filter_builder, &ecx)?;
+                    self.build_test_list(&ctx, binary_list, test_filter_builder, &profile)?;
 
                 let mut writer = output_writer.stdout_writer();
                 test_list.write(
@@ -1676,9 +2044,8 @@ impl App {
             double_spawn,
             target_runner,
         };
-        let ecx = profile.filterset_ecx();
 
-        let test_list = self.build_test_list(&ctx, binary_list, test_filter_builder, &ecx)?;
+        let test_list = self.build_test_list(&ctx, binary_list, test_filter_builder, &profile)?;
 
         let profile = profile.apply_build_platforms(&build_platforms);
 
@@ -1735,6 +2102,12 @@ impl App {
                 )?;
                 structured_reporter.set_libtest(libtest);
             }
+            MessageFormat::JsonLines => {
+                let json_lines = structured::JsonLinesReporter::new(
+                    reporter_opts.message_format_version.as_deref(),
+                )?;
+                structured_reporter.set_json_lines(json_lines);
+            }
         };
         use nextest_runner::test_output::CaptureStrategy;
 
@@ -1757,26 +2130,11 @@ impl App {
             double_spawn,
             target_runner,
         };
-        let ecx = profile.filterset_ecx();
 
-        let test_list = self.build_test_list(&ctx, binary_list, test_filter_builder, &ecx)?;
+        let test_list = self.build_test_list(&ctx, binary_list, test_filter_builder, &profile)?;
 
-        let output = output_writer.reporter_output();
         let profile = profile.apply_build_platforms(build_platforms);
 
-        let mut reporter = reporter_opts
-            .to_builder(no_capture)
-            .set_verbose(self.base.output.verbose)
-            .build(&test_list, &profile, output, structured_reporter);
-        if self
-            .base
-            .output
-            .color
-            .should_colorize(supports_color::Stream::Stderr)
-        {
-            reporter.colorize();
-        }
-
         let handler = SignalHandlerKind::Standard;
         let runner_builder = match runner_opts.to_builder(cap_strat) {
             Some(runner_builder) => runner_builder,
@@ -1795,7 +2153,30 @@ impl App {
             target_runner.clone(),
         )?;
 
-        configure_handle_inheritance(no_capture)?;
+        if runner_opts.print_command {
+            let mut writer = output_writer.stdout_writer();
+            for preview in runner.command_previews() {
+                write!(writer, "{preview}").map_err(WriteTestListError::Io)?;
+            }
+            writer.write_str_flush().map_err(WriteTestListError::Io)?;
+            return Ok(0);
+        }
+
+        let output = output_writer.reporter_output();
+        let mut reporter = reporter_opts
+            .to_builder(no_capture)
+            .set_verbose(self.base.output.verbose)
+            .build(&test_list, &profile, output, structured_reporter);
+        if self
+            .base
+            .output
+            .color
+            .should_colorize(supports_color::Stream::Stderr)
+        {
+            reporter.colorize();
+        }
+
+        configure_handle_inheritance(no_capture || profile.has_no_capture_tests(&test_list))?;
         let run_stats = runner.try_execute(|event| {
             // Write and flush the event.
             reporter.report_event(event)
@@ -2082,6 +2463,17 @@ enum DebugCommand {
         #[arg(value_enum)]
         output_format: ExtractOutputFormat,
     },
+
+    /// Check build metadata for problems.
+    ///
+    /// This reads a binaries-metadata file, for example one from a reuse archive, and prints every
+    /// diagnostic nextest would produce for it, along with its code and severity. Exits with a
+    /// non-zero code if any errors are found.
+    MetadataLint {
+        /// The path to the binaries-metadata file.
+        #[arg(long, value_name = "PATH")]
+        binaries_metadata: Utf8PathBuf,
+    },
 }
 
 impl DebugCommand {
@@ -2137,6 +2529,42 @@ impl DebugCommand {
                     display_description_kind(description_kind, output_format)?;
                 }
             }
+            DebugCommand::MetadataLint { binaries_metadata } => {
+                let ReusedBinaryList { binary_list } =
+                    ReusedBinaryList::materialize(&binaries_metadata).map_err(|err| {
+                        ExpectedError::metadata_materialize_error("binaries-metadata", err)
+                    })?;
+                let rust_build_meta = binary_list.rust_build_meta.map_paths(&PathMapper::noop());
+
+                let diagnostics = rust_build_meta.diagnostics();
+                for diagnostic in &diagnostics {
+                    println!(
+                        "{}[{}]: {}",
+                        diagnostic.severity, diagnostic.code, diagnostic.message
+                    );
+                    for path in &diagnostic.related_paths {
+                        println!("  {path}");
+                    }
+                }
+
+                let error_count = diagnostics
+                    .iter()
+                    .filter(|diagnostic| diagnostic.severity == DiagnosticSeverity::Error)
+                    .count();
+                let warning_count = diagnostics.len() - error_count;
+                log::info!(
+                    "found {error_count} {}, {warning_count} {}",
+                    if error_count == 1 { "error" } else { "errors" },
+                    if warning_count == 1 {
+                        "warning"
+                    } else {
+                        "warnings"
+                    },
+                );
+                if error_count > 0 {
+                    return Ok(nextest_metadata::NextestExitCode::METADATA_LINT_FAILED);
+                }
+            }
         }
 
         Ok(0)
@@ -2347,6 +2775,21 @@ fn warn_on_err(thing: &str, err: &(dyn std::error::Error)) {
     log::warn!("{}", s);
 }
 
+fn warn_on_list_cache_err(err: &ListCacheError) {
+    let mut s = String::with_capacity(256);
+    swrite!(s, "{err}, continuing without the test list cache");
+    if let Some(source) = err.source() {
+        swrite!(
+            s,
+            "\n  {} {}",
+            "caused by:".if_supports_color(Stream::Stderr, |s| s.style(Style::new().yellow())),
+            source
+        );
+    }
+
+    log::warn!("{}", s);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2405,9 +2848,18 @@ mod tests {
             // ---
             "cargo nextest list -E deps(foo)",
             "cargo nextest run --filterset 'test(bar)' --package=my-package test-filter",
+            "cargo nextest run --filterset-file filters.txt -E 'test(bar)'",
+            "cargo nextest run --print-command -E 'test(bar)'",
+            "cargo nextest list --filter-expr-file a.txt --filter-expr-file b.txt",
             "cargo nextest run --filter-expr 'test(bar)' --package=my-package test-filter",
             "cargo nextest list -E 'deps(foo)' --ignore-default-filter",
             // ---
+            // Partitioning
+            // ---
+            "cargo nextest run --partition hash:1/2",
+            "cargo nextest run --partition timing:1/4 --partition-timing-file timing.json",
+            "cargo nextest run --partition timing:2/4 --partition-timing-file timing.json --partition-default-secs 1.5",
+            // ---
             // Test binary arguments
             // ---
             "cargo nextest run -- --a an arbitrary arg",
@@ -2438,6 +2890,10 @@ mod tests {
             ("cargo nextest run --no-run -j8", ArgumentConflict),
             ("cargo nextest run --no-run --retries 3", ArgumentConflict),
             ("cargo nextest run --no-run --fail-fast", ArgumentConflict),
+            (
+                "cargo nextest run --no-run --print-command",
+                ArgumentConflict,
+            ),
             (
                 "cargo nextest run --no-run --no-fail-fast",
                 ArgumentConflict,
@@ -2528,6 +2984,21 @@ mod tests {
                 "cargo nextest run --archive-file foo --target-dir-remap bar",
                 ArgumentConflict,
             ),
+            // ---
+            // Partitioning
+            // ---
+            (
+                "cargo nextest run --partition-timing-file timing.json",
+                MissingRequiredArgument,
+            ),
+            (
+                "cargo nextest run --partition timing:1/2 --partition-default-secs 3",
+                MissingRequiredArgument,
+            ),
+            (
+                "cargo nextest run --partition timing:1/2 --partition-timing-file timing.json --partition-default-secs abc",
+                ValueValidation,
+            ),
             // Invalid test threads: 0
             ("cargo nextest run --jobs 0", ValueValidation),
         ];
@@ -2670,4 +3141,56 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_parse_filterset_file() {
+        let contents =
+            "# GPU tests\ntest(gpu)\n\n  package(foo) & test(/bar#baz/)  \n  # trailing\n";
+        assert_eq!(
+            parse_filterset_file(Utf8Path::new("filters.txt"), contents),
+            vec![
+                ("filters.txt:2".to_owned(), "test(gpu)".to_owned()),
+                (
+                    "filters.txt:4".to_owned(),
+                    "package(foo) & test(/bar#baz/)".to_owned()
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_resolve_profile_name() {
+        assert_eq!(
+            resolve_profile_name(Some("local"), Some("ci"), false),
+            ("local", ProfileSource::CommandLine),
+            "--profile wins over NEXTEST_PROFILE",
+        );
+        assert_eq!(
+            resolve_profile_name(Some("local"), Some("ci"), true),
+            ("local", ProfileSource::CommandLine),
+            "--profile wins over NEXTEST_PROFILE under miri",
+        );
+        assert_eq!(
+            resolve_profile_name(None, Some("ci"), false),
+            ("ci", ProfileSource::Environment),
+        );
+        assert_eq!(
+            resolve_profile_name(None, Some("ci"), true),
+            ("ci", ProfileSource::Environment),
+            "NEXTEST_PROFILE wins over the miri default",
+        );
+        assert_eq!(
+            resolve_profile_name(None, Some(""), false),
+            (NextestConfig::DEFAULT_PROFILE, ProfileSource::Default),
+            "empty NEXTEST_PROFILE is treated as unset",
+        );
+        assert_eq!(
+            resolve_profile_name(None, None, false),
+            (NextestConfig::DEFAULT_PROFILE, ProfileSource::Default),
+        );
+        assert_eq!(
+            resolve_profile_name(None, None, true),
+            (NextestConfig::DEFAULT_MIRI_PROFILE, ProfileSource::Default),
+        );
+    }
 }
@@ -78,6 +78,11 @@ pub enum ExpectedError {
         #[from]
         err: ConfigParseError,
     },
+    #[error("extra dylib paths error")]
+    ExtraDylibPathsError {
+        #[from]
+        err: ExtraDylibPathsError,
+    },
     #[error("test filter build error")]
     TestFilterBuilderError {
         #[from]
@@ -88,6 +93,11 @@ pub enum ExpectedError {
         #[from]
         err: UnknownHostPlatform,
     },
+    #[error("unsupported dynamic library platform")]
+    UnsupportedDylibPlatform {
+        #[from]
+        err: UnsupportedDylibPlatform,
+    },
     #[error("target triple error")]
     TargetTripleError {
         #[from]
@@ -226,8 +236,24 @@ pub enum ExpectedError {
     },
     #[error("filterset parse error")]
     FiltersetParseError {
-        all_errors: Vec<FiltersetParseErrors>,
+        /// Each error is paired with the `path:line` it was read from, if it came from a file.
+        all_errors: Vec<(Option<String>, FiltersetParseErrors)>,
+    },
+    #[error("filterset file read error")]
+    FiltersetFileReadError {
+        path: Utf8PathBuf,
+        #[source]
+        err: std::io::Error,
     },
+    #[error("partition timings read error")]
+    PartitionTimingsReadError {
+        #[source]
+        err: PartitionTimingsReadError,
+    },
+    #[error("partition timing file missing")]
+    PartitionTimingFileMissing,
+    #[error("partition timing file unused")]
+    PartitionTimingFileUnused,
     #[error("test binary args parse error")]
     TestBinaryArgsParseError {
         reason: &'static str,
@@ -355,7 +381,9 @@ impl ExpectedError {
         }
     }
 
-    pub(crate) fn filter_expression_parse_error(all_errors: Vec<FiltersetParseErrors>) -> Self {
+    pub(crate) fn filter_expression_parse_error(
+        all_errors: Vec<(Option<String>, FiltersetParseErrors)>,
+    ) -> Self {
         Self::FiltersetParseError { all_errors }
     }
 
@@ -382,11 +410,13 @@ impl ExpectedError {
             | Self::WorkspaceRootInvalid { .. }
             | Self::SetCurrentDirFailed { .. }
             | Self::ProfileNotFound { .. }
+            | Self::ExtraDylibPathsError { .. }
             | Self::StoreDirCreateError { .. }
             | Self::RootManifestNotFound { .. }
             | Self::CargoConfigError { .. }
             | Self::TestFilterBuilderError { .. }
             | Self::UnknownHostPlatform { .. }
+            | Self::UnsupportedDylibPlatform { .. }
             | Self::TargetTripleError { .. }
             | Self::MetadataMaterializeError { .. }
             | Self::UnknownArchiveFormat { .. }
@@ -401,7 +431,11 @@ impl ExpectedError {
             | Self::SignalHandlerSetupError { .. }
             | Self::ShowTestGroupsError { .. }
             | Self::InvalidMessageFormatVersion { .. }
-            | Self::DebugExtractReadError { .. } => NextestExitCode::SETUP_ERROR,
+            | Self::DebugExtractReadError { .. }
+            | Self::FiltersetFileReadError { .. }
+            | Self::PartitionTimingsReadError { .. }
+            | Self::PartitionTimingFileMissing
+            | Self::PartitionTimingFileUnused => NextestExitCode::SETUP_ERROR,
             Self::ConfigParseError { err } => {
                 // Experimental features not being enabled are their own error.
                 match err.kind() {
@@ -483,6 +517,10 @@ impl ExpectedError {
                 log::error!("{}", err);
                 err.source()
             }
+            Self::ExtraDylibPathsError { err } => {
+                log::error!("{}", err);
+                err.source()
+            }
             Self::RootManifestNotFound {
                 path,
                 reuse_build_kind,
@@ -632,6 +670,10 @@ impl ExpectedError {
                 log::error!("the host platform was unknown to nextest");
                 Some(err as &dyn Error)
             }
+            Self::UnsupportedDylibPlatform { err } => {
+                log::error!("{err} (strict platform checks are enabled)");
+                None
+            }
             Self::TargetTripleError { err } => {
                 log::error!("{err}");
                 err.source()
@@ -818,10 +860,16 @@ impl ExpectedError {
                 None
             }
             Self::FiltersetParseError { all_errors } => {
-                for errors in all_errors {
+                for (source, errors) in all_errors {
                     for single_error in &errors.errors {
-                        let report = miette::Report::new(single_error.clone())
-                            .with_source_code(errors.input.to_owned());
+                        let report = miette::Report::new(single_error.clone());
+                        let report = match source {
+                            Some(source) => report.with_source_code(miette::NamedSource::new(
+                                source,
+                                errors.input.to_owned(),
+                            )),
+                            None => report.with_source_code(errors.input.to_owned()),
+                        };
                         log::error!(target: "cargo_nextest::no_heading", "{:?}", report);
                     }
                 }
@@ -829,6 +877,33 @@ impl ExpectedError {
                 log::error!("failed to parse filterset");
                 None
             }
+            Self::FiltersetFileReadError { path, err } => {
+                log::error!(
+                    "error reading filterset file `{}`",
+                    path.if_supports_color(Stream::Stderr, |x| x.bold()),
+                );
+                Some(err as &dyn Error)
+            }
+            Self::PartitionTimingsReadError { err } => {
+                log::error!("{err}");
+                err.source()
+            }
+            Self::PartitionTimingFileMissing => {
+                log::error!(
+                    "{} requires {} to be specified",
+                    "--partition timing:M/N".if_supports_color(Stream::Stderr, |x| x.bold()),
+                    "--partition-timing-file".if_supports_color(Stream::Stderr, |x| x.bold()),
+                );
+                None
+            }
+            Self::PartitionTimingFileUnused => {
+                log::error!(
+                    "{} can only be used with {}",
+                    "--partition-timing-file".if_supports_color(Stream::Stderr, |x| x.bold()),
+                    "--partition timing:M/N".if_supports_color(Stream::Stderr, |x| x.bold()),
+                );
+                None
+            }
             Self::TestBinaryArgsParseError { reason, args } => {
                 log::error!(
                     "failed to parse test binary arguments `{}`: arguments are {reason}",
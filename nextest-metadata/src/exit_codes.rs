@@ -71,6 +71,11 @@ impl NextestExitCode {
     /// *Since nextest 0.9.55*.
     pub const REQUIRED_VERSION_NOT_MET: i32 = 92;
 
+    /// `cargo nextest debug metadata-lint` found errors in build metadata.
+    ///
+    /// *Since nextest 0.9.79*.
+    pub const METADATA_LINT_FAILED: i32 = 91;
+
     /// The current version of nextest is older than the minimum recommended version.
     ///
     /// This advisory exit code is only produced by `cargo nextest show-config version`.
@@ -470,6 +470,16 @@ pub struct RustBuildMetaSummary {
     /// Base output directories, relative to the target directory.
     pub base_output_directories: BTreeSet<Utf8PathBuf>,
 
+    /// The artifact category of each base output directory, keyed by the directory relative to
+    /// the target directory.
+    ///
+    /// Base output directories missing from this map are
+    /// [uncategorized](BaseOutputDirCategory::UNCATEGORIZED).
+    ///
+    /// Added in cargo-nextest 0.9.79.
+    #[serde(default)]
+    pub base_output_directory_categories: BTreeMap<Utf8PathBuf, BaseOutputDirCategory>,
+
     /// Information about non-test binaries, keyed by package ID.
     pub non_test_binaries: BTreeMap<String, BTreeSet<RustNonTestBinarySummary>>,
 
@@ -483,6 +493,109 @@ pub struct RustBuildMetaSummary {
     /// Linked paths, relative to the target directory.
     pub linked_paths: BTreeSet<Utf8PathBuf>,
 
+    /// Linked paths outside the target directory, as absolute paths.
+    ///
+    /// Added in cargo-nextest 0.9.79.
+    #[serde(default)]
+    pub absolute_linked_paths: BTreeSet<Utf8PathBuf>,
+
+    /// The package IDs of the packages whose build scripts requested each linked path, keyed by
+    /// the path as it appears in [`Self::linked_paths`] or [`Self::absolute_linked_paths`].
+    ///
+    /// Linked paths missing from this map were requested by unknown packages.
+    ///
+    /// Added in cargo-nextest 0.9.79.
+    #[serde(default)]
+    pub linked_path_requested_by: BTreeMap<Utf8PathBuf, BTreeSet<String>>,
+
+    /// Native libraries linked by build scripts via `cargo::rustc-link-lib`, keyed by library
+    /// name (without the kind or modifiers). The values are the package IDs of the packages that
+    /// link the library.
+    ///
+    /// Added in cargo-nextest 0.9.79.
+    #[serde(default)]
+    pub linked_libs: BTreeMap<String, BTreeSet<String>>,
+
+    /// Dynamic library search directories that existed when this metadata was produced, relative
+    /// to the target directory.
+    ///
+    /// If present, these are checked against the directories found on the machine tests are run
+    /// on, and a warning is produced for any that have disappeared.
+    ///
+    /// Added in cargo-nextest 0.9.79.
+    #[serde(default)]
+    pub resolved_search_dirs: Option<Vec<Utf8PathBuf>>,
+
+    /// Absolute output directories outside the target directory, for example those used with
+    /// `--artifact-dir`.
+    ///
+    /// Added in cargo-nextest 0.9.79.
+    #[serde(default)]
+    pub extra_output_roots: BTreeSet<Utf8PathBuf>,
+
+    /// A user-defined label for this metadata, for example a git commit hash or a CI job ID.
+    ///
+    /// This is purely informational and is ignored by nextest.
+    ///
+    /// Added in cargo-nextest 0.9.79.
+    #[serde(default)]
+    pub label: Option<String>,
+
+    /// The version of the host operating system component relevant to dynamic linking, at the
+    /// time this metadata was produced (e.g. `glibc 2.35` or `macOS 14.5`).
+    ///
+    /// Added in cargo-nextest 0.9.79.
+    #[serde(default)]
+    pub host_os_version: Option<String>,
+
+    /// The exact version of rustc used for the build, as reported by `rustc -vV` (e.g. `1.80.0`
+    /// or `1.81.0-nightly`).
+    ///
+    /// This is the toolchain's own version, not the minimum supported Rust version of any
+    /// package.
+    ///
+    /// Added in cargo-nextest 0.9.79.
+    #[serde(default)]
+    pub rust_version: Option<String>,
+
+    /// The command-line arguments that produced this metadata, with arguments that might contain
+    /// sensitive information redacted.
+    ///
+    /// Added in cargo-nextest 0.9.79.
+    #[serde(default)]
+    pub list_command: Option<Vec<String>>,
+
+    /// The time this metadata was produced, in RFC 3339 format.
+    ///
+    /// This is purely informational and is ignored by nextest.
+    ///
+    /// Added in cargo-nextest 0.9.79.
+    #[serde(default)]
+    pub built_at: Option<String>,
+
+    /// The debug info level test binaries were built with, as reported by Cargo (e.g. `none`,
+    /// `line-tables-only`, `limited` or `full`).
+    ///
+    /// This is purely informational and is ignored by nextest.
+    ///
+    /// Added in cargo-nextest 0.9.79.
+    #[serde(default)]
+    pub debuginfo: Option<String>,
+
+    /// Whether incremental compilation was enabled for the build, if known.
+    ///
+    /// This is purely informational and is ignored by nextest.
+    ///
+    /// Added in cargo-nextest 0.9.79.
+    #[serde(default)]
+    pub incremental: Option<bool>,
+
+    /// The features enabled for each package with test binaries, keyed by package ID.
+    ///
+    /// Added in cargo-nextest 0.9.79.
+    #[serde(default)]
+    pub enabled_features: BTreeMap<String, BTreeSet<String>>,
+
     /// The build platforms used while compiling the Rust artifacts.
     ///
     /// Added in cargo-nextest 0.9.72.
@@ -553,6 +666,19 @@ pub struct TargetPlatformSummary {
     ///
     /// Err if we failed to discover it.
     pub libdir: PlatformLibdirSummary,
+
+    /// Environment variables required by the emulator used to run binaries for this target, e.g.
+    /// `QEMU_LD_PREFIX`.
+    ///
+    /// Added in cargo-nextest 0.9.79.
+    #[serde(default)]
+    pub emulator_env: BTreeMap<String, String>,
+
+    /// The sysroot for the target platform, as printed by `rustc --print sysroot --target`.
+    ///
+    /// Added in cargo-nextest 0.9.79.
+    #[serde(default)]
+    pub sysroot: Option<Utf8PathBuf>,
 }
 
 /// Serialized representation of a platform's library directory.
@@ -596,6 +722,9 @@ impl PlatformLibdirUnavailable {
     /// present in the archive
     pub const NOT_IN_ARCHIVE: Self = Self::new_const("not-in-archive");
 
+    /// The libdir is not available because rustc printed a path that isn't valid UTF-8.
+    pub const NON_UTF8_PATH: Self = Self::new_const("non-utf8-path");
+
     /// Converts a static string into Self.
     pub const fn new_const(reason: &'static str) -> Self {
         Self(Cow::Borrowed(reason))
@@ -652,6 +781,49 @@ impl fmt::Display for RustNonTestBinaryKind {
     }
 }
 
+/// The category of artifacts that a base output directory was detected from.
+///
+/// This is part of [`RustBuildMetaSummary`].
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct BaseOutputDirCategory(pub Cow<'static, str>);
+
+impl BaseOutputDirCategory {
+    /// Creates a new `BaseOutputDirCategory` from a string.
+    #[inline]
+    pub fn new(category: impl Into<Cow<'static, str>>) -> Self {
+        Self(category.into())
+    }
+
+    /// Creates a new `BaseOutputDirCategory` from a static string.
+    #[inline]
+    pub const fn new_const(category: &'static str) -> Self {
+        Self(Cow::Borrowed(category))
+    }
+
+    /// Returns the category as a string.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The "tests" category, used for directories containing a `deps` subdirectory with test
+    /// binaries and their dependencies (e.g. `debug`).
+    pub const TESTS: Self = Self::new_const("tests");
+
+    /// The "examples" category, used for directories containing examples (e.g.
+    /// `debug/examples`).
+    pub const EXAMPLES: Self = Self::new_const("examples");
+
+    /// The "uncategorized" category, used for directories recorded by older versions of nextest.
+    pub const UNCATEGORIZED: Self = Self::new_const("uncategorized");
+}
+
+impl fmt::Display for BaseOutputDirCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 /// A serializable suite of tests within a Rust test binary.
 ///
 /// Part of a [`TestListSummary`].
@@ -815,9 +987,23 @@ mod tests {
     }"#, RustBuildMetaSummary {
         target_directory: "/foo".into(),
         base_output_directories: BTreeSet::new(),
+        base_output_directory_categories: BTreeMap::new(),
         non_test_binaries: BTreeMap::new(),
         build_script_out_dirs: BTreeMap::new(),
         linked_paths: BTreeSet::new(),
+        resolved_search_dirs: None,
+        extra_output_roots: BTreeSet::new(),
+        label: None,
+        host_os_version: None,
+        rust_version: None,
+        linked_libs: BTreeMap::new(),
+        absolute_linked_paths: BTreeSet::new(),
+        linked_path_requested_by: BTreeMap::new(),
+        list_command: None,
+        built_at: None,
+        debuginfo: None,
+        incremental: None,
+        enabled_features: BTreeMap::new(),
         target_platform: None,
         target_platforms: vec![],
         platforms: None,
@@ -831,9 +1017,23 @@ mod tests {
     }"#, RustBuildMetaSummary {
         target_directory: "/foo".into(),
         base_output_directories: BTreeSet::new(),
+        base_output_directory_categories: BTreeMap::new(),
         non_test_binaries: BTreeMap::new(),
         build_script_out_dirs: BTreeMap::new(),
         linked_paths: BTreeSet::new(),
+        resolved_search_dirs: None,
+        extra_output_roots: BTreeSet::new(),
+        label: None,
+        host_os_version: None,
+        rust_version: None,
+        linked_libs: BTreeMap::new(),
+        absolute_linked_paths: BTreeSet::new(),
+        linked_path_requested_by: BTreeMap::new(),
+        list_command: None,
+        built_at: None,
+        debuginfo: None,
+        incremental: None,
+        enabled_features: BTreeMap::new(),
         target_platform: Some("x86_64-unknown-linux-gnu".to_owned()),
         target_platforms: vec![],
         platforms: None,
@@ -3,9 +3,10 @@
 
 use super::TrackDefault;
 use crate::config::helpers::deserialize_relative_path;
-use camino::{Utf8Path, Utf8PathBuf};
+use camino::{Utf8Component, Utf8Path, Utf8PathBuf};
+use globset::{Glob, GlobBuilder, GlobMatcher};
 use serde::{de::Unexpected, Deserialize};
-use std::fmt;
+use std::{collections::BTreeSet, fmt};
 
 /// Configuration for archives.
 #[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
@@ -13,6 +14,126 @@ use std::fmt;
 pub struct ArchiveConfig {
     /// Files to include in the archive.
     pub include: Vec<ArchiveInclude>,
+
+    /// Files from the workspace to include in the archive, selected by glob patterns.
+    #[serde(default)]
+    pub workspace_files: ArchiveWorkspaceFiles,
+}
+
+/// Type for the archive.workspace-files key.
+///
+/// Patterns are matched against paths relative to the workspace root, using `/` as the separator.
+/// `*` doesn't match across directories, while `**` does.
+#[derive(Debug, Clone, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ArchiveWorkspaceFiles {
+    /// Files matching any of these patterns are included in the archive.
+    #[serde(default)]
+    include: Vec<ArchiveGlob>,
+
+    /// Files matching any of these patterns are excluded from the archive, even if they're
+    /// included.
+    #[serde(default)]
+    exclude: Vec<ArchiveGlob>,
+}
+
+impl ArchiveWorkspaceFiles {
+    /// Returns true if no files are included.
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty()
+    }
+
+    /// Returns true if `rel_path`, relative to the workspace root, should be archived.
+    pub fn is_match(&self, rel_path: &Utf8Path) -> bool {
+        let rel_path = rel_path.as_str().replace('\\', "/");
+        self.include.iter().any(|glob| glob.is_match(&rel_path))
+            && !self.exclude.iter().any(|glob| glob.is_match(&rel_path))
+    }
+
+    /// Returns the directories (or files) that need to be walked to find all matches, relative to
+    /// the workspace root.
+    ///
+    /// This is the literal prefix of each include pattern, with roots that are within other roots
+    /// removed.
+    pub(crate) fn roots(&self) -> BTreeSet<Utf8PathBuf> {
+        let prefixes: BTreeSet<_> = self
+            .include
+            .iter()
+            .map(|glob| glob.literal_prefix())
+            .collect();
+        prefixes
+            .iter()
+            .filter(|prefix| {
+                !prefixes
+                    .iter()
+                    .any(|other| other != *prefix && prefix.starts_with(other))
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+/// A glob pattern in the archive configuration.
+#[derive(Clone, Debug)]
+struct ArchiveGlob {
+    glob: Glob,
+    matcher: GlobMatcher,
+}
+
+impl ArchiveGlob {
+    fn new(pattern: &str) -> Result<Self, globset::Error> {
+        let glob = GlobBuilder::new(pattern)
+            .literal_separator(true)
+            .backslash_escape(true)
+            .build()?;
+        let matcher = glob.compile_matcher();
+        Ok(Self { glob, matcher })
+    }
+
+    fn is_match(&self, rel_path: &str) -> bool {
+        self.matcher.is_match(rel_path)
+    }
+
+    /// Returns the leading components of the pattern that don't contain any glob metacharacters.
+    fn literal_prefix(&self) -> Utf8PathBuf {
+        self.glob
+            .glob()
+            .split('/')
+            .take_while(|component| !component.contains(['*', '?', '[', '{', '\\']))
+            .collect()
+    }
+}
+
+impl PartialEq for ArchiveGlob {
+    fn eq(&self, other: &Self) -> bool {
+        self.glob == other.glob
+    }
+}
+
+impl Eq for ArchiveGlob {}
+
+impl<'de> Deserialize<'de> for ArchiveGlob {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let pattern = String::deserialize(deserializer)?;
+        // Like archive.include paths, patterns must stay within the workspace root.
+        let is_relative = Utf8Path::new(&pattern)
+            .components()
+            .all(|component| matches!(component, Utf8Component::Normal(_)));
+        if !is_relative {
+            return Err(serde::de::Error::invalid_value(
+                Unexpected::Str(&pattern),
+                &"a relative glob pattern with no parent or current directory components",
+            ));
+        }
+
+        ArchiveGlob::new(&pattern).map_err(|error| {
+            let expected = format!("a valid glob pattern ({})", error.kind());
+            serde::de::Error::invalid_value(Unexpected::Str(&pattern), &expected.as_str())
+        })
+    }
 }
 
 /// Type for the archive-include key.
@@ -237,6 +358,7 @@ mod tests {
                 { path = "foo", relative-to = "target" },
                 { path = "bar", relative-to = "target", depth = 1, on-missing = "error" },
             ]
+            workspace-files = { include = ["fixtures/**/*.json"], exclude = ["fixtures/private/**"] }
 
             [profile.profile1]
             archive.include = [
@@ -277,6 +399,10 @@ mod tests {
                     on_missing: ArchiveIncludeOnMissing::Error,
                 },
             ],
+            workspace_files: ArchiveWorkspaceFiles {
+                include: vec![ArchiveGlob::new("fixtures/**/*.json").unwrap()],
+                exclude: vec![ArchiveGlob::new("fixtures/private/**").unwrap()],
+            },
         };
 
         assert_eq!(
@@ -302,6 +428,7 @@ mod tests {
                     depth: TrackDefault::with_deserialized_value(RecursionDepth::ZERO),
                     on_missing: ArchiveIncludeOnMissing::Ignore,
                 }],
+                workspace_files: ArchiveWorkspaceFiles::default(),
             },
             "profile1 matches"
         );
@@ -312,7 +439,10 @@ mod tests {
                 .expect("default profile exists")
                 .apply_build_platforms(&build_platforms())
                 .archive_config(),
-            &ArchiveConfig { include: vec![] },
+            &ArchiveConfig {
+                include: vec![],
+                workspace_files: ArchiveWorkspaceFiles::default(),
+            },
             "profile2 matches"
         );
 
@@ -397,6 +527,30 @@ mod tests {
         "#},
         r#"invalid type: integer `42`, expected a string: "ignore", "warn", or "error""#
         ; "invalid on-missing type")]
+    #[test_case(
+        indoc!{r#"
+            [profile.default]
+            archive.include = []
+            archive.workspace-files = { include = ["../fixtures/*.json"] }
+        "#},
+        r#"invalid value: string "../fixtures/*.json", expected a relative glob pattern with no parent or current directory components"#
+        ; "workspace files parent component")]
+    #[test_case(
+        indoc!{r#"
+            [profile.default]
+            archive.include = []
+            archive.workspace-files = { include = ["fixtures/[a"] }
+        "#},
+        r#"invalid value: string "fixtures/[a", expected a valid glob pattern (unclosed character class; missing ']')"#
+        ; "workspace files invalid glob")]
+    #[test_case(
+        indoc!{r#"
+            [profile.default]
+            archive.include = []
+            archive.workspace-files = { includes = ["fixtures/*.json"] }
+        "#},
+        r#"unknown field `includes`, expected `include` or `exclude`"#
+        ; "workspace files unknown field")]
     fn parse_invalid(config_contents: &str, expected_message: &str) {
         let workspace_dir = tempdir().unwrap();
         let workspace_path: &Utf8Path = workspace_dir.path();
@@ -431,4 +585,44 @@ mod tests {
             "expected message: {expected_message}\nactual message: {message}"
         );
     }
+
+    #[test]
+    fn workspace_files_match() {
+        let workspace_files = ArchiveWorkspaceFiles {
+            include: vec![
+                ArchiveGlob::new("fixtures/**/*.json").unwrap(),
+                ArchiveGlob::new("fixtures/data/*.bin").unwrap(),
+                ArchiveGlob::new("testdata/config.toml").unwrap(),
+            ],
+            exclude: vec![ArchiveGlob::new("fixtures/private/**").unwrap()],
+        };
+
+        for (path, expected) in [
+            ("fixtures/a.json", true),
+            ("fixtures/nested/dir/b.json", true),
+            ("fixtures/a.txt", false),
+            ("fixtures/private/secret.json", false),
+            ("fixtures/data/blob.bin", true),
+            ("fixtures/data/nested/blob.bin", false),
+            ("testdata/config.toml", true),
+            ("other/a.json", false),
+        ] {
+            assert_eq!(
+                workspace_files.is_match(Utf8Path::new(path)),
+                expected,
+                "for path {path}"
+            );
+        }
+
+        assert_eq!(
+            workspace_files.roots(),
+            [
+                Utf8PathBuf::from("fixtures"),
+                Utf8PathBuf::from("testdata/config.toml"),
+            ]
+            .into(),
+            "fixtures/data is within fixtures",
+        );
+        assert!(ArchiveWorkspaceFiles::default().is_empty());
+    }
 }
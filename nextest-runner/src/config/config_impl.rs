@@ -2,16 +2,16 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use super::{
-    ArchiveConfig, CompiledByProfile, CompiledData, CompiledDefaultSet, ConfigExperimental,
-    CustomTestGroup, DeserializedOverride, DeserializedProfileScriptConfig,
-    NextestVersionDeserialize, RetryPolicy, ScriptConfig, ScriptId, SettingSource, SetupScripts,
-    SlowTimeout, TestGroup, TestGroupConfig, TestSettings, TestThreads, ThreadsRequired,
-    ToolConfigFile,
+    extra_dylib_paths::resolve_extra_dylib_paths, ArchiveConfig, CompiledByProfile, CompiledData,
+    CompiledDefaultSet, ConfigExperimental, CustomTestGroup, DeserializedOverride,
+    DeserializedProfileScriptConfig, ExtraDylibPathsContext, NextestVersionDeserialize,
+    RetryPolicy, ScriptConfig, ScriptId, SettingSource, SetupScripts, SlowTimeout, TestGroup,
+    TestGroupConfig, TestSettings, TestThreads, ThreadsRequired, ToolConfigFile,
 };
 use crate::{
     errors::{
-        provided_by_tool, ConfigParseError, ConfigParseErrorKind, ProfileNotFound,
-        UnknownConfigScriptError, UnknownTestGroupError,
+        provided_by_tool, ConfigParseError, ConfigParseErrorKind, ExtraDylibPathsError,
+        ProfileNotFound, UnknownConfigScriptError, UnknownTestGroupError,
     },
     list::TestList,
     platform::BuildPlatforms,
@@ -625,6 +625,23 @@ impl<'cfg, State> NextestProfile<'cfg, State> {
         self.scripts
     }
 
+    /// Returns the extra dynamic library paths configured for this profile, with variables
+    /// expanded.
+    ///
+    /// Relative paths are resolved against the workspace root. Paths aren't checked for existence
+    /// here: they're added to the dynamic library path the same way absolute linked paths are, so
+    /// missing directories are skipped at that point.
+    pub fn extra_dylib_paths(
+        &self,
+        ctx: ExtraDylibPathsContext<'_>,
+    ) -> Result<Vec<Utf8PathBuf>, ExtraDylibPathsError> {
+        let paths = self
+            .custom_profile
+            .and_then(|profile| profile.extra_dylib_paths.as_deref())
+            .unwrap_or(&self.default_profile.extra_dylib_paths);
+        resolve_extra_dylib_paths(&self.name, paths, ctx, |name| std::env::var(name).ok())
+    }
+
     #[allow(dead_code)]
     pub(super) fn custom_profile(&self) -> Option<&'cfg CustomProfileImpl> {
         self.custom_profile
@@ -739,6 +756,17 @@ impl<'cfg> NextestProfile<'cfg, FinalConfig> {
         TestSettings::new(self, query)
     }
 
+    /// Returns true if output capture is disabled through overrides for any test in `test_list`
+    /// that will be run.
+    pub fn has_no_capture_tests(&self, test_list: &TestList<'_>) -> bool {
+        test_list.iter_tests().any(|test_instance| {
+            test_instance.test_info.filter_match.is_match()
+                && self
+                    .settings_for(&test_instance.to_test_query())
+                    .no_capture()
+        })
+    }
+
     /// Returns override settings for individual tests, with sources attached.
     pub(crate) fn settings_with_source_for(
         &self,
@@ -777,6 +805,56 @@ impl<'cfg> NextestProfile<'cfg, FinalConfig> {
             }
         })
     }
+
+    /// Returns the TAP configuration for this profile.
+    pub fn tap(&self) -> Option<NextestTapConfig> {
+        let path = self
+            .custom_profile
+            .and_then(|profile| profile.tap.path.as_deref())
+            .or(self.default_profile.tap.path.as_deref());
+
+        path.map(|path| NextestTapConfig {
+            path: self.store_dir.join(path),
+        })
+    }
+
+    /// Returns the timing report configuration for this profile.
+    pub fn timing(&self) -> Option<NextestTimingConfig> {
+        let path = self
+            .custom_profile
+            .and_then(|profile| profile.timing.path.as_deref())
+            .or(self.default_profile.timing.path.as_deref());
+
+        path.map(|path| NextestTimingConfig {
+            path: self.store_dir.join(path),
+        })
+    }
+}
+
+/// Timing report configuration for nextest, returned by a [`NextestProfile`].
+#[derive(Clone, Debug)]
+pub struct NextestTimingConfig {
+    path: Utf8PathBuf,
+}
+
+impl NextestTimingConfig {
+    /// Returns the absolute path to the timing report.
+    pub fn path(&self) -> &Utf8Path {
+        &self.path
+    }
+}
+
+/// TAP (Test Anything Protocol) configuration for nextest, returned by a [`NextestProfile`].
+#[derive(Clone, Debug)]
+pub struct NextestTapConfig {
+    path: Utf8PathBuf,
+}
+
+impl NextestTapConfig {
+    /// Returns the absolute path to the TAP report.
+    pub fn path(&self) -> &Utf8Path {
+        &self.path
+    }
 }
 
 /// JUnit configuration for nextest, returned by a [`NextestProfile`].
@@ -910,9 +988,12 @@ pub(super) struct DefaultProfileImpl {
     fail_fast: bool,
     slow_timeout: SlowTimeout,
     leak_timeout: Duration,
+    extra_dylib_paths: Vec<String>,
     overrides: Vec<DeserializedOverride>,
     scripts: Vec<DeserializedProfileScriptConfig>,
     junit: DefaultJunitImpl,
+    tap: TapImpl,
+    timing: TimingImpl,
     archive: ArchiveConfig,
 }
 
@@ -948,6 +1029,9 @@ impl DefaultProfileImpl {
             leak_timeout: p
                 .leak_timeout
                 .expect("leak-timeout present in default profile"),
+            extra_dylib_paths: p
+                .extra_dylib_paths
+                .expect("extra-dylib-paths present in default profile"),
             overrides: p.overrides,
             scripts: p.scripts,
             junit: DefaultJunitImpl {
@@ -965,6 +1049,8 @@ impl DefaultProfileImpl {
                     .store_failure_output
                     .expect("junit.store-failure-output present in default profile"),
             },
+            tap: p.tap,
+            timing: p.timing,
             archive: p.archive.expect("archive present in default profile"),
         }
     }
@@ -1017,12 +1103,18 @@ pub(super) struct CustomProfileImpl {
     #[serde(default, with = "humantime_serde::option")]
     leak_timeout: Option<Duration>,
     #[serde(default)]
+    extra_dylib_paths: Option<Vec<String>>,
+    #[serde(default)]
     overrides: Vec<DeserializedOverride>,
     #[serde(default)]
     scripts: Vec<DeserializedProfileScriptConfig>,
     #[serde(default)]
     junit: JunitImpl,
     #[serde(default)]
+    tap: TapImpl,
+    #[serde(default)]
+    timing: TimingImpl,
+    #[serde(default)]
     archive: Option<ArchiveConfig>,
 }
 
@@ -1058,6 +1150,20 @@ struct JunitImpl {
     store_failure_output: Option<bool>,
 }
 
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct TapImpl {
+    #[serde(default)]
+    path: Option<Utf8PathBuf>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct TimingImpl {
+    #[serde(default)]
+    path: Option<Utf8PathBuf>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
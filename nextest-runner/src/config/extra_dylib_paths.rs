@@ -0,0 +1,131 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Support for the `extra-dylib-paths` profile setting.
+//!
+//! Each configured path can refer to variables with `${NAME}` syntax:
+//!
+//! * `${CARGO_TARGET_DIR}` expands to the target directory in use, after any remapping.
+//! * `${NEXTEST_WORKSPACE_ROOT}` expands to the workspace root, after any remapping.
+//! * Any other `${NAME}` expands to the value of the environment variable `NAME`.
+//!
+//! `$$` expands to a literal `$`. Paths that are relative after expansion are interpreted relative
+//! to the workspace root.
+
+use crate::errors::{ExtraDylibPathsError, ExtraDylibPathsErrorKind};
+use camino::{Utf8Path, Utf8PathBuf};
+
+/// The context in which configured extra dynamic library paths are resolved.
+#[derive(Clone, Copy, Debug)]
+pub struct ExtraDylibPathsContext<'a> {
+    /// The target directory in use, after any remapping.
+    pub target_dir: &'a Utf8Path,
+
+    /// The workspace root, after any remapping.
+    pub workspace_root: &'a Utf8Path,
+}
+
+/// Resolves `paths`, the configured `extra-dylib-paths` for `profile_name`.
+pub(super) fn resolve_extra_dylib_paths(
+    profile_name: &str,
+    paths: &[String],
+    ctx: ExtraDylibPathsContext<'_>,
+    env_lookup: impl Fn(&str) -> Option<String>,
+) -> Result<Vec<Utf8PathBuf>, ExtraDylibPathsError> {
+    paths
+        .iter()
+        .map(|path| {
+            let expanded = expand_vars(path, ctx, &env_lookup)
+                .map_err(|kind| ExtraDylibPathsError::new(profile_name, path.clone(), kind))?;
+            Ok(ctx.workspace_root.join(expanded))
+        })
+        .collect()
+}
+
+fn expand_vars(
+    input: &str,
+    ctx: ExtraDylibPathsContext<'_>,
+    env_lookup: &impl Fn(&str) -> Option<String>,
+) -> Result<String, ExtraDylibPathsErrorKind> {
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(idx) = rest.find('$') {
+        out.push_str(&rest[..idx]);
+        rest = &rest[idx + 1..];
+        if let Some(after) = rest.strip_prefix('$') {
+            out.push('$');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix('{') {
+            let end = after
+                .find('}')
+                .ok_or(ExtraDylibPathsErrorKind::UnterminatedVariable)?;
+            let name = &after[..end];
+            if name.is_empty() {
+                return Err(ExtraDylibPathsErrorKind::EmptyVariable);
+            }
+            match name {
+                "CARGO_TARGET_DIR" => out.push_str(ctx.target_dir.as_str()),
+                "NEXTEST_WORKSPACE_ROOT" => out.push_str(ctx.workspace_root.as_str()),
+                _ => {
+                    let value = env_lookup(name).ok_or_else(|| {
+                        ExtraDylibPathsErrorKind::UnknownVariable {
+                            name: name.to_owned(),
+                        }
+                    })?;
+                    out.push_str(&value);
+                }
+            }
+            rest = &after[end + 1..];
+        } else {
+            return Err(ExtraDylibPathsErrorKind::BareDollar);
+        }
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_case::test_case;
+
+    fn ctx() -> ExtraDylibPathsContext<'static> {
+        ExtraDylibPathsContext {
+            target_dir: Utf8Path::new("/ws/target"),
+            workspace_root: Utf8Path::new("/ws"),
+        }
+    }
+
+    fn env_lookup(name: &str) -> Option<String> {
+        (name == "MY_LIBS").then(|| "/opt/libs".to_owned())
+    }
+
+    #[test_case("${CARGO_TARGET_DIR}/native", "/ws/target/native"; "target dir")]
+    #[test_case("${NEXTEST_WORKSPACE_ROOT}/vendor", "/ws/vendor"; "workspace root")]
+    #[test_case("${MY_LIBS}/lib", "/opt/libs/lib"; "env var")]
+    #[test_case("vendor/lib", "/ws/vendor/lib"; "relative")]
+    #[test_case("/abs/$$lib", "/abs/$lib"; "escaped dollar")]
+    fn test_resolve_valid(input: &str, expected: &str) {
+        let resolved = resolve_extra_dylib_paths("default", &[input.to_owned()], ctx(), env_lookup)
+            .expect("path resolved");
+        assert_eq!(resolved, vec![Utf8PathBuf::from(expected)]);
+    }
+
+    #[test_case(
+        "${MISSING}/lib",
+        ExtraDylibPathsErrorKind::UnknownVariable { name: "MISSING".to_owned() };
+        "unknown variable"
+    )]
+    #[test_case("${CARGO_TARGET_DIR", ExtraDylibPathsErrorKind::UnterminatedVariable; "unterminated")]
+    #[test_case("${}/lib", ExtraDylibPathsErrorKind::EmptyVariable; "empty")]
+    #[test_case("$HOME/lib", ExtraDylibPathsErrorKind::BareDollar; "bare dollar")]
+    fn test_resolve_invalid(input: &str, expected: ExtraDylibPathsErrorKind) {
+        let err = resolve_extra_dylib_paths("ci", &[input.to_owned()], ctx(), env_lookup)
+            .expect_err("path failed to resolve");
+        assert_eq!(err.kind(), &expected);
+        assert!(
+            err.to_string().contains("profile.ci.extra-dylib-paths"),
+            "error names the key: {err}"
+        );
+    }
+}
@@ -5,6 +5,7 @@
 
 mod archive;
 mod config_impl;
+mod extra_dylib_paths;
 mod helpers;
 mod identifier;
 mod nextest_version;
@@ -20,6 +21,7 @@ mod track_default;
 
 pub use archive::*;
 pub use config_impl::*;
+pub use extra_dylib_paths::ExtraDylibPathsContext;
 pub use identifier::*;
 pub use nextest_version::*;
 pub use overrides::*;
@@ -35,6 +35,7 @@ pub struct TestSettings<Source = ()> {
     failure_output: (TestOutputDisplayStreams, Source),
     junit_store_success_output: (bool, Source),
     junit_store_failure_output: (bool, Source),
+    no_capture: (bool, Source),
 }
 
 pub(crate) trait TrackSource<'p>: Sized {
@@ -113,6 +114,11 @@ impl TestSettings {
     pub fn junit_store_failure_output(&self) -> bool {
         self.junit_store_failure_output.0
     }
+
+    /// Returns whether output capture is disabled for this test.
+    pub fn no_capture(&self) -> bool {
+        self.no_capture.0
+    }
 }
 
 #[allow(dead_code)]
@@ -135,6 +141,7 @@ impl<Source: Copy> TestSettings<Source> {
         let mut failure_output = None;
         let mut junit_store_success_output = None;
         let mut junit_store_failure_output = None;
+        let mut no_capture = None;
 
         for override_ in &profile.compiled_data.overrides {
             if !override_.state.host_eval {
@@ -200,6 +207,11 @@ impl<Source: Copy> TestSettings<Source> {
                     junit_store_failure_output = Some(Source::track_override(f, override_));
                 }
             }
+            if no_capture.is_none() {
+                if let Some(n) = override_.data.no_capture {
+                    no_capture = Some(Source::track_override(n, override_));
+                }
+            }
         }
 
         // If no overrides were found, use the profile defaults.
@@ -223,6 +235,8 @@ impl<Source: Copy> TestSettings<Source> {
             // If the profile doesn't have JUnit enabled, failure output can just be false.
             Source::track_profile(profile.junit().map_or(false, |j| j.store_failure_output()))
         });
+        // no-capture can only be set through overrides (or the command line).
+        let no_capture = no_capture.unwrap_or_else(|| Source::track_profile(false));
 
         TestSettings {
             threads_required,
@@ -234,6 +248,7 @@ impl<Source: Copy> TestSettings<Source> {
             failure_output,
             junit_store_success_output,
             junit_store_failure_output,
+            no_capture,
         }
     }
 
@@ -500,6 +515,7 @@ pub(super) struct ProfileOverrideData {
     success_output: Option<TestOutputDisplayStreams>,
     failure_output: Option<TestOutputDisplayStreams>,
     junit: DeserializedJunitOutput,
+    no_capture: Option<bool>,
 }
 
 impl CompiledOverride<PreBuildPlatform> {
@@ -555,6 +571,7 @@ impl CompiledOverride<PreBuildPlatform> {
                     success_output: source.success_output,
                     failure_output: source.failure_output,
                     junit: source.junit,
+                    no_capture: source.no_capture,
                 },
             }),
             (maybe_host_err, maybe_platform_err, maybe_parse_err) => {
@@ -667,6 +684,8 @@ pub(super) struct DeserializedOverride {
     failure_output: Option<TestOutputDisplayStreams>,
     #[serde(default)]
     junit: DeserializedJunitOutput,
+    #[serde(default)]
+    no_capture: Option<bool>,
 }
 
 #[derive(Copy, Clone, Debug, Default, Deserialize)]
@@ -766,6 +785,7 @@ mod tests {
             test-group = "my-group"
             failure-output = "final"
             junit = { store-failure-output = false }
+            no-capture = true
 
             # Override 3
             [[profile.default.overrides]]
@@ -844,6 +864,7 @@ mod tests {
             assert_eq!(overrides.junit_store_success_output(), false);
             assert_eq!(overrides.junit_store_failure_output(), false);
         }
+        assert!(overrides.no_capture());
 
         // This query matches override 1 and 2.
         let target_binary_query = binary_query(
@@ -893,6 +914,7 @@ mod tests {
             assert_eq!(overrides.junit_store_success_output(), true);
             assert_eq!(overrides.junit_store_failure_output(), false);
         }
+        assert!(overrides.no_capture());
 
         // This query matches override 3.
         let query = TestQuery {
@@ -917,6 +939,7 @@ mod tests {
         };
         let overrides = profile.settings_for(&query);
         assert_eq!(overrides.retries(), RetryPolicy::new_without_delay(0));
+        assert!(!overrides.no_capture());
     }
 
     #[test_case(
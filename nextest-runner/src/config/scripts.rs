@@ -209,7 +209,11 @@ impl SetupScriptCommand {
             // Setup scripts can define environment variables which are written out here.
             .env("NEXTEST_ENV", &env_path);
 
-        apply_ld_dyld_env(&mut cmd, lctx.dylib_path);
+        apply_ld_dyld_env(
+            &mut cmd,
+            lctx.rust_build_meta.dylib_path_envvar(),
+            lctx.dylib_path,
+        );
 
         let double_spawn = lctx.double_spawn.spawn_context();
 
@@ -24,6 +24,14 @@ impl SlowTimeout {
         terminate_after: None,
         grace_period: Duration::from_secs(10),
     };
+
+    /// Returns the time after which a test is terminated, if `terminate-after` is set.
+    pub(crate) fn timeout(&self) -> Option<Duration> {
+        self.terminate_after.map(|terminate_after| {
+            self.period
+                .saturating_mul(u32::try_from(terminate_after.get()).unwrap_or(u32::MAX))
+        })
+    }
 }
 
 fn default_grace_period() -> Duration {
@@ -227,4 +235,15 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_timeout() {
+        let slow_timeout = SlowTimeout {
+            period: Duration::from_secs(30),
+            terminate_after: NonZeroUsize::new(4),
+            grace_period: Duration::from_secs(10),
+        };
+        assert_eq!(slow_timeout.timeout(), Some(Duration::from_secs(120)));
+        assert_eq!(SlowTimeout::VERY_LARGE.timeout(), None);
+    }
 }
@@ -2,10 +2,10 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use super::{ConfigIdentifier, TestThreads};
-use crate::errors::InvalidCustomTestGroupName;
+use crate::{errors::InvalidCustomTestGroupName, test_command::is_sip_sanitized};
 use serde::Deserialize;
 use smol_str::SmolStr;
-use std::{fmt, str::FromStr};
+use std::{collections::BTreeMap, ffi::OsStr, fmt, process::Command, str::FromStr};
 
 /// Represents the test group a test is in.
 #[derive(Clone, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
@@ -116,7 +116,112 @@ impl fmt::Display for CustomTestGroup {
 #[serde(rename_all = "kebab-case")]
 pub struct TestGroupConfig {
     /// The maximum number of threads allowed for this test group.
-    pub max_threads: TestThreads,
+    ///
+    /// If not specified, tests in this group are only limited by the global number of test
+    /// threads.
+    #[serde(default)]
+    pub max_threads: Option<TestThreads>,
+
+    /// Environment variables to set for tests in this group.
+    ///
+    /// These take precedence over variables set through Cargo's `[env]` table and by setup
+    /// scripts.
+    #[serde(default)]
+    pub env: BTreeMap<String, TestGroupEnvValue>,
+}
+
+impl TestGroupConfig {
+    /// Returns the maximum number of threads for this group, given the global number of test
+    /// threads.
+    pub fn compute_max_threads(&self, test_threads: usize) -> usize {
+        self.max_threads
+            .map_or(test_threads, |max_threads| max_threads.compute())
+    }
+
+    /// Applies this group's environment variables to `command`.
+    ///
+    /// Variables that are set in the environment nextest is run in are left alone unless `force`
+    /// is set. The dynamic library path variable is special: configured paths are prepended to the
+    /// paths computed by nextest rather than replacing them.
+    pub(crate) fn apply_env(&self, command: &mut Command, dylib_path_envvar: &str) {
+        self.apply_env_impl(command, dylib_path_envvar, |name| {
+            std::env::var_os(name).is_some()
+        })
+    }
+
+    fn apply_env_impl(
+        &self,
+        command: &mut Command,
+        dylib_path_envvar: &str,
+        is_inherited: impl Fn(&str) -> bool,
+    ) {
+        for (name, value) in &self.env {
+            if name == dylib_path_envvar {
+                let current = command
+                    .get_envs()
+                    .find(|(key, _)| *key == OsStr::new(name))
+                    .and_then(|(_, value)| value.map(|value| value.to_owned()));
+                let paths = std::env::split_paths(value.value())
+                    .chain(current.iter().flat_map(std::env::split_paths));
+                match std::env::join_paths(paths) {
+                    Ok(joined) => {
+                        // Double-spawn restores SIP-sanitized variables from their NEXTEST_
+                        // copies, so keep the copy in sync (see apply_ld_dyld_env).
+                        if is_sip_sanitized(name) {
+                            command.env(format!("NEXTEST_{name}"), &joined);
+                        }
+                        command.env(name, joined);
+                    }
+                    Err(error) => {
+                        log::warn!("not prepending test group paths to {name}: {error}");
+                    }
+                }
+                continue;
+            }
+
+            if !value.force() && is_inherited(name) {
+                continue;
+            }
+            command.env(name, value.value());
+        }
+    }
+}
+
+/// The value of an environment variable set for a test group.
+///
+/// This is either a plain string, or a table of the form `{ value = "...", force = true }`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(untagged)]
+pub enum TestGroupEnvValue {
+    /// A plain value.
+    Value(String),
+
+    /// A value along with options.
+    WithOptions {
+        /// The value of the environment variable.
+        value: String,
+
+        /// Whether to override the variable if it's set in the environment nextest is run in.
+        #[serde(default)]
+        force: bool,
+    },
+}
+
+impl TestGroupEnvValue {
+    /// Returns the value of the environment variable.
+    pub fn value(&self) -> &str {
+        match self {
+            Self::Value(value) | Self::WithOptions { value, .. } => value,
+        }
+    }
+
+    /// Returns true if the variable overrides one set in the environment nextest is run in.
+    pub fn force(&self) -> bool {
+        match self {
+            Self::Value(_) => false,
+            Self::WithOptions { force, .. } => *force,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -128,8 +233,10 @@ mod tests {
     };
     use camino::Utf8Path;
     use camino_tempfile::tempdir;
+    use guppy::graph::cargo::BuildPlatform;
     use indoc::indoc;
-    use maplit::btreeset;
+    use maplit::{btreemap, btreeset};
+    use nextest_filtering::TestQuery;
     use std::collections::BTreeSet;
     use test_case::test_case;
 
@@ -264,6 +371,12 @@ mod tests {
         "#},
         Ok(btreeset! {custom_test_group("my-group")})
         ; "group name valid")]
+    #[test_case(
+        indoc!{r#"
+            [test-groups."my-group"]
+        "#},
+        Ok(btreeset! {custom_test_group("my-group")})
+        ; "group without max-threads")]
     #[test_case(
         indoc!{r#"
             [test-groups."@tool:"]
@@ -471,4 +584,105 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_compute_max_threads() {
+        let limited = TestGroupConfig {
+            max_threads: Some(TestThreads::Count(2)),
+            env: BTreeMap::new(),
+        };
+        assert_eq!(limited.compute_max_threads(16), 2);
+        let unlimited = TestGroupConfig {
+            max_threads: None,
+            env: BTreeMap::new(),
+        };
+        assert_eq!(unlimited.compute_max_threads(16), 16);
+    }
+
+    #[test]
+    fn test_group_env() {
+        let config_contents = indoc! {r#"
+            [[profile.default.overrides]]
+            filter = 'test(gpu)'
+            test-group = "gpu"
+
+            [test-groups.gpu]
+            max-threads = 1
+
+            [test-groups.gpu.env]
+            CUDA_VISIBLE_DEVICES = "1"
+            INHERITED_VAR = "not-applied"
+            FORCED_VAR = { value = "applied", force = true }
+            LD_LIBRARY_PATH = "/opt/cuda/lib"
+        "#};
+        let workspace_dir = tempdir().unwrap();
+        let graph = temp_workspace(workspace_dir.path(), config_contents);
+        let package_id = graph.workspace().iter().next().unwrap().id();
+
+        let config = NextestConfig::from_sources(
+            graph.workspace().root(),
+            &graph,
+            None,
+            &[][..],
+            &Default::default(),
+        )
+        .expect("config is valid");
+        let profile = config
+            .profile("default")
+            .expect("default profile is known")
+            .apply_build_platforms(&build_platforms());
+        let binary_query =
+            binary_query(&graph, package_id, "lib", "my-binary", BuildPlatform::Host);
+
+        // This mirrors how the runner applies test group environment variables.
+        let env_for = |test_name: &str| {
+            let settings = profile.settings_for(&TestQuery {
+                binary_query: binary_query.to_query(),
+                test_name,
+            });
+            let mut command = Command::new("my-binary");
+            command.env("LD_LIBRARY_PATH", "/target/debug/deps");
+            // apply_ld_dyld_env sets this copy, which double-spawn restores on macOS.
+            command.env("NEXTEST_LD_LIBRARY_PATH", "/target/debug/deps");
+            if let TestGroup::Custom(group) = settings.test_group() {
+                profile.test_group_config()[group].apply_env_impl(
+                    &mut command,
+                    "LD_LIBRARY_PATH",
+                    |name| matches!(name, "INHERITED_VAR" | "FORCED_VAR"),
+                );
+            }
+            command
+                .get_envs()
+                .map(|(key, value)| {
+                    (
+                        key.to_str().unwrap().to_owned(),
+                        value.map(|value| value.to_str().unwrap().to_owned()),
+                    )
+                })
+                .collect::<BTreeMap<_, _>>()
+        };
+
+        let expected_dylib_path = std::env::join_paths(["/opt/cuda/lib", "/target/debug/deps"])
+            .unwrap()
+            .into_string()
+            .unwrap();
+        assert_eq!(
+            env_for("gpu_test"),
+            btreemap! {
+                "CUDA_VISIBLE_DEVICES".to_owned() => Some("1".to_owned()),
+                "FORCED_VAR".to_owned() => Some("applied".to_owned()),
+                "LD_LIBRARY_PATH".to_owned() => Some(expected_dylib_path.clone()),
+                "NEXTEST_LD_LIBRARY_PATH".to_owned() => Some(expected_dylib_path),
+            },
+            "grouped test sees the group's variables",
+        );
+        assert_eq!(
+            env_for("cpu_test"),
+            btreemap! {
+                "LD_LIBRARY_PATH".to_owned() => Some("/target/debug/deps".to_owned()),
+                "NEXTEST_LD_LIBRARY_PATH".to_owned() => Some("/target/debug/deps".to_owned()),
+            },
+            "ungrouped test doesn't see the group's variables",
+        );
+    }
 }
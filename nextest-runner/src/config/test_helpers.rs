@@ -14,7 +14,7 @@ use guppy::{
 use nextest_filtering::BinaryQuery;
 use nextest_metadata::{RustBinaryId, RustTestBinaryKind};
 use serde::Deserialize;
-use std::{io::Write, path::PathBuf, process::Command};
+use std::{collections::BTreeMap, io::Write, path::PathBuf, process::Command};
 use target_spec::{Platform, TargetFeatures};
 
 pub(super) fn temp_workspace(temp_dir: &Utf8Path, config_contents: &str) -> PackageGraph {
@@ -100,6 +100,8 @@ pub(super) fn build_platforms() -> BuildPlatforms {
             libdir: PlatformLibdir::Available(
                 Utf8PathBuf::from("/home/fake/.rustup/toolchains/stable-x86_64-unknown-linux-gnu/lib/rustlib/aarch64-apple-darwin/lib")
             ),
+            emulator_env: BTreeMap::new(),
+            sysroot: None,
         }),
     }
 }
@@ -312,6 +312,69 @@ pub struct UnknownConfigScriptError {
     pub name: ScriptId,
 }
 
+/// An error that occurred while resolving the `extra-dylib-paths` setting of a profile.
+#[derive(Clone, Debug, Error)]
+#[error("failed to resolve `{path}` in `profile.{profile_name}.extra-dylib-paths`")]
+pub struct ExtraDylibPathsError {
+    profile_name: String,
+    path: String,
+    #[source]
+    kind: ExtraDylibPathsErrorKind,
+}
+
+impl ExtraDylibPathsError {
+    pub(crate) fn new(
+        profile_name: impl Into<String>,
+        path: String,
+        kind: ExtraDylibPathsErrorKind,
+    ) -> Self {
+        Self {
+            profile_name: profile_name.into(),
+            path,
+            kind,
+        }
+    }
+
+    /// Returns the name of the profile the path was configured for.
+    pub fn profile_name(&self) -> &str {
+        &self.profile_name
+    }
+
+    /// Returns the configured path, before expansion.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Returns the kind of error this is.
+    pub fn kind(&self) -> &ExtraDylibPathsErrorKind {
+        &self.kind
+    }
+}
+
+/// The kind of [`ExtraDylibPathsError`].
+#[derive(Clone, Debug, Error, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ExtraDylibPathsErrorKind {
+    /// A variable isn't known to nextest and isn't set in the environment.
+    #[error("variable `{name}` is not known to nextest and is not set in the environment")]
+    UnknownVariable {
+        /// The name of the variable.
+        name: String,
+    },
+
+    /// A `${` wasn't followed by a closing `}`.
+    #[error("unterminated `${{`")]
+    UnterminatedVariable,
+
+    /// A `${}` was found.
+    #[error("empty variable name in `${{}}`")]
+    EmptyVariable,
+
+    /// A `$` wasn't followed by `{` or `$`.
+    #[error("`$` must be followed by `{{NAME}}`, or escaped as `$$`")]
+    BareDollar,
+}
+
 /// An error which indicates that a profile was requested but not known to nextest.
 #[derive(Clone, Debug, Error)]
 #[error("profile `{profile} not found (known profiles: {})`", .all_profiles.join(", "))]
@@ -455,6 +518,33 @@ impl fmt::Display for PartitionerBuilderParseError {
     }
 }
 
+/// An error that occurred while reading a timing report for timing-based partitioning.
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum PartitionTimingsReadError {
+    /// An I/O error occurred while reading the timing report.
+    #[error("I/O error reading timing report `{path}`")]
+    Read {
+        /// The path that was being read.
+        path: Utf8PathBuf,
+
+        /// The error that occurred.
+        #[source]
+        error: std::io::Error,
+    },
+
+    /// A JSON deserialization error occurred while reading the timing report.
+    #[error("error deserializing timing report `{path}`")]
+    Deserialize {
+        /// The path that was being read.
+        path: Utf8PathBuf,
+
+        /// The error that occurred.
+        #[source]
+        error: serde_json::Error,
+    },
+}
+
 /// An error that occurs while operating on a
 /// [`TestFilterBuilder`](crate::test_filter::TestFilterBuilder).
 #[derive(Clone, Debug, Error)]
@@ -570,6 +660,35 @@ pub enum RustBuildMetaParseError {
         /// The detailed error message.
         message: String,
     },
+
+    /// A path that must be within the target directory is absolute or contains `..`.
+    #[error(
+        "in `{field}`, path `{path}` must be relative to the target directory \
+         and must not contain `..`"
+    )]
+    PathOutsideTargetDir {
+        /// The summary field the path was found in.
+        field: &'static str,
+
+        /// The path.
+        path: Utf8PathBuf,
+    },
+
+    /// A supplied platform doesn't match the platform recorded in the build metadata.
+    #[error(
+        "supplied {kind} platform `{supplied}` doesn't match \
+         the {kind} platform in the build metadata (`{recorded}`)"
+    )]
+    PlatformMismatch {
+        /// The kind of platform: "host" or "target".
+        kind: &'static str,
+
+        /// The triple recorded in the build metadata, or "(none)".
+        recorded: String,
+
+        /// The supplied triple, or "(none)".
+        supplied: String,
+    },
 }
 
 /// An error that occurs in [`BinaryList::from_messages`](crate::list::BinaryList::from_messages) or
@@ -612,6 +731,16 @@ pub enum CreateTestListError {
         cwd: Utf8PathBuf,
     },
 
+    /// The target directory doesn't exist.
+    #[error(
+        "target directory `{target_directory}` does not exist\n\
+         (hint: did you forget to build?)"
+    )]
+    TargetDirectoryMissing {
+        /// The target directory that wasn't found.
+        target_directory: Utf8PathBuf,
+    },
+
     /// Running a command to gather the list of tests failed to execute.
     #[error(
         "for `{binary_id}`, running command `{}` failed to execute",
@@ -873,6 +1002,32 @@ fn kind_str(is_dir: Option<bool>) -> &'static str {
     }
 }
 
+/// An error occurred while loading a [`MetadataPolicy`](crate::list::MetadataPolicy).
+#[derive(Debug, Error)]
+pub enum MetadataPolicyLoadError {
+    /// An I/O error occurred while reading the policy file.
+    #[error("I/O error reading policy file `{path}`")]
+    Read {
+        /// The path that was being read.
+        path: Utf8PathBuf,
+
+        /// The error that occurred.
+        #[source]
+        error: std::io::Error,
+    },
+
+    /// A TOML deserialization error occurred while reading the policy file.
+    #[error("error deserializing policy file `{path}` as TOML")]
+    Deserialize {
+        /// The path that was being read.
+        path: Utf8PathBuf,
+
+        /// The error that occurred.
+        #[source]
+        error: toml::de::Error,
+    },
+}
+
 /// An error occurred while materializing a metadata path.
 #[derive(Debug, Error)]
 pub enum MetadataMaterializeError {
@@ -919,6 +1074,80 @@ pub enum MetadataMaterializeError {
         #[source]
         error: guppy::Error,
     },
+
+    /// Reading the metadata file was cancelled, for example because the Tokio runtime was shut
+    /// down.
+    #[error("reading metadata file `{path}` was cancelled")]
+    Cancelled {
+        /// The path that was being read.
+        path: Utf8PathBuf,
+
+        /// The error that occurred.
+        #[source]
+        error: tokio::task::JoinError,
+    },
+}
+
+/// An error occurred while merging Rust build metadata files.
+///
+/// Returned by [`RustBuildMeta::merge_from_files`](crate::list::RustBuildMeta::merge_from_files).
+#[derive(Debug, Error)]
+pub enum MetadataMergeError {
+    /// No metadata files were provided.
+    #[error("no metadata files provided to merge")]
+    NoInputs,
+
+    /// An error occurred while reading a metadata file.
+    #[error(transparent)]
+    Materialize(#[from] MetadataMaterializeError),
+
+    /// A metadata file conflicts with the files merged before it.
+    #[error("metadata file `{path}` conflicts with previously merged files")]
+    Conflict {
+        /// The path of the file that caused the conflict.
+        path: Utf8PathBuf,
+
+        /// The conflict.
+        #[source]
+        conflict: MetadataMergeConflict,
+    },
+}
+
+/// A conflict between two sets of Rust build metadata being merged.
+#[derive(Clone, Debug, Error, Eq, PartialEq)]
+pub enum MetadataMergeConflict {
+    /// The target directories are different.
+    #[error("target directory `{actual}` differs from `{expected}`")]
+    TargetDirectory {
+        /// The target directory merged so far.
+        expected: Utf8PathBuf,
+
+        /// The target directory in the new metadata.
+        actual: Utf8PathBuf,
+    },
+
+    /// The build platforms are different.
+    ///
+    /// Only one target platform is currently supported, so metadata for different targets can't
+    /// be merged.
+    #[error("build platforms differ")]
+    BuildPlatforms,
+
+    /// A package has different build script output directories.
+    #[error(
+        "build script output directory for `{package_id}` is `{actual}`, \
+         but was previously `{expected}`"
+    )]
+    BuildScriptOutDir {
+        /// The package ID.
+        package_id: String,
+
+        /// The output directory merged so far.
+        expected: Utf8PathBuf,
+
+        /// The output directory in the new metadata.
+        actual: Utf8PathBuf,
+    },
 }
 
 /// An error occurred while reading a file.
@@ -1212,6 +1441,95 @@ pub struct UnknownHostPlatform {
     pub(crate) error: target_spec::Error,
 }
 
+/// An error occurred while reading or writing a build platforms cache file.
+///
+/// Returned by `BuildPlatforms::save_cache` and `BuildPlatforms::load_cache`.
+#[derive(Debug, Error)]
+pub enum BuildPlatformsCacheError {
+    /// An error occurred while reading the cache file.
+    #[error("error reading build platforms cache `{path}`")]
+    Read {
+        /// The path to the cache file.
+        path: Utf8PathBuf,
+
+        /// The error that occurred.
+        #[source]
+        error: std::io::Error,
+    },
+
+    /// An error occurred while writing the cache file.
+    #[error("error writing build platforms cache `{path}`")]
+    Write {
+        /// The path to the cache file.
+        path: Utf8PathBuf,
+
+        /// The error that occurred.
+        #[source]
+        error: std::io::Error,
+    },
+}
+
+/// An error that occurred while reading or writing the test list cache.
+///
+/// Returned by `ListCache::load` and `ListCache::save`.
+#[derive(Debug, Error)]
+pub enum ListCacheError {
+    /// An error occurred while reading the cache file.
+    #[error("error reading test list cache `{path}`")]
+    Read {
+        /// The path to the cache file.
+        path: Utf8PathBuf,
+
+        /// The error that occurred.
+        #[source]
+        error: std::io::Error,
+    },
+
+    /// An error occurred while writing the cache file.
+    #[error("error writing test list cache `{path}`")]
+    Write {
+        /// The path to the cache file.
+        path: Utf8PathBuf,
+
+        /// The error that occurred.
+        #[source]
+        error: std::io::Error,
+    },
+}
+
+/// An error that occurred while writing a `.env` file.
+///
+/// Returned by `RustBuildMeta::write_dotenv`.
+#[derive(Debug, Error)]
+#[error("error writing .env file `{path}`")]
+pub struct WriteDotenvError {
+    /// The path to the `.env` file.
+    pub path: Utf8PathBuf,
+
+    /// The error that occurred.
+    #[source]
+    pub error: std::io::Error,
+}
+
+/// Nextest doesn't know which environment variable to use for dynamic library paths on a platform.
+///
+/// Returned by `BuildPlatforms::check_dylib_path_envvar`.
+#[derive(Clone, Debug, Error)]
+#[error(
+    "{kind} platform `{triple}` has OS `{os}`, for which nextest has no \
+     dynamic library path environment variable mapping"
+)]
+pub struct UnsupportedDylibPlatform {
+    /// Whether this is the host or target platform.
+    pub kind: &'static str,
+
+    /// The triple of the platform.
+    pub triple: String,
+
+    /// The operating system component of the triple.
+    pub os: String,
+}
+
 /// An error occurred while determining the cross-compiling target triple.
 #[derive(Debug, Error)]
 pub enum TargetTripleError {
@@ -2,9 +2,9 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use crate::{list::Styles, runner::AbortStatus, write_str::WriteStr};
-use camino::{Utf8Path, Utf8PathBuf};
+use camino::{Utf8Component, Utf8Path, Utf8PathBuf};
 use owo_colors::OwoColorize;
-use std::{fmt, io, path::PathBuf, process::ExitStatus, time::Duration};
+use std::{ffi::OsStr, fmt, io, path::PathBuf, process::ExitStatus, time::Duration};
 
 pub(crate) mod plural {
     pub(crate) fn were_plural_if(plural: bool) -> &'static str {
@@ -168,13 +168,14 @@ pub(crate) fn dylib_path_envvar() -> &'static str {
     }
 }
 
-/// Returns a list of directories that are searched for dynamic libraries.
+/// Returns a list of directories that are searched for dynamic libraries, given the value of the
+/// dynamic library path environment variable, if it's set.
 ///
 /// Note that some operating systems will have defaults if this is empty that
 /// will need to be dealt with.
-pub(crate) fn dylib_path() -> Vec<PathBuf> {
-    match std::env::var_os(dylib_path_envvar()) {
-        Some(var) => std::env::split_paths(&var).collect(),
+pub(crate) fn dylib_path(value: Option<&OsStr>) -> Vec<PathBuf> {
+    match value {
+        Some(value) => std::env::split_paths(value).collect(),
         None => Vec::new(),
     }
 }
@@ -220,6 +221,31 @@ pub(crate) fn rel_path_join(rel_path: &Utf8Path, path: &Utf8Path) -> Utf8PathBuf
     format!("{}/{}", rel_path, path).into()
 }
 
+/// Lexically normalizes a path, resolving `.` and `..` components without accessing the
+/// filesystem.
+///
+/// `..` components at the start of a relative path are preserved, and `..` components that would
+/// go above the root of an absolute path are dropped.
+pub(crate) fn normalize_path_lexically(path: &Utf8Path) -> Utf8PathBuf {
+    let mut out = Utf8PathBuf::new();
+    for component in path.components() {
+        match component {
+            Utf8Component::CurDir => {}
+            Utf8Component::ParentDir => {
+                let last_is_normal =
+                    matches!(out.components().next_back(), Some(Utf8Component::Normal(_)));
+                if last_is_normal {
+                    out.pop();
+                } else if !out.has_root() {
+                    out.push("..");
+                }
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
 #[derive(Debug)]
 pub(crate) struct FormattedDuration(pub(crate) Duration);
 
@@ -9,14 +9,17 @@ use crate::{
     write_str::WriteStr,
 };
 use camino::{Utf8Path, Utf8PathBuf};
-use cargo_metadata::{Artifact, BuildScript, Message, PackageId};
-use guppy::graph::PackageGraph;
+use cargo_metadata::{Artifact, ArtifactDebuginfo, BuildScript, Message, PackageId};
+use guppy::graph::{DependencyDirection, PackageGraph};
 use nextest_metadata::{
-    BinaryListSummary, BuildPlatform, RustBinaryId, RustNonTestBinaryKind,
+    BaseOutputDirCategory, BinaryListSummary, BuildPlatform, RustBinaryId, RustNonTestBinaryKind,
     RustNonTestBinarySummary, RustTestBinaryKind, RustTestBinarySummary,
 };
 use owo_colors::OwoColorize;
-use std::{collections::HashSet, io};
+use std::{
+    collections::{BTreeSet, HashSet},
+    io,
+};
 
 /// A Rust test binary built by Cargo.
 #[derive(Clone, Debug)]
@@ -83,6 +86,162 @@ impl BinaryList {
         })
     }
 
+    /// Returns a copy of this list restricted to the test binaries at the given paths.
+    ///
+    /// The build metadata is trimmed to what the selected binaries need:
+    ///
+    /// * base output directories containing the selected binaries;
+    /// * linked paths requested by the packages of the selected binaries, or by their transitive
+    ///   dependencies (linked paths without any recorded provenance are always kept);
+    /// * build script output directories for the packages of the selected binaries;
+    /// * non-test binaries for the packages of the selected binaries and their dependencies;
+    /// * native libraries linked by the packages of the selected binaries and their dependencies.
+    ///
+    /// Paths that don't correspond to a test binary in this list are ignored with a warning.
+    pub fn restrict_to_binaries(
+        &self,
+        paths: &BTreeSet<Utf8PathBuf>,
+        graph: &PackageGraph,
+    ) -> Self {
+        let rust_binaries: Vec<_> = self
+            .rust_binaries
+            .iter()
+            .filter(|bin| paths.contains(&bin.path))
+            .cloned()
+            .collect();
+        for path in paths {
+            if !rust_binaries.iter().any(|bin| &bin.path == path) {
+                log::warn!(
+                    target: "nextest-runner::list",
+                    "binary `{path}` not found in the list of test binaries, ignoring"
+                );
+            }
+        }
+
+        let package_ids: HashSet<&str> = rust_binaries
+            .iter()
+            .map(|bin| bin.package_id.as_str())
+            .collect();
+        let guppy_ids: Vec<_> = package_ids
+            .iter()
+            .map(|id| guppy::PackageId::new(*id))
+            .collect();
+        // None means that all packages are considered relevant.
+        let relevant_ids: Option<HashSet<String>> = match graph.query_forward(&guppy_ids) {
+            Ok(query) => Some(
+                query
+                    .resolve()
+                    .package_ids(DependencyDirection::Forward)
+                    .map(|id| id.repr().to_owned())
+                    .collect(),
+            ),
+            Err(error) => {
+                log::warn!(
+                    target: "nextest-runner::list",
+                    "error querying dependencies of selected binaries, keeping all linked paths: \
+                     {error}"
+                );
+                None
+            }
+        };
+        let is_relevant = |package_id: &str| match &relevant_ids {
+            Some(ids) => ids.contains(package_id),
+            None => true,
+        };
+
+        let mut rust_build_meta = self.rust_build_meta.clone();
+        let rel_binary_paths: Vec<_> = rust_binaries
+            .iter()
+            .filter_map(|bin| {
+                bin.path
+                    .strip_prefix(&rust_build_meta.target_directory)
+                    .ok()
+            })
+            .collect();
+        rust_build_meta.base_output_directories.retain(|base| {
+            rel_binary_paths
+                .iter()
+                .any(|rel_path| rel_path.starts_with(base))
+        });
+        let base_output_directories = &rust_build_meta.base_output_directories;
+        rust_build_meta
+            .base_output_directory_categories
+            .retain(|base, _| base_output_directories.contains(base));
+        rust_build_meta
+            .extra_output_roots
+            .retain(|root| rust_binaries.iter().any(|bin| bin.path.starts_with(root)));
+        rust_build_meta.linked_paths.retain(|_, requested_by| {
+            requested_by.is_empty() || requested_by.iter().any(|id| is_relevant(id))
+        });
+        rust_build_meta
+            .absolute_linked_paths
+            .retain(|_, requested_by| {
+                requested_by.is_empty() || requested_by.iter().any(|id| is_relevant(id))
+            });
+        rust_build_meta
+            .build_script_out_dirs
+            .retain(|package_id, _| package_ids.contains(package_id.as_str()));
+        rust_build_meta
+            .non_test_binaries
+            .retain(|package_id, _| is_relevant(package_id));
+        rust_build_meta
+            .enabled_features
+            .retain(|package_id, _| package_ids.contains(package_id.as_str()));
+        rust_build_meta.linked_libs.retain(|_, linked_by| {
+            linked_by.retain(|id| is_relevant(id));
+            !linked_by.is_empty()
+        });
+
+        let search_dirs: HashSet<_> = rust_build_meta.relative_search_dirs().collect();
+        if let Some(resolved_search_dirs) = &mut rust_build_meta.resolved_search_dirs {
+            resolved_search_dirs.retain(|dir| search_dirs.contains(dir));
+        }
+
+        Self {
+            rust_build_meta,
+            rust_binaries,
+        }
+    }
+
+    /// Returns the IDs of test binaries whose package, or any of its transitive dependencies, links
+    /// the native library `lib_name`.
+    ///
+    /// `lib_name` is matched against the name passed to `-l`, without the kind or modifiers (e.g.
+    /// `ssl` for `cargo::rustc-link-lib=dylib=ssl`). Only libraries linked by build scripts are
+    /// known.
+    pub fn tests_using_lib(&self, lib_name: &str, graph: &PackageGraph) -> Vec<RustBinaryId> {
+        let Some(linked_by) = self.rust_build_meta.linked_libs.get(lib_name) else {
+            return Vec::new();
+        };
+
+        let guppy_ids: Vec<_> = linked_by
+            .iter()
+            .map(|id| guppy::PackageId::new(id.as_str()))
+            .collect();
+        // Packages that depend on any of the packages that link the library.
+        let dependents: HashSet<String> = match graph.query_reverse(&guppy_ids) {
+            Ok(query) => query
+                .resolve()
+                .package_ids(DependencyDirection::Reverse)
+                .map(|id| id.repr().to_owned())
+                .collect(),
+            Err(error) => {
+                log::warn!(
+                    target: "nextest-runner::list",
+                    "error querying dependents of packages linking `{lib_name}`, \
+                     only matching those packages directly: {error}"
+                );
+                linked_by.iter().cloned().collect()
+            }
+        };
+
+        self.rust_binaries
+            .iter()
+            .filter(|bin| dependents.contains(&bin.package_id))
+            .map(|bin| bin.id.clone())
+            .collect()
+    }
+
     /// Outputs this list to the given writer.
     pub fn write(
         &self,
@@ -135,6 +294,12 @@ impl BinaryList {
             if verbose {
                 writeln!(writer, "{}:", bin.id.style(styles.binary_id))?;
                 writeln!(writer, "  {} {}", "bin:".style(styles.field), bin.path)?;
+                writeln!(
+                    writer,
+                    "  {} {}",
+                    "package id:".style(styles.field),
+                    bin.package_id,
+                )?;
                 writeln!(
                     writer,
                     "  {} {}",
@@ -145,6 +310,23 @@ impl BinaryList {
                 writeln!(writer, "{}", bin.id.style(styles.binary_id))?;
             }
         }
+
+        // Non-test binaries are only of interest when inspecting build output in detail.
+        if verbose && !self.rust_build_meta.non_test_binaries.is_empty() {
+            writeln!(writer, "{}", "non-test binaries:".style(styles.field))?;
+            for (package_id, binaries) in &self.rust_build_meta.non_test_binaries {
+                writeln!(writer, "  {package_id}:")?;
+                for binary in binaries {
+                    writeln!(
+                        writer,
+                        "    {} ({}): {}",
+                        binary.name.style(styles.binary_id),
+                        binary.kind,
+                        self.rust_build_meta.target_directory.join(&binary.path),
+                    )?;
+                }
+            }
+        }
         Ok(())
     }
 
@@ -195,7 +377,17 @@ impl<'g> BinaryListBuildState<'g> {
             self.detect_base_output_dir(&path);
 
             if artifact.profile.test {
+                if self.rust_build_meta.debuginfo.is_none() {
+                    self.rust_build_meta.debuginfo =
+                        Some(debuginfo_name(&artifact.profile.debuginfo));
+                }
+
                 let package_id = artifact.package_id.repr;
+                self.rust_build_meta
+                    .enabled_features
+                    .entry(package_id.clone())
+                    .or_default()
+                    .extend(artifact.features);
 
                 // Look up the executable by package ID.
 
@@ -292,22 +484,44 @@ impl<'g> BinaryListBuildState<'g> {
     /// to base output dirs.
     ///
     /// Note that test binaries are always present in "deps", so we should always
-    /// have a match.
+    /// have a match. Such directories are categorized as
+    /// [`BaseOutputDirCategory::TESTS`].
+    ///
+    /// Paths with "examples" in their second-to-last component are examples, and the "examples"
+    /// directory itself is recorded as a base output directory, categorized as
+    /// [`BaseOutputDirCategory::EXAMPLES`].
+    ///
+    /// Artifacts outside the target directory (e.g. with `--artifact-dir`) have their output root
+    /// recorded as an absolute extra output root instead: the parent of "deps" if present,
+    /// otherwise the directory containing the artifact.
     ///
     /// The `Option` in the return value is to let ? work.
     fn detect_base_output_dir(&mut self, artifact_path: &Utf8Path) -> Option<()> {
-        // Artifact paths must be relative to the target directory.
-        let rel_path = artifact_path
-            .strip_prefix(&self.rust_build_meta.target_directory)
-            .ok()?;
+        let Ok(rel_path) = artifact_path.strip_prefix(&self.rust_build_meta.target_directory)
+        else {
+            let parent = artifact_path.parent()?;
+            let root = if parent.file_name() == Some("deps") {
+                parent.parent()?
+            } else {
+                parent
+            };
+            self.rust_build_meta
+                .extra_output_roots
+                .insert(root.to_path_buf());
+            return Some(());
+        };
         let parent = rel_path.parent()?;
-        if parent.file_name() == Some("deps") {
-            let base = parent.parent()?;
-            if !self.rust_build_meta.base_output_directories.contains(base) {
-                self.rust_build_meta
-                    .base_output_directories
-                    .insert(convert_rel_path_to_forward_slash(base));
-            }
+        let (base, category) = match parent.file_name() {
+            Some("deps") => (parent.parent()?, BaseOutputDirCategory::TESTS),
+            Some("examples") => (parent, BaseOutputDirCategory::EXAMPLES),
+            _ => return Some(()),
+        };
+        if !self.rust_build_meta.base_output_directories.contains(base) {
+            let base = convert_rel_path_to_forward_slash(base);
+            self.rust_build_meta
+                .base_output_directory_categories
+                .insert(base.clone(), category);
+            self.rust_build_meta.base_output_directories.insert(base);
         }
         Some(())
     }
@@ -316,6 +530,13 @@ impl<'g> BinaryListBuildState<'g> {
         for path in build_script.linked_paths {
             self.detect_linked_path(&build_script.package_id, &path);
         }
+        for lib in &build_script.linked_libs {
+            self.rust_build_meta
+                .linked_libs
+                .entry(linked_lib_name(lib.as_str()).to_owned())
+                .or_default()
+                .insert(build_script.package_id.repr.clone());
+        }
 
         // We only care about build scripts for workspace packages.
         let package_id = guppy::PackageId::new(build_script.package_id.repr);
@@ -347,24 +568,34 @@ impl<'g> BinaryListBuildState<'g> {
         Ok(())
     }
 
-    /// The `Option` in the return value is to let ? work.
-    fn detect_linked_path(&mut self, package_id: &PackageId, path: &Utf8Path) -> Option<()> {
+    fn detect_linked_path(&mut self, package_id: &PackageId, path: &Utf8Path) {
         // Remove anything up to the first "=" (e.g. "native=").
         let actual_path = match path.as_str().split_once('=') {
             Some((_, p)) => p.into(),
             None => path,
         };
-        let rel_path = actual_path
-            .strip_prefix(&self.rust_build_meta.target_directory)
-            .ok()?;
-
-        self.rust_build_meta
-            .linked_paths
-            .entry(convert_rel_path_to_forward_slash(rel_path))
-            .or_default()
-            .insert(package_id.repr.clone());
-
-        Some(())
+        match actual_path.strip_prefix(&self.rust_build_meta.target_directory) {
+            Ok(rel_path) => {
+                self.rust_build_meta
+                    .linked_paths
+                    .entry(convert_rel_path_to_forward_slash(rel_path))
+                    .or_default()
+                    .insert(package_id.repr.clone());
+            }
+            Err(_) => {
+                // Linked paths outside the target directory (e.g. native libraries installed
+                // elsewhere) are stored as absolute paths. Relative paths can't be resolved
+                // reliably, so skip them.
+                if actual_path.is_relative() {
+                    return;
+                }
+                self.rust_build_meta
+                    .absolute_linked_paths
+                    .entry(actual_path.to_path_buf())
+                    .or_default()
+                    .insert(package_id.repr.clone());
+            }
+        }
     }
 
     fn finish(mut self) -> BinaryList {
@@ -388,6 +619,34 @@ impl<'g> BinaryListBuildState<'g> {
     }
 }
 
+/// Returns the library name from a `-l` argument, stripping the kind, modifiers, and any rename.
+///
+/// For example, `static:+whole-archive=foo:bar` returns `foo`.
+fn linked_lib_name(lib: &str) -> &str {
+    let name = match lib.split_once('=') {
+        Some((_, name)) => name,
+        None => lib,
+    };
+    match name.split_once(':') {
+        Some((name, _)) => name,
+        None => name,
+    }
+}
+
+/// Returns the name Cargo uses for a debug info level in profiles.
+fn debuginfo_name(debuginfo: &ArtifactDebuginfo) -> String {
+    match debuginfo {
+        ArtifactDebuginfo::None => "none".to_owned(),
+        ArtifactDebuginfo::LineDirectivesOnly => "line-directives-only".to_owned(),
+        ArtifactDebuginfo::LineTablesOnly => "line-tables-only".to_owned(),
+        ArtifactDebuginfo::Limited => "limited".to_owned(),
+        ArtifactDebuginfo::Full => "full".to_owned(),
+        ArtifactDebuginfo::UnknownInt(level) => level.to_string(),
+        ArtifactDebuginfo::UnknownString(level) => level.clone(),
+        _ => "unknown".to_owned(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -400,6 +659,7 @@ mod tests {
     use maplit::btreeset;
     use nextest_metadata::PlatformLibdirUnavailable;
     use pretty_assertions::assert_eq;
+    use std::collections::BTreeMap;
     use target_spec::{Platform, TargetFeatures};
 
     #[test]
@@ -438,6 +698,8 @@ mod tests {
                 triple: fake_triple,
                 // Test out the error case for unavailable libdirs.
                 libdir: PlatformLibdir::Unavailable(PlatformLibdirUnavailable::RUSTC_OUTPUT_ERROR),
+                emulator_env: BTreeMap::new(),
+                sysroot: None,
             }),
         };
 
@@ -479,10 +741,17 @@ mod tests {
         static EXPECTED_HUMAN_VERBOSE: &str = indoc! {r#"
         fake-package::bin/fake-binary:
           bin: /fake/binary
+          package id: fake-package 0.1.0 (path+file:///Users/fakeuser/project/fake-package)
           build platform: target
         fake-macro::proc-macro/fake-macro:
           bin: /fake/macro
+          package id: fake-macro 0.1.0 (path+file:///Users/fakeuser/project/fake-macro)
           build platform: host
+        non-test binaries:
+          my-package-id:
+            my-name (bin-exe): /fake/target/my-profile/my-name
+            your-name (dylib): /fake/target/my-profile/your-name.dll
+            your-name (dylib): /fake/target/my-profile/your-name.exp
         "#};
         static EXPECTED_JSON_PRETTY: &str = indoc! {r#"
         {
@@ -491,6 +760,7 @@ mod tests {
             "base-output-directories": [
               "my-profile"
             ],
+            "base-output-directory-categories": {},
             "non-test-binaries": {
               "my-package-id": [
                 {
@@ -512,6 +782,19 @@ mod tests {
             },
             "build-script-out-dirs": {},
             "linked-paths": [],
+            "absolute-linked-paths": [],
+            "linked-path-requested-by": {},
+            "linked-libs": {},
+            "resolved-search-dirs": null,
+            "extra-output-roots": [],
+            "label": null,
+            "host-os-version": null,
+            "rust-version": null,
+            "list-command": null,
+            "built-at": null,
+            "debuginfo": null,
+            "incremental": null,
+            "enabled-features": {},
             "platforms": {
               "host": {
                 "platform": {
@@ -532,7 +815,9 @@ mod tests {
                   "libdir": {
                     "status": "unavailable",
                     "reason": "rustc-output-error"
-                  }
+                  },
+                  "emulator-env": {},
+                  "sysroot": null
                 }
               ]
             },
@@ -583,4 +868,236 @@ mod tests {
             EXPECTED_JSON_PRETTY
         );
     }
+
+    #[test]
+    fn test_linked_lib_name() {
+        assert_eq!(linked_lib_name("foo"), "foo");
+        assert_eq!(linked_lib_name("dylib=ssl"), "ssl");
+        assert_eq!(linked_lib_name("static:+whole-archive=foo"), "foo");
+        assert_eq!(linked_lib_name("static=foo:bar"), "foo");
+        assert_eq!(
+            linked_lib_name("framework=CoreFoundation"),
+            "CoreFoundation"
+        );
+    }
+
+    #[test]
+    fn test_debuginfo_name() {
+        assert_eq!(debuginfo_name(&ArtifactDebuginfo::None), "none");
+        assert_eq!(
+            debuginfo_name(&ArtifactDebuginfo::LineTablesOnly),
+            "line-tables-only"
+        );
+        assert_eq!(debuginfo_name(&ArtifactDebuginfo::Full), "full");
+        assert_eq!(debuginfo_name(&ArtifactDebuginfo::UnknownInt(3)), "3");
+    }
+
+    static BASE_ID: &str =
+        "metadata-base 0.1.0 (path+file:///Users/fakeuser/local/testcrates/metadata/metadata-base)";
+    static HELPER_ID: &str = "metadata-helper 0.1.0 (path+file:///Users/fakeuser/local/testcrates/metadata/metadata-helper)";
+    /// A package ID that isn't in the fixture package graph.
+    static UNKNOWN_ID: &str = "unknown 0.1.0 (path+file:///fake/unknown)";
+
+    fn fixture_graph() -> PackageGraph {
+        static FIXTURE_JSON: &str = include_str!("../../../fixtures/cargo-metadata.json");
+        guppy::CargoMetadata::parse_json(FIXTURE_JSON)
+            .expect("fixture is valid JSON")
+            .build_graph()
+            .expect("fixture is valid PackageGraph")
+    }
+
+    fn binary_ids(binary_list: &BinaryList) -> Vec<&RustBinaryId> {
+        binary_list
+            .rust_binaries
+            .iter()
+            .map(|bin| &bin.id)
+            .collect()
+    }
+
+    fn fake_test_binary(name: &str, path: &str, package_id: &str) -> RustTestBinary {
+        RustTestBinary {
+            id: format!("{name}::{name}").into(),
+            path: path.into(),
+            package_id: package_id.to_owned(),
+            kind: RustTestBinaryKind::TEST,
+            name: name.to_owned(),
+            build_platform: BuildPlatform::Target,
+        }
+    }
+
+    #[test]
+    fn test_restrict_to_binaries() {
+        let base_bin = fake_test_binary("base", "/fake/target/debug/deps/base-1234", BASE_ID);
+        let unknown_bin = fake_test_binary(
+            "unknown",
+            "/fake/target/release/deps/unknown-5678",
+            UNKNOWN_ID,
+        );
+        let helper_out = Utf8PathBuf::from("debug/build/helper/out");
+        let unknown_out = Utf8PathBuf::from("release/build/unknown/out");
+        let no_provenance = Utf8PathBuf::from("debug/build/old/out");
+        let helper_bin = RustNonTestBinarySummary {
+            name: "helper".to_owned(),
+            kind: RustNonTestBinaryKind::BIN_EXE,
+            path: "debug/helper".into(),
+        };
+        let unknown_tool = RustNonTestBinarySummary {
+            name: "unknown-tool".to_owned(),
+            kind: RustNonTestBinaryKind::BIN_EXE,
+            path: "release/unknown-tool".into(),
+        };
+
+        let binary_list = BinaryList {
+            rust_build_meta: RustBuildMeta {
+                target_directory: "/fake/target".into(),
+                base_output_directories: btreeset! {"debug".into(), "release".into()},
+                base_output_directory_categories: [
+                    (Utf8PathBuf::from("debug"), BaseOutputDirCategory::TESTS),
+                    (Utf8PathBuf::from("release"), BaseOutputDirCategory::TESTS),
+                ]
+                .into(),
+                linked_paths: [
+                    (helper_out.clone(), btreeset! {HELPER_ID.to_owned()}),
+                    (unknown_out.clone(), btreeset! {UNKNOWN_ID.to_owned()}),
+                    (no_provenance.clone(), BTreeSet::new()),
+                ]
+                .into(),
+                absolute_linked_paths: [
+                    (
+                        Utf8PathBuf::from("/opt/helper/lib"),
+                        btreeset! {HELPER_ID.to_owned()},
+                    ),
+                    (
+                        Utf8PathBuf::from("/opt/unknown/lib"),
+                        btreeset! {UNKNOWN_ID.to_owned()},
+                    ),
+                ]
+                .into(),
+                build_script_out_dirs: [
+                    (
+                        BASE_ID.to_owned(),
+                        Utf8PathBuf::from("debug/build/base/out"),
+                    ),
+                    (UNKNOWN_ID.to_owned(), unknown_out),
+                ]
+                .into(),
+                non_test_binaries: [
+                    (HELPER_ID.to_owned(), btreeset! {helper_bin.clone()}),
+                    (UNKNOWN_ID.to_owned(), btreeset! {unknown_tool}),
+                ]
+                .into(),
+                linked_libs: [
+                    ("ssl".to_owned(), btreeset! {HELPER_ID.to_owned()}),
+                    ("z".to_owned(), btreeset! {UNKNOWN_ID.to_owned()}),
+                ]
+                .into(),
+                ..Default::default()
+            },
+            rust_binaries: vec![base_bin.clone(), unknown_bin.clone()],
+        };
+        let graph = fixture_graph();
+
+        // Paths that aren't test binaries in the list are ignored.
+        let restricted = binary_list.restrict_to_binaries(
+            &btreeset! {
+                base_bin.path.clone(),
+                "/fake/target/debug/deps/missing-9abc".into(),
+            },
+            &graph,
+        );
+        assert_eq!(binary_ids(&restricted), [&base_bin.id]);
+        let meta = &restricted.rust_build_meta;
+        assert_eq!(
+            meta.base_output_directories,
+            btreeset! {"debug".into()},
+            "base output directories without selected binaries are dropped",
+        );
+        assert_eq!(
+            meta.base_output_directory_categories
+                .keys()
+                .cloned()
+                .collect::<BTreeSet<_>>(),
+            btreeset! {"debug".into()},
+        );
+        assert_eq!(
+            meta.linked_paths.keys().cloned().collect::<BTreeSet<_>>(),
+            btreeset! {helper_out, no_provenance},
+            "linked paths from dependencies and without provenance are kept",
+        );
+        assert_eq!(
+            meta.absolute_linked_paths
+                .keys()
+                .cloned()
+                .collect::<BTreeSet<_>>(),
+            btreeset! {"/opt/helper/lib".into()},
+        );
+        assert_eq!(
+            meta.build_script_out_dirs.keys().collect::<Vec<_>>(),
+            [BASE_ID],
+            "only the selected packages' build script output directories are kept",
+        );
+        assert_eq!(
+            meta.non_test_binaries,
+            [(HELPER_ID.to_owned(), btreeset! {helper_bin})].into(),
+            "non-test binaries from dependencies are kept",
+        );
+        assert_eq!(meta.linked_libs.keys().collect::<Vec<_>>(), ["ssl"]);
+
+        // If the selected packages aren't in the graph, every linked path is kept.
+        let restricted =
+            binary_list.restrict_to_binaries(&btreeset! {unknown_bin.path.clone()}, &graph);
+        assert_eq!(binary_ids(&restricted), [&unknown_bin.id]);
+        let meta = &restricted.rust_build_meta;
+        assert_eq!(meta.base_output_directories, btreeset! {"release".into()});
+        assert_eq!(
+            meta.linked_paths, binary_list.rust_build_meta.linked_paths,
+            "all linked paths are kept if dependencies can't be queried",
+        );
+        assert_eq!(
+            meta.absolute_linked_paths,
+            binary_list.rust_build_meta.absolute_linked_paths,
+        );
+        assert_eq!(
+            meta.build_script_out_dirs.keys().collect::<Vec<_>>(),
+            [UNKNOWN_ID],
+        );
+    }
+
+    #[test]
+    fn test_tests_using_lib() {
+        let base_bin = fake_test_binary("base", "/fake/target/debug/deps/base-1234", BASE_ID);
+        let helper_bin =
+            fake_test_binary("helper", "/fake/target/debug/deps/helper-5678", HELPER_ID);
+        let unknown_bin = fake_test_binary(
+            "unknown",
+            "/fake/target/debug/deps/unknown-9abc",
+            UNKNOWN_ID,
+        );
+        let binary_list = BinaryList {
+            rust_build_meta: RustBuildMeta {
+                target_directory: "/fake/target".into(),
+                linked_libs: [
+                    ("ssl".to_owned(), btreeset! {HELPER_ID.to_owned()}),
+                    ("z".to_owned(), btreeset! {UNKNOWN_ID.to_owned()}),
+                ]
+                .into(),
+                ..Default::default()
+            },
+            rust_binaries: vec![base_bin.clone(), helper_bin.clone(), unknown_bin.clone()],
+        };
+        let graph = fixture_graph();
+
+        // metadata-base depends on metadata-helper, so its tests use the library as well.
+        assert_eq!(
+            binary_list.tests_using_lib("ssl", &graph),
+            [base_bin.id, helper_bin.id],
+        );
+        // Packages that aren't in the graph are only matched directly.
+        assert_eq!(binary_list.tests_using_lib("z", &graph), [unknown_bin.id]);
+        assert_eq!(
+            binary_list.tests_using_lib("missing", &graph),
+            Vec::<RustBinaryId>::new(),
+            "no tests use a library that isn't linked",
+        );
+    }
 }
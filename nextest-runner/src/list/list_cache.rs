@@ -0,0 +1,213 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! An on-disk cache of test binary list outputs.
+//!
+//! Listing tests requires running each test binary twice (with and without `--ignored`). The
+//! cache stores the outputs of those invocations, keyed by the binary's path, size and
+//! modification time, so that unchanged binaries don't need to be run again.
+
+use crate::errors::ListCacheError;
+use atomicwrites::{AtomicFile, OverwriteBehavior};
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, io, io::Write, sync::Mutex, time::SystemTime};
+
+/// An on-disk cache of test binary list outputs.
+///
+/// The cache is stored at `<target-dir>/nextest/list-cache.json`, so it's removed by `cargo
+/// clean`. Entries are invalidated if the binary's size or modification time changes.
+#[derive(Debug)]
+pub struct ListCache {
+    path: Utf8PathBuf,
+    entries: Mutex<BTreeMap<Utf8PathBuf, ListCacheEntry>>,
+}
+
+impl ListCache {
+    /// The version of the cache format. Caches written with a different version are ignored.
+    pub const VERSION: u32 = 1;
+
+    /// The path to the cache file, relative to the target directory.
+    pub const PATH: &'static str = "nextest/list-cache.json";
+
+    /// Loads the cache for the given target directory.
+    ///
+    /// If the cache file doesn't exist, can't be parsed, or was written with a different
+    /// [`Self::VERSION`], an empty cache is returned.
+    pub fn load(target_dir: &Utf8Path) -> Result<Self, ListCacheError> {
+        let path = target_dir.join(Self::PATH);
+        let entries = match std::fs::read_to_string(&path) {
+            Ok(contents) => match serde_json::from_str::<ListCacheFile>(&contents) {
+                Ok(file) if file.version == Self::VERSION => file.entries,
+                Ok(file) => {
+                    log::debug!(
+                        "ignoring list cache `{path}` with version {} (expected {})",
+                        file.version,
+                        Self::VERSION,
+                    );
+                    BTreeMap::new()
+                }
+                Err(error) => {
+                    log::debug!("ignoring unparseable list cache `{path}`: {error}");
+                    BTreeMap::new()
+                }
+            },
+            Err(error) if error.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(error) => return Err(ListCacheError::Read { path, error }),
+        };
+
+        Ok(Self {
+            path,
+            entries: Mutex::new(entries),
+        })
+    }
+
+    /// Returns the path to the cache file.
+    pub fn path(&self) -> &Utf8Path {
+        &self.path
+    }
+
+    /// Returns the cached `(non_ignored, ignored)` list outputs for the binary at `binary_path`,
+    /// if the binary hasn't changed since they were recorded.
+    pub(crate) fn get(&self, binary_path: &Utf8Path) -> Option<(String, String)> {
+        let fingerprint = BinaryFingerprint::read(binary_path)?;
+        let entries = self.entries.lock().expect("list cache lock poisoned");
+        let entry = entries.get(binary_path)?;
+        (entry.fingerprint == fingerprint)
+            .then(|| (entry.non_ignored.clone(), entry.ignored.clone()))
+    }
+
+    /// Records the list outputs for the binary at `binary_path`, replacing any existing entry.
+    pub(crate) fn insert(&self, binary_path: &Utf8Path, non_ignored: &str, ignored: &str) {
+        let Some(fingerprint) = BinaryFingerprint::read(binary_path) else {
+            return;
+        };
+        self.entries
+            .lock()
+            .expect("list cache lock poisoned")
+            .insert(
+                binary_path.to_owned(),
+                ListCacheEntry {
+                    fingerprint,
+                    non_ignored: non_ignored.to_owned(),
+                    ignored: ignored.to_owned(),
+                },
+            );
+    }
+
+    /// Writes the cache back to disk.
+    ///
+    /// Entries for binaries that no longer exist are dropped.
+    pub fn save(&self) -> Result<(), ListCacheError> {
+        let mut entries = self.entries.lock().expect("list cache lock poisoned");
+        entries.retain(|binary_path, _| binary_path.exists());
+        let file = ListCacheFile {
+            version: Self::VERSION,
+            entries: entries.clone(),
+        };
+        drop(entries);
+        let contents = serde_json::to_vec(&file).expect("serializing ListCacheFile should succeed");
+
+        let write_error = |error| ListCacheError::Write {
+            path: self.path.clone(),
+            error,
+        };
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent).map_err(write_error)?;
+        }
+        AtomicFile::new(&self.path, OverwriteBehavior::AllowOverwrite)
+            .write(|f| f.write_all(&contents))
+            .map_err(|error| {
+                write_error(match error {
+                    atomicwrites::Error::Internal(error) => error,
+                    atomicwrites::Error::User(error) => error,
+                })
+            })
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct ListCacheFile {
+    version: u32,
+    entries: BTreeMap<Utf8PathBuf, ListCacheEntry>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct ListCacheEntry {
+    #[serde(flatten)]
+    fingerprint: BinaryFingerprint,
+    non_ignored: String,
+    ignored: String,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct BinaryFingerprint {
+    size: u64,
+    modified: SystemTime,
+}
+
+impl BinaryFingerprint {
+    fn read(binary_path: &Utf8Path) -> Option<Self> {
+        let metadata = std::fs::metadata(binary_path).ok()?;
+        Some(Self {
+            size: metadata.len(),
+            modified: metadata.modified().ok()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use camino_tempfile::Utf8TempDir;
+    use std::time::Duration;
+
+    #[test]
+    fn test_list_cache_round_trip() {
+        let dir = Utf8TempDir::new().expect("created temp dir");
+        let target_dir = dir.path();
+        let binary_path = target_dir.join("debug/deps/my_test-0123");
+        std::fs::create_dir_all(binary_path.parent().unwrap()).unwrap();
+        std::fs::write(&binary_path, b"binary").unwrap();
+
+        let cache = ListCache::load(target_dir).expect("loaded empty cache");
+        assert_eq!(cache.get(&binary_path), None);
+        cache.insert(&binary_path, "test_a: test\n", "test_b: test\n");
+        cache.save().expect("saved cache");
+
+        let cache = ListCache::load(target_dir).expect("loaded cache");
+        assert_eq!(
+            cache.get(&binary_path),
+            Some(("test_a: test\n".to_owned(), "test_b: test\n".to_owned())),
+        );
+
+        // Changing the modification time invalidates the entry.
+        let file = std::fs::File::options()
+            .write(true)
+            .open(&binary_path)
+            .unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(60))
+            .unwrap();
+        assert_eq!(cache.get(&binary_path), None);
+
+        // Removed binaries are dropped on save.
+        std::fs::remove_file(&binary_path).unwrap();
+        cache.save().expect("saved cache");
+        let cache = ListCache::load(target_dir).expect("loaded cache");
+        assert!(cache.entries.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_list_cache_version_mismatch() {
+        let dir = Utf8TempDir::new().expect("created temp dir");
+        let path = dir.path().join(ListCache::PATH);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, r#"{"version": 0, "entries": {}}"#).unwrap();
+
+        let cache = ListCache::load(dir.path()).expect("loaded cache");
+        assert!(cache.entries.lock().unwrap().is_empty());
+    }
+}
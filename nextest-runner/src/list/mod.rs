@@ -9,12 +9,14 @@
 
 mod binary_list;
 mod display_filter;
+mod list_cache;
 mod output_format;
 mod rust_build_meta;
 mod test_list;
 
 pub use binary_list::*;
 pub(crate) use display_filter::*;
+pub use list_cache::*;
 pub use output_format::*;
 pub use rust_build_meta::*;
 pub use test_list::*;
@@ -2,19 +2,58 @@
 // SPDX-License-Identifier: MIT OR Apache-2.0
 
 use crate::{
-    errors::RustBuildMetaParseError,
-    helpers::convert_rel_path_to_main_sep,
+    cargo_config::TargetTriple,
+    errors::{CreateTestListError, MetadataMaterializeError, RustBuildMetaParseError},
+    helpers::{
+        convert_rel_path_to_forward_slash, convert_rel_path_to_main_sep, dylib_path,
+        dylib_path_envvar,
+    },
     list::{BinaryListState, TestListState},
-    platform::{BuildPlatforms, TargetPlatform},
+    platform::{
+        detect_host_os_version, detect_libdir, detect_rustc_version, is_older_os_version,
+        is_older_rustc_version, BuildPlatforms, PlatformLibdir, TargetPlatform,
+    },
     reuse_build::PathMapper,
+    test_command::{is_sip_sanitized, target_dylib_env_value},
 };
-use camino::Utf8PathBuf;
+use camino::{Utf8Component, Utf8Path, Utf8PathBuf};
+use chrono::{SecondsFormat, Utc};
+use guppy::graph::{DependencyDirection, PackageGraph};
 use itertools::Itertools;
-use nextest_metadata::{BuildPlatformsSummary, RustBuildMetaSummary, RustNonTestBinarySummary};
+use nextest_metadata::{
+    BaseOutputDirCategory, BuildPlatform, BuildPlatformsSummary, PlatformLibdirUnavailable,
+    RustBuildMetaSummary, RustNonTestBinaryKind, RustNonTestBinarySummary, RustTestBinaryKind,
+};
 use std::{
-    collections::{BTreeMap, BTreeSet},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    ffi::{OsStr, OsString},
+    fmt,
     marker::PhantomData,
+    path::PathBuf,
 };
+use xxhash_rust::xxh64::xxh64;
+
+mod diagnostic;
+#[cfg(feature = "graphviz")]
+mod dot;
+mod incremental;
+mod merge;
+mod per_platform;
+mod policy;
+mod repro;
+mod slice;
+mod split;
+#[cfg(test)]
+mod test_helpers;
+mod validate;
+
+pub use diagnostic::*;
+pub use incremental::*;
+pub use merge::ProfileAliases;
+pub use per_platform::*;
+pub use policy::*;
+pub use slice::*;
+pub use validate::*;
 
 /// Rust-related metadata used for builds and test runs.
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -26,6 +65,10 @@ pub struct RustBuildMeta<State> {
     /// and their "deps" subdirectories are added to the dynamic library path.
     pub base_output_directories: BTreeSet<Utf8PathBuf>,
 
+    /// The artifact category of each base output directory, keyed by the directory relative to
+    /// the target directory. Directories missing from this map are uncategorized.
+    pub base_output_directory_categories: BTreeMap<Utf8PathBuf, BaseOutputDirCategory>,
+
     /// Information about non-test executables, keyed by package ID.
     pub non_test_binaries: BTreeMap<String, BTreeSet<RustNonTestBinarySummary>>,
 
@@ -36,12 +79,124 @@ pub struct RustBuildMeta<State> {
     /// A list of linked paths, relative to the target directory. These directories are
     /// added to the dynamic library path.
     ///
-    /// The values are the package IDs of the libraries that requested the linked paths.
-    ///
-    /// Note that the serialized metadata only has the paths for now, not the libraries that
-    /// requested them. We might consider adding a new field with metadata about that.
+    /// The values are the package IDs of the libraries that requested the linked paths. Metadata
+    /// produced by older versions of nextest doesn't record them, in which case the values are
+    /// empty.
     pub linked_paths: BTreeMap<Utf8PathBuf, BTreeSet<String>>,
 
+    /// Linked paths outside the target directory, as absolute paths. These directories are added
+    /// to the dynamic library path as-is, after [`Self::linked_paths`].
+    ///
+    /// As with [`Self::linked_paths`], the values are the package IDs of the libraries that
+    /// requested the linked paths. These paths aren't remapped when the target directory is.
+    pub absolute_linked_paths: BTreeMap<Utf8PathBuf, BTreeSet<String>>,
+
+    /// Native libraries linked by build scripts via `cargo::rustc-link-lib`, keyed by library name
+    /// (without the kind or modifiers, e.g. `ssl` for `dylib=ssl`).
+    ///
+    /// The values are the package IDs of the packages that link the library.
+    pub linked_libs: BTreeMap<String, BTreeSet<String>>,
+
+    /// Dynamic library search directories, relative to the target directory, that existed on disk
+    /// when [`RustBuildMeta::record_resolved_search_dirs`] was called. cargo-nextest records
+    /// these right after building tests.
+    ///
+    /// If present, [`RustBuildMeta::dylib_paths`] warns about any of these directories that no
+    /// longer exist.
+    pub resolved_search_dirs: Option<Vec<Utf8PathBuf>>,
+
+    /// Absolute output directories that aren't within the target directory, for example those
+    /// used with `--artifact-dir`. These directories and their "deps" subdirectories are added to
+    /// the dynamic library path as-is.
+    ///
+    /// These paths aren't remapped when the target directory is, and aren't included in archives.
+    pub extra_output_roots: BTreeSet<Utf8PathBuf>,
+
+    /// A user-defined label, for example a git commit hash or a CI job ID.
+    ///
+    /// This is persisted in the summary for provenance, and is otherwise ignored by nextest.
+    pub label: Option<String>,
+
+    /// The version of the host operating system component relevant to dynamic linking, as
+    /// recorded by [`RustBuildMeta::record_host_os_version`].
+    ///
+    /// When reusing a build, nextest warns if the current host has an older version, since test
+    /// binaries might fail to load.
+    pub host_os_version: Option<String>,
+
+    /// The exact version of rustc used for the build, as recorded by
+    /// [`RustBuildMeta::record_rust_version`].
+    ///
+    /// When reusing a build, nextest warns if the current rustc is older, since some runtime
+    /// behavior depends on the toolchain version.
+    pub rust_version: Option<String>,
+
+    /// The command-line arguments that produced this metadata, as recorded by
+    /// [`RustBuildMeta::record_list_command`].
+    ///
+    /// This is persisted in the summary for provenance, and is otherwise ignored by nextest.
+    pub list_command: Option<Vec<String>>,
+
+    /// The time this metadata was produced, in RFC 3339 format, as recorded by
+    /// [`RustBuildMeta::record_built_at`].
+    ///
+    /// This is persisted in the summary so that tooling can tell how old a reused build is, and
+    /// is otherwise ignored by nextest.
+    pub built_at: Option<String>,
+
+    /// The debug info level test binaries were built with, as reported by Cargo (e.g. `none`,
+    /// `line-tables-only`, `limited` or `full`).
+    ///
+    /// This is taken from the first test binary Cargo reports, and is persisted in the summary so
+    /// that tooling can tell whether binaries can be debugged. It's otherwise ignored by nextest.
+    pub debuginfo: Option<String>,
+
+    /// Whether incremental compilation was enabled for the build, as recorded by
+    /// [`RustBuildMeta::record_incremental`].
+    ///
+    /// This is persisted in the summary for reproducibility audits, and is otherwise ignored by
+    /// nextest.
+    pub incremental: Option<bool>,
+
+    /// The features enabled for each package with test binaries, keyed by package ID.
+    ///
+    /// When reusing a build, [`RustBuildMeta::check_enabled_features`] can be used to warn if the
+    /// features requested for the test run differ.
+    pub enabled_features: BTreeMap<String, BTreeSet<String>>,
+
+    /// Whether to also add the parent directory of each linked path to the dynamic library path.
+    ///
+    /// This is a workaround for a few native libraries that expect both the link search directory
+    /// and its parent to be on the search path, and is off by default. It isn't persisted in the
+    /// summary.
+    pub include_linked_path_parents: bool,
+
+    /// Whether to scan each linked path one level deep, and also add subdirectories containing
+    /// shared libraries to the dynamic library path.
+    ///
+    /// This is a workaround for vendored SDKs that place libraries one directory below the path
+    /// passed to `cargo::rustc-link-search`, and is off by default. Scanning reads every linked
+    /// path, which can slow down listing, and the extra directories can change which libraries
+    /// are resolved at runtime. It isn't persisted in the summary.
+    pub scan_linked_path_subdirs: bool,
+
+    /// Whether to set `DYLD_LIBRARY_PATH` rather than `DYLD_FALLBACK_LIBRARY_PATH` on macOS.
+    ///
+    /// dyld searches `DYLD_LIBRARY_PATH` before a library's install path, so libraries in it
+    /// override those the binary was linked against, including system libraries. It searches
+    /// `DYLD_FALLBACK_LIBRARY_PATH` only if a library can't be found at its install path, which is
+    /// why that is the default. Some tests need to override libraries, though, and this is the
+    /// switch for them.
+    ///
+    /// This has no effect on other platforms, and isn't persisted in the summary.
+    pub use_dyld_library_path: bool,
+
+    /// Whether to leave base output directories in the
+    /// [examples](BaseOutputDirCategory::EXAMPLES) category out of the dynamic library path.
+    ///
+    /// This is off by default, and isn't persisted in the summary.
+    pub exclude_example_output_dirs: bool,
+
     /// The build platforms: host and target triple
     pub build_platforms: BuildPlatforms,
 
@@ -54,16 +209,99 @@ impl RustBuildMeta<BinaryListState> {
         Self {
             target_directory: target_directory.into(),
             base_output_directories: BTreeSet::new(),
+            base_output_directory_categories: BTreeMap::new(),
             non_test_binaries: BTreeMap::new(),
             build_script_out_dirs: BTreeMap::new(),
             linked_paths: BTreeMap::new(),
+            absolute_linked_paths: BTreeMap::new(),
+            linked_libs: BTreeMap::new(),
+            resolved_search_dirs: None,
+            extra_output_roots: BTreeSet::new(),
+            label: None,
+            host_os_version: None,
+            rust_version: None,
+            list_command: None,
+            built_at: None,
+            debuginfo: None,
+            incremental: None,
+            enabled_features: BTreeMap::new(),
+            include_linked_path_parents: false,
+            scan_linked_path_subdirs: false,
+            use_dyld_library_path: false,
+            exclude_example_output_dirs: false,
             state: PhantomData,
             build_platforms,
         }
     }
 
+    /// Sets a user-defined label, for example a git commit hash or a CI job ID.
+    ///
+    /// The label is persisted in the summary and is otherwise ignored by nextest.
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Records the version of the current host's operating system, as returned by
+    /// [`detect_host_os_version`].
+    pub fn record_host_os_version(&mut self) {
+        self.host_os_version = detect_host_os_version();
+    }
+
+    /// Records the version of rustc used for builds, as returned by [`detect_rustc_version`].
+    pub fn record_rust_version(&mut self) {
+        self.rust_version = detect_rustc_version();
+    }
+
+    /// Records the command-line arguments that produced this metadata.
+    ///
+    /// Only options in an allowlist of known non-sensitive Cargo and nextest options are recorded
+    /// as-is. Other options are replaced with `<redacted>`, along with the argument following
+    /// them if it might be the option's value.
+    pub fn record_list_command(&mut self, args: impl IntoIterator<Item = String>) {
+        self.list_command = Some(redact_list_command(args));
+    }
+
+    /// Records the current time as the time this metadata was produced.
+    pub fn record_built_at(&mut self) {
+        self.built_at = Some(Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true));
+    }
+
+    /// Records whether incremental compilation is enabled, based on the `CARGO_INCREMENTAL`
+    /// environment variable.
+    ///
+    /// If the variable isn't set to `0` or `1`, incremental compilation is controlled by the Cargo
+    /// profile, which nextest doesn't have access to, and nothing is recorded.
+    pub fn record_incremental(&mut self) {
+        self.incremental =
+            parse_cargo_incremental(std::env::var("CARGO_INCREMENTAL").ok().as_deref());
+    }
+
     /// Maps paths using a [`PathMapper`] to convert this to [`TestListState`].
+    ///
+    /// Absolute linked paths within the original target directory are made relative to the new
+    /// target directory, and those within the original workspace root are moved to the new
+    /// workspace root. If paths are being remapped, a warning is logged for absolute linked paths
+    /// that don't exist after mapping.
     pub fn map_paths(&self, path_mapper: &PathMapper) -> RustBuildMeta<TestListState> {
+        let MappedLinkedPaths {
+            linked_paths,
+            absolute_linked_paths,
+            not_remapped,
+        } = self.map_absolute_linked_paths(path_mapper, &RealPathChecker);
+        if !not_remapped.is_empty() {
+            Diagnostic::warning(
+                DiagnosticCode::LINKED_PATHS_NOT_REMAPPED,
+                format!(
+                    "linked paths outside the target directory couldn't be remapped and don't \
+                     exist, libraries in them may fail to load: {}",
+                    not_remapped.iter().join(", "),
+                ),
+            )
+            .with_related_paths(not_remapped)
+            .log();
+        }
+
         RustBuildMeta {
             target_directory: path_mapper
                 .new_target_dir()
@@ -71,13 +309,89 @@ impl RustBuildMeta<BinaryListState> {
                 .to_path_buf(),
             // Since these are relative paths, they don't need to be mapped.
             base_output_directories: self.base_output_directories.clone(),
+            base_output_directory_categories: self.base_output_directory_categories.clone(),
             non_test_binaries: self.non_test_binaries.clone(),
             build_script_out_dirs: self.build_script_out_dirs.clone(),
-            linked_paths: self.linked_paths.clone(),
+            linked_paths,
+            absolute_linked_paths,
+            linked_libs: self.linked_libs.clone(),
+            resolved_search_dirs: self.resolved_search_dirs.clone(),
+            extra_output_roots: self.extra_output_roots.clone(),
+            label: self.label.clone(),
+            host_os_version: self.host_os_version.clone(),
+            rust_version: self.rust_version.clone(),
+            list_command: self.list_command.clone(),
+            built_at: self.built_at.clone(),
+            debuginfo: self.debuginfo.clone(),
+            incremental: self.incremental,
+            enabled_features: self.enabled_features.clone(),
+            include_linked_path_parents: self.include_linked_path_parents,
+            scan_linked_path_subdirs: self.scan_linked_path_subdirs,
+            use_dyld_library_path: self.use_dyld_library_path,
+            exclude_example_output_dirs: self.exclude_example_output_dirs,
             state: PhantomData,
             build_platforms: self.build_platforms.map_libdir(path_mapper.libdir_mapper()),
         }
     }
+
+    /// Maps absolute linked paths for [`Self::map_paths`].
+    ///
+    /// Absolute linked paths within the original target directory are converted to relative
+    /// linked paths, so they follow the target directory if it's remapped. Other absolute linked
+    /// paths are mapped like working directories, and the ones that don't exist according to
+    /// `checker` after remapping are returned in `not_remapped`.
+    fn map_absolute_linked_paths(
+        &self,
+        path_mapper: &PathMapper,
+        checker: &dyn PathChecker,
+    ) -> MappedLinkedPaths {
+        // Relative linked paths don't need to be mapped.
+        let mut linked_paths = self.linked_paths.clone();
+        let mut absolute_linked_paths = BTreeMap::<_, BTreeSet<_>>::new();
+        let mut not_remapped = Vec::new();
+
+        for (abs_path, requested_by) in &self.absolute_linked_paths {
+            if let Some(rel_path) = path_mapper.strip_orig_target_dir(abs_path) {
+                linked_paths
+                    .entry(convert_rel_path_to_forward_slash(rel_path))
+                    .or_default()
+                    .extend(requested_by.iter().cloned());
+                continue;
+            }
+
+            let mapped = path_mapper.map_cwd(abs_path.clone());
+            if path_mapper.is_remapping() && !checker.exists(&mapped) {
+                not_remapped.push(mapped.clone());
+            }
+            absolute_linked_paths
+                .entry(mapped)
+                .or_default()
+                .extend(requested_by.iter().cloned());
+        }
+
+        MappedLinkedPaths {
+            linked_paths,
+            absolute_linked_paths,
+            not_remapped,
+        }
+    }
+
+    /// Records the dynamic library search directories that currently exist on disk.
+    ///
+    /// The directories are stored relative to the target directory and persisted in the summary.
+    /// On the machine tests are run on, [`RustBuildMeta::dylib_paths`] cross-checks against them
+    /// and warns about any directories that have disappeared.
+    pub fn record_resolved_search_dirs(&mut self) {
+        let resolved = self
+            .relative_search_dirs()
+            .filter(|rel_path| {
+                self.target_directory
+                    .join(convert_rel_path_to_main_sep(rel_path))
+                    .exists()
+            })
+            .collect();
+        self.resolved_search_dirs = Some(resolved);
+    }
 }
 
 impl RustBuildMeta<TestListState> {
@@ -87,9 +401,26 @@ impl RustBuildMeta<TestListState> {
         Self {
             target_directory: Utf8PathBuf::new(),
             base_output_directories: BTreeSet::new(),
+            base_output_directory_categories: BTreeMap::new(),
             non_test_binaries: BTreeMap::new(),
             build_script_out_dirs: BTreeMap::new(),
             linked_paths: BTreeMap::new(),
+            absolute_linked_paths: BTreeMap::new(),
+            linked_libs: BTreeMap::new(),
+            resolved_search_dirs: None,
+            extra_output_roots: BTreeSet::new(),
+            label: None,
+            host_os_version: None,
+            rust_version: None,
+            list_command: None,
+            built_at: None,
+            debuginfo: None,
+            incremental: None,
+            enabled_features: BTreeMap::new(),
+            include_linked_path_parents: false,
+            scan_linked_path_subdirs: false,
+            use_dyld_library_path: false,
+            exclude_example_output_dirs: false,
             state: PhantomData,
             build_platforms: BuildPlatforms::new_with_no_target().unwrap(),
         }
@@ -101,322 +432,3620 @@ impl RustBuildMeta<TestListState> {
     /// more.](https://doc.rust-lang.org/cargo/reference/environment-variables.html#dynamic-library-paths)
     ///
     /// These paths are prepended to the dynamic library environment variable for the current
-    /// platform (e.g. `LD_LIBRARY_PATH` on non-Apple Unix platforms).
+    /// platform (e.g. `LD_LIBRARY_PATH` on non-Apple Unix platforms). Linked paths are only
+    /// included if they're directories that exist on disk. Paths containing NUL or newline
+    /// characters are skipped, and an error is logged for them.
+    ///
+    /// The order only depends on the contents of the metadata, so identical metadata always
+    /// produces identical paths. The paths are, in order:
+    ///
+    /// 1. linked paths relative to the target directory, then absolute linked paths, each sorted
+    /// 2. subdirectories of linked paths, if [`Self::scan_linked_path_subdirs`] is set
+    /// 3. base output directories, then extra output roots, each sorted and preceded by its
+    ///    `deps` subdirectory
+    /// 4. the host and target libdirs, sorted
+    ///
+    /// If a path appears more than once, only its first occurrence is kept.
     pub fn dylib_paths(&self) -> Vec<Utf8PathBuf> {
-        // Add rust libdirs to the path if available, so we can run test binaries that depend on
-        // libstd.
-        //
-        // We could be smarter here and only add the host libdir for host binaries and the target
-        // libdir for target binaries, but it's simpler to just add both for now.
-        let libdirs = self
-            .build_platforms
-            .host
-            .libdir
-            .as_path()
-            .into_iter()
-            .chain(
-                self.build_platforms
-                    .target
-                    .as_ref()
-                    .and_then(|target| target.libdir.as_path()),
-            )
-            .map(|libdir| libdir.to_path_buf())
-            .collect::<Vec<_>>();
-        if libdirs.is_empty() {
-            log::warn!("failed to detect the rustc libdir, may fail to list or run tests");
+        self.dylib_paths_with_checker(&RealPathChecker)
+    }
+
+    /// Returns the dynamic library paths corresponding to this metadata, using `checker` to
+    /// determine which directories exist.
+    ///
+    /// This is the same as [`Self::dylib_paths`], except that existence checks go through the
+    /// given [`PathChecker`] rather than the real filesystem.
+    pub fn dylib_paths_with_checker(&self, checker: &dyn PathChecker) -> Vec<Utf8PathBuf> {
+        for diagnostic in self.dylib_path_diagnostics(checker) {
+            diagnostic.log();
         }
+        self.dylib_search_dirs_with_checker(checker)
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect()
+    }
+
+    /// Returns the paths in [`Self::dylib_paths`], in the same order, along with the kind of each
+    /// path.
+    ///
+    /// This is meant for tools that display or replicate the dynamic library path nextest sets up.
+    /// Unlike [`Self::dylib_paths`], this doesn't log any diagnostics.
+    pub fn dylib_search_dirs(&self) -> Vec<(Utf8PathBuf, DylibPathKind)> {
+        self.dylib_search_dirs_with_checker(&RealPathChecker)
+    }
+
+    fn dylib_search_dirs_with_checker(
+        &self,
+        checker: &dyn PathChecker,
+    ) -> Vec<(Utf8PathBuf, DylibPathKind)> {
+        self.dylib_path_candidates_with_checker(checker)
+            .into_iter()
+            .filter(|candidate| candidate.status == DylibPathStatus::Kept)
+            .map(|candidate| (candidate.path, candidate.kind))
+            .collect()
+    }
+
+    /// Returns every path considered for [`Self::dylib_paths`], along with whether it was kept
+    /// or skipped and why.
+    ///
+    /// Candidates are returned in the order they're considered in, so the kept candidates are the
+    /// paths returned by [`Self::dylib_paths`], in the same order. This is meant for diagnosing
+    /// tests that fail to start because of missing shared libraries, for example because a linked
+    /// path didn't exist yet when tests were listed. Unlike [`Self::dylib_paths`], this doesn't log
+    /// any diagnostics.
+    pub fn dylib_paths_with_status(&self) -> Vec<DylibPathCandidate> {
+        self.dylib_path_candidates_with_checker(&RealPathChecker)
+    }
+
+    fn dylib_path_candidates_with_checker(
+        &self,
+        checker: &dyn PathChecker,
+    ) -> Vec<DylibPathCandidate> {
+        // Sort the libdirs so that the order doesn't depend on which platform each was detected
+        // for.
+        let mut libdirs = self.libdirs();
+        libdirs.sort();
+
+        // Only add linked paths if they're directories that exist on disk. Build scripts can emit
+        // paths that collide with regular files, and those shouldn't be passed to the dynamic
+        // loader.
+        let linked_path_status = |path: &Utf8Path| {
+            if checker.is_dir(path) {
+                DylibPathStatus::Kept
+            } else if checker.exists(path) {
+                DylibPathStatus::Skipped(DylibPathSkipReason::NotADirectory)
+            } else {
+                DylibPathStatus::Skipped(DylibPathSkipReason::NotFound)
+            }
+        };
+        // Output directories are added even if they don't exist, as Cargo does, but not if
+        // something other than a directory is in their place.
+        let output_status = |path: &Utf8Path| {
+            if checker.is_dir(path) || !checker.exists(path) {
+                DylibPathStatus::Kept
+            } else {
+                DylibPathStatus::Skipped(DylibPathSkipReason::NotADirectory)
+            }
+        };
 
         // Cargo puts linked paths before base output directories.
-        self.linked_paths
+        let linked_paths = self
+            .linked_paths
             .keys()
-            .filter_map(|rel_path| {
-                let join_path = self
-                    .target_directory
-                    .join(convert_rel_path_to_main_sep(rel_path));
-                // Only add the directory to the path if it exists on disk.
-                join_path.exists().then_some(join_path)
+            .flat_map(|rel_path| {
+                let parent = self
+                    .include_linked_path_parents
+                    .then(|| rel_path.parent())
+                    .flatten()
+                    .filter(|parent| !parent.as_str().is_empty());
+                std::iter::once(rel_path.as_path()).chain(parent)
             })
-            .chain(self.base_output_directories.iter().flat_map(|base_output| {
-                let abs_base = self
-                    .target_directory
-                    .join(convert_rel_path_to_main_sep(base_output));
-                let with_deps = abs_base.join("deps");
-                // This is the order paths are added in by Cargo.
-                [with_deps, abs_base]
-            }))
+            .map(|rel_path| {
+                self.target_directory
+                    .join(convert_rel_path_to_main_sep(rel_path))
+            })
+            .chain(self.absolute_linked_paths.keys().cloned())
+            .map(|path| {
+                let status = linked_path_status(&path);
+                (path, DylibPathKind::LinkedPath, status)
+            })
+            .chain(
+                self.linked_path_subdirs(checker)
+                    .into_iter()
+                    .map(|path| (path, DylibPathKind::LinkedPath, DylibPathStatus::Kept)),
+            );
+        let output_paths = self.output_dylib_paths().map(|(path, kind)| {
+            let status = output_status(&path);
+            (path, kind, status)
+        });
+        let libdirs = libdirs
+            .into_iter()
+            .map(|libdir| (libdir, DylibPathKind::RustcLibdir, DylibPathStatus::Kept));
+
+        let mut seen = HashSet::new();
+        linked_paths
+            .chain(output_paths)
             .chain(libdirs)
-            .unique()
+            .map(|(path, kind, mut status)| {
+                if status == DylibPathStatus::Kept {
+                    if !seen.insert(path.clone()) {
+                        status = DylibPathStatus::Skipped(DylibPathSkipReason::Duplicate);
+                    } else if has_dylib_path_control_chars(&path) {
+                        // These are reported by dylib_path_diagnostics.
+                        status = DylibPathStatus::Skipped(DylibPathSkipReason::ControlCharacters);
+                    }
+                }
+                DylibPathCandidate { path, kind, status }
+            })
             .collect()
     }
-}
 
-impl<State> RustBuildMeta<State> {
-    /// Creates a `RustBuildMeta` from a serializable summary.
-    pub fn from_summary(summary: RustBuildMetaSummary) -> Result<Self, RustBuildMetaParseError> {
-        let build_platforms = if let Some(summary) = summary.platforms {
-            BuildPlatforms::from_summary(summary.clone())?
-        } else if let Some(summary) = summary.target_platforms.first() {
-            // Compatibility with metadata generated by older versions of nextest.
-            BuildPlatforms::from_target_summary(summary.clone())?
-        } else {
-            // Compatibility with metadata generated by older versions of nextest.
-            BuildPlatforms::from_summary_str(summary.target_platform.clone())?
+    /// Returns the paths in [`Self::dylib_paths`], in the same order, keeping only directories
+    /// that contain at least one shared library for the platform tests are run on.
+    ///
+    /// Shared libraries are recognized by file name: `.dll` files for Windows targets, `.dylib`
+    /// files for Apple targets, and `.so` files (including versioned names like `libfoo.so.1`)
+    /// for other targets. The target platform is used if one is set, and the host platform
+    /// otherwise.
+    ///
+    /// This reads every candidate directory, so it's slower than [`Self::dylib_paths`]. In
+    /// exchange, it can shorten the search path considerably, which matters on platforms where the
+    /// length of the dynamic library environment variable is limited (such as `PATH` on Windows).
+    pub fn dylib_paths_nonempty(&self) -> Vec<Utf8PathBuf> {
+        let triple = match &self.build_platforms.target {
+            Some(target) => target.triple.platform.triple_str(),
+            None => self.build_platforms.host.platform.triple_str(),
         };
+        self.dylib_paths()
+            .into_iter()
+            .filter(|dir| contains_shared_libraries(dir, Some(triple)))
+            .collect()
+    }
 
-        Ok(Self {
-            target_directory: summary.target_directory,
-            base_output_directories: summary.base_output_directories,
-            build_script_out_dirs: summary.build_script_out_dirs,
-            non_test_binaries: summary.non_test_binaries,
-            linked_paths: summary
-                .linked_paths
-                .into_iter()
-                .map(|linked_path| (linked_path, BTreeSet::new()))
-                .collect(),
-            state: PhantomData,
-            build_platforms,
-        })
+    /// Returns at most `max` dynamic library paths, for platforms where the length of the
+    /// dynamic library environment variable is limited (such as `PATH` on Windows).
+    ///
+    /// If [`Self::dylib_paths`] returns more than `max` paths, the ones least likely to be needed
+    /// are dropped and a warning listing them is logged. Libdirs are kept first, followed by
+    /// linked paths, followed by base output directories and extra output roots. Within each
+    /// group, later paths are dropped first. The returned paths are in the same order as in
+    /// [`Self::dylib_paths`].
+    pub fn dylib_paths_capped(&self, max: usize) -> Vec<Utf8PathBuf> {
+        self.dylib_paths_capped_with_checker(max, &RealPathChecker)
     }
 
-    /// Converts self to a serializable form.
-    pub fn to_summary(&self) -> RustBuildMetaSummary {
-        RustBuildMetaSummary {
-            target_directory: self.target_directory.clone(),
-            base_output_directories: self.base_output_directories.clone(),
-            non_test_binaries: self.non_test_binaries.clone(),
-            build_script_out_dirs: self.build_script_out_dirs.clone(),
-            linked_paths: self.linked_paths.keys().cloned().collect(),
-            target_platform: self.build_platforms.to_summary_str(),
-            target_platforms: vec![self.build_platforms.to_target_or_host_summary()],
-            // TODO: support multiple --target options
-            platforms: Some(BuildPlatformsSummary {
-                host: self.build_platforms.host.to_summary(),
-                targets: self
-                    .build_platforms
-                    .target
-                    .as_ref()
-                    .into_iter()
-                    .map(TargetPlatform::to_summary)
-                    .collect(),
-            }),
+    fn dylib_paths_capped_with_checker(
+        &self,
+        max: usize,
+        checker: &dyn PathChecker,
+    ) -> Vec<Utf8PathBuf> {
+        let paths = self.dylib_paths_with_checker(checker);
+        if paths.len() <= max {
+            return paths;
         }
+
+        let libdirs: HashSet<_> = self.libdirs().into_iter().collect();
+        let output_paths: HashSet<_> = self.output_dylib_paths().map(|(path, _)| path).collect();
+        let priority = |path: &Utf8PathBuf| {
+            if libdirs.contains(path) {
+                0
+            } else if output_paths.contains(path) {
+                2
+            } else {
+                1
+            }
+        };
+
+        // The sort is stable, so paths within a group stay in their original order.
+        let mut indexes: Vec<usize> = (0..paths.len()).collect();
+        indexes.sort_by_key(|&index| priority(&paths[index]));
+        let kept: HashSet<usize> = indexes[..max].iter().copied().collect();
+
+        let (kept, dropped): (Vec<_>, Vec<_>) = paths
+            .into_iter()
+            .enumerate()
+            .partition(|(index, _)| kept.contains(index));
+        log::warn!(
+            target: "nextest-runner::list",
+            "dropped {} dynamic library paths to stay within the limit of {max}: {}",
+            dropped.len(),
+            dropped.iter().map(|(_, path)| path).join(", "),
+        );
+        kept.into_iter().map(|(_, path)| path).collect()
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::{
-        cargo_config::TargetTriple,
-        platform::{BuildPlatforms, HostPlatform, PlatformLibdir, TargetPlatform},
-    };
-    use nextest_metadata::{
-        BuildPlatformsSummary, HostPlatformSummary, PlatformLibdirSummary,
-        PlatformLibdirUnavailable,
-    };
-    use target_spec::summaries::PlatformSummary;
-    use test_case::test_case;
+    /// If [`Self::scan_linked_path_subdirs`] is set, returns the immediate subdirectories of linked
+    /// paths that contain shared libraries.
+    ///
+    /// Linked paths are filtered through `checker`, but are then read from the real filesystem.
+    fn linked_path_subdirs(&self, checker: &dyn PathChecker) -> Vec<Utf8PathBuf> {
+        if !self.scan_linked_path_subdirs {
+            return Vec::new();
+        }
 
-    impl Default for RustBuildMeta<BinaryListState> {
-        fn default() -> Self {
-            RustBuildMeta::<BinaryListState>::new(
-                Utf8PathBuf::default(),
-                BuildPlatforms::new_with_no_target()
-                    .expect("creating BuildPlatforms without target triple should succeed"),
-            )
+        let linked_paths = self
+            .linked_paths
+            .keys()
+            .filter_map(|rel_path| self.existing_linked_path(rel_path, checker))
+            .chain(
+                self.absolute_linked_paths
+                    .keys()
+                    .filter(|abs_path| checker.is_dir(abs_path))
+                    .cloned(),
+            );
+
+        let mut subdirs = Vec::new();
+        for linked_path in linked_paths {
+            let entries = match linked_path.read_dir_utf8() {
+                Ok(entries) => entries,
+                Err(error) => {
+                    log::warn!(
+                        target: "nextest-runner::list",
+                        "error reading linked path `{linked_path}`, not scanning it: {error}"
+                    );
+                    continue;
+                }
+            };
+            let mut linked_subdirs: Vec<_> = entries
+                .flatten()
+                .filter(|entry| entry.file_type().is_ok_and(|file_type| file_type.is_dir()))
+                .map(|entry| entry.into_path())
+                .filter(|subdir| contains_shared_libraries(subdir, None))
+                .collect();
+            linked_subdirs.sort_unstable();
+            subdirs.extend(linked_subdirs);
         }
+        subdirs
     }
 
-    fn x86_64_pc_windows_msvc_triple() -> TargetTriple {
-        TargetTriple::deserialize_str(Some("x86_64-pc-windows-msvc".to_owned()))
-            .expect("creating TargetTriple should succeed")
-            .expect("the output of deserialize_str shouldn't be None")
+    /// Returns the absolute base output directories and extra output roots to add to the dynamic
+    /// library path, each preceded by its `deps` subdirectory.
+    fn output_dylib_paths(&self) -> impl Iterator<Item = (Utf8PathBuf, DylibPathKind)> + '_ {
+        self.dylib_base_output_dirs()
+            .map(|base_output| {
+                self.target_directory
+                    .join(convert_rel_path_to_main_sep(base_output))
+            })
+            .chain(self.extra_output_roots.iter().cloned())
+            .flat_map(|abs_base| {
+                // This is the order paths are added in by Cargo.
+                [
+                    (abs_base.join("deps"), DylibPathKind::BaseOutputDeps),
+                    (abs_base, DylibPathKind::BaseOutput),
+                ]
+            })
     }
 
-    fn host_current() -> HostPlatform {
-        HostPlatform::current(PlatformLibdir::Unavailable(
-            PlatformLibdirUnavailable::OLD_SUMMARY,
-        ))
-        .expect("should detect the host platform successfully")
+    /// Returns the base output directories to add to the dynamic library path, taking
+    /// [`Self::exclude_example_output_dirs`] into account.
+    fn dylib_base_output_dirs(&self) -> impl Iterator<Item = &Utf8PathBuf> + '_ {
+        self.base_output_directories.iter().filter(|base_output| {
+            !(self.exclude_example_output_dirs
+                && self.base_output_dir_category(base_output) == BaseOutputDirCategory::EXAMPLES)
+        })
     }
 
-    fn host_current_with_libdir(libdir: &str) -> HostPlatform {
-        HostPlatform::current(PlatformLibdir::Available(libdir.into()))
-            .expect("should detect the host platform successfully")
+    /// Returns all diagnostics for this metadata: those produced while computing
+    /// [`Self::dylib_paths`], those from [`Self::validate`], diagnostics if the current host's
+    /// OS or rustc is older than the one tests were built with, and diagnostics if the libdirs
+    /// differ from the current toolchain's.
+    ///
+    /// Unlike [`Self::dylib_paths`], this doesn't log anything.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = self.dylib_path_diagnostics(&RealPathChecker);
+        diagnostics.extend(self.host_os_version_diagnostic());
+        diagnostics.extend(self.rust_version_diagnostic());
+        diagnostics.extend(self.libdir_changed_diagnostics_with(detect_libdir));
+        diagnostics.extend(self.validate().iter().map(|issue| issue.to_diagnostic()));
+        diagnostics
     }
 
-    fn host_not_current_with_libdir(libdir: &str) -> HostPlatform {
-        cfg_if::cfg_if! {
-            if #[cfg(windows)] {
-                let triple = TargetTriple::x86_64_unknown_linux_gnu();
-            } else {
-                let triple = x86_64_pc_windows_msvc_triple();
+    fn dylib_path_diagnostics(&self, checker: &dyn PathChecker) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+
+        let libdirs = std::iter::once(("host", &self.build_platforms.host.libdir)).chain(
+            self.build_platforms
+                .target
+                .as_ref()
+                .map(|target| ("target", &target.libdir)),
+        );
+        for (kind, libdir) in libdirs {
+            if libdir == &PlatformLibdir::Unavailable(PlatformLibdirUnavailable::NON_UTF8_PATH) {
+                diagnostics.push(Diagnostic::warning(
+                    DiagnosticCode::LIBDIR_NON_UTF8,
+                    format!(
+                        "the rustc libdir for the {kind} platform isn't valid UTF-8, \
+                         so it isn't included in the dynamic library path"
+                    ),
+                ));
             }
-        };
+        }
 
-        HostPlatform {
-            platform: triple.platform,
-            libdir: PlatformLibdir::Available(libdir.into()),
+        let control_char_paths: Vec<_> = self
+            .linked_paths
+            .keys()
+            .map(|rel_path| {
+                self.target_directory
+                    .join(convert_rel_path_to_main_sep(rel_path))
+            })
+            .chain(self.absolute_linked_paths.keys().cloned())
+            .chain(self.output_dylib_paths().map(|(path, _)| path))
+            .chain(self.libdirs())
+            .filter(|path| has_dylib_path_control_chars(path))
+            .unique()
+            .collect();
+        if !control_char_paths.is_empty() {
+            diagnostics.push(
+                Diagnostic::error(
+                    DiagnosticCode::DYLIB_PATH_CONTROL_CHARS,
+                    format!(
+                        "dynamic library paths containing NUL or newline characters \
+                         were skipped: {}",
+                        control_char_paths
+                            .iter()
+                            .map(|path| format!("{:?}", path.as_str()))
+                            .join(", "),
+                    ),
+                )
+                .with_related_paths(control_char_paths),
+            );
+        }
+
+        if let Some(shared_root) = self.build_platforms.libdir_shared_root() {
+            if self.absolute_linked_paths.contains_key(shared_root) {
+                diagnostics.push(
+                    Diagnostic::warning(
+                        DiagnosticCode::LIBDIR_SHARED_ROOT_LINKED,
+                        format!(
+                            "linked path `{shared_root}` is the parent of both the host and \
+                             target libdirs, libraries for the wrong architecture may be loaded"
+                        ),
+                    )
+                    .with_related_paths([shared_root.to_path_buf()]),
+                );
+            }
+        }
+
+        if self.libdirs().is_empty() {
+            diagnostics.push(Diagnostic::warning(
+                DiagnosticCode::LIBDIR_MISSING,
+                "failed to detect the rustc libdir, may fail to list or run tests",
+            ));
         }
+
+        if let Some(sysroot) = self
+            .build_platforms
+            .target
+            .as_ref()
+            .and_then(|target| target.sysroot.as_ref())
+        {
+            if !checker.exists(sysroot) {
+                diagnostics.push(
+                    Diagnostic::warning(
+                        DiagnosticCode::TARGET_SYSROOT_MISSING,
+                        format!(
+                            "target sysroot `{sysroot}` present at build time is missing, \
+                             emulators that rely on it may fail to run tests"
+                        ),
+                    )
+                    .with_related_paths([sysroot.clone()]),
+                );
+            }
+        }
+
+        if let Some(resolved_search_dirs) = &self.resolved_search_dirs {
+            let missing = resolved_search_dirs
+                .iter()
+                .filter(|rel_path| {
+                    !checker.exists(
+                        &self
+                            .target_directory
+                            .join(convert_rel_path_to_main_sep(rel_path)),
+                    )
+                })
+                .collect::<Vec<_>>();
+            if !missing.is_empty() {
+                diagnostics.push(
+                    Diagnostic::warning(
+                        DiagnosticCode::SEARCH_DIRS_MISSING,
+                        format!(
+                            "dynamic library search directories present at build time are \
+                             missing, may fail to list or run tests: {}",
+                            missing.iter().join(", "),
+                        ),
+                    )
+                    .with_related_paths(missing.into_iter().cloned()),
+                );
+            }
+        }
+
+        diagnostics
     }
 
-    fn target_linux() -> TargetPlatform {
-        TargetPlatform::new(
-            TargetTriple::x86_64_unknown_linux_gnu(),
-            PlatformLibdir::Unavailable(PlatformLibdirUnavailable::OLD_SUMMARY),
-        )
+    /// Returns the name of the dynamic library path environment variable nextest sets for test
+    /// binaries, taking [`Self::use_dyld_library_path`] into account.
+    ///
+    /// This is based on the platform nextest is running on, e.g. `LD_LIBRARY_PATH` on Linux.
+    pub fn dylib_path_envvar(&self) -> &'static str {
+        let default = dylib_path_envvar();
+        if self.use_dyld_library_path && default == "DYLD_FALLBACK_LIBRARY_PATH" {
+            "DYLD_LIBRARY_PATH"
+        } else {
+            default
+        }
+    }
+
+    /// Returns the environment nextest sets for test binaries in the given package, on top of
+    /// `existing`.
+    ///
+    /// `existing` stands in for the environment nextest runs in. On top of it, this sets:
+    ///
+    /// * the dynamic library path environment variable, to [`Self::dylib_paths`] followed by its
+    ///   value in `existing`, joined as nextest does for the platform it's running on
+    /// * `NEXTEST_LD_*` and `NEXTEST_DYLD_*` copies of the `LD_*` and `DYLD_*` variables, which
+    ///   survive macOS System Integrity Protection
+    /// * for target binaries, the emulator environment of the target platform,
+    ///   `NEXTEST_TARGET_SYSROOT` if a sysroot was detected, and the target's dynamic library path
+    ///   environment variable if it's different from the host's
+    /// * `OUT_DIR`, if the package has a build script
+    /// * `NEXTEST_BIN_EXE_<name>` for each of the package's binaries, if `kind` is an integration
+    ///   test or benchmark
+    /// * `NEXTEST` and `NEXTEST_EXECUTION_MODE`
+    ///
+    /// Variables that depend on the package graph (such as `CARGO_MANIFEST_DIR` and
+    /// `CARGO_PKG_*`) or on nextest's configuration aren't included. Variables set by build
+    /// scripts through `cargo::rustc-env` aren't recorded in the metadata, so they aren't
+    /// included either.
+    ///
+    /// Returns an error if the dynamic library paths can't be joined, in which case nextest
+    /// can't run tests either.
+    pub fn spawn_env(
+        &self,
+        package_id: &str,
+        kind: &RustTestBinaryKind,
+        build_platform: BuildPlatform,
+        existing: &HashMap<String, String>,
+    ) -> Result<HashMap<String, String>, CreateTestListError> {
+        let mut env = existing.clone();
+
+        if build_platform == BuildPlatform::Target {
+            if let Some(target) = &self.build_platforms.target {
+                env.extend(target.emulator_env.clone());
+                if let Some(sysroot) = &target.sysroot {
+                    env.insert(
+                        "NEXTEST_TARGET_SYSROOT".to_owned(),
+                        sysroot.clone().into_string(),
+                    );
+                }
+            }
+        }
+
+        env.insert("NEXTEST".to_owned(), "1".to_owned());
+        env.insert(
+            "NEXTEST_EXECUTION_MODE".to_owned(),
+            "process-per-test".to_owned(),
+        );
+
+        let envvar = self.dylib_path_envvar();
+        let dylib_path = self.dylib_path_with_existing(existing.get(envvar).map(OsStr::new))?;
+        env.insert(envvar.to_owned(), dylib_path.to_string_lossy().into_owned());
+        for (key, value) in existing {
+            if is_sip_sanitized(key) && key != envvar {
+                env.insert(format!("NEXTEST_{key}"), value.clone());
+            }
+        }
+        if is_sip_sanitized(envvar) {
+            env.insert(
+                format!("NEXTEST_{envvar}"),
+                dylib_path.to_string_lossy().into_owned(),
+            );
+        }
+
+        if build_platform == BuildPlatform::Target {
+            let target_envvar = self.build_platforms.target_dylib_path_envvar();
+            let target_existing = existing.get(target_envvar).map(OsString::from);
+            if let Some(value) = target_dylib_env_value(self, &dylib_path, target_existing) {
+                env.insert(
+                    target_envvar.to_owned(),
+                    value.to_string_lossy().into_owned(),
+                );
+            }
+        }
+
+        if let Some(out_dir) = self.out_dir(package_id) {
+            env.insert("OUT_DIR".to_owned(), out_dir.into_string());
+        }
+
+        for (name, path) in self.exposed_non_test_binaries(package_id, kind) {
+            env.insert(format!("NEXTEST_BIN_EXE_{name}"), path.into_string());
+        }
+
+        Ok(env)
+    }
+
+    /// Returns the absolute path to the build script output directory of the given package, which
+    /// nextest exposes to tests as `OUT_DIR`.
+    pub(crate) fn out_dir(&self, package_id: &str) -> Option<Utf8PathBuf> {
+        self.build_script_out_dirs.get(package_id).map(|out_dir| {
+            self.target_directory
+                .join(convert_rel_path_to_main_sep(out_dir))
+        })
+    }
+
+    /// Returns the names and absolute paths of the non-test binaries in the given package that
+    /// nextest exposes to a test binary of the given kind.
+    ///
+    /// Only `bin` executables are exposed, and only to integration tests and benchmarks.
+    pub(crate) fn exposed_non_test_binaries(
+        &self,
+        package_id: &str,
+        kind: &RustTestBinaryKind,
+    ) -> BTreeSet<(String, Utf8PathBuf)> {
+        if *kind != RustTestBinaryKind::TEST && *kind != RustTestBinaryKind::BENCH {
+            return BTreeSet::new();
+        }
+        self.non_test_binaries
+            .get(package_id)
+            .into_iter()
+            .flatten()
+            .filter(|binary| binary.kind == RustNonTestBinaryKind::BIN_EXE)
+            .map(|binary| {
+                let path = self
+                    .target_directory
+                    .join(convert_rel_path_to_main_sep(&binary.path));
+                (binary.name.clone(), path)
+            })
+            .collect()
+    }
+
+    /// Returns the linked paths and libdirs needed to run tests for the given package.
+    ///
+    /// Linked paths are included if they were requested by the package or by any of its
+    /// transitive dependencies in `graph`, or if the libraries that requested them are unknown
+    /// (as with metadata produced by older versions of nextest). As with [`Self::dylib_paths`],
+    /// linked paths are only included if they exist on disk.
+    ///
+    /// If `package_id` isn't in `graph`, a warning is logged and only linked paths requested by the
+    /// package itself are included.
+    pub fn native_search_set(
+        &self,
+        package_id: &str,
+        graph: &PackageGraph,
+    ) -> BTreeSet<Utf8PathBuf> {
+        let guppy_id = guppy::PackageId::new(package_id);
+        let relevant_ids: HashSet<String> = match graph.query_forward([&guppy_id]) {
+            Ok(query) => query
+                .resolve()
+                .package_ids(DependencyDirection::Forward)
+                .map(|id| id.repr().to_owned())
+                .collect(),
+            Err(error) => {
+                log::warn!(
+                    target: "nextest-runner::list",
+                    "error querying dependencies of {package_id}, \
+                     only including its own linked paths: {error}"
+                );
+                [package_id.to_owned()].into()
+            }
+        };
+
+        let is_relevant = |requested_by: &BTreeSet<String>| {
+            requested_by.is_empty() || requested_by.iter().any(|id| relevant_ids.contains(id))
+        };
+
+        self.linked_paths
+            .iter()
+            .filter(|(_, requested_by)| is_relevant(requested_by))
+            .filter_map(|(rel_path, _)| self.existing_linked_path(rel_path, &RealPathChecker))
+            .chain(
+                self.absolute_linked_paths
+                    .iter()
+                    .filter(|(abs_path, requested_by)| {
+                        is_relevant(requested_by) && RealPathChecker.exists(abs_path)
+                    })
+                    .map(|(abs_path, _)| abs_path.clone()),
+            )
+            .chain(self.libdirs())
+            .collect()
+    }
+
+    /// Returns dangling symlinks directly within linked path directories.
+    ///
+    /// With a reused build, a linked path directory might exist while the libraries within it are
+    /// symlinks to files that have since been moved or deleted. Such directories pass the
+    /// existence check in [`Self::dylib_paths`], but loading libraries from them fails at
+    /// runtime.
+    ///
+    /// This reads every linked path directory (relative and absolute) that exists on disk, so it
+    /// isn't performed by default. Directories that can't be read are skipped with a warning.
+    pub fn linked_paths_with_broken_symlinks(&self) -> Vec<Utf8PathBuf> {
+        let dirs = self
+            .linked_paths
+            .keys()
+            .filter_map(|rel_path| self.existing_linked_path(rel_path, &RealPathChecker))
+            .chain(
+                self.absolute_linked_paths
+                    .keys()
+                    .filter(|abs_path| RealPathChecker.exists(abs_path))
+                    .cloned(),
+            );
+
+        let mut broken = Vec::new();
+        for dir in dirs {
+            let entries = match dir.read_dir_utf8() {
+                Ok(entries) => entries,
+                Err(error) => {
+                    log::warn!(
+                        target: "nextest-runner::list",
+                        "error reading linked path `{dir}`, skipping: {error}"
+                    );
+                    continue;
+                }
+            };
+            for entry in entries.flatten() {
+                let path = entry.path();
+                // symlink_metadata doesn't follow the symlink, while metadata does and fails if
+                // the symlink is dangling.
+                let is_symlink = path
+                    .symlink_metadata()
+                    .is_ok_and(|metadata| metadata.file_type().is_symlink());
+                if is_symlink && path.metadata().is_err() {
+                    broken.push(path.to_path_buf());
+                }
+            }
+        }
+        broken.sort_unstable();
+        broken
+    }
+
+    /// Returns the absolute path of a linked path, if it's a directory on disk according to
+    /// `checker`.
+    fn existing_linked_path(
+        &self,
+        rel_path: &Utf8Path,
+        checker: &dyn PathChecker,
+    ) -> Option<Utf8PathBuf> {
+        let join_path = self
+            .target_directory
+            .join(convert_rel_path_to_main_sep(rel_path));
+        checker.is_dir(&join_path).then_some(join_path)
+    }
+
+    /// Returns the host and target libdirs, if available.
+    fn libdirs(&self) -> Vec<Utf8PathBuf> {
+        // Add rust libdirs to the path if available, so we can run test binaries that depend on
+        // libstd.
+        //
+        // We could be smarter here and only add the host libdir for host binaries and the target
+        // libdir for target binaries, but it's simpler to just add both for now.
+        self.build_platforms
+            .host
+            .libdir
+            .as_path()
+            .into_iter()
+            .chain(
+                self.build_platforms
+                    .target
+                    .as_ref()
+                    .and_then(|target| target.libdir.as_path()),
+            )
+            .map(|libdir| libdir.to_path_buf())
+            .collect()
+    }
+
+    /// Returns [`Self::dylib_paths`] joined into a single value suitable for the dynamic library
+    /// environment variable.
+    ///
+    /// The paths are joined with the separator for the platform test binaries are built for (see
+    /// [`BuildPlatforms::path_separator`]). Unlike the value nextest sets while running tests,
+    /// this does not include the existing value of the environment variable.
+    pub fn dylib_path_env_value(&self) -> String {
+        let separator = self.build_platforms.path_separator().to_string();
+        self.dylib_paths().iter().join(&separator)
+    }
+
+    /// Returns the value nextest sets for the dynamic library path environment variable while
+    /// running tests: [`Self::dylib_paths`], followed by the paths in `existing`.
+    ///
+    /// `existing` is the current value of the variable, if set. The paths are joined with
+    /// [`std::env::join_paths`], so they're in the format of the platform nextest is running on.
+    pub(crate) fn dylib_path_with_existing(
+        &self,
+        existing: Option<&OsStr>,
+    ) -> Result<OsString, CreateTestListError> {
+        let envvar = self.dylib_path_envvar();
+        let dylib_path = dylib_path(existing);
+        let dylib_path_is_empty = dylib_path.is_empty();
+        let new_paths = self.dylib_paths();
+
+        let mut updated_dylib_path: Vec<PathBuf> =
+            Vec::with_capacity(dylib_path.len() + new_paths.len());
+        updated_dylib_path.extend(
+            new_paths
+                .iter()
+                .map(|path| path.clone().into_std_path_buf()),
+        );
+        updated_dylib_path.extend(dylib_path);
+
+        // On macOS, these are the defaults when DYLD_FALLBACK_LIBRARY_PATH isn't set or set to an
+        // empty string. (This is relevant if nextest is invoked as its own process and not
+        // a Cargo subcommand.) DYLD_LIBRARY_PATH doesn't have defaults.
+        //
+        // This copies the logic from
+        // https://cs.github.com/rust-lang/cargo/blob/7d289b171183578d45dcabc56db6db44b9accbff/src/cargo/core/compiler/compilation.rs#L292.
+        if envvar == "DYLD_FALLBACK_LIBRARY_PATH" && dylib_path_is_empty {
+            if let Some(home) = home::home_dir() {
+                updated_dylib_path.push(home.join("lib"));
+            }
+            updated_dylib_path.push("/usr/local/lib".into());
+            updated_dylib_path.push("/usr/lib".into());
+        }
+
+        std::env::join_paths(updated_dylib_path)
+            .map_err(move |error| CreateTestListError::dylib_join_paths(new_paths, error))
+    }
+}
+
+impl<State> RustBuildMeta<State> {
+    /// Creates a `RustBuildMeta` from a serializable summary.
+    ///
+    /// Returns an error if a base output directory or build script output directory would be
+    /// outside the target directory.
+    pub fn from_summary(summary: RustBuildMetaSummary) -> Result<Self, RustBuildMetaParseError> {
+        validate::check_summary_paths_within_target_dir(&summary)?;
+        let build_platforms = Self::build_platforms_from_summary(&summary)?;
+        Ok(Self::from_summary_and_platforms(summary, build_platforms))
+    }
+
+    /// Creates a `RustBuildMeta` from a serializable summary, using `build_platforms` rather than
+    /// the platforms recorded in the summary.
+    ///
+    /// This is useful if the libdirs are already known, for example from an external build
+    /// system. The triples in `build_platforms` must match the ones recorded in the summary. The
+    /// host triple is only checked if the summary records it, which isn't the case for summaries
+    /// produced by older versions of nextest.
+    pub fn from_summary_with_platforms(
+        summary: RustBuildMetaSummary,
+        build_platforms: BuildPlatforms,
+    ) -> Result<Self, RustBuildMetaParseError> {
+        validate::check_summary_paths_within_target_dir(&summary)?;
+        let recorded = Self::build_platforms_from_summary(&summary)?;
+
+        if summary.platforms.is_some()
+            && recorded.host.platform.triple_str() != build_platforms.host.platform.triple_str()
+        {
+            return Err(RustBuildMetaParseError::PlatformMismatch {
+                kind: "host",
+                recorded: recorded.host.platform.triple_str().to_owned(),
+                supplied: build_platforms.host.platform.triple_str().to_owned(),
+            });
+        }
+
+        let recorded_target = recorded
+            .target
+            .as_ref()
+            .map(|target| target.triple.platform.triple_str());
+        let supplied_target = build_platforms
+            .target
+            .as_ref()
+            .map(|target| target.triple.platform.triple_str());
+        if recorded_target != supplied_target {
+            return Err(RustBuildMetaParseError::PlatformMismatch {
+                kind: "target",
+                recorded: recorded_target.unwrap_or("(none)").to_owned(),
+                supplied: supplied_target.unwrap_or("(none)").to_owned(),
+            });
+        }
+
+        Ok(Self::from_summary_and_platforms(summary, build_platforms))
+    }
+
+    fn build_platforms_from_summary(
+        summary: &RustBuildMetaSummary,
+    ) -> Result<BuildPlatforms, RustBuildMetaParseError> {
+        if let Some(summary) = &summary.platforms {
+            BuildPlatforms::from_summary(summary.clone())
+        } else if let Some(summary) = summary.target_platforms.first() {
+            // Compatibility with metadata generated by older versions of nextest.
+            BuildPlatforms::from_target_summary(summary.clone())
+        } else {
+            // Compatibility with metadata generated by older versions of nextest.
+            BuildPlatforms::from_summary_str(summary.target_platform.clone())
+        }
+    }
+
+    fn from_summary_and_platforms(
+        summary: RustBuildMetaSummary,
+        build_platforms: BuildPlatforms,
+    ) -> Self {
+        // Metadata produced by older versions of nextest doesn't record the packages that
+        // requested each linked path.
+        let mut requested_by = summary.linked_path_requested_by;
+        let mut with_requested_by = |linked_path: Utf8PathBuf| {
+            let requested_by = requested_by.remove(&linked_path).unwrap_or_default();
+            (linked_path, requested_by)
+        };
+
+        Self {
+            target_directory: summary.target_directory,
+            base_output_directories: summary.base_output_directories,
+            base_output_directory_categories: summary.base_output_directory_categories,
+            build_script_out_dirs: summary.build_script_out_dirs,
+            non_test_binaries: summary.non_test_binaries,
+            linked_paths: summary
+                .linked_paths
+                .into_iter()
+                .map(&mut with_requested_by)
+                .collect(),
+            absolute_linked_paths: summary
+                .absolute_linked_paths
+                .into_iter()
+                .map(&mut with_requested_by)
+                .collect(),
+            linked_libs: summary.linked_libs,
+            resolved_search_dirs: summary.resolved_search_dirs,
+            extra_output_roots: summary.extra_output_roots,
+            label: summary.label,
+            host_os_version: summary.host_os_version,
+            rust_version: summary.rust_version,
+            list_command: summary.list_command,
+            built_at: summary.built_at,
+            debuginfo: summary.debuginfo,
+            incremental: summary.incremental,
+            enabled_features: summary.enabled_features,
+            include_linked_path_parents: false,
+            scan_linked_path_subdirs: false,
+            use_dyld_library_path: false,
+            exclude_example_output_dirs: false,
+            state: PhantomData,
+            build_platforms,
+        }
+    }
+
+    /// Reads a serialized [`RustBuildMetaSummary`] from `path` and converts it to a
+    /// `RustBuildMeta`.
+    ///
+    /// Reading and parsing the file are blocking operations, so they're performed on Tokio's
+    /// blocking thread pool. This makes it possible to call this from within an async runtime
+    /// without stalling it. Must be called from within the context of a Tokio runtime.
+    pub async fn load_summary(path: &Utf8Path) -> Result<Self, MetadataMaterializeError>
+    where
+        State: Send + 'static,
+    {
+        let path = path.to_owned();
+        let task_path = path.clone();
+        match tokio::task::spawn_blocking(move || Self::load_summary_blocking(&task_path)).await {
+            Ok(res) => res,
+            Err(error) => match error.try_into_panic() {
+                // Propagate panics from the blocking task.
+                Ok(panic) => std::panic::resume_unwind(panic),
+                // The task was cancelled, for example because the runtime is shutting down.
+                Err(error) => Err(MetadataMaterializeError::Cancelled { path, error }),
+            },
+        }
+    }
+
+    fn load_summary_blocking(path: &Utf8Path) -> Result<Self, MetadataMaterializeError> {
+        // Buffering the contents in memory is generally much faster than trying to read it
+        // using a BufReader.
+        let contents =
+            std::fs::read_to_string(path).map_err(|error| MetadataMaterializeError::Read {
+                path: path.to_owned(),
+                error,
+            })?;
+        let summary: RustBuildMetaSummary = serde_json::from_str(&contents).map_err(|error| {
+            MetadataMaterializeError::Deserialize {
+                path: path.to_owned(),
+                error,
+            }
+        })?;
+        Self::from_summary(summary).map_err(|error| MetadataMaterializeError::RustBuildMeta {
+            path: path.to_owned(),
+            error,
+        })
+    }
+
+    /// Warns if the current host's operating system is older than the one recorded in
+    /// [`Self::host_os_version`].
+    ///
+    /// Test binaries built against a newer glibc or macOS SDK might fail to load on an older
+    /// host, for example with "GLIBC_2.34 not found".
+    pub fn check_host_os_version(&self) {
+        if let Some(diagnostic) = self.host_os_version_diagnostic() {
+            diagnostic.log();
+        }
+    }
+
+    fn host_os_version_diagnostic(&self) -> Option<Diagnostic> {
+        let recorded = self.host_os_version.as_ref()?;
+        let current = detect_host_os_version()?;
+        is_older_os_version(&current, recorded).then(|| {
+            Diagnostic::warning(
+                DiagnosticCode::HOST_OS_OLDER,
+                format!(
+                    "this machine has {current}, which is older than {recorded} on the machine \
+                     tests were built on: test binaries may fail to load",
+                ),
+            )
+        })
+    }
+
+    /// Warns if the current rustc is older than the one recorded in [`Self::rust_version`].
+    ///
+    /// This usually means a build is being reused on a machine with a different toolchain, where
+    /// some runtime behavior might differ from the machine tests were built on.
+    pub fn check_rust_version(&self) {
+        if let Some(diagnostic) = self.rust_version_diagnostic() {
+            diagnostic.log();
+        }
+    }
+
+    fn rust_version_diagnostic(&self) -> Option<Diagnostic> {
+        self.rust_version_diagnostic_with(&detect_rustc_version()?)
+    }
+
+    fn rust_version_diagnostic_with(&self, current: &str) -> Option<Diagnostic> {
+        let recorded = self.rust_version.as_ref()?;
+        is_older_rustc_version(current, recorded).then(|| {
+            Diagnostic::warning(
+                DiagnosticCode::RUSTC_OLDER,
+                format!(
+                    "this machine has rustc {current}, which is older than rustc {recorded} that \
+                     tests were built with: runtime behavior may differ",
+                ),
+            )
+        })
+    }
+
+    /// Checks the features recorded in this metadata against `requested`, the features that would
+    /// be enabled for the current test run, keyed by package ID.
+    ///
+    /// Packages missing from either this metadata or `requested` are skipped, as is metadata that
+    /// didn't record features at all. A warning is logged for each mismatch, and `true` is
+    /// returned if there were no mismatches.
+    pub fn check_enabled_features(&self, requested: &BTreeMap<String, BTreeSet<String>>) -> bool {
+        let mismatches = self.enabled_features_mismatches(requested);
+        for mismatch in &mismatches {
+            log::warn!(target: "nextest-runner::list", "{mismatch}");
+        }
+        mismatches.is_empty()
+    }
+
+    /// Returns the packages whose recorded features differ from `requested`.
+    ///
+    /// See [`Self::check_enabled_features`] for more.
+    pub fn enabled_features_mismatches(
+        &self,
+        requested: &BTreeMap<String, BTreeSet<String>>,
+    ) -> Vec<EnabledFeaturesMismatch> {
+        self.enabled_features
+            .iter()
+            .filter_map(|(package_id, recorded)| {
+                let requested = requested.get(package_id)?;
+                (recorded != requested).then(|| EnabledFeaturesMismatch {
+                    package_id: package_id.clone(),
+                    recorded: recorded.clone(),
+                    requested: requested.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the number of distinct packages that requested linked paths, including linked
+    /// paths outside the target directory.
+    ///
+    /// Summaries produced by older versions of nextest don't record the packages that requested
+    /// linked paths, so this returns 0 for metadata read from them.
+    pub fn packages_with_linked_paths(&self) -> usize {
+        self.linked_paths
+            .values()
+            .chain(self.absolute_linked_paths.values())
+            .flatten()
+            .collect::<HashSet<_>>()
+            .len()
+    }
+
+    /// Splits linked paths by the packages that requested them, returning `(workspace, external)`.
+    ///
+    /// A path is in the first list if it was requested by any package in `workspace_members`, and
+    /// in the second list if it was requested by any other package, so a path requested by both
+    /// appears in both lists. Linked paths within the target directory are returned relative to
+    /// it, followed by absolute linked paths.
+    ///
+    /// This relies on the packages that requested each linked path being recorded. Paths without
+    /// any recorded packages are omitted: this only happens for metadata read from a summary
+    /// written by an older version of nextest, which didn't record them.
+    pub fn linked_paths_by_origin(
+        &self,
+        workspace_members: &BTreeSet<String>,
+    ) -> (Vec<Utf8PathBuf>, Vec<Utf8PathBuf>) {
+        let mut workspace = Vec::new();
+        let mut external = Vec::new();
+        for (path, package_ids) in self.linked_paths.iter().chain(&self.absolute_linked_paths) {
+            if package_ids.iter().any(|id| workspace_members.contains(id)) {
+                workspace.push(path.clone());
+            }
+            if package_ids.iter().any(|id| !workspace_members.contains(id)) {
+                external.push(path.clone());
+            }
+        }
+        (workspace, external)
+    }
+
+    /// Returns the libdir used for proc-macro test binaries, if available.
+    ///
+    /// Proc macros are always built for and run on the host, so this is the host libdir, even
+    /// when cross-compiling.
+    pub fn proc_macro_libdir(&self) -> Option<&Utf8Path> {
+        self.build_platforms.host.libdir.as_path()
+    }
+
+    /// Returns true if the target directory exists and is a directory.
+    ///
+    /// If the target directory is missing, every search directory returned by
+    /// [`RustBuildMeta::dylib_paths`] is filtered out, so this should be checked before running
+    /// tests.
+    pub fn target_directory_exists(&self) -> bool {
+        self.target_directory.is_dir()
+    }
+
+    /// Returns true if the host and target libdirs of `self` and `other` belong to the same
+    /// toolchains, as determined by
+    /// [`PlatformLibdir::toolchain_name`](crate::platform::PlatformLibdir::toolchain_name).
+    ///
+    /// This is a heuristic check for reusing builds across toolchains, e.g. different nightlies,
+    /// which can break tests that load proc macros due to ABI changes. Libdirs whose toolchain
+    /// can't be determined are assumed to match. Mismatches are logged as warnings; use
+    /// [`Self::libdir_toolchain_mismatches`] to get them.
+    pub fn libdir_basename_matches(&self, other: &Self) -> bool {
+        let mismatches = self.libdir_toolchain_mismatches(other);
+        for mismatch in &mismatches {
+            log::warn!(target: "nextest-runner::list", "{mismatch}");
+        }
+        mismatches.is_empty()
+    }
+
+    /// Returns true if the libdirs in this metadata match the ones detected for the current
+    /// toolchain, with [`detect_libdir`].
+    ///
+    /// If rustc resolves to a different toolchain than when tests were built, for example because
+    /// of a rustup override in the current directory, tests that load the standard library
+    /// dynamically (such as proc-macro tests) might fail. Libdirs that are unavailable, either in
+    /// this metadata or for the current toolchain, are assumed to match. Mismatches are logged as
+    /// warnings.
+    pub fn libdir_matches_current(&self) -> bool {
+        let diagnostics = self.libdir_changed_diagnostics_with(detect_libdir);
+        for diagnostic in &diagnostics {
+            diagnostic.log();
+        }
+        diagnostics.is_empty()
+    }
+
+    fn libdir_changed_diagnostics_with(
+        &self,
+        detect: impl Fn(Option<&TargetTriple>) -> PlatformLibdir,
+    ) -> Vec<Diagnostic> {
+        let host = Some(("host", None, &self.build_platforms.host.libdir));
+        let target = self
+            .build_platforms
+            .target
+            .as_ref()
+            .map(|target| ("target", Some(&target.triple), &target.libdir));
+
+        host.into_iter()
+            .chain(target)
+            .filter_map(|(kind, triple, recorded)| {
+                let recorded = recorded.as_path()?;
+                let current = detect(triple);
+                let current = current.as_path()?;
+                (recorded != current).then(|| {
+                    Diagnostic::warning(
+                        DiagnosticCode::LIBDIR_CHANGED,
+                        format!(
+                            "{kind} libdir `{recorded}` recorded when tests were built differs \
+                             from `{current}` detected now: rustc might resolve to a different \
+                             toolchain, e.g. due to a rustup override",
+                        ),
+                    )
+                    .with_related_paths([recorded.to_owned(), current.to_owned()])
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the libdirs of `self` and `other` that belong to different toolchains.
+    ///
+    /// See [`Self::libdir_basename_matches`] for more.
+    pub fn libdir_toolchain_mismatches(&self, other: &Self) -> Vec<LibdirToolchainMismatch> {
+        let host = Some((
+            "host",
+            &self.build_platforms.host.libdir,
+            &other.build_platforms.host.libdir,
+        ));
+        let target = self
+            .build_platforms
+            .target
+            .as_ref()
+            .zip(other.build_platforms.target.as_ref())
+            .map(|(this, other)| ("target", &this.libdir, &other.libdir));
+
+        host.into_iter()
+            .chain(target)
+            .filter_map(|(kind, this, other)| {
+                let this = this.toolchain_name()?;
+                let other = other.toolchain_name()?;
+                (this != other).then(|| LibdirToolchainMismatch {
+                    kind,
+                    this: this.to_owned(),
+                    other: other.to_owned(),
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the non-test binaries that were added or removed in `other` compared to `self`.
+    ///
+    /// Binaries are compared by package ID and name, since their paths are machine-specific.
+    pub fn non_test_binary_diff(&self, other: &Self) -> NonTestBinaryDiff {
+        let this = self.non_test_binary_names();
+        let other = other.non_test_binary_names();
+        NonTestBinaryDiff {
+            added: names_difference(&other, &this),
+            removed: names_difference(&this, &other),
+        }
+    }
+
+    /// Returns the names of non-test binaries, keyed by package ID.
+    fn non_test_binary_names(&self) -> BTreeMap<&str, BTreeSet<&str>> {
+        self.non_test_binaries
+            .iter()
+            .map(|(package_id, binaries)| {
+                let names = binaries.iter().map(|binary| binary.name.as_str()).collect();
+                (package_id.as_str(), names)
+            })
+            .collect()
+    }
+
+    /// Checks that each base output directory is on the same filesystem as the target directory.
+    ///
+    /// Returns the absolute paths of base output directories that are on a different device, in
+    /// which case hard links between them and the target directory can't be created. Directories
+    /// that can't be read are skipped.
+    ///
+    /// This check is only performed on Unix. On other platforms, it always returns `Ok(())`.
+    pub fn base_dirs_same_filesystem(&self) -> Result<(), Vec<Utf8PathBuf>> {
+        cfg_if::cfg_if! {
+            if #[cfg(unix)] {
+                use std::os::unix::fs::MetadataExt;
+
+                let Ok(target_dev) = self.target_directory.metadata().map(|m| m.dev()) else {
+                    // Nothing to compare against.
+                    return Ok(());
+                };
+                let different: Vec<_> = self
+                    .base_output_directories
+                    .iter()
+                    .map(|base| self.target_directory.join(convert_rel_path_to_main_sep(base)))
+                    .filter(|abs_base| {
+                        abs_base
+                            .metadata()
+                            .is_ok_and(|metadata| metadata.dev() != target_dev)
+                    })
+                    .collect();
+                if different.is_empty() {
+                    Ok(())
+                } else {
+                    Err(different)
+                }
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    /// Returns a fingerprint of the machine-independent parts of this metadata.
+    ///
+    /// The fingerprint covers the host and target triples, relative directories, non-test
+    /// binaries, and package IDs. It excludes the absolute target directory and libdirs, and the
+    /// local paths embedded in the IDs of path dependencies (e.g. `path+file:///...#foo@0.1.0`).
+    /// Two checkouts of the same commit on different machines produce the same fingerprint.
+    ///
+    /// The fingerprint is stable across nextest versions as long as the set of fields it covers
+    /// doesn't change.
+    pub fn logical_fingerprint(&self) -> String {
+        let mut lines = Vec::new();
+        let mut push = |key: &str, value: String| lines.push(format!("{key}\t{value}"));
+
+        push(
+            "host",
+            self.build_platforms.host.platform.triple_str().to_owned(),
+        );
+        if let Some(target) = &self.build_platforms.target {
+            push("target", target.triple.platform.triple_str().to_owned());
+        }
+        for dir in &self.base_output_directories {
+            push(
+                "base-output-dir",
+                convert_rel_path_to_forward_slash(dir).into(),
+            );
+        }
+        for (package_id, binaries) in &self.non_test_binaries {
+            for binary in binaries {
+                push(
+                    "non-test-binary",
+                    format!(
+                        "{}\t{}\t{}\t{}",
+                        logical_package_id(package_id),
+                        binary.name,
+                        binary.kind,
+                        convert_rel_path_to_forward_slash(&binary.path),
+                    ),
+                );
+            }
+        }
+        for (package_id, out_dir) in &self.build_script_out_dirs {
+            push(
+                "build-script-out-dir",
+                format!(
+                    "{}\t{}",
+                    logical_package_id(package_id),
+                    convert_rel_path_to_forward_slash(out_dir),
+                ),
+            );
+        }
+        for (linked_path, package_ids) in &self.linked_paths {
+            for package_id in package_ids {
+                push(
+                    "linked-path",
+                    format!(
+                        "{}\t{}",
+                        convert_rel_path_to_forward_slash(linked_path),
+                        logical_package_id(package_id),
+                    ),
+                );
+            }
+        }
+
+        // Sort the lines, since normalizing package IDs can change their relative order.
+        lines.sort_unstable();
+        let input = lines.join("\n");
+        format!("{:016x}", xxh64(input.as_bytes(), 0))
+    }
+
+    /// Returns a stable file name for a reuse archive built from this metadata, suitable as a key
+    /// in a content-addressed cache.
+    ///
+    /// The name is of the form `nextest-archive-<platforms>-<profiles>-<fingerprint>.tar.zst`,
+    /// where:
+    ///
+    /// * `<platforms>` is the host triple, followed by the target triple if specified.
+    /// * `<profiles>` is the set of Cargo profile directories (e.g. `debug`) across the base
+    ///   output directories.
+    /// * `<fingerprint>` is the [logical fingerprint](Self::logical_fingerprint).
+    ///
+    /// Multiple platforms or profiles are joined with `+`. Characters other than ASCII
+    /// alphanumerics, `-`, `_`, `.` and `+` are replaced with `_`, so the name is valid on all
+    /// platforms.
+    pub fn suggested_archive_name(&self) -> String {
+        let mut platforms = vec![self.build_platforms.host.platform.triple_str()];
+        if let Some(target) = &self.build_platforms.target {
+            platforms.push(target.triple.platform.triple_str());
+        }
+        let profiles: BTreeSet<_> = self
+            .base_output_directories
+            .iter()
+            .filter_map(|dir| self.output_dir_profile(dir))
+            .collect();
+
+        let mut components = vec![platforms.join("+")];
+        if !profiles.is_empty() {
+            components.push(profiles.into_iter().join("+"));
+        }
+        components.push(self.logical_fingerprint());
+
+        let name: String = components
+            .join("-")
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '+') {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        format!("nextest-archive-{name}.tar.zst")
+    }
+
+    /// Returns the Cargo profile directory that a base output directory is in.
+    ///
+    /// Target artifacts are in `<triple>/<profile>`, and host artifacts in `<profile>`.
+    fn output_dir_profile<'a>(&self, dir: &'a Utf8Path) -> Option<&'a str> {
+        let rel_dir = self
+            .build_platforms
+            .target
+            .as_ref()
+            .and_then(|target| dir.strip_prefix(target.triple.platform.triple_str()).ok())
+            .unwrap_or(dir);
+        match rel_dir.components().next() {
+            Some(Utf8Component::Normal(profile)) => Some(profile),
+            _ => None,
+        }
+    }
+
+    /// Returns the non-test `bin` executables built in the workspace, with paths resolved against
+    /// the target directory.
+    ///
+    /// The tools are sorted by package ID, then by name.
+    pub fn runnable_tools(&self) -> Vec<RunnableTool> {
+        self.non_test_binaries
+            .iter()
+            .flat_map(|(package_id, binaries)| {
+                binaries
+                    .iter()
+                    .filter(|binary| binary.kind == RustNonTestBinaryKind::BIN_EXE)
+                    .map(move |binary| RunnableTool {
+                        package_id: package_id.clone(),
+                        name: binary.name.clone(),
+                        path: self
+                            .target_directory
+                            .join(convert_rel_path_to_main_sep(&binary.path)),
+                    })
+            })
+            .collect()
+    }
+
+    /// Returns the artifact category of the given base output directory, relative to the target
+    /// directory.
+    ///
+    /// Returns [`BaseOutputDirCategory::UNCATEGORIZED`] if the directory wasn't categorized, e.g.
+    /// if it was recorded by an older version of nextest.
+    pub fn base_output_dir_category(&self, dir: &Utf8Path) -> BaseOutputDirCategory {
+        self.base_output_directory_categories
+            .get(dir)
+            .cloned()
+            .unwrap_or(BaseOutputDirCategory::UNCATEGORIZED)
+    }
+
+    /// Returns the base output directories in the given category, relative to the target
+    /// directory.
+    pub fn base_output_dirs_for(&self, category: &BaseOutputDirCategory) -> Vec<Utf8PathBuf> {
+        self.base_output_directories
+            .iter()
+            .filter(|dir| self.base_output_dir_category(dir) == *category)
+            .cloned()
+            .collect()
+    }
+
+    /// Returns the directories under the target directory that tests might write to, as absolute
+    /// paths.
+    ///
+    /// This consists of build script output directories (exposed to tests as `OUT_DIR`), followed
+    /// by nextest's default store directory, `<target-dir>/nextest`. A custom `store.dir` set in
+    /// nextest's configuration isn't known to the metadata, so it isn't included.
+    ///
+    /// This only lists directories: it doesn't check whether they exist.
+    pub fn writable_test_dirs(&self) -> Vec<Utf8PathBuf> {
+        self.build_script_out_dirs
+            .values()
+            .map(|out_dir| {
+                self.target_directory
+                    .join(convert_rel_path_to_main_sep(out_dir))
+            })
+            .chain(std::iter::once(self.target_directory.join("nextest")))
+            .unique()
+            .collect()
+    }
+
+    /// Returns the directories known to the metadata that tests are likely to write to at
+    /// runtime, as absolute paths.
+    ///
+    /// This is a best-effort heuristic, meant for deciding which directories to mount read-write
+    /// in a sandbox. It returns:
+    ///
+    /// 1. Build script output directories, since tests sometimes write to `OUT_DIR`.
+    /// 2. Base output directories (e.g. `<target-dir>/debug`), since tests sometimes write next to
+    ///    their own binaries. Subdirectories such as `deps` are covered by these.
+    ///
+    /// Directories that tests create themselves, or that are only known from environment
+    /// variables, can't be listed. Unlike [`Self::writable_test_dirs`], nextest's store directory
+    /// isn't included, since tests don't write to it. Existence isn't checked.
+    pub fn probably_writable_dirs(&self) -> Vec<Utf8PathBuf> {
+        self.build_script_out_dirs
+            .values()
+            .chain(&self.base_output_directories)
+            .map(|dir| {
+                self.target_directory
+                    .join(convert_rel_path_to_main_sep(dir))
+            })
+            .unique()
+            .collect()
+    }
+
+    /// Returns the search directories derived from linked paths and base output directories,
+    /// relative to the target directory, in the order Cargo adds them.
+    pub(crate) fn relative_search_dirs(&self) -> impl Iterator<Item = Utf8PathBuf> + '_ {
+        self.linked_paths
+            .keys()
+            .cloned()
+            .chain(self.base_output_directories.iter().flat_map(|base_output| {
+                [
+                    convert_rel_path_to_forward_slash(&base_output.join("deps")),
+                    base_output.clone(),
+                ]
+            }))
+            .unique()
+    }
+
+    /// Converts self to a serializable form.
+    pub fn to_summary(&self) -> RustBuildMetaSummary {
+        RustBuildMetaSummary {
+            target_directory: self.target_directory.clone(),
+            base_output_directories: self.base_output_directories.clone(),
+            base_output_directory_categories: self.base_output_directory_categories.clone(),
+            non_test_binaries: self.non_test_binaries.clone(),
+            build_script_out_dirs: self.build_script_out_dirs.clone(),
+            linked_paths: self.linked_paths.keys().cloned().collect(),
+            absolute_linked_paths: self.absolute_linked_paths.keys().cloned().collect(),
+            linked_path_requested_by: self
+                .linked_paths
+                .iter()
+                .chain(&self.absolute_linked_paths)
+                .filter(|(_, requested_by)| !requested_by.is_empty())
+                .map(|(path, requested_by)| (path.clone(), requested_by.clone()))
+                .collect(),
+            linked_libs: self.linked_libs.clone(),
+            resolved_search_dirs: self.resolved_search_dirs.clone(),
+            extra_output_roots: self.extra_output_roots.clone(),
+            label: self.label.clone(),
+            host_os_version: self.host_os_version.clone(),
+            rust_version: self.rust_version.clone(),
+            list_command: self.list_command.clone(),
+            built_at: self.built_at.clone(),
+            debuginfo: self.debuginfo.clone(),
+            incremental: self.incremental,
+            enabled_features: self.enabled_features.clone(),
+            target_platform: self.build_platforms.to_summary_str(),
+            target_platforms: vec![self.build_platforms.to_target_or_host_summary()],
+            // TODO: support multiple --target options
+            platforms: Some(BuildPlatformsSummary {
+                host: self.build_platforms.host.to_summary(),
+                targets: self
+                    .build_platforms
+                    .target
+                    .as_ref()
+                    .into_iter()
+                    .map(TargetPlatform::to_summary)
+                    .collect(),
+            }),
+        }
+    }
+}
+
+/// A package whose features differ between the build and the current test run, returned by
+/// [`RustBuildMeta::enabled_features_mismatches`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EnabledFeaturesMismatch {
+    /// The package ID.
+    pub package_id: String,
+
+    /// The features enabled when the build was produced.
+    pub recorded: BTreeSet<String>,
+
+    /// The features requested for the current test run.
+    pub requested: BTreeSet<String>,
+}
+
+impl fmt::Display for EnabledFeaturesMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let display_features = |features: &BTreeSet<String>| {
+            if features.is_empty() {
+                "(none)".to_owned()
+            } else {
+                features.iter().join(", ")
+            }
+        };
+        write!(
+            f,
+            "package `{}` was built with features {}, but features {} were requested",
+            self.package_id,
+            display_features(&self.recorded),
+            display_features(&self.requested),
+        )
+    }
+}
+
+/// A libdir whose toolchain differs between two [`RustBuildMeta`] instances, returned by
+/// [`RustBuildMeta::libdir_toolchain_mismatches`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LibdirToolchainMismatch {
+    /// The kind of libdir: `"host"` or `"target"`.
+    pub kind: &'static str,
+
+    /// The toolchain name for `self`.
+    pub this: String,
+
+    /// The toolchain name for `other`.
+    pub other: String,
+}
+
+impl fmt::Display for LibdirToolchainMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} libdir toolchain differs: `{}` vs `{}`, tests that load proc macros may fail",
+            self.kind, self.this, self.other,
+        )
+    }
+}
+
+/// The non-test binaries added or removed between two builds, returned by
+/// [`RustBuildMeta::non_test_binary_diff`].
+///
+/// Both maps are keyed by package ID, and contain binary names. Packages without changes are
+/// omitted.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct NonTestBinaryDiff {
+    /// Binaries present in the other build but not in this one.
+    pub added: BTreeMap<String, BTreeSet<String>>,
+
+    /// Binaries present in this build but not in the other one.
+    pub removed: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl NonTestBinaryDiff {
+    /// Returns true if no binaries were added or removed.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+impl fmt::Display for NonTestBinaryDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "no non-test binaries added or removed");
+        }
+        let lines = [("+", &self.added), ("-", &self.removed)]
+            .into_iter()
+            .flat_map(|(prefix, changes)| {
+                changes.iter().flat_map(move |(package_id, names)| {
+                    names
+                        .iter()
+                        .map(move |name| format!("{prefix} {name} (package `{package_id}`)"))
+                })
+            });
+        write!(f, "{}", lines.format("\n"))
+    }
+}
+
+/// The kind of a path returned by [`RustBuildMeta::dylib_search_dirs`].
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum DylibPathKind {
+    /// A linked path requested by a build script, or one of its parents or subdirectories.
+    LinkedPath,
+
+    /// A base output directory or extra output root.
+    BaseOutput,
+
+    /// The `deps` subdirectory of a base output directory or extra output root.
+    BaseOutputDeps,
+
+    /// The rustc libdir for the host or target platform.
+    RustcLibdir,
+}
+
+impl fmt::Display for DylibPathKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::LinkedPath => write!(f, "linked path"),
+            Self::BaseOutput => write!(f, "base output directory"),
+            Self::BaseOutputDeps => write!(f, "base output deps directory"),
+            Self::RustcLibdir => write!(f, "rustc libdir"),
+        }
+    }
+}
+
+/// A path considered for the dynamic library path, returned by
+/// [`RustBuildMeta::dylib_paths_with_status`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DylibPathCandidate {
+    /// The absolute path.
+    pub path: Utf8PathBuf,
+
+    /// The kind of path.
+    pub kind: DylibPathKind,
+
+    /// Whether the path was kept or skipped.
+    pub status: DylibPathStatus,
+}
+
+impl fmt::Display for DylibPathCandidate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:<30} {:<26} {}",
+            self.status.to_string(),
+            self.kind.to_string(),
+            self.path
+        )
+    }
+}
+
+/// Whether a [`DylibPathCandidate`] was kept or skipped.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DylibPathStatus {
+    /// The path is part of the dynamic library path.
+    Kept,
+
+    /// The path was skipped.
+    Skipped(DylibPathSkipReason),
+}
+
+impl fmt::Display for DylibPathStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Kept => write!(f, "kept"),
+            Self::Skipped(reason) => write!(f, "skipped ({reason})"),
+        }
+    }
+}
+
+/// The reason a [`DylibPathCandidate`] was skipped.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum DylibPathSkipReason {
+    /// The path doesn't exist on disk.
+    NotFound,
+
+    /// The path was already added as an earlier candidate.
+    Duplicate,
+
+    /// The path exists, but isn't a directory.
+    NotADirectory,
+
+    /// The path contains NUL or newline characters.
+    ControlCharacters,
+}
+
+impl fmt::Display for DylibPathSkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound => write!(f, "not found"),
+            Self::Duplicate => write!(f, "duplicate"),
+            Self::NotADirectory => write!(f, "not a directory"),
+            Self::ControlCharacters => write!(f, "control characters"),
+        }
+    }
+}
+
+/// A non-test executable built in the workspace, returned by [`RustBuildMeta::runnable_tools`].
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct RunnableTool {
+    /// The package ID of the package the executable belongs to.
+    pub package_id: String,
+
+    /// The name of the executable.
+    pub name: String,
+
+    /// The absolute path to the executable.
+    pub path: Utf8PathBuf,
+}
+
+/// Linked paths after mapping, returned by `RustBuildMeta::map_absolute_linked_paths`.
+struct MappedLinkedPaths {
+    linked_paths: BTreeMap<Utf8PathBuf, BTreeSet<String>>,
+    absolute_linked_paths: BTreeMap<Utf8PathBuf, BTreeSet<String>>,
+    /// Mapped absolute linked paths that don't exist, if paths are being remapped.
+    not_remapped: Vec<Utf8PathBuf>,
+}
+
+/// Checks whether paths exist, for [`RustBuildMeta::dylib_paths_with_checker`].
+///
+/// [`RealPathChecker`] queries the filesystem. Other implementations can be used to compute dynamic
+/// library paths without performing any I/O.
+pub trait PathChecker {
+    /// Returns true if `path` exists.
+    fn exists(&self, path: &Utf8Path) -> bool;
+
+    /// Returns true if `path` exists and is a directory.
+    ///
+    /// The default implementation treats every path that exists as a directory.
+    fn is_dir(&self, path: &Utf8Path) -> bool {
+        self.exists(path)
+    }
+}
+
+/// A [`PathChecker`] that queries the real filesystem.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RealPathChecker;
+
+impl PathChecker for RealPathChecker {
+    fn exists(&self, path: &Utf8Path) -> bool {
+        path.exists()
+    }
+
+    fn is_dir(&self, path: &Utf8Path) -> bool {
+        path.is_dir()
+    }
+}
+
+/// Options that are recorded as-is by [`RustBuildMeta::record_list_command`], along with whether
+/// they take a value.
+static LIST_COMMAND_ALLOWED_OPTIONS: &[(&str, bool)] = &[
+    ("--all", false),
+    ("--all-features", false),
+    ("--all-targets", false),
+    ("--archive-file", true),
+    ("--bench", true),
+    ("--benches", false),
+    ("--bin", true),
+    ("--binaries-metadata", true),
+    ("--bins", false),
+    ("--cargo-profile", true),
+    ("--example", true),
+    ("--examples", false),
+    ("--exclude", true),
+    ("--features", true),
+    ("--filterset", true),
+    ("--frozen", false),
+    ("--ignore-default-filter", false),
+    ("--lib", false),
+    ("--locked", false),
+    ("--manifest-path", true),
+    ("--message-format", true),
+    ("--no-default-features", false),
+    ("--offline", false),
+    ("--package", true),
+    ("--partition", true),
+    ("--platform-filter", true),
+    ("--profile", true),
+    ("--release", false),
+    ("--run-ignored", true),
+    ("--target", true),
+    ("--target-dir", true),
+    ("--test", true),
+    ("--tests", false),
+    ("--workspace", false),
+    ("-E", true),
+    ("-F", true),
+    ("-P", true),
+    ("-T", true),
+    ("-p", true),
+    ("-r", false),
+];
+
+fn redact_list_command(args: impl IntoIterator<Item = String>) -> Vec<String> {
+    const REDACTED: &str = "<redacted>";
+
+    let mut args = args.into_iter().peekable();
+    let mut out = Vec::new();
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            // Test name filters and arguments passed through to test binaries.
+            out.push(arg);
+            out.extend(args);
+            break;
+        }
+        if !arg.starts_with('-') {
+            out.push(arg);
+            continue;
+        }
+
+        let (name, has_inline_value) = match arg.split_once('=') {
+            Some((name, _)) => (name, true),
+            None => (arg.as_str(), false),
+        };
+        let allowed = LIST_COMMAND_ALLOWED_OPTIONS
+            .iter()
+            .find(|(allowed, _)| *allowed == name);
+        match allowed {
+            Some((_, takes_value)) => {
+                let takes_separate_value = *takes_value && !has_inline_value;
+                out.push(arg);
+                if takes_separate_value {
+                    out.extend(args.next());
+                }
+            }
+            None => {
+                out.push(REDACTED.to_owned());
+                // It isn't known whether unrecognized options take a value, so assume that they
+                // do if the next argument isn't an option.
+                if !has_inline_value && args.peek().is_some_and(|next| !next.starts_with('-')) {
+                    args.next();
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Parses the value of the `CARGO_INCREMENTAL` environment variable, which Cargo only recognizes
+/// as `0` or `1`.
+fn parse_cargo_incremental(value: Option<&str>) -> Option<bool> {
+    match value? {
+        "0" => Some(false),
+        "1" => Some(true),
+        _ => None,
+    }
+}
+
+/// Strips the local path out of a path dependency's package ID, leaving the name and version.
+///
+/// Handles both the current (`path+file:///path#name@version`) and the older
+/// (`name version (path+file:///path)`) package ID formats.
+fn logical_package_id(package_id: &str) -> &str {
+    if package_id.starts_with("path+file://") {
+        match package_id.rsplit_once('#') {
+            Some((_, name_version)) => name_version,
+            None => package_id,
+        }
+    } else if package_id.ends_with(')') {
+        match package_id.split_once(" (path+file://") {
+            Some((name_version, _)) => name_version,
+            None => package_id,
+        }
+    } else {
+        package_id
+    }
+}
+
+/// Returns the names in `a` that aren't in `b` for the same package, omitting packages without
+/// any such names.
+fn names_difference(
+    a: &BTreeMap<&str, BTreeSet<&str>>,
+    b: &BTreeMap<&str, BTreeSet<&str>>,
+) -> BTreeMap<String, BTreeSet<String>> {
+    a.iter()
+        .filter_map(|(package_id, names)| {
+            let names: BTreeSet<_> = names
+                .iter()
+                .filter(|name| {
+                    !b.get(package_id)
+                        .is_some_and(|b_names| b_names.contains(*name))
+                })
+                .map(|name| (*name).to_owned())
+                .collect();
+            (!names.is_empty()).then(|| ((*package_id).to_owned(), names))
+        })
+        .collect()
+}
+
+/// Returns true if `path` contains NUL or newline characters.
+///
+/// Such paths can't be passed through environment variables or scripts safely, so they're never
+/// added to the dynamic library path.
+fn has_dylib_path_control_chars(path: &Utf8Path) -> bool {
+    path.as_str().contains(['\0', '\n', '\r'])
+}
+
+/// Returns true if `name` is the file name of a shared library for the platform with the given
+/// triple.
+fn is_shared_library_for(triple: &str, name: &str) -> bool {
+    SharedLibraryFamily::for_triple(triple).matches(name)
+}
+
+/// Returns true if `dir` directly contains a file that looks like a shared library for any
+/// platform, or for the platform with the given triple if one is specified.
+fn contains_shared_libraries(dir: &Utf8Path, triple: Option<&str>) -> bool {
+    let Ok(entries) = dir.read_dir_utf8() else {
+        return false;
+    };
+    entries.flatten().any(|entry| {
+        let name = entry.file_name();
+        match triple {
+            Some(triple) => is_shared_library_for(triple, name),
+            None => SharedLibraryFamily::ALL
+                .iter()
+                .any(|family| family.matches(name)),
+        }
+    })
+}
+
+/// The naming conventions for shared libraries across platforms.
+#[derive(Clone, Copy, Debug)]
+enum SharedLibraryFamily {
+    Windows,
+    Apple,
+    Unix,
+}
+
+impl SharedLibraryFamily {
+    const ALL: [Self; 3] = [Self::Windows, Self::Apple, Self::Unix];
+
+    fn for_triple(triple: &str) -> Self {
+        if triple.contains("-windows") {
+            Self::Windows
+        } else if triple.contains("-apple-") {
+            Self::Apple
+        } else {
+            Self::Unix
+        }
+    }
+
+    fn matches(self, name: &str) -> bool {
+        match self {
+            Self::Windows => name.to_ascii_lowercase().ends_with(".dll"),
+            Self::Apple => name.ends_with(".dylib"),
+            // Versioned shared libraries on Linux are named like libfoo.so.1.2.
+            Self::Unix => name.ends_with(".so") || name.contains(".so."),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        test_helpers::{x86_64_pc_windows_msvc_triple, VirtualPaths},
+        *,
+    };
+    use crate::platform::{BuildPlatforms, HostPlatform, PlatformLibdir, TargetPlatform};
+    use nextest_metadata::{
+        BuildPlatformsSummary, HostPlatformSummary, PlatformLibdirSummary,
+        PlatformLibdirUnavailable,
+    };
+    use target_spec::summaries::PlatformSummary;
+    use test_case::test_case;
+
+    impl Default for RustBuildMeta<BinaryListState> {
+        fn default() -> Self {
+            RustBuildMeta::<BinaryListState>::new(
+                Utf8PathBuf::default(),
+                BuildPlatforms::new_with_no_target()
+                    .expect("creating BuildPlatforms without target triple should succeed"),
+            )
+        }
+    }
+
+    fn host_current() -> HostPlatform {
+        HostPlatform::current(PlatformLibdir::Unavailable(
+            PlatformLibdirUnavailable::OLD_SUMMARY,
+        ))
+        .expect("should detect the host platform successfully")
+    }
+
+    fn host_current_with_libdir(libdir: &str) -> HostPlatform {
+        HostPlatform::current(PlatformLibdir::Available(libdir.into()))
+            .expect("should detect the host platform successfully")
+    }
+
+    fn host_not_current_with_libdir(libdir: &str) -> HostPlatform {
+        cfg_if::cfg_if! {
+            if #[cfg(windows)] {
+                let triple = TargetTriple::x86_64_unknown_linux_gnu();
+            } else {
+                let triple = x86_64_pc_windows_msvc_triple();
+            }
+        };
+
+        HostPlatform {
+            platform: triple.platform,
+            libdir: PlatformLibdir::Available(libdir.into()),
+        }
+    }
+
+    fn target_linux() -> TargetPlatform {
+        TargetPlatform::new(
+            TargetTriple::x86_64_unknown_linux_gnu(),
+            PlatformLibdir::Unavailable(PlatformLibdirUnavailable::OLD_SUMMARY),
+        )
     }
 
     fn target_linux_with_libdir(libdir: &str) -> TargetPlatform {
         TargetPlatform::new(
             TargetTriple::x86_64_unknown_linux_gnu(),
-            PlatformLibdir::Available(libdir.into()),
+            PlatformLibdir::Available(libdir.into()),
+        )
+    }
+
+    fn target_windows() -> TargetPlatform {
+        TargetPlatform::new(
+            x86_64_pc_windows_msvc_triple(),
+            PlatformLibdir::Unavailable(PlatformLibdirUnavailable::OLD_SUMMARY),
+        )
+    }
+
+    #[test_case(RustBuildMetaSummary {
+        ..Default::default()
+    }, RustBuildMeta::<BinaryListState> {
+        build_platforms: BuildPlatforms {
+            host: host_current(),
+            target: None,
+        },
+        ..Default::default()
+    }; "no target platforms")]
+    #[test_case(RustBuildMetaSummary {
+        target_platform: Some("x86_64-unknown-linux-gnu".to_owned()),
+        ..Default::default()
+    }, RustBuildMeta::<BinaryListState> {
+        build_platforms: BuildPlatforms {
+            host: host_current(),
+            target: Some(target_linux()),
+        },
+        ..Default::default()
+    }; "only target platform field")]
+    #[test_case(RustBuildMetaSummary {
+        target_platform: Some("x86_64-unknown-linux-gnu".to_owned()),
+        // target_platforms should be preferred over target_platform
+        target_platforms: vec![PlatformSummary::new("x86_64-pc-windows-msvc")],
+        ..Default::default()
+    }, RustBuildMeta::<BinaryListState> {
+        build_platforms: BuildPlatforms {
+            host: host_current(),
+            target: Some(target_windows()),
+        },
+        ..Default::default()
+    }; "target platform and target platforms field")]
+    #[test_case(RustBuildMetaSummary {
+        target_platform: Some("aarch64-unknown-linux-gnu".to_owned()),
+        target_platforms: vec![PlatformSummary::new("x86_64-pc-windows-msvc")],
+        // platforms should be preferred over both target_platform and target_platforms
+        platforms: Some(BuildPlatformsSummary {
+            host: host_not_current_with_libdir("/fake/test/libdir/281").to_summary(),
+            targets: vec![target_linux_with_libdir("/fake/test/libdir/837").to_summary()],
+        }),
+        ..Default::default()
+    }, RustBuildMeta::<BinaryListState> {
+        build_platforms: BuildPlatforms {
+            host: host_not_current_with_libdir("/fake/test/libdir/281"),
+            target: Some(target_linux_with_libdir("/fake/test/libdir/837")),
+        },
+        ..Default::default()
+    }; "target platform and target platforms and platforms field")]
+    #[test_case(RustBuildMetaSummary {
+        platforms: Some(BuildPlatformsSummary {
+            host: host_current().to_summary(),
+            targets: vec![],
+        }),
+        ..Default::default()
+    }, RustBuildMeta::<BinaryListState> {
+        build_platforms: BuildPlatforms {
+            host: host_current(),
+            target: None,
+        },
+        ..Default::default()
+    }; "platforms with zero targets")]
+    fn test_from_summary(summary: RustBuildMetaSummary, expected: RustBuildMeta<BinaryListState>) {
+        let actual = RustBuildMeta::<BinaryListState>::from_summary(summary)
+            .expect("RustBuildMeta should deserialize from summary with success.");
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_from_summary_error_multiple_targets() {
+        let summary = RustBuildMetaSummary {
+            platforms: Some(BuildPlatformsSummary {
+                host: host_current().to_summary(),
+                targets: vec![target_linux().to_summary(), target_windows().to_summary()],
+            }),
+            ..Default::default()
+        };
+        let actual = RustBuildMeta::<BinaryListState>::from_summary(summary);
+        assert!(matches!(actual, Err(RustBuildMetaParseError::Unsupported { .. })), "Expect the parse result to be an error of RustBuildMetaParseError::Unsupported, actual {:?}", actual);
+    }
+
+    #[test]
+    fn test_from_summary_error_invalid_host_platform_summary() {
+        let summary = RustBuildMetaSummary {
+            platforms: Some(BuildPlatformsSummary {
+                host: HostPlatformSummary {
+                    platform: PlatformSummary::new("invalid-platform-triple"),
+                    libdir: PlatformLibdirSummary::Unavailable {
+                        reason: PlatformLibdirUnavailable::RUSTC_FAILED,
+                    },
+                },
+                targets: vec![],
+            }),
+            ..Default::default()
+        };
+        let actual = RustBuildMeta::<BinaryListState>::from_summary(summary);
+        actual.expect_err("parse result should be an error");
+    }
+
+    #[test_case(RustBuildMetaSummary {
+        base_output_directories: [Utf8PathBuf::from("../../etc")].into(),
+        ..Default::default()
+    }, "base-output-directories", "../../etc"; "base output directory with parent components")]
+    #[test_case(RustBuildMetaSummary {
+        build_script_out_dirs: [("foo-id".to_owned(), "debug/../../../etc".into())].into(),
+        ..Default::default()
+    }, "build-script-out-dirs", "debug/../../../etc"; "build script out dir with parent components")]
+    #[test_case(RustBuildMetaSummary {
+        build_script_out_dirs: [("foo-id".to_owned(), "/etc".into())].into(),
+        ..Default::default()
+    }, "build-script-out-dirs", "/etc"; "absolute build script out dir")]
+    fn test_from_summary_error_path_outside_target_dir(
+        summary: RustBuildMetaSummary,
+        expected_field: &str,
+        expected_path: &str,
+    ) {
+        let error = RustBuildMeta::<BinaryListState>::from_summary(summary)
+            .expect_err("paths outside the target directory should be rejected");
+        match error {
+            RustBuildMetaParseError::PathOutsideTargetDir { field, path } => {
+                assert_eq!(field, expected_field);
+                assert_eq!(path, expected_path);
+            }
+            other => panic!("expected PathOutsideTargetDir, found {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_from_summary_with_platforms() {
+        let summary = RustBuildMetaSummary {
+            platforms: Some(BuildPlatformsSummary {
+                host: host_not_current_with_libdir("/fake/test/libdir/281").to_summary(),
+                targets: vec![target_linux_with_libdir("/fake/test/libdir/837").to_summary()],
+            }),
+            ..Default::default()
+        };
+
+        let supplied = BuildPlatforms {
+            host: host_not_current_with_libdir("/external/host-libdir"),
+            target: Some(target_linux_with_libdir("/external/target-libdir")),
+        };
+        let actual = RustBuildMeta::<BinaryListState>::from_summary_with_platforms(
+            summary.clone(),
+            supplied.clone(),
+        )
+        .expect("matching triples should be accepted");
+        assert_eq!(actual.build_platforms, supplied);
+
+        let mismatched = BuildPlatforms {
+            host: host_not_current_with_libdir("/external/host-libdir"),
+            target: Some(target_windows()),
+        };
+        let error =
+            RustBuildMeta::<BinaryListState>::from_summary_with_platforms(summary, mismatched)
+                .expect_err("mismatched target triples should be rejected");
+        assert!(
+            matches!(
+                error,
+                RustBuildMetaParseError::PlatformMismatch { kind: "target", .. }
+            ),
+            "{error:?}"
+        );
+    }
+
+    #[test_case(RustBuildMeta::<BinaryListState> {
+        build_platforms: BuildPlatforms {
+            host: host_current(),
+            target: None,
+        },
+        ..Default::default()
+    }, RustBuildMetaSummary {
+        target_platform: None,
+        target_platforms: vec![host_current().to_summary().platform],
+        platforms: Some(BuildPlatformsSummary {
+            host: host_current().to_summary(),
+            targets: vec![],
+        }),
+        ..Default::default()
+    }; "build platforms without target")]
+    #[test_case(RustBuildMeta::<BinaryListState> {
+        build_platforms: BuildPlatforms {
+            host: host_current_with_libdir("/fake/test/libdir/736"),
+            target: Some(target_linux_with_libdir("/fake/test/libdir/873")),
+        },
+        ..Default::default()
+    }, RustBuildMetaSummary {
+        target_platform: Some(
+            target_linux_with_libdir("/fake/test/libdir/873")
+                .triple
+                .platform
+                .triple_str()
+                .to_owned(),
+        ),
+        target_platforms: vec![target_linux_with_libdir("/fake/test/libdir/873").triple.platform.to_summary()],
+        platforms: Some(BuildPlatformsSummary {
+            host: host_current_with_libdir("/fake/test/libdir/736").to_summary(),
+            targets: vec![target_linux_with_libdir("/fake/test/libdir/873").to_summary()],
+        }),
+        ..Default::default()
+    }; "build platforms with target")]
+    fn test_to_summary(meta: RustBuildMeta<BinaryListState>, expected: RustBuildMetaSummary) {
+        let actual = meta.to_summary();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_record_built_at() {
+        let mut meta = RustBuildMeta::<BinaryListState>::default();
+        meta.record_built_at();
+        let built_at = meta.built_at.clone().expect("built_at should be recorded");
+        chrono::DateTime::parse_from_rfc3339(&built_at).expect("built_at should be RFC 3339");
+
+        let roundtrip = RustBuildMeta::<BinaryListState>::from_summary(meta.to_summary())
+            .expect("summary should convert back");
+        assert_eq!(roundtrip.built_at, Some(built_at));
+    }
+
+    #[test]
+    fn test_rust_version_diagnostic() {
+        let meta = RustBuildMeta::<BinaryListState> {
+            rust_version: Some("1.80.0".to_owned()),
+            ..Default::default()
+        };
+        let roundtrip = RustBuildMeta::<BinaryListState>::from_summary(meta.to_summary())
+            .expect("summary should convert back");
+        assert_eq!(roundtrip.rust_version.as_deref(), Some("1.80.0"));
+
+        let diagnostic = meta
+            .rust_version_diagnostic_with("1.79.0")
+            .expect("older rustc should produce a diagnostic");
+        assert_eq!(diagnostic.code, DiagnosticCode::RUSTC_OLDER);
+        assert!(meta.rust_version_diagnostic_with("1.80.0").is_none());
+        assert!(meta.rust_version_diagnostic_with("1.81.0").is_none());
+        assert!(RustBuildMeta::<BinaryListState>::default()
+            .rust_version_diagnostic_with("1.79.0")
+            .is_none());
+    }
+
+    #[test]
+    fn test_libdir_changed_diagnostics() {
+        let target_triple =
+            TargetTriple::deserialize_str(Some("aarch64-unknown-linux-gnu".to_owned()))
+                .expect("creating TargetTriple should succeed")
+                .expect("the output of deserialize_str shouldn't be None");
+        let meta = RustBuildMeta::<BinaryListState> {
+            build_platforms: BuildPlatforms {
+                host: HostPlatform {
+                    platform: TargetTriple::x86_64_unknown_linux_gnu().platform,
+                    libdir: PlatformLibdir::Available("/fake/stable/host/lib".into()),
+                },
+                target: Some(TargetPlatform::new(
+                    target_triple,
+                    PlatformLibdir::Available("/fake/stable/target/lib".into()),
+                )),
+            },
+            ..Default::default()
+        };
+
+        let unchanged = |triple: Option<&TargetTriple>| match triple {
+            Some(_) => PlatformLibdir::Available("/fake/stable/target/lib".into()),
+            None => PlatformLibdir::Available("/fake/stable/host/lib".into()),
+        };
+        assert!(meta.libdir_changed_diagnostics_with(unchanged).is_empty());
+
+        let changed = |triple: Option<&TargetTriple>| match triple {
+            Some(_) => PlatformLibdir::Unavailable(PlatformLibdirUnavailable::RUSTC_FAILED),
+            None => PlatformLibdir::Available("/fake/nightly/host/lib".into()),
+        };
+        let diagnostics = meta.libdir_changed_diagnostics_with(changed);
+        assert_eq!(
+            diagnostics
+                .iter()
+                .map(|diagnostic| (diagnostic.code, diagnostic.related_paths.clone()))
+                .collect::<Vec<_>>(),
+            vec![(
+                DiagnosticCode::LIBDIR_CHANGED,
+                vec![
+                    Utf8PathBuf::from("/fake/stable/host/lib"),
+                    Utf8PathBuf::from("/fake/nightly/host/lib"),
+                ],
+            )],
+            "unavailable libdirs are assumed to match",
+        );
+    }
+
+    #[test_case(None, None; "unset")]
+    #[test_case(Some("0"), Some(false); "disabled")]
+    #[test_case(Some("1"), Some(true); "enabled")]
+    #[test_case(Some("yes"), None; "unrecognized")]
+    fn test_parse_cargo_incremental(value: Option<&str>, expected: Option<bool>) {
+        assert_eq!(parse_cargo_incremental(value), expected);
+    }
+
+    #[test]
+    fn test_incremental_roundtrip() {
+        let meta = RustBuildMeta::<BinaryListState> {
+            incremental: Some(false),
+            ..Default::default()
+        };
+        let summary = meta.to_summary();
+        assert_eq!(summary.incremental, Some(false));
+
+        let roundtrip = RustBuildMeta::<BinaryListState>::from_summary(summary)
+            .expect("summary should convert back");
+        assert_eq!(roundtrip.incremental, Some(false));
+    }
+
+    #[test]
+    fn test_debuginfo_roundtrip() {
+        let meta = RustBuildMeta::<BinaryListState> {
+            debuginfo: Some("line-tables-only".to_owned()),
+            ..Default::default()
+        };
+        let summary = meta.to_summary();
+        assert_eq!(summary.debuginfo.as_deref(), Some("line-tables-only"));
+
+        let roundtrip = RustBuildMeta::<BinaryListState>::from_summary(summary)
+            .expect("summary should convert back");
+        assert_eq!(roundtrip.debuginfo.as_deref(), Some("line-tables-only"));
+    }
+
+    #[test]
+    fn test_linked_path_requested_by_roundtrip() {
+        let meta = RustBuildMeta::<BinaryListState> {
+            linked_paths: [
+                (
+                    Utf8PathBuf::from("debug/build/foo/out/lib"),
+                    ["foo-id".to_owned()].into(),
+                ),
+                (Utf8PathBuf::from("debug/unknown"), BTreeSet::new()),
+            ]
+            .into(),
+            absolute_linked_paths: [(
+                Utf8PathBuf::from("/fake/native"),
+                ["bar-id".to_owned()].into(),
+            )]
+            .into(),
+            ..Default::default()
+        };
+
+        let summary = meta.to_summary();
+        assert_eq!(
+            summary.linked_path_requested_by,
+            [
+                (
+                    Utf8PathBuf::from("/fake/native"),
+                    BTreeSet::from(["bar-id".to_owned()]),
+                ),
+                (
+                    Utf8PathBuf::from("debug/build/foo/out/lib"),
+                    BTreeSet::from(["foo-id".to_owned()]),
+                ),
+            ]
+            .into(),
+            "linked paths requested by unknown packages are omitted"
+        );
+
+        let roundtrip = RustBuildMeta::<BinaryListState>::from_summary(summary)
+            .expect("summary should convert back");
+        assert_eq!(roundtrip.linked_paths, meta.linked_paths);
+        assert_eq!(roundtrip.absolute_linked_paths, meta.absolute_linked_paths);
+    }
+
+    #[test_case(
+        &["cargo", "nextest", "list", "--all-features", "--target", "x86_64-pc-windows-msvc"],
+        &["cargo", "nextest", "list", "--all-features", "--target", "x86_64-pc-windows-msvc"];
+        "allowed options"
+    )]
+    #[test_case(
+        &["cargo-nextest", "nextest", "list", "--config", "token=secret", "-E=all()", "--lib"],
+        &["cargo-nextest", "nextest", "list", "<redacted>", "-E=all()", "--lib"];
+        "unknown option with separate value"
+    )]
+    #[test_case(
+        &["cargo", "nextest", "list", "--config=token=secret", "filter", "--", "--exact"],
+        &["cargo", "nextest", "list", "<redacted>", "filter", "--", "--exact"];
+        "unknown option with inline value"
+    )]
+    fn test_redact_list_command(args: &[&str], expected: &[&str]) {
+        assert_eq!(
+            redact_list_command(args.iter().map(|arg| (*arg).to_owned())),
+            expected,
+        );
+    }
+
+    #[test]
+    fn test_label_round_trip() {
+        let meta = RustBuildMeta::<BinaryListState>::default().with_label("ci-job-1234");
+        let json = serde_json::to_string(&meta.to_summary()).expect("summary should serialize");
+        let summary: RustBuildMetaSummary =
+            serde_json::from_str(&json).expect("summary should deserialize");
+        assert_eq!(summary.label.as_deref(), Some("ci-job-1234"));
+
+        let round_tripped = RustBuildMeta::<BinaryListState>::from_summary(summary)
+            .expect("summary should convert back");
+        assert_eq!(round_tripped.label.as_deref(), Some("ci-job-1234"));
+    }
+
+    #[test]
+    fn test_load_summary() {
+        let tmpdir = camino_tempfile::tempdir().expect("should create temp dir successfully");
+        let meta = RustBuildMeta::<BinaryListState> {
+            target_directory: "/fake/target".into(),
+            base_output_directories: [Utf8PathBuf::from("debug")].into(),
+            build_platforms: BuildPlatforms {
+                host: host_current_with_libdir("/fake/test/libdir/4592"),
+                target: None,
+            },
+            ..Default::default()
+        };
+        let path = tmpdir.path().join("rust-build-meta.json");
+        std::fs::write(
+            &path,
+            serde_json::to_string(&meta.to_summary()).expect("summary should serialize"),
+        )
+        .expect("should write summary successfully");
+
+        let runtime = tokio::runtime::Runtime::new().expect("should create runtime successfully");
+        let loaded = runtime
+            .block_on(RustBuildMeta::<BinaryListState>::load_summary(&path))
+            .expect("summary should load successfully");
+        assert_eq!(loaded, meta);
+
+        let missing = runtime.block_on(RustBuildMeta::<BinaryListState>::load_summary(
+            &tmpdir.path().join("missing.json"),
+        ));
+        assert!(
+            matches!(missing, Err(MetadataMaterializeError::Read { .. })),
+            "expected a read error, actual {:?}",
+            missing
+        );
+    }
+
+    #[test]
+    fn test_record_resolved_search_dirs() {
+        let tmpdir = camino_tempfile::tempdir().expect("should create temp dir successfully");
+        std::fs::create_dir_all(tmpdir.path().join("debug/deps"))
+            .expect("should create base output dir successfully");
+        std::fs::create_dir_all(tmpdir.path().join("debug/build/foo/out"))
+            .expect("should create linked path successfully");
+
+        let mut meta = RustBuildMeta::<BinaryListState> {
+            target_directory: tmpdir.path().to_path_buf(),
+            base_output_directories: [Utf8PathBuf::from("debug"), Utf8PathBuf::from("release")]
+                .into(),
+            linked_paths: [
+                (Utf8PathBuf::from("debug/build/foo/out"), BTreeSet::new()),
+                (Utf8PathBuf::from("debug/build/bar/out"), BTreeSet::new()),
+            ]
+            .into(),
+            ..Default::default()
+        };
+        meta.record_resolved_search_dirs();
+
+        assert_eq!(
+            meta.resolved_search_dirs,
+            Some(vec![
+                Utf8PathBuf::from("debug/build/foo/out"),
+                Utf8PathBuf::from("debug/deps"),
+                Utf8PathBuf::from("debug"),
+            ]),
+        );
+        let summary = meta.to_summary();
+        assert_eq!(summary.resolved_search_dirs, meta.resolved_search_dirs);
+    }
+
+    fn fake_target_join(rel_path: &str) -> Utf8PathBuf {
+        Utf8PathBuf::from("/fake/target")
+            .join(convert_rel_path_to_main_sep(Utf8Path::new(rel_path)))
+    }
+
+    #[test]
+    fn test_map_absolute_linked_paths() {
+        let new_target_dir =
+            camino_tempfile::tempdir().expect("should create temp dir successfully");
+        let path_mapper = PathMapper::new(
+            "/orig/workspace",
+            None,
+            "/orig/target",
+            Some(new_target_dir.path()),
+            Default::default(),
+        )
+        .expect("remapped target dir exists");
+        let rust_build_meta = RustBuildMeta::<BinaryListState> {
+            target_directory: "/orig/target".into(),
+            linked_paths: [(
+                Utf8PathBuf::from("debug/build/foo/out"),
+                ["foo-id".to_owned()].into(),
+            )]
+            .into(),
+            absolute_linked_paths: [
+                (
+                    Utf8PathBuf::from("/orig/target/debug/native"),
+                    ["bar-id".to_owned()].into(),
+                ),
+                (
+                    Utf8PathBuf::from("/usr/lib/native"),
+                    ["baz-id".to_owned()].into(),
+                ),
+                (Utf8PathBuf::from("/elsewhere/native"), Default::default()),
+            ]
+            .into(),
+            ..Default::default()
+        };
+        let checker = VirtualPaths(["/usr/lib/native".into()].into());
+
+        let mapped = rust_build_meta.map_absolute_linked_paths(&path_mapper, &checker);
+        assert_eq!(
+            mapped.linked_paths,
+            [
+                (
+                    Utf8PathBuf::from("debug/build/foo/out"),
+                    BTreeSet::from(["foo-id".to_owned()]),
+                ),
+                (
+                    Utf8PathBuf::from("debug/native"),
+                    BTreeSet::from(["bar-id".to_owned()]),
+                ),
+            ]
+            .into(),
+            "absolute paths within the original target directory are made relative"
+        );
+        assert_eq!(
+            mapped.absolute_linked_paths,
+            [
+                (Utf8PathBuf::from("/elsewhere/native"), BTreeSet::new()),
+                (
+                    Utf8PathBuf::from("/usr/lib/native"),
+                    BTreeSet::from(["baz-id".to_owned()]),
+                ),
+            ]
+            .into(),
+        );
+        assert_eq!(
+            mapped.not_remapped,
+            vec![Utf8PathBuf::from("/elsewhere/native")],
+        );
+
+        let mapped = rust_build_meta.map_absolute_linked_paths(&PathMapper::noop(), &checker);
+        assert_eq!(mapped.linked_paths, rust_build_meta.linked_paths);
+        assert_eq!(
+            mapped.absolute_linked_paths,
+            rust_build_meta.absolute_linked_paths
+        );
+        assert!(
+            mapped.not_remapped.is_empty(),
+            "without remapping, nothing is reported"
+        );
+    }
+
+    #[test]
+    fn test_dylib_paths_deterministic_order() {
+        let meta_with_libdirs =
+            |host_libdir: &str, target_libdir: &str| RustBuildMeta::<TestListState> {
+                target_directory: "/fake/target".into(),
+                base_output_directories: [Utf8PathBuf::from("release"), Utf8PathBuf::from("debug")]
+                    .into(),
+                linked_paths: [
+                    (Utf8PathBuf::from("debug/b"), Default::default()),
+                    (Utf8PathBuf::from("debug/a"), Default::default()),
+                ]
+                .into(),
+                build_platforms: BuildPlatforms {
+                    host: HostPlatform::current(PlatformLibdir::Available(host_libdir.into()))
+                        .expect("should detect the host platform successfully"),
+                    target: Some(TargetPlatform::new(
+                        TargetTriple::x86_64_unknown_linux_gnu(),
+                        PlatformLibdir::Available(target_libdir.into()),
+                    )),
+                },
+                ..RustBuildMeta::empty()
+            };
+        let checker = VirtualPaths(
+            [
+                fake_target_join("debug/a"),
+                fake_target_join("debug/b"),
+                "/fake/libdir-1".into(),
+                "/fake/libdir-2".into(),
+            ]
+            .into(),
+        );
+
+        let first = meta_with_libdirs("/fake/libdir-2", "/fake/libdir-1")
+            .dylib_paths_with_checker(&checker);
+        let second = meta_with_libdirs("/fake/libdir-1", "/fake/libdir-2")
+            .dylib_paths_with_checker(&checker);
+        assert_eq!(first, second);
+        assert_eq!(
+            first,
+            vec![
+                fake_target_join("debug/a"),
+                fake_target_join("debug/b"),
+                fake_target_join("debug/deps"),
+                fake_target_join("debug"),
+                fake_target_join("release/deps"),
+                fake_target_join("release"),
+                "/fake/libdir-1".into(),
+                "/fake/libdir-2".into(),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_dylib_paths_skip_control_chars() {
+        let evil = Utf8PathBuf::from("/fake/native\nLD_PRELOAD=/tmp/evil.so");
+        let nul = Utf8PathBuf::from("/fake/native\0");
+        let rust_build_meta = RustBuildMeta::<TestListState> {
+            target_directory: "/fake/target".into(),
+            absolute_linked_paths: [
+                (Utf8PathBuf::from("/fake/native"), Default::default()),
+                (evil.clone(), Default::default()),
+                (nul.clone(), Default::default()),
+            ]
+            .into(),
+            ..RustBuildMeta::empty()
+        };
+        let checker =
+            VirtualPaths([Utf8PathBuf::from("/fake/native"), evil.clone(), nul.clone()].into());
+
+        assert_eq!(
+            rust_build_meta.dylib_paths_with_checker(&checker),
+            vec![Utf8PathBuf::from("/fake/native")],
+        );
+
+        let diagnostic = rust_build_meta
+            .dylib_path_diagnostics(&checker)
+            .into_iter()
+            .find(|diagnostic| diagnostic.code == DiagnosticCode::DYLIB_PATH_CONTROL_CHARS)
+            .expect("paths with control characters should be reported");
+        assert_eq!(diagnostic.severity, DiagnosticSeverity::Error);
+        assert_eq!(diagnostic.related_paths, vec![nul, evil]);
+    }
+
+    #[test]
+    fn test_dylib_path_envvar_dyld_library_path() {
+        let mut rust_build_meta = RustBuildMeta::<TestListState>::empty();
+        assert_eq!(rust_build_meta.dylib_path_envvar(), dylib_path_envvar());
+
+        rust_build_meta.use_dyld_library_path = true;
+        let expected = if cfg!(target_os = "macos") {
+            "DYLD_LIBRARY_PATH"
+        } else {
+            dylib_path_envvar()
+        };
+        assert_eq!(rust_build_meta.dylib_path_envvar(), expected);
+    }
+
+    #[test]
+    fn test_dylib_paths_capped() {
+        let rust_build_meta = RustBuildMeta::<TestListState> {
+            target_directory: "/fake/target".into(),
+            base_output_directories: [Utf8PathBuf::from("debug"), Utf8PathBuf::from("release")]
+                .into(),
+            linked_paths: [
+                (Utf8PathBuf::from("debug/a"), Default::default()),
+                (Utf8PathBuf::from("debug/b"), Default::default()),
+            ]
+            .into(),
+            build_platforms: BuildPlatforms {
+                host: HostPlatform::current(PlatformLibdir::Available("/fake/libdir".into()))
+                    .expect("should detect the host platform successfully"),
+                target: None,
+            },
+            ..RustBuildMeta::empty()
+        };
+        let checker =
+            VirtualPaths([fake_target_join("debug/a"), fake_target_join("debug/b")].into());
+
+        let all = rust_build_meta.dylib_paths_with_checker(&checker);
+        assert_eq!(
+            all.len(),
+            7,
+            "2 linked paths, 4 base output paths, 1 libdir"
+        );
+        assert_eq!(
+            rust_build_meta.dylib_paths_capped_with_checker(7, &checker),
+            all,
+            "nothing is dropped at the limit"
+        );
+        assert_eq!(
+            rust_build_meta.dylib_paths_capped_with_checker(4, &checker),
+            vec![
+                fake_target_join("debug/a"),
+                fake_target_join("debug/b"),
+                fake_target_join("debug/deps"),
+                Utf8PathBuf::from("/fake/libdir"),
+            ],
+            "base output directories are dropped first, later ones first"
+        );
+        assert_eq!(
+            rust_build_meta.dylib_paths_capped_with_checker(2, &checker),
+            vec![
+                fake_target_join("debug/a"),
+                Utf8PathBuf::from("/fake/libdir")
+            ],
+            "libdirs are kept over linked paths"
+        );
+    }
+
+    #[test]
+    fn test_dylib_paths_with_checker() {
+        let rust_build_meta = RustBuildMeta::<BinaryListState> {
+            target_directory: "/fake/target".into(),
+            base_output_directories: [Utf8PathBuf::from("debug")].into(),
+            linked_paths: [
+                (Utf8PathBuf::from("debug/build/foo/lib"), BTreeSet::new()),
+                (
+                    Utf8PathBuf::from("debug/build/missing/lib"),
+                    BTreeSet::new(),
+                ),
+                // A linked path that's the same as a base output directory is only included once.
+                (Utf8PathBuf::from("debug/deps"), BTreeSet::new()),
+            ]
+            .into(),
+            absolute_linked_paths: [
+                (Utf8PathBuf::from("/fake/native/lib"), BTreeSet::new()),
+                (Utf8PathBuf::from("/fake/native/missing"), BTreeSet::new()),
+            ]
+            .into(),
+            extra_output_roots: [Utf8PathBuf::from("/fake/artifacts")].into(),
+            build_platforms: BuildPlatforms {
+                host: host_current_with_libdir("/fake/host-libdir"),
+                target: Some(target_linux_with_libdir("/fake/target-libdir")),
+            },
+            ..Default::default()
+        }
+        .map_paths(&PathMapper::noop());
+
+        let checker = VirtualPaths(
+            [
+                fake_target_join("debug/build/foo/lib"),
+                fake_target_join("debug/deps"),
+                Utf8PathBuf::from("/fake/native/lib"),
+            ]
+            .into(),
+        );
+
+        assert_eq!(
+            rust_build_meta.dylib_paths_with_checker(&checker),
+            vec![
+                // Linked paths that exist come first, in sorted order.
+                fake_target_join("debug/build/foo/lib"),
+                fake_target_join("debug/deps"),
+                // Followed by absolute linked paths that exist.
+                Utf8PathBuf::from("/fake/native/lib"),
+                // Base output directories aren't checked for existence.
+                fake_target_join("debug"),
+                // Extra output roots are added as-is.
+                Utf8PathBuf::from("/fake/artifacts/deps"),
+                Utf8PathBuf::from("/fake/artifacts"),
+                // Libdirs come last.
+                Utf8PathBuf::from("/fake/host-libdir"),
+                Utf8PathBuf::from("/fake/target-libdir"),
+            ],
+        );
+
+        assert_eq!(
+            rust_build_meta.dylib_search_dirs_with_checker(&checker),
+            vec![
+                (
+                    fake_target_join("debug/build/foo/lib"),
+                    DylibPathKind::LinkedPath,
+                ),
+                // The first occurrence of a path determines its kind.
+                (fake_target_join("debug/deps"), DylibPathKind::LinkedPath),
+                (
+                    Utf8PathBuf::from("/fake/native/lib"),
+                    DylibPathKind::LinkedPath,
+                ),
+                (fake_target_join("debug"), DylibPathKind::BaseOutput),
+                (
+                    Utf8PathBuf::from("/fake/artifacts/deps"),
+                    DylibPathKind::BaseOutputDeps,
+                ),
+                (
+                    Utf8PathBuf::from("/fake/artifacts"),
+                    DylibPathKind::BaseOutput,
+                ),
+                (
+                    Utf8PathBuf::from("/fake/host-libdir"),
+                    DylibPathKind::RustcLibdir,
+                ),
+                (
+                    Utf8PathBuf::from("/fake/target-libdir"),
+                    DylibPathKind::RustcLibdir,
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_dylib_paths_include_linked_path_parents() {
+        let mut rust_build_meta = RustBuildMeta::<BinaryListState> {
+            target_directory: "/fake/target".into(),
+            linked_paths: [
+                (Utf8PathBuf::from("debug/build/foo/lib"), BTreeSet::new()),
+                (Utf8PathBuf::from("debug/build/bar/lib"), BTreeSet::new()),
+            ]
+            .into(),
+            ..Default::default()
+        }
+        .map_paths(&PathMapper::noop());
+
+        // debug/build/bar doesn't exist, so it's skipped.
+        let checker = VirtualPaths(
+            [
+                fake_target_join("debug/build/foo/lib"),
+                fake_target_join("debug/build/foo"),
+                fake_target_join("debug/build/bar/lib"),
+            ]
+            .into(),
+        );
+
+        assert_eq!(
+            rust_build_meta.dylib_paths_with_checker(&checker),
+            vec![
+                fake_target_join("debug/build/bar/lib"),
+                fake_target_join("debug/build/foo/lib"),
+            ],
+            "parents are excluded by default",
+        );
+
+        rust_build_meta.include_linked_path_parents = true;
+        assert_eq!(
+            rust_build_meta.dylib_paths_with_checker(&checker),
+            vec![
+                fake_target_join("debug/build/bar/lib"),
+                fake_target_join("debug/build/foo/lib"),
+                fake_target_join("debug/build/foo"),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_dylib_paths_scan_linked_path_subdirs() {
+        let tmpdir = camino_tempfile::tempdir().expect("should create temp dir successfully");
+        let lib_dir = tmpdir.path().join("debug/build/foo/out/sdk");
+        for (subdir, file) in [
+            ("linux-x64", "libsdk.so.1"),
+            ("darwin", "libsdk.dylib"),
+            ("include", "sdk.h"),
+        ] {
+            std::fs::create_dir_all(lib_dir.join(subdir)).expect("should create subdir");
+            std::fs::write(lib_dir.join(subdir).join(file), "").expect("should write file");
+        }
+
+        let mut rust_build_meta = RustBuildMeta::<TestListState> {
+            target_directory: tmpdir.path().to_path_buf(),
+            linked_paths: [(
+                Utf8PathBuf::from("debug/build/foo/out/sdk"),
+                BTreeSet::new(),
+            )]
+            .into(),
+            ..RustBuildMeta::empty()
+        };
+        let libdirs = rust_build_meta.libdirs();
+        assert_eq!(
+            rust_build_meta.dylib_paths(),
+            std::iter::once(lib_dir.clone())
+                .chain(libdirs.clone())
+                .collect::<Vec<_>>(),
+            "subdirectories aren't scanned by default",
+        );
+
+        rust_build_meta.scan_linked_path_subdirs = true;
+        assert_eq!(
+            rust_build_meta.dylib_paths(),
+            [
+                lib_dir.clone(),
+                lib_dir.join("darwin"),
+                lib_dir.join("linux-x64"),
+            ]
+            .into_iter()
+            .chain(libdirs)
+            .collect::<Vec<_>>(),
+            "only subdirectories with shared libraries are added",
+        );
+    }
+
+    #[test_case("x86_64-unknown-linux-gnu", "libfoo.so", true; "linux so")]
+    #[test_case("x86_64-unknown-linux-gnu", "libfoo.so.1.2", true; "linux versioned so")]
+    #[test_case("x86_64-unknown-linux-gnu", "foo.dll", false; "linux dll")]
+    #[test_case("aarch64-apple-darwin", "libfoo.dylib", true; "macos dylib")]
+    #[test_case("aarch64-apple-darwin", "libfoo.so", false; "macos so")]
+    #[test_case("x86_64-pc-windows-msvc", "Foo.DLL", true; "windows dll")]
+    #[test_case("x86_64-pc-windows-msvc", "foo.lib", false; "windows import lib")]
+    fn test_is_shared_library_for(triple: &str, name: &str, expected: bool) {
+        assert_eq!(is_shared_library_for(triple, name), expected);
+    }
+
+    #[test]
+    fn test_dylib_paths_nonempty() {
+        let tmpdir = camino_tempfile::tempdir().expect("should create temp dir successfully");
+        for (dir, file) in [
+            ("debug/build/foo/out", Some("libfoo.so.1")),
+            ("debug/build/bar/out", Some("bar.dll")),
+            ("debug/build/baz/out", None),
+        ] {
+            let dir = tmpdir.path().join(dir);
+            std::fs::create_dir_all(&dir).expect("should create dir");
+            if let Some(file) = file {
+                std::fs::write(dir.join(file), "").expect("should write file");
+            }
+        }
+
+        let rust_build_meta = RustBuildMeta::<TestListState> {
+            target_directory: tmpdir.path().to_path_buf(),
+            linked_paths: [
+                "debug/build/foo/out",
+                "debug/build/bar/out",
+                "debug/build/baz/out",
+            ]
+            .into_iter()
+            .map(|path| (Utf8PathBuf::from(path), BTreeSet::new()))
+            .collect(),
+            build_platforms: BuildPlatforms {
+                host: HostPlatform {
+                    platform: TargetTriple::x86_64_unknown_linux_gnu().platform,
+                    libdir: PlatformLibdir::Unavailable(PlatformLibdirUnavailable::RUSTC_FAILED),
+                },
+                target: None,
+            },
+            ..RustBuildMeta::empty()
+        };
+
+        assert_eq!(
+            rust_build_meta.dylib_paths_nonempty(),
+            vec![tmpdir.path().join("debug/build/foo/out")],
+            "only directories with Linux shared libraries are kept",
+        );
+    }
+
+    #[test]
+    fn test_base_output_dirs_for() {
+        let mut rust_build_meta = RustBuildMeta::<BinaryListState> {
+            target_directory: "/fake/target".into(),
+            base_output_directories: [
+                Utf8PathBuf::from("debug"),
+                Utf8PathBuf::from("debug/examples"),
+                Utf8PathBuf::from("release"),
+            ]
+            .into(),
+            base_output_directory_categories: [
+                (Utf8PathBuf::from("debug"), BaseOutputDirCategory::TESTS),
+                (
+                    Utf8PathBuf::from("debug/examples"),
+                    BaseOutputDirCategory::EXAMPLES,
+                ),
+            ]
+            .into(),
+            ..Default::default()
+        }
+        .map_paths(&PathMapper::noop());
+
+        assert_eq!(
+            rust_build_meta.base_output_dirs_for(&BaseOutputDirCategory::EXAMPLES),
+            vec![Utf8PathBuf::from("debug/examples")],
+        );
+        assert_eq!(
+            rust_build_meta.base_output_dirs_for(&BaseOutputDirCategory::UNCATEGORIZED),
+            vec![Utf8PathBuf::from("release")],
+            "directories missing from the map are uncategorized",
+        );
+
+        let checker = VirtualPaths(BTreeSet::new());
+        assert!(rust_build_meta
+            .dylib_paths_with_checker(&checker)
+            .contains(&fake_target_join("debug/examples")));
+        rust_build_meta.exclude_example_output_dirs = true;
+        assert!(!rust_build_meta
+            .dylib_paths_with_checker(&checker)
+            .contains(&fake_target_join("debug/examples")));
+    }
+
+    #[test]
+    fn test_spawn_env() {
+        let rust_build_meta = RustBuildMeta::<BinaryListState> {
+            target_directory: "/fake/target".into(),
+            base_output_directories: [Utf8PathBuf::from("debug")].into(),
+            build_script_out_dirs: [("foo-id".to_owned(), "debug/build/foo/out".into())].into(),
+            non_test_binaries: [(
+                "foo-id".to_owned(),
+                [RustNonTestBinarySummary {
+                    name: "foo-bin".to_owned(),
+                    kind: RustNonTestBinaryKind::BIN_EXE,
+                    path: "debug/foo-bin".into(),
+                }]
+                .into(),
+            )]
+            .into(),
+            build_platforms: BuildPlatforms {
+                host: host_current(),
+                target: None,
+            },
+            ..Default::default()
+        }
+        .map_paths(&PathMapper::noop());
+
+        let existing: HashMap<_, _> = [
+            ("SOME_VAR".to_owned(), "value".to_owned()),
+            ("LD_SOME_VAR".to_owned(), "ld-value".to_owned()),
+            (dylib_path_envvar().to_owned(), "/existing/lib".to_owned()),
+        ]
+        .into();
+        let env = rust_build_meta
+            .spawn_env(
+                "foo-id",
+                &RustTestBinaryKind::TEST,
+                BuildPlatform::Host,
+                &existing,
+            )
+            .expect("dylib paths can be joined");
+
+        // The dynamic library path is joined the same way as while running tests.
+        let expected_dylib_path = std::env::join_paths(
+            rust_build_meta
+                .dylib_paths()
+                .into_iter()
+                .map(Utf8PathBuf::into_std_path_buf)
+                .chain([PathBuf::from("/existing/lib")]),
+        )
+        .expect("dylib paths can be joined");
+        assert_eq!(
+            env[dylib_path_envvar()],
+            expected_dylib_path
+                .to_str()
+                .expect("dylib path is valid UTF-8"),
+        );
+        assert_eq!(
+            rust_build_meta
+                .dylib_path_with_existing(Some(OsStr::new("/existing/lib")))
+                .expect("dylib paths can be joined"),
+            expected_dylib_path,
+        );
+
+        assert_eq!(env["SOME_VAR"], "value");
+        assert_eq!(env["NEXTEST_LD_SOME_VAR"], "ld-value");
+        assert!(!env.contains_key("NEXTEST_SOME_VAR"));
+        assert_eq!(
+            env["OUT_DIR"],
+            fake_target_join("debug/build/foo/out").as_str()
+        );
+        assert_eq!(
+            env["NEXTEST_BIN_EXE_foo-bin"],
+            fake_target_join("debug/foo-bin").as_str()
+        );
+        assert_eq!(env["NEXTEST"], "1");
+        assert!(!env.contains_key("NEXTEST_TARGET_SYSROOT"));
+
+        // Non-test binaries are only exposed to integration tests and benchmarks.
+        let lib_env = rust_build_meta
+            .spawn_env(
+                "foo-id",
+                &RustTestBinaryKind::LIB,
+                BuildPlatform::Host,
+                &existing,
+            )
+            .expect("dylib paths can be joined");
+        assert!(!lib_env.contains_key("NEXTEST_BIN_EXE_foo-bin"));
+        assert_eq!(lib_env["OUT_DIR"], env["OUT_DIR"]);
+
+        let other_env = rust_build_meta
+            .spawn_env(
+                "bar-id",
+                &RustTestBinaryKind::TEST,
+                BuildPlatform::Host,
+                &HashMap::new(),
+            )
+            .expect("dylib paths can be joined");
+        assert!(!other_env.contains_key("OUT_DIR"));
+        assert_eq!(
+            OsString::from(&other_env[dylib_path_envvar()]),
+            rust_build_meta
+                .dylib_path_with_existing(None)
+                .expect("dylib paths can be joined"),
+        );
+    }
+
+    #[test]
+    fn test_spawn_env_target() {
+        let target = TargetPlatform::new(
+            TargetTriple::x86_64_unknown_linux_gnu(),
+            PlatformLibdir::Unavailable(PlatformLibdirUnavailable::RUSTC_FAILED),
         )
+        .with_emulator_env([("QEMU_LD_PREFIX".to_owned(), "/sysroot".to_owned())].into())
+        .with_sysroot(Some("/sysroot".into()));
+        let rust_build_meta = RustBuildMeta::<BinaryListState> {
+            target_directory: "/fake/target".into(),
+            build_platforms: BuildPlatforms {
+                host: host_current(),
+                target: Some(target),
+            },
+            ..Default::default()
+        }
+        .map_paths(&PathMapper::noop());
+
+        let target_env = rust_build_meta
+            .spawn_env(
+                "foo-id",
+                &RustTestBinaryKind::LIB,
+                BuildPlatform::Target,
+                &HashMap::new(),
+            )
+            .expect("dylib paths can be joined");
+        assert_eq!(target_env["QEMU_LD_PREFIX"], "/sysroot");
+        assert_eq!(target_env["NEXTEST_TARGET_SYSROOT"], "/sysroot");
+
+        let host_env = rust_build_meta
+            .spawn_env(
+                "foo-id",
+                &RustTestBinaryKind::LIB,
+                BuildPlatform::Host,
+                &HashMap::new(),
+            )
+            .expect("dylib paths can be joined");
+        assert!(!host_env.contains_key("QEMU_LD_PREFIX"));
+        assert!(!host_env.contains_key("NEXTEST_TARGET_SYSROOT"));
+    }
+
+    #[test]
+    fn test_native_search_set() {
+        static FIXTURE_JSON: &str = include_str!("../../../fixtures/cargo-metadata.json");
+        let graph = guppy::CargoMetadata::parse_json(FIXTURE_JSON)
+            .expect("fixture is valid JSON")
+            .build_graph()
+            .expect("fixture is valid PackageGraph");
+        // metadata-base depends on metadata-helper.
+        let base_id = "metadata-base 0.1.0 (path+file:///Users/fakeuser/local/testcrates/metadata/metadata-base)";
+        let helper_id = "metadata-helper 0.1.0 (path+file:///Users/fakeuser/local/testcrates/metadata/metadata-helper)";
+
+        let tmpdir = camino_tempfile::tempdir().expect("should create temp dir successfully");
+        for dir in ["helper-lib", "unrelated-lib", "unknown-lib"] {
+            std::fs::create_dir(tmpdir.path().join(dir)).expect("should create dir");
+        }
+
+        let rust_build_meta = RustBuildMeta::<BinaryListState> {
+            target_directory: tmpdir.path().to_path_buf(),
+            linked_paths: [
+                (
+                    Utf8PathBuf::from("helper-lib"),
+                    [helper_id.to_owned()].into(),
+                ),
+                (
+                    Utf8PathBuf::from("unrelated-lib"),
+                    ["unrelated 0.1.0".to_owned()].into(),
+                ),
+                // Linked paths without provenance are always included.
+                (Utf8PathBuf::from("unknown-lib"), BTreeSet::new()),
+                // Linked paths that don't exist are never included.
+                (Utf8PathBuf::from("missing-lib"), BTreeSet::new()),
+            ]
+            .into(),
+            build_platforms: BuildPlatforms {
+                host: host_current_with_libdir("/fake/host-libdir"),
+                target: None,
+            },
+            ..Default::default()
+        }
+        .map_paths(&PathMapper::noop());
+
+        assert_eq!(
+            rust_build_meta.native_search_set(base_id, &graph),
+            [
+                tmpdir.path().join("helper-lib"),
+                tmpdir.path().join("unknown-lib"),
+                Utf8PathBuf::from("/fake/host-libdir"),
+            ]
+            .into(),
+        );
+    }
+
+    #[test]
+    fn test_logical_fingerprint() {
+        let make_meta = |target_directory: &str, workspace: &str| RustBuildMeta::<BinaryListState> {
+            target_directory: target_directory.into(),
+            base_output_directories: [Utf8PathBuf::from("debug")].into(),
+            build_script_out_dirs: [(
+                format!("path+file://{workspace}/foo#0.1.0"),
+                Utf8PathBuf::from("debug/build/foo-1234/out"),
+            )]
+            .into(),
+            linked_paths: [(
+                Utf8PathBuf::from("debug/build/foo-1234/out/lib"),
+                [format!("path+file://{workspace}/foo#0.1.0")].into(),
+            )]
+            .into(),
+            build_platforms: BuildPlatforms {
+                host: host_current_with_libdir("/home/alice/.rustup/lib"),
+                target: None,
+            },
+            ..Default::default()
+        };
+
+        let alice = make_meta("/home/alice/repo/target", "/home/alice/repo");
+        let bob = make_meta("/work/bob/checkout/target", "/work/bob/checkout");
+        assert_eq!(
+            alice.logical_fingerprint(),
+            bob.logical_fingerprint(),
+            "machine-specific paths don't affect the fingerprint"
+        );
+
+        let mut changed = alice.clone();
+        changed
+            .base_output_directories
+            .insert(Utf8PathBuf::from("release"));
+        assert_ne!(
+            alice.logical_fingerprint(),
+            changed.logical_fingerprint(),
+            "relative directories affect the fingerprint"
+        );
+    }
+
+    #[test_case("path+file:///home/alice/repo/foo#0.1.0", "0.1.0"; "path dependency")]
+    #[test_case("path+file:///home/alice/repo/foo#bar@0.1.0", "bar@0.1.0"; "path dependency with name")]
+    #[test_case("foo 0.1.0 (path+file:///home/alice/repo/foo)", "foo 0.1.0"; "old path dependency")]
+    #[test_case(
+        "registry+https://github.com/rust-lang/crates.io-index#serde@1.0.0",
+        "registry+https://github.com/rust-lang/crates.io-index#serde@1.0.0";
+        "registry dependency"
+    )]
+    fn test_logical_package_id(package_id: &str, expected: &str) {
+        assert_eq!(logical_package_id(package_id), expected);
     }
 
-    fn target_windows() -> TargetPlatform {
-        TargetPlatform::new(
-            x86_64_pc_windows_msvc_triple(),
-            PlatformLibdir::Unavailable(PlatformLibdirUnavailable::OLD_SUMMARY),
-        )
+    #[test]
+    fn test_base_dirs_same_filesystem() {
+        let tmpdir = camino_tempfile::tempdir().expect("should create temp dir successfully");
+        std::fs::create_dir(tmpdir.path().join("debug")).expect("should create debug dir");
+        let meta = RustBuildMeta::<BinaryListState> {
+            target_directory: tmpdir.path().to_path_buf(),
+            // "release" doesn't exist, so it's skipped.
+            base_output_directories: [Utf8PathBuf::from("debug"), Utf8PathBuf::from("release")]
+                .into(),
+            ..Default::default()
+        };
+
+        assert_eq!(meta.base_dirs_same_filesystem(), Ok(()));
     }
 
-    #[test_case(RustBuildMetaSummary {
-        ..Default::default()
-    }, RustBuildMeta::<BinaryListState> {
-        build_platforms: BuildPlatforms {
-            host: host_current(),
-            target: None,
-        },
-        ..Default::default()
-    }; "no target platforms")]
-    #[test_case(RustBuildMetaSummary {
-        target_platform: Some("x86_64-unknown-linux-gnu".to_owned()),
-        ..Default::default()
-    }, RustBuildMeta::<BinaryListState> {
-        build_platforms: BuildPlatforms {
-            host: host_current(),
-            target: Some(target_linux()),
-        },
-        ..Default::default()
-    }; "only target platform field")]
-    #[test_case(RustBuildMetaSummary {
-        target_platform: Some("x86_64-unknown-linux-gnu".to_owned()),
-        // target_platforms should be preferred over target_platform
-        target_platforms: vec![PlatformSummary::new("x86_64-pc-windows-msvc")],
-        ..Default::default()
-    }, RustBuildMeta::<BinaryListState> {
-        build_platforms: BuildPlatforms {
-            host: host_current(),
-            target: Some(target_windows()),
-        },
-        ..Default::default()
-    }; "target platform and target platforms field")]
-    #[test_case(RustBuildMetaSummary {
-        target_platform: Some("aarch64-unknown-linux-gnu".to_owned()),
-        target_platforms: vec![PlatformSummary::new("x86_64-pc-windows-msvc")],
-        // platforms should be preferred over both target_platform and target_platforms
-        platforms: Some(BuildPlatformsSummary {
-            host: host_not_current_with_libdir("/fake/test/libdir/281").to_summary(),
-            targets: vec![target_linux_with_libdir("/fake/test/libdir/837").to_summary()],
-        }),
-        ..Default::default()
-    }, RustBuildMeta::<BinaryListState> {
-        build_platforms: BuildPlatforms {
-            host: host_not_current_with_libdir("/fake/test/libdir/281"),
-            target: Some(target_linux_with_libdir("/fake/test/libdir/837")),
-        },
-        ..Default::default()
-    }; "target platform and target platforms and platforms field")]
-    #[test_case(RustBuildMetaSummary {
-        platforms: Some(BuildPlatformsSummary {
-            host: host_current().to_summary(),
-            targets: vec![],
-        }),
-        ..Default::default()
-    }, RustBuildMeta::<BinaryListState> {
-        build_platforms: BuildPlatforms {
-            host: host_current(),
-            target: None,
-        },
-        ..Default::default()
-    }; "platforms with zero targets")]
-    fn test_from_summary(summary: RustBuildMetaSummary, expected: RustBuildMeta<BinaryListState>) {
-        let actual = RustBuildMeta::<BinaryListState>::from_summary(summary)
-            .expect("RustBuildMeta should deserialize from summary with success.");
-        assert_eq!(actual, expected);
+    #[test]
+    fn test_non_test_binary_diff() {
+        let binary = |name: &str, path: &str| RustNonTestBinarySummary {
+            name: name.to_owned(),
+            kind: RustNonTestBinaryKind::BIN_EXE,
+            path: path.into(),
+        };
+        let before = RustBuildMeta::<BinaryListState> {
+            non_test_binaries: [
+                (
+                    "foo-id".to_owned(),
+                    [
+                        binary("foo", "debug/foo"),
+                        binary("old-tool", "debug/old-tool"),
+                    ]
+                    .into(),
+                ),
+                ("bar-id".to_owned(), [binary("bar", "debug/bar")].into()),
+            ]
+            .into(),
+            ..Default::default()
+        };
+        let after = RustBuildMeta::<BinaryListState> {
+            non_test_binaries: [
+                (
+                    "foo-id".to_owned(),
+                    // Paths are machine-specific, so they're ignored.
+                    [
+                        binary("foo", "release/foo"),
+                        binary("new-tool", "release/new-tool"),
+                    ]
+                    .into(),
+                ),
+                ("baz-id".to_owned(), [binary("baz", "release/baz")].into()),
+            ]
+            .into(),
+            ..Default::default()
+        };
+
+        let diff = before.non_test_binary_diff(&after);
+        assert_eq!(
+            diff,
+            NonTestBinaryDiff {
+                added: [
+                    ("baz-id".to_owned(), BTreeSet::from(["baz".to_owned()])),
+                    ("foo-id".to_owned(), BTreeSet::from(["new-tool".to_owned()])),
+                ]
+                .into(),
+                removed: [
+                    ("bar-id".to_owned(), BTreeSet::from(["bar".to_owned()])),
+                    ("foo-id".to_owned(), BTreeSet::from(["old-tool".to_owned()])),
+                ]
+                .into(),
+            }
+        );
+        assert_eq!(
+            diff.to_string(),
+            "+ baz (package `baz-id`)\n\
+             + new-tool (package `foo-id`)\n\
+             - bar (package `bar-id`)\n\
+             - old-tool (package `foo-id`)",
+        );
+
+        let diff = before.non_test_binary_diff(&before);
+        assert!(diff.is_empty(), "{diff:?}");
+        assert_eq!(diff.to_string(), "no non-test binaries added or removed");
     }
 
     #[test]
-    fn test_from_summary_error_multiple_targets() {
-        let summary = RustBuildMetaSummary {
-            platforms: Some(BuildPlatformsSummary {
-                host: host_current().to_summary(),
-                targets: vec![target_linux().to_summary(), target_windows().to_summary()],
-            }),
+    fn test_enabled_features_mismatches() {
+        let meta = RustBuildMeta::<BinaryListState> {
+            enabled_features: [
+                ("foo-id".to_owned(), ["default".to_owned()].into()),
+                ("bar-id".to_owned(), BTreeSet::new()),
+                ("baz-id".to_owned(), BTreeSet::new()),
+            ]
+            .into(),
             ..Default::default()
         };
-        let actual = RustBuildMeta::<BinaryListState>::from_summary(summary);
-        assert!(matches!(actual, Err(RustBuildMetaParseError::Unsupported { .. })), "Expect the parse result to be an error of RustBuildMetaParseError::Unsupported, actual {:?}", actual);
+        let requested = [
+            ("foo-id".to_owned(), BTreeSet::new()),
+            ("bar-id".to_owned(), BTreeSet::new()),
+        ]
+        .into();
+
+        assert_eq!(
+            meta.enabled_features_mismatches(&requested),
+            vec![EnabledFeaturesMismatch {
+                package_id: "foo-id".to_owned(),
+                recorded: ["default".to_owned()].into(),
+                requested: BTreeSet::new(),
+            }],
+        );
+        assert!(!meta.check_enabled_features(&requested));
     }
 
     #[test]
-    fn test_from_summary_error_invalid_host_platform_summary() {
-        let summary = RustBuildMetaSummary {
-            platforms: Some(BuildPlatformsSummary {
-                host: HostPlatformSummary {
-                    platform: PlatformSummary::new("invalid-platform-triple"),
-                    libdir: PlatformLibdirSummary::Unavailable {
-                        reason: PlatformLibdirUnavailable::RUSTC_FAILED,
+    fn test_packages_with_linked_paths() {
+        let meta = RustBuildMeta::<BinaryListState> {
+            linked_paths: [
+                (
+                    Utf8PathBuf::from("debug/build/foo/out"),
+                    ["foo-id".to_owned(), "bar-id".to_owned()].into(),
+                ),
+                (
+                    Utf8PathBuf::from("debug/build/bar/out"),
+                    ["bar-id".to_owned()].into(),
+                ),
+                (Utf8PathBuf::from("debug/build/baz/out"), BTreeSet::new()),
+            ]
+            .into(),
+            absolute_linked_paths: [(
+                Utf8PathBuf::from("/usr/local/lib"),
+                ["qux-id".to_owned()].into(),
+            )]
+            .into(),
+            ..Default::default()
+        };
+        assert_eq!(meta.packages_with_linked_paths(), 3);
+
+        let summary = meta.to_summary();
+        let from_summary = RustBuildMeta::<BinaryListState>::from_summary(summary)
+            .expect("summary round-trips successfully");
+        assert_eq!(
+            from_summary.packages_with_linked_paths(),
+            0,
+            "provenance isn't persisted in the summary"
+        );
+    }
+
+    #[test]
+    fn test_linked_paths_by_origin() {
+        let meta = RustBuildMeta::<BinaryListState> {
+            linked_paths: [
+                (
+                    Utf8PathBuf::from("debug/build/foo/out"),
+                    ["foo-id".to_owned()].into(),
+                ),
+                (
+                    Utf8PathBuf::from("debug/build/shared/out"),
+                    ["foo-id".to_owned(), "dep-id".to_owned()].into(),
+                ),
+                (
+                    Utf8PathBuf::from("debug/build/dep/out"),
+                    ["dep-id".to_owned()].into(),
+                ),
+                (
+                    Utf8PathBuf::from("debug/build/unknown/out"),
+                    BTreeSet::new(),
+                ),
+            ]
+            .into(),
+            absolute_linked_paths: [(
+                Utf8PathBuf::from("/usr/local/lib"),
+                ["sys-id".to_owned()].into(),
+            )]
+            .into(),
+            ..Default::default()
+        };
+        let workspace_members = ["foo-id".to_owned()].into();
+
+        let (workspace, external) = meta.linked_paths_by_origin(&workspace_members);
+        assert_eq!(
+            workspace,
+            vec![
+                Utf8PathBuf::from("debug/build/foo/out"),
+                Utf8PathBuf::from("debug/build/shared/out"),
+            ]
+        );
+        assert_eq!(
+            external,
+            vec![
+                Utf8PathBuf::from("debug/build/dep/out"),
+                Utf8PathBuf::from("debug/build/shared/out"),
+                Utf8PathBuf::from("/usr/local/lib"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_proc_macro_libdir() {
+        let meta = RustBuildMeta::<BinaryListState> {
+            build_platforms: BuildPlatforms {
+                host: host_current_with_libdir("/fake/host-libdir"),
+                target: Some(target_linux_with_libdir("/fake/target-libdir")),
+            },
+            ..Default::default()
+        };
+        assert_eq!(
+            meta.proc_macro_libdir(),
+            Some(Utf8Path::new("/fake/host-libdir")),
+            "proc macros use the host libdir while cross-compiling"
+        );
+    }
+
+    #[test]
+    fn test_target_directory_exists() {
+        let tmpdir = camino_tempfile::tempdir().expect("should create temp dir successfully");
+        let meta = RustBuildMeta::<BinaryListState> {
+            target_directory: tmpdir.path().to_path_buf(),
+            ..Default::default()
+        };
+        assert!(meta.target_directory_exists());
+
+        let meta = RustBuildMeta::<BinaryListState> {
+            target_directory: tmpdir.path().join("missing"),
+            ..Default::default()
+        };
+        assert!(!meta.target_directory_exists());
+    }
+
+    #[test]
+    fn test_libdir_toolchain_mismatches() {
+        let rustup_libdir = |toolchain: &str| {
+            format!("/home/user/.rustup/toolchains/{toolchain}/lib/rustlib/x86_64/lib")
+        };
+        let meta = |host_toolchain: &str, target_libdir: &str| RustBuildMeta::<BinaryListState> {
+            build_platforms: BuildPlatforms {
+                host: host_current_with_libdir(&rustup_libdir(host_toolchain)),
+                target: Some(target_linux_with_libdir(target_libdir)),
+            },
+            ..Default::default()
+        };
+
+        let this = meta("nightly-2024-01-01", "/fake/target-libdir");
+        let same_toolchain = meta("nightly-2024-01-01", "/other/target-libdir");
+        assert!(this.libdir_basename_matches(&same_toolchain));
+
+        let other_toolchain = meta("nightly-2024-02-01", "/fake/target-libdir");
+        assert_eq!(
+            this.libdir_toolchain_mismatches(&other_toolchain),
+            vec![LibdirToolchainMismatch {
+                kind: "host",
+                this: "nightly-2024-01-01".to_owned(),
+                other: "nightly-2024-02-01".to_owned(),
+            }],
+        );
+        assert!(!this.libdir_basename_matches(&other_toolchain));
+    }
+
+    #[test]
+    fn test_suggested_archive_name() {
+        let meta = RustBuildMeta::<BinaryListState> {
+            base_output_directories: [
+                Utf8PathBuf::from("x86_64-pc-windows-msvc/debug"),
+                Utf8PathBuf::from("x86_64-pc-windows-msvc/debug/examples"),
+                Utf8PathBuf::from("x86_64-pc-windows-msvc/custom profile"),
+            ]
+            .into(),
+            build_platforms: BuildPlatforms {
+                host: host_current(),
+                target: Some(target_windows()),
+            },
+            ..Default::default()
+        };
+
+        let name = meta.suggested_archive_name();
+        assert_eq!(
+            name,
+            format!(
+                "nextest-archive-{}+x86_64-pc-windows-msvc-custom_profile+debug-{}.tar.zst",
+                host_current().platform.triple_str(),
+                meta.logical_fingerprint(),
+            )
+        );
+        assert!(
+            !name.contains(['/', '\\', ':', ' ']),
+            "name {name} has no invalid characters"
+        );
+    }
+
+    #[test]
+    fn test_runnable_tools() {
+        let meta = RustBuildMeta::<BinaryListState> {
+            target_directory: "/fake/target".into(),
+            non_test_binaries: [(
+                "foo-id".to_owned(),
+                [
+                    RustNonTestBinarySummary {
+                        name: "foo-cli".to_owned(),
+                        kind: RustNonTestBinaryKind::BIN_EXE,
+                        path: "debug/foo-cli".into(),
                     },
-                },
-                targets: vec![],
-            }),
+                    RustNonTestBinarySummary {
+                        name: "foo".to_owned(),
+                        kind: RustNonTestBinaryKind::DYLIB,
+                        path: "debug/libfoo.so".into(),
+                    },
+                ]
+                .into(),
+            )]
+            .into(),
             ..Default::default()
         };
-        let actual = RustBuildMeta::<BinaryListState>::from_summary(summary);
-        actual.expect_err("parse result should be an error");
+
+        assert_eq!(
+            meta.runnable_tools(),
+            vec![RunnableTool {
+                package_id: "foo-id".to_owned(),
+                name: "foo-cli".to_owned(),
+                path: fake_target_join("debug/foo-cli"),
+            }]
+        );
     }
 
-    #[test_case(RustBuildMeta::<BinaryListState> {
-        build_platforms: BuildPlatforms {
-            host: host_current(),
-            target: None,
-        },
-        ..Default::default()
-    }, RustBuildMetaSummary {
-        target_platform: None,
-        target_platforms: vec![host_current().to_summary().platform],
-        platforms: Some(BuildPlatformsSummary {
-            host: host_current().to_summary(),
-            targets: vec![],
-        }),
-        ..Default::default()
-    }; "build platforms without target")]
-    #[test_case(RustBuildMeta::<BinaryListState> {
-        build_platforms: BuildPlatforms {
-            host: host_current_with_libdir("/fake/test/libdir/736"),
-            target: Some(target_linux_with_libdir("/fake/test/libdir/873")),
-        },
-        ..Default::default()
-    }, RustBuildMetaSummary {
-        target_platform: Some(
-            target_linux_with_libdir("/fake/test/libdir/873")
-                .triple
-                .platform
-                .triple_str()
-                .to_owned(),
-        ),
-        target_platforms: vec![target_linux_with_libdir("/fake/test/libdir/873").triple.platform.to_summary()],
-        platforms: Some(BuildPlatformsSummary {
-            host: host_current_with_libdir("/fake/test/libdir/736").to_summary(),
-            targets: vec![target_linux_with_libdir("/fake/test/libdir/873").to_summary()],
-        }),
-        ..Default::default()
-    }; "build platforms with target")]
-    fn test_to_summary(meta: RustBuildMeta<BinaryListState>, expected: RustBuildMetaSummary) {
-        let actual = meta.to_summary();
-        assert_eq!(actual, expected);
+    #[test]
+    fn test_writable_test_dirs() {
+        let meta = RustBuildMeta::<BinaryListState> {
+            target_directory: "/fake/target".into(),
+            build_script_out_dirs: [
+                (
+                    "foo-id".to_owned(),
+                    Utf8PathBuf::from("debug/build/foo-1234/out"),
+                ),
+                (
+                    "bar-id".to_owned(),
+                    Utf8PathBuf::from("debug/build/bar-5678/out"),
+                ),
+            ]
+            .into(),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            meta.writable_test_dirs(),
+            vec![
+                Utf8PathBuf::from("/fake/target").join(convert_rel_path_to_main_sep(
+                    Utf8Path::new("debug/build/bar-5678/out")
+                )),
+                Utf8PathBuf::from("/fake/target").join(convert_rel_path_to_main_sep(
+                    Utf8Path::new("debug/build/foo-1234/out")
+                )),
+                Utf8PathBuf::from("/fake/target").join("nextest"),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_probably_writable_dirs() {
+        let meta = RustBuildMeta::<BinaryListState> {
+            target_directory: "/fake/target".into(),
+            base_output_directories: [
+                Utf8PathBuf::from("debug"),
+                Utf8PathBuf::from("x86_64-unknown-linux-gnu/debug"),
+            ]
+            .into(),
+            build_script_out_dirs: [(
+                "foo-id".to_owned(),
+                Utf8PathBuf::from("debug/build/foo-1234/out"),
+            )]
+            .into(),
+            ..Default::default()
+        };
+        let fake_target_join = |rel: &str| {
+            Utf8PathBuf::from("/fake/target").join(convert_rel_path_to_main_sep(Utf8Path::new(rel)))
+        };
+
+        assert_eq!(
+            meta.probably_writable_dirs(),
+            vec![
+                fake_target_join("debug/build/foo-1234/out"),
+                fake_target_join("debug"),
+                fake_target_join("x86_64-unknown-linux-gnu/debug"),
+            ],
+        );
     }
 
     #[test]
@@ -487,4 +4116,174 @@ mod tests {
             dylib_paths
         );
     }
+
+    #[test]
+    fn test_dylib_paths_should_not_contain_regular_files() {
+        let tmpdir = camino_tempfile::tempdir().expect("should create temp dir successfully");
+        let target_join = |rel_path: &str| {
+            tmpdir
+                .path()
+                .join(convert_rel_path_to_main_sep(Utf8Path::new(rel_path)))
+        };
+        let lib_dir = target_join("debug/build/foo/out/lib");
+        std::fs::create_dir_all(&lib_dir).expect("should create lib dir");
+        let file_path = target_join("debug/build/bar/out/lib");
+        std::fs::create_dir_all(file_path.parent().expect("file path should have a parent"))
+            .expect("should create parent dir");
+        std::fs::write(&file_path, "").expect("should write regular file");
+        std::fs::write(target_join("release"), "").expect("should write regular file");
+
+        let rust_build_meta = RustBuildMeta::<TestListState> {
+            target_directory: tmpdir.path().to_path_buf(),
+            linked_paths: [
+                (
+                    Utf8PathBuf::from("debug/build/foo/out/lib"),
+                    Default::default(),
+                ),
+                (
+                    Utf8PathBuf::from("debug/build/bar/out/lib"),
+                    Default::default(),
+                ),
+            ]
+            .into(),
+            absolute_linked_paths: [(file_path.clone(), Default::default())].into(),
+            base_output_directories: [Utf8PathBuf::from("debug"), Utf8PathBuf::from("release")]
+                .into(),
+            ..RustBuildMeta::empty()
+        };
+        let dylib_paths = rust_build_meta.dylib_paths();
+
+        assert!(
+            dylib_paths.contains(&lib_dir),
+            "{dylib_paths:?} should contain the linked directory"
+        );
+        assert!(
+            dylib_paths.contains(&target_join("debug/deps")),
+            "{dylib_paths:?} should contain output directories that don't exist"
+        );
+        for excluded in [file_path, target_join("release")] {
+            assert!(
+                !dylib_paths.contains(&excluded),
+                "{dylib_paths:?} should not contain the regular file {excluded}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_dylib_paths_with_status() {
+        let tmpdir = camino_tempfile::tempdir().expect("should create temp dir successfully");
+        let target_join = |rel_path: &str| {
+            tmpdir
+                .path()
+                .join(convert_rel_path_to_main_sep(Utf8Path::new(rel_path)))
+        };
+        let lib_dir = target_join("debug/build/foo/out/lib");
+        std::fs::create_dir_all(&lib_dir).expect("should create lib dir");
+        std::fs::create_dir_all(target_join("debug/deps")).expect("should create deps dir");
+        let file_path = target_join("debug/build/bar/out/lib");
+        std::fs::create_dir_all(file_path.parent().expect("file path should have a parent"))
+            .expect("should create parent dir");
+        std::fs::write(&file_path, "").expect("should write regular file");
+
+        let rust_build_meta = RustBuildMeta::<TestListState> {
+            target_directory: tmpdir.path().to_path_buf(),
+            linked_paths: [
+                (
+                    Utf8PathBuf::from("debug/build/foo/out/lib"),
+                    Default::default(),
+                ),
+                (
+                    Utf8PathBuf::from("debug/build/bar/out/lib"),
+                    Default::default(),
+                ),
+                (
+                    Utf8PathBuf::from("debug/build/missing/out/lib"),
+                    Default::default(),
+                ),
+                (Utf8PathBuf::from("debug/deps"), Default::default()),
+            ]
+            .into(),
+            base_output_directories: [Utf8PathBuf::from("debug")].into(),
+            ..RustBuildMeta::empty()
+        };
+
+        let candidate = |path: Utf8PathBuf, kind, status| DylibPathCandidate { path, kind, status };
+        let mut expected = vec![
+            candidate(
+                file_path,
+                DylibPathKind::LinkedPath,
+                DylibPathStatus::Skipped(DylibPathSkipReason::NotADirectory),
+            ),
+            candidate(lib_dir, DylibPathKind::LinkedPath, DylibPathStatus::Kept),
+            candidate(
+                target_join("debug/build/missing/out/lib"),
+                DylibPathKind::LinkedPath,
+                DylibPathStatus::Skipped(DylibPathSkipReason::NotFound),
+            ),
+            candidate(
+                target_join("debug/deps"),
+                DylibPathKind::LinkedPath,
+                DylibPathStatus::Kept,
+            ),
+            candidate(
+                target_join("debug/deps"),
+                DylibPathKind::BaseOutputDeps,
+                DylibPathStatus::Skipped(DylibPathSkipReason::Duplicate),
+            ),
+            candidate(
+                target_join("debug"),
+                DylibPathKind::BaseOutput,
+                DylibPathStatus::Kept,
+            ),
+        ];
+        expected.extend(
+            rust_build_meta
+                .libdirs()
+                .into_iter()
+                .sorted()
+                .map(|libdir| candidate(libdir, DylibPathKind::RustcLibdir, DylibPathStatus::Kept)),
+        );
+
+        let candidates = rust_build_meta.dylib_paths_with_status();
+        assert_eq!(candidates, expected);
+        assert_eq!(
+            rust_build_meta.dylib_paths(),
+            candidates
+                .into_iter()
+                .filter(|candidate| candidate.status == DylibPathStatus::Kept)
+                .map(|candidate| candidate.path)
+                .collect::<Vec<_>>(),
+            "kept candidates are the dylib paths",
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_linked_paths_with_broken_symlinks() {
+        let tmpdir = camino_tempfile::tempdir().expect("should create temp dir successfully");
+        let lib_dir = tmpdir.path().join("debug/build/foo/out/lib");
+        std::fs::create_dir_all(&lib_dir).expect("should create lib dir");
+        std::fs::write(lib_dir.join("libgood.so"), "").expect("should write library");
+        std::os::unix::fs::symlink(lib_dir.join("libgood.so"), lib_dir.join("libgood.so.1"))
+            .expect("should create symlink");
+        std::os::unix::fs::symlink(tmpdir.path().join("moved.so"), lib_dir.join("libbad.so"))
+            .expect("should create symlink");
+
+        let rust_build_meta = RustBuildMeta::<TestListState> {
+            target_directory: tmpdir.path().to_path_buf(),
+            linked_paths: [
+                (
+                    Utf8PathBuf::from("debug/build/foo/out/lib"),
+                    Default::default(),
+                ),
+                (Utf8PathBuf::from("debug/missing"), Default::default()),
+            ]
+            .into(),
+            ..RustBuildMeta::empty()
+        };
+        assert_eq!(
+            rust_build_meta.linked_paths_with_broken_symlinks(),
+            vec![lib_dir.join("libbad.so")],
+        );
+    }
 }
@@ -0,0 +1,318 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Structured diagnostics produced for [`RustBuildMeta`](super::RustBuildMeta).
+
+use super::RustBuildMetaValidationIssue;
+use camino::Utf8PathBuf;
+use std::fmt;
+
+/// A stable, machine-readable code identifying the kind of a [`Diagnostic`].
+///
+/// Codes are never reused or changed once published, so they can be used to gate on specific
+/// diagnostics in CI.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct DiagnosticCode(&'static str);
+
+impl DiagnosticCode {
+    /// No rustc libdir was detected for either the host or the target platform.
+    pub const LIBDIR_MISSING: Self = Self("NEXTEST_LIBDIR_MISSING");
+
+    /// rustc printed a libdir that isn't valid UTF-8, so it was skipped.
+    pub const LIBDIR_NON_UTF8: Self = Self("NEXTEST_LIBDIR_NON_UTF8");
+
+    /// Dynamic library search directories that existed at build time are missing.
+    pub const SEARCH_DIRS_MISSING: Self = Self("NEXTEST_SEARCH_DIRS_MISSING");
+
+    /// Dynamic library paths contain NUL or newline characters, so they were skipped.
+    pub const DYLIB_PATH_CONTROL_CHARS: Self = Self("NEXTEST_DYLIB_PATH_CONTROL_CHARS");
+
+    /// Linked paths outside the target directory don't exist after remapping paths. See
+    /// [`RustBuildMeta::map_paths`](super::RustBuildMeta::map_paths).
+    pub const LINKED_PATHS_NOT_REMAPPED: Self = Self("NEXTEST_LINKED_PATHS_NOT_REMAPPED");
+
+    /// A linked path is the parent directory shared by the host and target libdirs. See
+    /// [`BuildPlatforms::libdir_shared_root`](crate::platform::BuildPlatforms::libdir_shared_root).
+    pub const LIBDIR_SHARED_ROOT_LINKED: Self = Self("NEXTEST_LIBDIR_SHARED_ROOT_LINKED");
+
+    /// The target sysroot recorded at build time doesn't exist.
+    pub const TARGET_SYSROOT_MISSING: Self = Self("NEXTEST_TARGET_SYSROOT_MISSING");
+
+    /// The current host's OS is older than the one tests were built on.
+    pub const HOST_OS_OLDER: Self = Self("NEXTEST_HOST_OS_OLDER");
+
+    /// A libdir recorded in the metadata differs from the one detected for the current toolchain.
+    pub const LIBDIR_CHANGED: Self = Self("NEXTEST_LIBDIR_CHANGED");
+
+    /// The current rustc is older than the one tests were built with.
+    pub const RUSTC_OLDER: Self = Self("NEXTEST_RUSTC_OLDER");
+
+    /// A search directory covers the target directory. See
+    /// [`RustBuildMetaValidationIssue::SearchDirCoversTargetDir`].
+    pub const SEARCH_DIR_COVERS_TARGET_DIR: Self = Self("NEXTEST_SEARCH_DIR_COVERS_TARGET_DIR");
+
+    /// The host and target platforms have the same triple, but different libdirs. See
+    /// [`RustBuildMetaValidationIssue::HostTargetLibdirMismatch`].
+    pub const HOST_TARGET_LIBDIR_MISMATCH: Self = Self("NEXTEST_HOST_TARGET_LIBDIR_MISMATCH");
+
+    /// Dynamic libraries are built for a target without a detected libdir. See
+    /// [`RustBuildMetaValidationIssue::TargetLibdirMissingForDylibs`].
+    pub const TARGET_LIBDIR_MISSING_FOR_DYLIBS: Self =
+        Self("NEXTEST_TARGET_LIBDIR_MISSING_FOR_DYLIBS");
+
+    /// A libdir is missing when cross-compiling. See
+    /// [`RustBuildMetaValidationIssue::LibdirMissingWhenCrossCompiling`].
+    pub const LIBDIR_MISSING_WHEN_CROSS_COMPILING: Self =
+        Self("NEXTEST_LIBDIR_MISSING_WHEN_CROSS_COMPILING");
+
+    /// Base output directories belong to more than one profile. See
+    /// [`RustBuildMetaValidationIssue::MultipleProfiles`].
+    pub const MULTIPLE_PROFILES: Self = Self("NEXTEST_MULTIPLE_PROFILES");
+
+    /// A base output directory exists without a `deps` subdirectory. See
+    /// [`RustBuildMetaValidationIssue::BaseOutputDirMissingDeps`].
+    pub const BASE_OUTPUT_DIR_MISSING_DEPS: Self = Self("NEXTEST_BASE_OUTPUT_DIR_MISSING_DEPS");
+
+    /// A linked path uses `..` to reach outside the target directory. See
+    /// [`RustBuildMetaValidationIssue::LinkedPathEscapesTargetDir`].
+    pub const LINKED_PATH_ESCAPES_TARGET_DIR: Self = Self("NEXTEST_LINKED_PATH_ESCAPES_TARGET_DIR");
+
+    /// A package ID isn't well-formed. See
+    /// [`RustBuildMetaValidationIssue::MalformedPackageId`].
+    pub const MALFORMED_PACKAGE_ID: Self = Self("NEXTEST_MALFORMED_PACKAGE_ID");
+
+    /// Returns the code as a string.
+    pub fn as_str(&self) -> &'static str {
+        self.0
+    }
+}
+
+impl fmt::Display for DiagnosticCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The severity of a [`Diagnostic`].
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum DiagnosticSeverity {
+    /// Something might go wrong, but nextest can continue.
+    Warning,
+
+    /// Something is definitely wrong.
+    Error,
+}
+
+impl fmt::Display for DiagnosticSeverity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Warning => write!(f, "warning"),
+            Self::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A structured diagnostic about Rust build metadata.
+///
+/// The [`Display`](fmt::Display) implementation renders the message followed by the code, for
+/// human-readable output.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Diagnostic {
+    /// The stable code for this diagnostic.
+    pub code: DiagnosticCode,
+
+    /// The severity of this diagnostic.
+    pub severity: DiagnosticSeverity,
+
+    /// A human-readable message.
+    pub message: String,
+
+    /// Paths related to this diagnostic, if any.
+    pub related_paths: Vec<Utf8PathBuf>,
+}
+
+impl Diagnostic {
+    /// Creates a new warning.
+    pub fn warning(code: DiagnosticCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            severity: DiagnosticSeverity::Warning,
+            message: message.into(),
+            related_paths: Vec::new(),
+        }
+    }
+
+    /// Creates a new error.
+    pub fn error(code: DiagnosticCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            severity: DiagnosticSeverity::Error,
+            message: message.into(),
+            related_paths: Vec::new(),
+        }
+    }
+
+    /// Sets the related paths for this diagnostic.
+    pub fn with_related_paths(
+        mut self,
+        related_paths: impl IntoIterator<Item = Utf8PathBuf>,
+    ) -> Self {
+        self.related_paths = related_paths.into_iter().collect();
+        self
+    }
+
+    /// Logs this diagnostic at the level corresponding to its severity.
+    pub(crate) fn log(&self) {
+        match self.severity {
+            DiagnosticSeverity::Warning => log::warn!("{self}"),
+            DiagnosticSeverity::Error => log::error!("{self}"),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} [{}]", self.message, self.code)
+    }
+}
+
+impl RustBuildMetaValidationIssue {
+    /// Converts this issue into a [`Diagnostic`].
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let (code, related_paths) = match self {
+            Self::SearchDirCoversTargetDir { resolved, .. } => (
+                DiagnosticCode::SEARCH_DIR_COVERS_TARGET_DIR,
+                vec![resolved.clone()],
+            ),
+            Self::LinkedPathEscapesTargetDir { resolved, .. } => (
+                DiagnosticCode::LINKED_PATH_ESCAPES_TARGET_DIR,
+                vec![resolved.clone()],
+            ),
+            Self::HostTargetLibdirMismatch {
+                host_libdir,
+                target_libdir,
+                ..
+            } => (
+                DiagnosticCode::HOST_TARGET_LIBDIR_MISMATCH,
+                host_libdir
+                    .as_path()
+                    .into_iter()
+                    .chain(target_libdir.as_path())
+                    .map(|path| path.to_path_buf())
+                    .collect(),
+            ),
+            Self::TargetLibdirMissingForDylibs { .. } => {
+                (DiagnosticCode::TARGET_LIBDIR_MISSING_FOR_DYLIBS, Vec::new())
+            }
+            Self::LibdirMissingWhenCrossCompiling { .. } => (
+                DiagnosticCode::LIBDIR_MISSING_WHEN_CROSS_COMPILING,
+                Vec::new(),
+            ),
+            Self::MultipleProfiles { profiles } => (
+                DiagnosticCode::MULTIPLE_PROFILES,
+                profiles.values().flatten().cloned().collect(),
+            ),
+            Self::BaseOutputDirMissingDeps { deps_dir, .. } => (
+                DiagnosticCode::BASE_OUTPUT_DIR_MISSING_DEPS,
+                vec![deps_dir.clone()],
+            ),
+            Self::MalformedPackageId { .. } => (DiagnosticCode::MALFORMED_PACKAGE_ID, Vec::new()),
+        };
+        Diagnostic::warning(code, self.to_string()).with_related_paths(related_paths)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cargo_config::TargetTriple,
+        list::{RustBuildMeta, TestListState},
+        platform::{BuildPlatforms, HostPlatform, PlatformLibdir, TargetPlatform},
+    };
+
+    #[test]
+    fn test_diagnostics() {
+        let meta = RustBuildMeta::<TestListState> {
+            target_directory: "/fake/workspace/target".into(),
+            base_output_directories: [Utf8PathBuf::from(".")].into(),
+            resolved_search_dirs: Some(vec!["debug/build/foo/out".into()]),
+            ..RustBuildMeta::empty()
+        };
+
+        let codes: Vec<_> = meta
+            .diagnostics()
+            .iter()
+            .map(|diagnostic| diagnostic.code)
+            .collect();
+        assert_eq!(
+            codes,
+            vec![
+                DiagnosticCode::LIBDIR_MISSING,
+                DiagnosticCode::SEARCH_DIRS_MISSING,
+                DiagnosticCode::SEARCH_DIR_COVERS_TARGET_DIR,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diagnostics_libdir_non_utf8() {
+        let meta = RustBuildMeta::<TestListState> {
+            build_platforms: BuildPlatforms {
+                host: HostPlatform::current(PlatformLibdir::from_rustc_stdout(Some(
+                    b"/fake/libdir/\xff".to_vec(),
+                )))
+                .expect("should detect the host platform successfully"),
+                target: None,
+            },
+            ..RustBuildMeta::empty()
+        };
+
+        let codes: Vec<_> = meta
+            .diagnostics()
+            .iter()
+            .map(|diagnostic| diagnostic.code)
+            .collect();
+        assert_eq!(
+            codes,
+            vec![
+                DiagnosticCode::LIBDIR_NON_UTF8,
+                DiagnosticCode::LIBDIR_MISSING
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diagnostics_target_sysroot_missing() {
+        let target = TargetPlatform::new(
+            TargetTriple::x86_64_unknown_linux_gnu(),
+            PlatformLibdir::Available("/fake/libdir".into()),
+        )
+        .with_sysroot(Some("/fake/missing-sysroot".into()));
+        let meta = RustBuildMeta::<TestListState> {
+            build_platforms: BuildPlatforms {
+                host: HostPlatform::current(PlatformLibdir::Available("/fake/libdir".into()))
+                    .expect("should detect the host platform successfully"),
+                target: Some(target),
+            },
+            ..RustBuildMeta::empty()
+        };
+
+        let diagnostics = meta.diagnostics();
+        assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+        assert_eq!(diagnostics[0].code, DiagnosticCode::TARGET_SYSROOT_MISSING);
+        assert_eq!(
+            diagnostics[0].related_paths,
+            vec![Utf8PathBuf::from("/fake/missing-sysroot")],
+        );
+    }
+
+    #[test]
+    fn test_diagnostic_display() {
+        let diagnostic = Diagnostic::warning(DiagnosticCode::LIBDIR_MISSING, "libdir missing");
+        assert_eq!(
+            diagnostic.to_string(),
+            "libdir missing [NEXTEST_LIBDIR_MISSING]"
+        );
+    }
+}
@@ -0,0 +1,136 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! GraphViz export for [`RustBuildMeta`].
+
+use super::RustBuildMeta;
+use std::collections::BTreeMap;
+
+impl<State> RustBuildMeta<State> {
+    /// Returns a GraphViz (DOT) graph of packages and the native search paths they requested.
+    ///
+    /// Packages are drawn as boxes and linked paths as ellipses. An edge from a package to a path
+    /// means that the package's build script requested that path. Linked paths without recorded
+    /// provenance have no incoming edges.
+    ///
+    /// Linked paths within the target directory are labeled relative to it, and are followed by
+    /// absolute linked paths outside of it.
+    ///
+    /// Requires the `graphviz` feature.
+    pub fn to_dot(&self) -> String {
+        let linked_paths = || self.linked_paths.iter().chain(&self.absolute_linked_paths);
+
+        let mut package_nodes = BTreeMap::new();
+        for package_id in linked_paths().flat_map(|(_, requested_by)| requested_by) {
+            let next_index = package_nodes.len();
+            package_nodes
+                .entry(package_id.as_str())
+                .or_insert(next_index);
+        }
+
+        let mut out = String::from("digraph {\n");
+        for (package_id, index) in &package_nodes {
+            out.push_str(&format!(
+                "    package{index} [label=\"{}\", shape=box];\n",
+                escape_label(package_id),
+            ));
+        }
+        for (path_index, (path, requested_by)) in linked_paths().enumerate() {
+            out.push_str(&format!(
+                "    path{path_index} [label=\"{}\", shape=ellipse];\n",
+                escape_label(path.as_str()),
+            ));
+            for package_id in requested_by {
+                out.push_str(&format!(
+                    "    package{} -> path{path_index};\n",
+                    package_nodes[package_id.as_str()],
+                ));
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+fn escape_label(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::list::TestListState;
+    use camino::Utf8PathBuf;
+    use indoc::indoc;
+
+    #[test]
+    fn test_to_dot() {
+        let meta = RustBuildMeta::<TestListState> {
+            linked_paths: [
+                (
+                    Utf8PathBuf::from("debug/build/foo-sys/out"),
+                    ["foo-sys 0.1.0".to_owned()].into(),
+                ),
+                (
+                    Utf8PathBuf::from("debug/build/shared/out"),
+                    ["bar \"quoted\"".to_owned(), "foo-sys 0.1.0".to_owned()].into(),
+                ),
+                (Utf8PathBuf::from("debug/unknown"), Default::default()),
+            ]
+            .into(),
+            ..RustBuildMeta::empty()
+        };
+
+        assert_eq!(
+            meta.to_dot(),
+            indoc! {r#"
+                digraph {
+                    package1 [label="bar \"quoted\"", shape=box];
+                    package0 [label="foo-sys 0.1.0", shape=box];
+                    path0 [label="debug/build/foo-sys/out", shape=ellipse];
+                    package0 -> path0;
+                    path1 [label="debug/build/shared/out", shape=ellipse];
+                    package1 -> path1;
+                    package0 -> path1;
+                    path2 [label="debug/unknown", shape=ellipse];
+                }
+            "#}
+        );
+    }
+
+    #[test]
+    fn test_to_dot_absolute_linked_paths() {
+        let meta = RustBuildMeta::<TestListState> {
+            linked_paths: [(
+                Utf8PathBuf::from("debug/build/foo-sys/out"),
+                ["foo-sys 0.1.0".to_owned()].into(),
+            )]
+            .into(),
+            absolute_linked_paths: [
+                (
+                    Utf8PathBuf::from("/opt/vendor/lib"),
+                    ["bar-sys 0.1.0".to_owned(), "foo-sys 0.1.0".to_owned()].into(),
+                ),
+                (Utf8PathBuf::from("/usr/local/lib"), Default::default()),
+            ]
+            .into(),
+            ..RustBuildMeta::empty()
+        };
+
+        assert_eq!(
+            meta.to_dot(),
+            indoc! {r#"
+                digraph {
+                    package1 [label="bar-sys 0.1.0", shape=box];
+                    package0 [label="foo-sys 0.1.0", shape=box];
+                    path0 [label="debug/build/foo-sys/out", shape=ellipse];
+                    package0 -> path0;
+                    path1 [label="/opt/vendor/lib", shape=ellipse];
+                    package1 -> path1;
+                    package0 -> path1;
+                    path2 [label="/usr/local/lib", shape=ellipse];
+                }
+            "#}
+        );
+    }
+}
@@ -0,0 +1,225 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Computing dynamic library paths incrementally, as binaries are discovered.
+
+use super::{PathChecker, RealPathChecker, RustBuildMeta};
+use crate::{helpers::convert_rel_path_to_main_sep, list::TestListState};
+use camino::{Utf8Path, Utf8PathBuf};
+use std::collections::HashMap;
+
+/// Computes [`RustBuildMeta::dylib_paths`] incrementally, as linked paths and base output
+/// directories are discovered.
+///
+/// Each path is checked for existence as soon as it's added, so that filesystem access overlaps
+/// with listing rather than happening after it. [`Self::finish`] then returns the same paths, in
+/// the same order, as [`RustBuildMeta::dylib_paths`] would for the accumulated metadata, as long
+/// as the filesystem doesn't change in between.
+#[derive(Clone, Debug)]
+pub struct DylibPathsBuilder {
+    meta: RustBuildMeta<TestListState>,
+    checked: CheckedPaths,
+}
+
+impl DylibPathsBuilder {
+    /// Creates a new builder starting from `meta`.
+    ///
+    /// `meta` determines the target directory, build platforms and options such as
+    /// [`RustBuildMeta::include_linked_path_parents`]. Any paths it already contains are checked
+    /// immediately.
+    pub fn new(meta: RustBuildMeta<TestListState>) -> Self {
+        let mut builder = Self {
+            meta,
+            checked: CheckedPaths::default(),
+        };
+        let linked_paths: Vec<_> = builder.meta.linked_paths.keys().cloned().collect();
+        for rel_path in linked_paths {
+            builder.check_linked_path(&rel_path);
+        }
+        let absolute_linked_paths: Vec<_> =
+            builder.meta.absolute_linked_paths.keys().cloned().collect();
+        for abs_path in absolute_linked_paths {
+            builder.checked.check(abs_path);
+        }
+        let base_output_directories: Vec<_> = builder
+            .meta
+            .base_output_directories
+            .iter()
+            .cloned()
+            .collect();
+        for base_output in base_output_directories {
+            builder.check_output_root(
+                builder
+                    .meta
+                    .target_directory
+                    .join(convert_rel_path_to_main_sep(&base_output)),
+            );
+        }
+        let extra_output_roots: Vec<_> = builder.meta.extra_output_roots.iter().cloned().collect();
+        for root in extra_output_roots {
+            builder.check_output_root(root);
+        }
+        builder
+    }
+
+    /// Adds a linked path relative to the target directory, requested by the package
+    /// `package_id`.
+    pub fn add_linked_path(&mut self, rel_path: Utf8PathBuf, package_id: impl Into<String>) {
+        self.check_linked_path(&rel_path);
+        self.meta
+            .linked_paths
+            .entry(rel_path)
+            .or_default()
+            .insert(package_id.into());
+    }
+
+    /// Adds an absolute linked path, requested by the package `package_id`.
+    pub fn add_absolute_linked_path(
+        &mut self,
+        abs_path: Utf8PathBuf,
+        package_id: impl Into<String>,
+    ) {
+        self.checked.check(abs_path.clone());
+        self.meta
+            .absolute_linked_paths
+            .entry(abs_path)
+            .or_default()
+            .insert(package_id.into());
+    }
+
+    /// Adds a base output directory, relative to the target directory.
+    pub fn add_base_output_directory(&mut self, rel_path: Utf8PathBuf) {
+        self.check_output_root(
+            self.meta
+                .target_directory
+                .join(convert_rel_path_to_main_sep(&rel_path)),
+        );
+        self.meta.base_output_directories.insert(rel_path);
+    }
+
+    /// Returns the metadata accumulated so far.
+    pub fn meta(&self) -> &RustBuildMeta<TestListState> {
+        &self.meta
+    }
+
+    /// Returns the dynamic library paths for the accumulated metadata.
+    ///
+    /// This is equivalent to calling [`RustBuildMeta::dylib_paths`] on [`Self::meta`], except that
+    /// paths added to the builder aren't checked again.
+    pub fn finish(self) -> Vec<Utf8PathBuf> {
+        self.meta.dylib_paths_with_checker(&self.checked)
+    }
+
+    fn check_linked_path(&mut self, rel_path: &Utf8Path) {
+        let parent = self
+            .meta
+            .include_linked_path_parents
+            .then(|| rel_path.parent())
+            .flatten()
+            .filter(|parent| !parent.as_str().is_empty());
+        for path in std::iter::once(rel_path).chain(parent) {
+            self.checked.check(
+                self.meta
+                    .target_directory
+                    .join(convert_rel_path_to_main_sep(path)),
+            );
+        }
+    }
+
+    fn check_output_root(&mut self, abs_path: Utf8PathBuf) {
+        self.checked.check(abs_path.join("deps"));
+        self.checked.check(abs_path);
+    }
+}
+
+/// The results of filesystem checks performed while paths were added.
+#[derive(Clone, Debug, Default)]
+struct CheckedPaths {
+    /// Whether each path exists, and whether it's a directory.
+    paths: HashMap<Utf8PathBuf, (bool, bool)>,
+}
+
+impl CheckedPaths {
+    fn check(&mut self, path: Utf8PathBuf) {
+        self.paths.entry(path).or_insert_with_key(|path| {
+            (RealPathChecker.exists(path), RealPathChecker.is_dir(path))
+        });
+    }
+}
+
+impl PathChecker for CheckedPaths {
+    fn exists(&self, path: &Utf8Path) -> bool {
+        match self.paths.get(path) {
+            Some(&(exists, _)) => exists,
+            None => RealPathChecker.exists(path),
+        }
+    }
+
+    fn is_dir(&self, path: &Utf8Path) -> bool {
+        match self.paths.get(path) {
+            Some(&(_, is_dir)) => is_dir,
+            None => RealPathChecker.is_dir(path),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dylib_paths_builder_matches_batch() {
+        let tmpdir = camino_tempfile::tempdir().expect("should create temp dir successfully");
+        let target_join = |rel_path: &str| {
+            tmpdir
+                .path()
+                .join(convert_rel_path_to_main_sep(Utf8Path::new(rel_path)))
+        };
+        for dir in [
+            "debug/build/foo/out/lib",
+            "debug/build/bar/out/lib",
+            "debug/deps",
+        ] {
+            std::fs::create_dir_all(target_join(dir)).expect("should create dir");
+        }
+        let native_dir = target_join("native");
+        std::fs::create_dir_all(&native_dir).expect("should create native dir");
+
+        let empty = RustBuildMeta::<TestListState> {
+            target_directory: tmpdir.path().to_path_buf(),
+            include_linked_path_parents: true,
+            ..RustBuildMeta::empty()
+        };
+        let mut builder = DylibPathsBuilder::new(empty.clone());
+        // Add paths out of order, and with duplicates, as they might arrive during listing.
+        builder.add_base_output_directory("debug".into());
+        builder.add_linked_path("debug/build/foo/out/lib".into(), "foo-id");
+        builder.add_absolute_linked_path(native_dir.clone(), "bar-id");
+        builder.add_linked_path("debug/build/missing/out/lib".into(), "baz-id");
+        builder.add_linked_path("debug/build/bar/out/lib".into(), "bar-id");
+        builder.add_linked_path("debug/build/foo/out/lib".into(), "bar-id");
+
+        let batch = RustBuildMeta::<TestListState> {
+            base_output_directories: [Utf8PathBuf::from("debug")].into(),
+            linked_paths: [
+                (
+                    Utf8PathBuf::from("debug/build/bar/out/lib"),
+                    ["bar-id".to_owned()].into(),
+                ),
+                (
+                    Utf8PathBuf::from("debug/build/foo/out/lib"),
+                    ["bar-id".to_owned(), "foo-id".to_owned()].into(),
+                ),
+                (
+                    Utf8PathBuf::from("debug/build/missing/out/lib"),
+                    ["baz-id".to_owned()].into(),
+                ),
+            ]
+            .into(),
+            absolute_linked_paths: [(native_dir, ["bar-id".to_owned()].into())].into(),
+            ..empty
+        };
+        assert_eq!(builder.meta(), &batch);
+        assert_eq!(builder.finish(), batch.dylib_paths());
+    }
+}
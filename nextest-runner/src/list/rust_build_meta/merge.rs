@@ -0,0 +1,559 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Merging [`RustBuildMeta`] instances produced by separate listing runs.
+
+use super::RustBuildMeta;
+use crate::{
+    errors::{MetadataMergeConflict, MetadataMergeError},
+    list::BinaryListState,
+    platform::{BuildPlatforms, PlatformLibdir, TargetPlatform},
+};
+use camino::{Utf8Component, Utf8Path, Utf8PathBuf};
+use nextest_metadata::RustNonTestBinarySummary;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// A mapping from Cargo profile names to the profiles they're equivalent to, used to normalize
+/// base output directories before merging.
+///
+/// For example, a custom profile `ci` that inherits from `dev` builds into `target/ci` rather than
+/// `target/debug`. If its outputs are shared with `dev` builds, mapping `ci` to `debug` lets
+/// metadata from both be merged without spurious conflicts.
+///
+/// Aliases are resolved once, so an alias can't map to another alias.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ProfileAliases {
+    aliases: BTreeMap<String, String>,
+}
+
+impl ProfileAliases {
+    /// Creates a new, empty set of aliases.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an alias from the profile directory `alias` to `canonical`.
+    pub fn insert(&mut self, alias: impl Into<String>, canonical: impl Into<String>) -> &mut Self {
+        self.aliases.insert(alias.into(), canonical.into());
+        self
+    }
+
+    /// Returns true if no aliases have been added.
+    pub fn is_empty(&self) -> bool {
+        self.aliases.is_empty()
+    }
+
+    /// Normalizes the profile segment of a path relative to the target directory.
+    ///
+    /// The profile segment is the first component, or the second if the first is a target
+    /// triple. Since target triples are never profile names, the first of the two that matches an
+    /// alias is replaced.
+    fn normalize(&self, path: &Utf8Path) -> Utf8PathBuf {
+        let mut components: Vec<_> = path.components().collect();
+        let profile_idx = components.iter().take(2).position(|component| {
+            matches!(component, Utf8Component::Normal(name) if self.aliases.contains_key(*name))
+        });
+        if let Some(idx) = profile_idx {
+            let Utf8Component::Normal(name) = components[idx] else {
+                unreachable!("position only matches normal components");
+            };
+            components[idx] = Utf8Component::Normal(&self.aliases[name]);
+        }
+        components.into_iter().collect()
+    }
+}
+
+impl RustBuildMeta<BinaryListState> {
+    /// Merges serialized metadata files produced by separate listing runs, for example by
+    /// sharded listing in CI.
+    ///
+    /// Files are read and merged into an accumulator one at a time, so at most one file is held
+    /// in memory in addition to the accumulator. All files must have the same target directory
+    /// and build platforms, and must agree on build script output directories. The label, host OS
+    /// version, list command, build time and debug info level are taken from the first file.
+    ///
+    /// If a file conflicts with the files merged before it, the returned error names that file.
+    pub fn merge_from_files(paths: &[Utf8PathBuf]) -> Result<Self, MetadataMergeError> {
+        Self::merge_from_files_with_aliases(paths, &ProfileAliases::new())
+    }
+
+    /// Like [`Self::merge_from_files`], but first normalizes each file's paths using `aliases`.
+    ///
+    /// See [`Self::normalize_profile_aliases`] for details.
+    pub fn merge_from_files_with_aliases(
+        paths: &[Utf8PathBuf],
+        aliases: &ProfileAliases,
+    ) -> Result<Self, MetadataMergeError> {
+        let (first, rest) = paths.split_first().ok_or(MetadataMergeError::NoInputs)?;
+        let mut merged = Self::load_summary_blocking(first)?;
+        merged.normalize_profile_aliases(aliases);
+        for path in rest {
+            let mut other = Self::load_summary_blocking(path)?;
+            other.normalize_profile_aliases(aliases);
+            merged
+                .merge(other)
+                .map_err(|conflict| MetadataMergeError::Conflict {
+                    path: path.clone(),
+                    conflict,
+                })?;
+        }
+        Ok(merged)
+    }
+
+    /// Rewrites the profile segment of paths relative to the target directory using `aliases`.
+    ///
+    /// This applies to base output directories, build script output directories, linked paths and
+    /// non-test binaries. Base output directories and linked paths that become identical are
+    /// collapsed into one.
+    ///
+    /// Only use this for profiles whose outputs really are shared, for example through a symlink:
+    /// nextest looks for files at the normalized paths.
+    pub fn normalize_profile_aliases(&mut self, aliases: &ProfileAliases) {
+        if aliases.is_empty() {
+            return;
+        }
+
+        self.base_output_directories = std::mem::take(&mut self.base_output_directories)
+            .into_iter()
+            .map(|dir| aliases.normalize(&dir))
+            .collect();
+        self.base_output_directory_categories =
+            std::mem::take(&mut self.base_output_directory_categories)
+                .into_iter()
+                .map(|(dir, category)| (aliases.normalize(&dir), category))
+                .collect();
+        for out_dir in self.build_script_out_dirs.values_mut() {
+            *out_dir = aliases.normalize(out_dir);
+        }
+        let mut linked_paths = BTreeMap::<_, BTreeSet<_>>::new();
+        for (path, requested_by) in std::mem::take(&mut self.linked_paths) {
+            linked_paths
+                .entry(aliases.normalize(&path))
+                .or_default()
+                .extend(requested_by);
+        }
+        self.linked_paths = linked_paths;
+        for binaries in self.non_test_binaries.values_mut() {
+            *binaries = std::mem::take(binaries)
+                .into_iter()
+                .map(|binary| RustNonTestBinarySummary {
+                    path: aliases.normalize(&binary.path),
+                    ..binary
+                })
+                .collect();
+        }
+    }
+
+    /// Merges `other` into `self`, for example to combine metadata from separate builds into a
+    /// single archive.
+    ///
+    /// Base output directories, non-test binaries, build script output directories and linked
+    /// paths are unioned, and the sets of packages that requested each linked path are merged.
+    ///
+    /// Both must have the same target directory, and the same host and target platforms. If a
+    /// platform's libdir was only detected for one of them, the detected libdir is used. Only one
+    /// target platform is currently supported, so metadata for different targets can't be merged.
+    ///
+    /// On conflict, `self` is left unchanged.
+    pub fn merge(&mut self, other: Self) -> Result<(), MetadataMergeConflict> {
+        if self.target_directory != other.target_directory {
+            return Err(MetadataMergeConflict::TargetDirectory {
+                expected: self.target_directory.clone(),
+                actual: other.target_directory,
+            });
+        }
+        let build_platforms = merge_build_platforms(&self.build_platforms, &other.build_platforms)
+            .ok_or(MetadataMergeConflict::BuildPlatforms)?;
+        for (package_id, out_dir) in &other.build_script_out_dirs {
+            match self.build_script_out_dirs.get(package_id) {
+                Some(existing) if existing != out_dir => {
+                    return Err(MetadataMergeConflict::BuildScriptOutDir {
+                        package_id: package_id.clone(),
+                        expected: existing.clone(),
+                        actual: out_dir.clone(),
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        self.build_platforms = build_platforms;
+        self.base_output_directories
+            .extend(other.base_output_directories);
+        self.base_output_directory_categories
+            .extend(other.base_output_directory_categories);
+        for (package_id, binaries) in other.non_test_binaries {
+            self.non_test_binaries
+                .entry(package_id)
+                .or_default()
+                .extend(binaries);
+        }
+        self.build_script_out_dirs
+            .extend(other.build_script_out_dirs);
+        for (path, requested_by) in other.linked_paths {
+            self.linked_paths
+                .entry(path)
+                .or_default()
+                .extend(requested_by);
+        }
+        for (path, requested_by) in other.absolute_linked_paths {
+            self.absolute_linked_paths
+                .entry(path)
+                .or_default()
+                .extend(requested_by);
+        }
+        for (lib, linked_by) in other.linked_libs {
+            self.linked_libs.entry(lib).or_default().extend(linked_by);
+        }
+        for (package_id, features) in other.enabled_features {
+            self.enabled_features
+                .entry(package_id)
+                .or_default()
+                .extend(features);
+        }
+        self.extra_output_roots.extend(other.extra_output_roots);
+        self.resolved_search_dirs =
+            match (self.resolved_search_dirs.take(), other.resolved_search_dirs) {
+                (Some(mut dirs), Some(other_dirs)) => {
+                    for dir in other_dirs {
+                        if !dirs.contains(&dir) {
+                            dirs.push(dir);
+                        }
+                    }
+                    Some(dirs)
+                }
+                // If either side didn't record its search directories, the merged set would be
+                // incomplete.
+                _ => None,
+            };
+
+        Ok(())
+    }
+}
+
+/// Combines build platforms that only differ in which libdirs were detected.
+fn merge_build_platforms(a: &BuildPlatforms, b: &BuildPlatforms) -> Option<BuildPlatforms> {
+    if a.host.platform != b.host.platform {
+        return None;
+    }
+    let mut merged = a.clone();
+    merged.host.libdir = merge_libdir(&a.host.libdir, &b.host.libdir)?;
+    merged.target = match (&a.target, &b.target) {
+        (None, None) => None,
+        (Some(a_target), Some(b_target)) => {
+            let libdir = merge_libdir(&a_target.libdir, &b_target.libdir)?;
+            let with_libdir = |target: &TargetPlatform| TargetPlatform {
+                libdir: libdir.clone(),
+                ..target.clone()
+            };
+            let merged_target = with_libdir(a_target);
+            if merged_target != with_libdir(b_target) {
+                return None;
+            }
+            Some(merged_target)
+        }
+        (Some(_), None) | (None, Some(_)) => return None,
+    };
+    Some(merged)
+}
+
+fn merge_libdir(a: &PlatformLibdir, b: &PlatformLibdir) -> Option<PlatformLibdir> {
+    match (a, b) {
+        _ if a == b => Some(a.clone()),
+        (PlatformLibdir::Available(_), PlatformLibdir::Unavailable(_)) => Some(a.clone()),
+        (PlatformLibdir::Unavailable(_), PlatformLibdir::Available(_)) => Some(b.clone()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cargo_config::TargetTriple, platform::HostPlatform};
+    use nextest_metadata::PlatformLibdirUnavailable;
+
+    fn write_meta(
+        dir: &camino::Utf8Path,
+        name: &str,
+        meta: &RustBuildMeta<BinaryListState>,
+    ) -> Utf8PathBuf {
+        let path = dir.join(name);
+        std::fs::write(
+            &path,
+            serde_json::to_string(&meta.to_summary()).expect("summary should serialize"),
+        )
+        .expect("should write metadata file");
+        path
+    }
+
+    #[test]
+    fn test_merge_from_files() {
+        let tmpdir = camino_tempfile::tempdir().expect("should create temp dir successfully");
+        let shard1 = RustBuildMeta::<BinaryListState> {
+            target_directory: "/fake/target".into(),
+            base_output_directories: [Utf8PathBuf::from("debug")].into(),
+            build_script_out_dirs: [("foo-id".to_owned(), "debug/build/foo/out".into())].into(),
+            linked_paths: [(
+                Utf8PathBuf::from("debug/build/foo/out/lib"),
+                ["foo-id".to_owned()].into(),
+            )]
+            .into(),
+            ..Default::default()
+        };
+        let shard2 = RustBuildMeta::<BinaryListState> {
+            target_directory: "/fake/target".into(),
+            base_output_directories: [Utf8PathBuf::from("debug")].into(),
+            build_script_out_dirs: [("bar-id".to_owned(), "debug/build/bar/out".into())].into(),
+            linked_paths: [(
+                Utf8PathBuf::from("debug/build/foo/out/lib"),
+                ["bar-id".to_owned()].into(),
+            )]
+            .into(),
+            ..Default::default()
+        };
+        let paths = [
+            write_meta(tmpdir.path(), "shard1.json", &shard1),
+            write_meta(tmpdir.path(), "shard2.json", &shard2),
+        ];
+
+        let merged =
+            RustBuildMeta::merge_from_files(&paths).expect("merging shards should succeed");
+        assert_eq!(
+            merged.build_script_out_dirs,
+            [
+                ("bar-id".to_owned(), "debug/build/bar/out".into()),
+                ("foo-id".to_owned(), "debug/build/foo/out".into()),
+            ]
+            .into(),
+        );
+        assert_eq!(
+            merged.linked_paths,
+            [(
+                Utf8PathBuf::from("debug/build/foo/out/lib"),
+                BTreeSet::from(["bar-id".to_owned(), "foo-id".to_owned()]),
+            )]
+            .into(),
+        );
+    }
+
+    #[test]
+    fn test_merge_from_files_conflict() {
+        let tmpdir = camino_tempfile::tempdir().expect("should create temp dir successfully");
+        let shard1 = RustBuildMeta::<BinaryListState> {
+            target_directory: "/fake/target".into(),
+            ..Default::default()
+        };
+        let shard2 = RustBuildMeta::<BinaryListState> {
+            target_directory: "/other/target".into(),
+            ..Default::default()
+        };
+        let paths = [
+            write_meta(tmpdir.path(), "shard1.json", &shard1),
+            write_meta(tmpdir.path(), "shard2.json", &shard2),
+        ];
+
+        let error = RustBuildMeta::merge_from_files(&paths).expect_err("target dirs conflict");
+        match error {
+            MetadataMergeError::Conflict { path, conflict } => {
+                assert_eq!(path, paths[1]);
+                assert_eq!(
+                    conflict,
+                    MetadataMergeConflict::TargetDirectory {
+                        expected: "/fake/target".into(),
+                        actual: "/other/target".into(),
+                    }
+                );
+            }
+            other => panic!("expected a conflict, found {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_merge_overlapping_linked_paths() {
+        let mut meta = RustBuildMeta::<BinaryListState> {
+            target_directory: "/fake/target".into(),
+            base_output_directories: [Utf8PathBuf::from("debug")].into(),
+            linked_paths: [(
+                Utf8PathBuf::from("debug/build/shared/out/lib"),
+                ["foo-id".to_owned()].into(),
+            )]
+            .into(),
+            absolute_linked_paths: [(
+                Utf8PathBuf::from("/fake/native/lib"),
+                ["foo-id".to_owned()].into(),
+            )]
+            .into(),
+            ..Default::default()
+        };
+        let other = RustBuildMeta::<BinaryListState> {
+            target_directory: "/fake/target".into(),
+            base_output_directories: [Utf8PathBuf::from("aarch64-unknown-linux-gnu/debug")].into(),
+            linked_paths: [
+                (
+                    Utf8PathBuf::from("debug/build/shared/out/lib"),
+                    ["bar-id".to_owned()].into(),
+                ),
+                (
+                    Utf8PathBuf::from("debug/build/bar/out/lib"),
+                    ["bar-id".to_owned()].into(),
+                ),
+            ]
+            .into(),
+            absolute_linked_paths: [(
+                Utf8PathBuf::from("/fake/native/lib"),
+                ["bar-id".to_owned()].into(),
+            )]
+            .into(),
+            ..Default::default()
+        };
+
+        meta.merge(other).expect("merging should succeed");
+        assert_eq!(
+            meta.base_output_directories,
+            [
+                Utf8PathBuf::from("aarch64-unknown-linux-gnu/debug"),
+                Utf8PathBuf::from("debug"),
+            ]
+            .into(),
+        );
+        assert_eq!(
+            meta.linked_paths,
+            [
+                (
+                    Utf8PathBuf::from("debug/build/bar/out/lib"),
+                    BTreeSet::from(["bar-id".to_owned()]),
+                ),
+                (
+                    Utf8PathBuf::from("debug/build/shared/out/lib"),
+                    BTreeSet::from(["bar-id".to_owned(), "foo-id".to_owned()]),
+                ),
+            ]
+            .into(),
+        );
+        assert_eq!(
+            meta.absolute_linked_paths,
+            [(
+                Utf8PathBuf::from("/fake/native/lib"),
+                BTreeSet::from(["bar-id".to_owned(), "foo-id".to_owned()]),
+            )]
+            .into(),
+        );
+    }
+
+    #[test]
+    fn test_merge_build_platforms() {
+        let build_platforms = |libdir| BuildPlatforms {
+            host: HostPlatform {
+                platform: TargetTriple::x86_64_unknown_linux_gnu().platform,
+                libdir,
+            },
+            target: None,
+        };
+        let detected = build_platforms(PlatformLibdir::Available("/fake/libdir".into()));
+        let undetected = build_platforms(PlatformLibdir::Unavailable(
+            PlatformLibdirUnavailable::RUSTC_FAILED,
+        ));
+        let other_libdir = build_platforms(PlatformLibdir::Available("/other/libdir".into()));
+
+        assert_eq!(
+            merge_build_platforms(&undetected, &detected),
+            Some(detected.clone()),
+            "a detected libdir is used over an undetected one"
+        );
+        assert_eq!(
+            merge_build_platforms(&detected, &undetected),
+            Some(detected.clone())
+        );
+        assert_eq!(merge_build_platforms(&detected, &other_libdir), None);
+
+        let target_triple =
+            TargetTriple::deserialize_str(Some("aarch64-unknown-linux-gnu".to_owned()))
+                .expect("creating TargetTriple should succeed")
+                .expect("the output of deserialize_str shouldn't be None");
+        let with_target = BuildPlatforms {
+            target: Some(TargetPlatform::new(
+                target_triple,
+                PlatformLibdir::Available("/fake/target-libdir".into()),
+            )),
+            ..detected.clone()
+        };
+        assert_eq!(
+            merge_build_platforms(&detected, &with_target),
+            None,
+            "builds for different targets can't be merged"
+        );
+    }
+
+    #[test]
+    fn test_merge_from_files_with_aliases() {
+        let tmpdir = camino_tempfile::tempdir().expect("should create temp dir successfully");
+        let dev = RustBuildMeta::<BinaryListState> {
+            target_directory: "/fake/target".into(),
+            base_output_directories: [Utf8PathBuf::from("debug")].into(),
+            build_script_out_dirs: [("foo-id".to_owned(), "debug/build/foo/out".into())].into(),
+            ..Default::default()
+        };
+        let ci = RustBuildMeta::<BinaryListState> {
+            target_directory: "/fake/target".into(),
+            base_output_directories: [
+                Utf8PathBuf::from("ci"),
+                Utf8PathBuf::from("aarch64-unknown-linux-gnu/ci"),
+            ]
+            .into(),
+            build_script_out_dirs: [("foo-id".to_owned(), "ci/build/foo/out".into())].into(),
+            linked_paths: [(
+                Utf8PathBuf::from("ci/build/foo/out/lib"),
+                ["foo-id".to_owned()].into(),
+            )]
+            .into(),
+            ..Default::default()
+        };
+        let paths = [
+            write_meta(tmpdir.path(), "dev.json", &dev),
+            write_meta(tmpdir.path(), "ci.json", &ci),
+        ];
+
+        let error = RustBuildMeta::merge_from_files(&paths).expect_err("profiles conflict");
+        assert!(
+            matches!(
+                error,
+                MetadataMergeError::Conflict {
+                    conflict: MetadataMergeConflict::BuildScriptOutDir { .. },
+                    ..
+                }
+            ),
+            "{error:?}"
+        );
+
+        let mut aliases = ProfileAliases::new();
+        aliases.insert("ci", "debug");
+        let merged = RustBuildMeta::merge_from_files_with_aliases(&paths, &aliases)
+            .expect("aliased profiles should merge");
+        assert_eq!(
+            merged.base_output_directories,
+            [
+                Utf8PathBuf::from("aarch64-unknown-linux-gnu/debug"),
+                Utf8PathBuf::from("debug"),
+            ]
+            .into(),
+        );
+        assert_eq!(
+            merged.build_script_out_dirs,
+            [("foo-id".to_owned(), "debug/build/foo/out".into())].into(),
+        );
+        assert_eq!(
+            merged.linked_paths,
+            [(
+                Utf8PathBuf::from("debug/build/foo/out/lib"),
+                BTreeSet::from(["foo-id".to_owned()]),
+            )]
+            .into(),
+        );
+    }
+
+    #[test]
+    fn test_merge_from_files_empty() {
+        let error = RustBuildMeta::merge_from_files(&[]).expect_err("no inputs");
+        assert!(matches!(error, MetadataMergeError::NoInputs), "{error:?}");
+    }
+}
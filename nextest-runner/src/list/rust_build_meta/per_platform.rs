@@ -0,0 +1,328 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Dynamic library paths for host and target binaries.
+
+use super::{PathChecker, RealPathChecker, RustBuildMeta};
+use crate::list::{BinaryList, TestListState};
+use camino::{Utf8Path, Utf8PathBuf};
+use nextest_metadata::{BuildPlatform, RustBinaryId};
+
+/// The difference between the dynamic library paths for host and target binaries.
+///
+/// Returned by [`RustBuildMeta::dylib_path_host_vs_target_delta`]. Each list is in the order
+/// returned by [`RustBuildMeta::dylib_paths_for_platform`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct DylibPathDelta {
+    /// Paths only searched for host binaries.
+    pub host_only: Vec<Utf8PathBuf>,
+
+    /// Paths only searched for target binaries.
+    pub target_only: Vec<Utf8PathBuf>,
+
+    /// Paths searched for both host and target binaries.
+    pub common: Vec<Utf8PathBuf>,
+}
+
+impl DylibPathDelta {
+    /// Returns true if host and target binaries have the same search paths.
+    pub fn is_empty(&self) -> bool {
+        self.host_only.is_empty() && self.target_only.is_empty()
+    }
+}
+
+/// A test binary whose platform's rustc libdir wasn't detected.
+///
+/// Returned by [`RustBuildMeta::tests_blocked_by_missing_libdir`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LibdirBlockedBinary {
+    /// The binary ID.
+    pub binary_id: RustBinaryId,
+
+    /// The platform the binary was built for.
+    pub build_platform: BuildPlatform,
+
+    /// The triple of the platform whose libdir is missing.
+    pub triple: String,
+}
+
+impl RustBuildMeta<TestListState> {
+    /// Returns the binaries in `binary_list` that are built for a platform whose rustc libdir
+    /// wasn't detected.
+    ///
+    /// Such binaries fail to run if they dynamically link against the standard library. Nextest
+    /// can't tell which binaries do, so all binaries for the affected platform are returned. This
+    /// lets callers skip them with a reason rather than failing at runtime, for example if a
+    /// target toolchain is only partially installed.
+    ///
+    /// Without a target platform, target binaries are built for the host platform.
+    pub fn tests_blocked_by_missing_libdir(
+        &self,
+        binary_list: &BinaryList,
+    ) -> Vec<LibdirBlockedBinary> {
+        let host = &self.build_platforms.host;
+        let target = self.build_platforms.target.as_ref();
+        binary_list
+            .rust_binaries
+            .iter()
+            .filter_map(|binary| {
+                let (libdir, triple) = match (binary.build_platform, target) {
+                    (BuildPlatform::Target, Some(target)) => {
+                        (&target.libdir, target.triple.platform.triple_str())
+                    }
+                    (BuildPlatform::Target, None) | (BuildPlatform::Host, _) => {
+                        (&host.libdir, host.platform.triple_str())
+                    }
+                };
+                libdir.as_path().is_none().then(|| LibdirBlockedBinary {
+                    binary_id: binary.id.clone(),
+                    build_platform: binary.build_platform,
+                    triple: triple.to_owned(),
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the subset of [`Self::dylib_paths`] relevant to binaries built for
+    /// `build_platform`.
+    ///
+    /// If a target platform is specified, Cargo places target artifacts under a subdirectory of
+    /// the target directory named after the triple, and host artifacts (build scripts, proc
+    /// macros) directly under the target directory. Search paths under the target directory are
+    /// assigned based on that layout, as are the host and target libdirs. Paths outside the target
+    /// directory are kept for both platforms.
+    ///
+    /// If the host and target libdirs share a parent directory (see
+    /// [`BuildPlatforms::libdir_shared_root`](crate::platform::BuildPlatforms::libdir_shared_root)),
+    /// that directory is left out for both platforms, since it might contain libraries for either
+    /// architecture.
+    ///
+    /// Without a target platform, host and target binaries are the same, and this returns
+    /// [`Self::dylib_paths`].
+    ///
+    /// Nextest itself adds the full [`Self::dylib_paths`] for all binaries.
+    pub fn dylib_paths_for_platform(&self, build_platform: BuildPlatform) -> Vec<Utf8PathBuf> {
+        self.dylib_paths_for_platform_with_checker(build_platform, &RealPathChecker)
+    }
+
+    /// Returns the difference between the dynamic library paths for host and target binaries.
+    ///
+    /// This can be used to check that target binaries don't pick up host libdirs, for example.
+    pub fn dylib_path_host_vs_target_delta(&self) -> DylibPathDelta {
+        self.dylib_path_host_vs_target_delta_with_checker(&RealPathChecker)
+    }
+
+    fn dylib_path_host_vs_target_delta_with_checker(
+        &self,
+        checker: &dyn PathChecker,
+    ) -> DylibPathDelta {
+        let host = self.dylib_paths_for_platform_with_checker(BuildPlatform::Host, checker);
+        let target = self.dylib_paths_for_platform_with_checker(BuildPlatform::Target, checker);
+
+        let mut delta = DylibPathDelta::default();
+        for path in &host {
+            if target.contains(path) {
+                delta.common.push(path.clone());
+            } else {
+                delta.host_only.push(path.clone());
+            }
+        }
+        delta.target_only = target
+            .into_iter()
+            .filter(|path| !host.contains(path))
+            .collect();
+        delta
+    }
+
+    fn dylib_paths_for_platform_with_checker(
+        &self,
+        build_platform: BuildPlatform,
+        checker: &dyn PathChecker,
+    ) -> Vec<Utf8PathBuf> {
+        let paths = self.dylib_paths_with_checker(checker);
+        let Some(target) = &self.build_platforms.target else {
+            return paths;
+        };
+
+        let host_libdir = self.build_platforms.host.libdir.as_path();
+        let target_libdir = target.libdir.as_path();
+        let shared_root = self.build_platforms.libdir_shared_root();
+        let triple_dir = self
+            .target_directory
+            .join(target.triple.platform.triple_str());
+        let is_for_target = |path: &Utf8Path| {
+            if Some(path) == shared_root {
+                return false;
+            }
+            if Some(path) == host_libdir || Some(path) == target_libdir {
+                // The libdirs might be identical, e.g. if the target is the host triple.
+                return match build_platform {
+                    BuildPlatform::Host => Some(path) == host_libdir,
+                    BuildPlatform::Target => Some(path) == target_libdir,
+                };
+            }
+            if path.starts_with(&self.target_directory) {
+                let under_triple_dir = path.starts_with(&triple_dir);
+                return match build_platform {
+                    BuildPlatform::Host => !under_triple_dir,
+                    BuildPlatform::Target => under_triple_dir,
+                };
+            }
+            true
+        };
+
+        paths
+            .into_iter()
+            .filter(|path| is_for_target(path))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cargo_config::TargetTriple,
+        list::RustTestBinary,
+        platform::{BuildPlatforms, HostPlatform, PlatformLibdir, TargetPlatform},
+    };
+    use nextest_metadata::{PlatformLibdirUnavailable, RustTestBinaryKind};
+
+    struct AllPaths;
+
+    impl PathChecker for AllPaths {
+        fn exists(&self, _path: &Utf8Path) -> bool {
+            true
+        }
+    }
+
+    #[test]
+    fn test_dylib_path_host_vs_target_delta() {
+        let target_triple =
+            TargetTriple::deserialize_str(Some("aarch64-unknown-linux-gnu".to_owned()))
+                .expect("creating TargetTriple should succeed")
+                .expect("the output of deserialize_str shouldn't be None");
+        let mut meta = RustBuildMeta::<TestListState> {
+            target_directory: "/fake/target".into(),
+            base_output_directories: [
+                Utf8PathBuf::from("debug"),
+                Utf8PathBuf::from("aarch64-unknown-linux-gnu/debug"),
+            ]
+            .into(),
+            absolute_linked_paths: [(Utf8PathBuf::from("/fake/native"), Default::default())].into(),
+            build_platforms: BuildPlatforms {
+                host: HostPlatform {
+                    platform: TargetTriple::x86_64_unknown_linux_gnu().platform,
+                    libdir: PlatformLibdir::Available("/fake/host-libdir".into()),
+                },
+                target: Some(TargetPlatform::new(
+                    target_triple,
+                    PlatformLibdir::Available("/fake/target-libdir".into()),
+                )),
+            },
+            ..RustBuildMeta::empty()
+        };
+
+        assert_eq!(
+            meta.dylib_path_host_vs_target_delta_with_checker(&AllPaths),
+            DylibPathDelta {
+                host_only: vec![
+                    "/fake/target/debug/deps".into(),
+                    "/fake/target/debug".into(),
+                    "/fake/host-libdir".into(),
+                ],
+                target_only: vec![
+                    "/fake/target/aarch64-unknown-linux-gnu/debug/deps".into(),
+                    "/fake/target/aarch64-unknown-linux-gnu/debug".into(),
+                    "/fake/target-libdir".into(),
+                ],
+                common: vec!["/fake/native".into()],
+            }
+        );
+
+        meta.build_platforms.target = None;
+        let delta = meta.dylib_path_host_vs_target_delta_with_checker(&AllPaths);
+        assert!(
+            delta.is_empty(),
+            "without a target, there's no delta: {delta:?}"
+        );
+    }
+
+    #[test]
+    fn test_tests_blocked_by_missing_libdir() {
+        let target_triple =
+            TargetTriple::deserialize_str(Some("aarch64-unknown-linux-gnu".to_owned()))
+                .expect("creating TargetTriple should succeed")
+                .expect("the output of deserialize_str shouldn't be None");
+        let meta = RustBuildMeta::<TestListState> {
+            build_platforms: BuildPlatforms {
+                host: HostPlatform {
+                    platform: TargetTriple::x86_64_unknown_linux_gnu().platform,
+                    libdir: PlatformLibdir::Available("/fake/host-libdir".into()),
+                },
+                target: Some(TargetPlatform::new(
+                    target_triple,
+                    PlatformLibdir::Unavailable(PlatformLibdirUnavailable::RUSTC_FAILED),
+                )),
+            },
+            ..RustBuildMeta::empty()
+        };
+        let binary = |id: &str, build_platform| RustTestBinary {
+            id: RustBinaryId::new(id),
+            path: format!("/fake/target/debug/deps/{id}").into(),
+            package_id: format!("{id}-id"),
+            kind: RustTestBinaryKind::LIB,
+            name: id.to_owned(),
+            build_platform,
+        };
+        let binary_list = BinaryList {
+            rust_build_meta: Default::default(),
+            rust_binaries: vec![
+                binary("proc-macro", BuildPlatform::Host),
+                binary("foo", BuildPlatform::Target),
+            ],
+        };
+
+        assert_eq!(
+            meta.tests_blocked_by_missing_libdir(&binary_list),
+            vec![LibdirBlockedBinary {
+                binary_id: RustBinaryId::new("foo"),
+                build_platform: BuildPlatform::Target,
+                triple: "aarch64-unknown-linux-gnu".to_owned(),
+            }],
+        );
+    }
+
+    #[test]
+    fn test_dylib_paths_for_platform_shared_libdir_root() {
+        let target_triple =
+            TargetTriple::deserialize_str(Some("i686-unknown-linux-gnu".to_owned()))
+                .expect("creating TargetTriple should succeed")
+                .expect("the output of deserialize_str shouldn't be None");
+        let meta = RustBuildMeta::<TestListState> {
+            target_directory: "/fake/target".into(),
+            absolute_linked_paths: [(Utf8PathBuf::from("/opt/toolchain/lib"), Default::default())]
+                .into(),
+            build_platforms: BuildPlatforms {
+                host: HostPlatform {
+                    platform: TargetTriple::x86_64_unknown_linux_gnu().platform,
+                    libdir: PlatformLibdir::Available("/opt/toolchain/lib/64".into()),
+                },
+                target: Some(TargetPlatform::new(
+                    target_triple,
+                    PlatformLibdir::Available("/opt/toolchain/lib/32".into()),
+                )),
+            },
+            ..RustBuildMeta::empty()
+        };
+
+        assert_eq!(
+            meta.dylib_paths_for_platform_with_checker(BuildPlatform::Host, &AllPaths),
+            vec![Utf8PathBuf::from("/opt/toolchain/lib/64")],
+        );
+        assert_eq!(
+            meta.dylib_paths_for_platform_with_checker(BuildPlatform::Target, &AllPaths),
+            vec![Utf8PathBuf::from("/opt/toolchain/lib/32")],
+        );
+    }
+}
@@ -0,0 +1,395 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Checking [`RustBuildMeta`] against an organizational policy.
+
+use super::{validate::write_requested_by, RustBuildMeta};
+use crate::{errors::MetadataPolicyLoadError, helpers::normalize_path_lexically};
+use camino::{Utf8Path, Utf8PathBuf};
+use nextest_metadata::BuildPlatform;
+use serde::Deserialize;
+use std::{collections::BTreeSet, fmt};
+
+/// A policy that build metadata can be checked against, with [`RustBuildMeta::check_policy`].
+///
+/// Policies are loaded from a TOML file with [`MetadataPolicy::from_file`], in the same format as
+/// nextest's other configuration files. YAML policies aren't supported: convert them to TOML
+/// first. For example:
+///
+/// ```toml
+/// allowed-triples = ["x86_64-unknown-linux-gnu", "aarch64-unknown-linux-gnu"]
+/// banned-linked-path-prefixes = ["/usr/local/lib", "debug/build/vendored-openssl"]
+/// required-fields = ["label", "rust-version", "built-at"]
+/// ```
+///
+/// All keys are optional, and an empty policy allows everything.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct MetadataPolicy {
+    /// The triples that test binaries may be built for. If empty, any triple is allowed.
+    ///
+    /// This is checked against the target platform if one is set, and the host platform
+    /// otherwise.
+    #[serde(default)]
+    pub allowed_triples: BTreeSet<String>,
+
+    /// Linked paths that start with any of these prefixes are disallowed.
+    ///
+    /// Prefixes are matched by path component. Relative prefixes are resolved against the target
+    /// directory, and both linked paths and prefixes are normalized before matching.
+    #[serde(default)]
+    pub banned_linked_path_prefixes: Vec<Utf8PathBuf>,
+
+    /// Fields that must be recorded in the metadata.
+    #[serde(default)]
+    pub required_fields: BTreeSet<PolicyField>,
+}
+
+impl MetadataPolicy {
+    /// Reads a policy from a TOML file.
+    ///
+    /// The file is always parsed as TOML, whatever its extension.
+    pub fn from_file(path: &Utf8Path) -> Result<Self, MetadataPolicyLoadError> {
+        let contents =
+            std::fs::read_to_string(path).map_err(|error| MetadataPolicyLoadError::Read {
+                path: path.to_owned(),
+                error,
+            })?;
+        Self::from_toml_str(&contents).map_err(|error| MetadataPolicyLoadError::Deserialize {
+            path: path.to_owned(),
+            error,
+        })
+    }
+
+    /// Parses a policy from a TOML string.
+    pub fn from_toml_str(input: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(input)
+    }
+}
+
+/// An optional field of [`RustBuildMeta`] that a [`MetadataPolicy`] can require.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[serde(rename_all = "kebab-case")]
+#[non_exhaustive]
+pub enum PolicyField {
+    /// [`RustBuildMeta::label`].
+    Label,
+
+    /// [`RustBuildMeta::host_os_version`].
+    HostOsVersion,
+
+    /// [`RustBuildMeta::rust_version`].
+    RustVersion,
+
+    /// [`RustBuildMeta::list_command`].
+    ListCommand,
+
+    /// [`RustBuildMeta::built_at`].
+    BuiltAt,
+
+    /// [`RustBuildMeta::debuginfo`].
+    Debuginfo,
+
+    /// [`RustBuildMeta::incremental`].
+    Incremental,
+}
+
+impl PolicyField {
+    /// Returns the name of this field, as used in policy files and metadata summaries.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Label => "label",
+            Self::HostOsVersion => "host-os-version",
+            Self::RustVersion => "rust-version",
+            Self::ListCommand => "list-command",
+            Self::BuiltAt => "built-at",
+            Self::Debuginfo => "debuginfo",
+            Self::Incremental => "incremental",
+        }
+    }
+}
+
+impl fmt::Display for PolicyField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A violation found by [`RustBuildMeta::check_policy`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum PolicyViolation {
+    /// Test binaries are built for a triple that isn't in
+    /// [`MetadataPolicy::allowed_triples`].
+    DisallowedTriple {
+        /// The platform test binaries are built for.
+        build_platform: BuildPlatform,
+
+        /// The triple of that platform.
+        triple: String,
+    },
+
+    /// A linked path starts with one of [`MetadataPolicy::banned_linked_path_prefixes`].
+    BannedLinkedPath {
+        /// The path as stored in the metadata: relative to the target directory, or absolute for
+        /// linked paths outside of it.
+        path: Utf8PathBuf,
+
+        /// The path after being joined to the target directory and normalized.
+        resolved: Utf8PathBuf,
+
+        /// The banned prefix, as written in the policy.
+        prefix: Utf8PathBuf,
+
+        /// The package IDs that requested this path, if known.
+        requested_by: BTreeSet<String>,
+    },
+
+    /// A field in [`MetadataPolicy::required_fields`] isn't recorded.
+    MissingField {
+        /// The missing field.
+        field: PolicyField,
+    },
+}
+
+impl fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DisallowedTriple {
+                build_platform,
+                triple,
+            } => write!(
+                f,
+                "{build_platform} triple `{triple}` isn't in the list of allowed triples"
+            ),
+            Self::BannedLinkedPath {
+                path,
+                resolved,
+                prefix,
+                requested_by,
+            } => {
+                write!(
+                    f,
+                    "linked path `{path}` resolves to `{resolved}`, \
+                     which starts with banned prefix `{prefix}`"
+                )?;
+                write_requested_by(f, requested_by)
+            }
+            Self::MissingField { field } => {
+                write!(f, "required field `{field}` isn't recorded")
+            }
+        }
+    }
+}
+
+impl<State> RustBuildMeta<State> {
+    /// Checks this metadata against an organizational policy, returning every violation found.
+    ///
+    /// Violations are returned in a stable order: disallowed triples first, then banned linked
+    /// paths, then missing fields. An empty list means the metadata complies with the policy.
+    pub fn check_policy(&self, policy: &MetadataPolicy) -> Vec<PolicyViolation> {
+        let mut violations = Vec::new();
+        self.check_policy_triples(policy, &mut violations);
+        self.check_policy_linked_paths(policy, &mut violations);
+        self.check_policy_required_fields(policy, &mut violations);
+        violations
+    }
+
+    fn check_policy_triples(&self, policy: &MetadataPolicy, violations: &mut Vec<PolicyViolation>) {
+        if policy.allowed_triples.is_empty() {
+            return;
+        }
+        let (build_platform, triple) = match &self.build_platforms.target {
+            Some(target) => (BuildPlatform::Target, target.triple.platform.triple_str()),
+            None => (
+                BuildPlatform::Host,
+                self.build_platforms.host.platform.triple_str(),
+            ),
+        };
+        if !policy.allowed_triples.contains(triple) {
+            violations.push(PolicyViolation::DisallowedTriple {
+                build_platform,
+                triple: triple.to_owned(),
+            });
+        }
+    }
+
+    fn check_policy_linked_paths(
+        &self,
+        policy: &MetadataPolicy,
+        violations: &mut Vec<PolicyViolation>,
+    ) {
+        if policy.banned_linked_path_prefixes.is_empty() {
+            return;
+        }
+        let resolved_prefixes: Vec<_> = policy
+            .banned_linked_path_prefixes
+            .iter()
+            .map(|prefix| {
+                (
+                    prefix,
+                    normalize_path_lexically(&self.target_directory.join(prefix)),
+                )
+            })
+            .collect();
+
+        // Joining an absolute path to the target directory returns the absolute path unchanged.
+        let linked_paths = self.linked_paths.iter().chain(&self.absolute_linked_paths);
+        for (path, requested_by) in linked_paths {
+            let resolved = normalize_path_lexically(&self.target_directory.join(path));
+            if let Some((prefix, _)) = resolved_prefixes
+                .iter()
+                .find(|(_, resolved_prefix)| resolved.starts_with(resolved_prefix))
+            {
+                violations.push(PolicyViolation::BannedLinkedPath {
+                    path: path.clone(),
+                    resolved,
+                    prefix: (*prefix).clone(),
+                    requested_by: requested_by.clone(),
+                });
+            }
+        }
+    }
+
+    fn check_policy_required_fields(
+        &self,
+        policy: &MetadataPolicy,
+        violations: &mut Vec<PolicyViolation>,
+    ) {
+        for &field in &policy.required_fields {
+            let present = match field {
+                PolicyField::Label => self.label.is_some(),
+                PolicyField::HostOsVersion => self.host_os_version.is_some(),
+                PolicyField::RustVersion => self.rust_version.is_some(),
+                PolicyField::ListCommand => self.list_command.is_some(),
+                PolicyField::BuiltAt => self.built_at.is_some(),
+                PolicyField::Debuginfo => self.debuginfo.is_some(),
+                PolicyField::Incremental => self.incremental.is_some(),
+            };
+            if !present {
+                violations.push(PolicyViolation::MissingField { field });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cargo_config::TargetTriple,
+        list::TestListState,
+        platform::{PlatformLibdir, TargetPlatform},
+    };
+    use indoc::indoc;
+    use nextest_metadata::PlatformLibdirUnavailable;
+
+    #[test]
+    fn test_policy_from_toml_str() {
+        let policy = MetadataPolicy::from_toml_str(indoc! {r#"
+            allowed-triples = ["x86_64-unknown-linux-gnu"]
+            banned-linked-path-prefixes = ["/usr/local/lib", "debug/build/vendored"]
+            required-fields = ["label", "built-at"]
+        "#})
+        .expect("valid policy");
+        assert_eq!(
+            policy,
+            MetadataPolicy {
+                allowed_triples: ["x86_64-unknown-linux-gnu".to_owned()].into(),
+                banned_linked_path_prefixes: vec![
+                    "/usr/local/lib".into(),
+                    "debug/build/vendored".into(),
+                ],
+                required_fields: [PolicyField::Label, PolicyField::BuiltAt].into(),
+            }
+        );
+
+        assert_eq!(
+            MetadataPolicy::from_toml_str("").expect("empty policy is valid"),
+            MetadataPolicy::default(),
+        );
+        MetadataPolicy::from_toml_str("required-fields = [\"git-commit\"]")
+            .expect_err("unknown required field is rejected");
+        MetadataPolicy::from_toml_str("allowed-targets = []").expect_err("unknown key is rejected");
+    }
+
+    #[test]
+    fn test_check_policy() {
+        let target_triple = TargetTriple::deserialize_str(Some("aarch64-apple-darwin".to_owned()))
+            .expect("creating TargetTriple should succeed")
+            .expect("the output of deserialize_str shouldn't be None");
+        let mut meta = RustBuildMeta::<TestListState> {
+            target_directory: "/fake/target".into(),
+            linked_paths: [
+                ("debug/build/vendored-sys/out", "vendored-sys 0.1.0"),
+                ("debug/build/other-sys/out", "other-sys 0.1.0"),
+                (
+                    "debug/build/other-sys/../../../../../usr/local/lib",
+                    "escape-sys 0.1.0",
+                ),
+            ]
+            .into_iter()
+            .map(|(path, package_id)| (path.into(), [package_id.to_owned()].into()))
+            .collect(),
+            absolute_linked_paths: [("/usr/local/lib/foo".into(), BTreeSet::new())].into(),
+            label: Some("abc123".to_owned()),
+            ..RustBuildMeta::empty()
+        };
+        meta.build_platforms.target = Some(TargetPlatform::new(
+            target_triple,
+            PlatformLibdir::Unavailable(PlatformLibdirUnavailable::RUSTC_FAILED),
+        ));
+
+        assert!(
+            meta.check_policy(&MetadataPolicy::default()).is_empty(),
+            "an empty policy allows everything",
+        );
+
+        let policy = MetadataPolicy {
+            allowed_triples: ["x86_64-unknown-linux-gnu".to_owned()].into(),
+            banned_linked_path_prefixes: vec![
+                "/usr/local".into(),
+                "debug/build/vendored-sys".into(),
+            ],
+            required_fields: [
+                PolicyField::Label,
+                PolicyField::RustVersion,
+                PolicyField::BuiltAt,
+            ]
+            .into(),
+        };
+        assert_eq!(
+            meta.check_policy(&policy),
+            [
+                PolicyViolation::DisallowedTriple {
+                    build_platform: BuildPlatform::Target,
+                    triple: "aarch64-apple-darwin".to_owned(),
+                },
+                PolicyViolation::BannedLinkedPath {
+                    path: "debug/build/other-sys/../../../../../usr/local/lib".into(),
+                    resolved: "/usr/local/lib".into(),
+                    prefix: "/usr/local".into(),
+                    requested_by: ["escape-sys 0.1.0".to_owned()].into(),
+                },
+                PolicyViolation::BannedLinkedPath {
+                    path: "debug/build/vendored-sys/out".into(),
+                    resolved: "/fake/target/debug/build/vendored-sys/out".into(),
+                    prefix: "debug/build/vendored-sys".into(),
+                    requested_by: ["vendored-sys 0.1.0".to_owned()].into(),
+                },
+                PolicyViolation::BannedLinkedPath {
+                    path: "/usr/local/lib/foo".into(),
+                    resolved: "/usr/local/lib/foo".into(),
+                    prefix: "/usr/local".into(),
+                    requested_by: BTreeSet::new(),
+                },
+                PolicyViolation::MissingField {
+                    field: PolicyField::RustVersion,
+                },
+                PolicyViolation::MissingField {
+                    field: PolicyField::BuiltAt,
+                },
+            ],
+        );
+    }
+}
@@ -0,0 +1,477 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Generating scripts, `.env` files and minimal metadata that reproduce the environment nextest
+//! runs test binaries in.
+
+use super::RustBuildMeta;
+use crate::{
+    errors::WriteDotenvError,
+    list::{RustTestBinary, TestListState},
+};
+use camino::{Utf8Path, Utf8PathBuf};
+use nextest_metadata::BuildPlatform;
+use std::{collections::BTreeSet, fmt::Write};
+
+/// The kind of script produced by [`RustBuildMeta::repro_script`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum ScriptKind {
+    /// A POSIX shell script.
+    Sh,
+
+    /// A Windows batch file.
+    Batch,
+}
+
+/// A variable exported by a repro script.
+struct ScriptVar<'a> {
+    comment: &'static str,
+    name: &'a str,
+    value: String,
+    append_existing: bool,
+}
+
+impl RustBuildMeta<TestListState> {
+    /// Returns a script that runs `binary` from the package `package_id` with the dynamic library
+    /// search path and build script environment that nextest sets up.
+    ///
+    /// On Windows this is a batch file, and elsewhere a POSIX shell script. Each exported variable
+    /// is preceded by a comment explaining it, and arguments passed to the script are forwarded
+    /// to the binary. The script is meant to be attached to bug reports and for manual
+    /// reproduction, so it doesn't include variables that depend on nextest's configuration.
+    pub fn repro_script(&self, binary: &Utf8Path, package_id: &str) -> String {
+        let kind = if cfg!(windows) {
+            ScriptKind::Batch
+        } else {
+            ScriptKind::Sh
+        };
+        self.repro_script_for(kind, binary, package_id)
+    }
+
+    fn repro_script_for(&self, kind: ScriptKind, binary: &Utf8Path, package_id: &str) -> String {
+        let mut vars = vec![ScriptVar {
+            comment: "Directories searched for dynamic libraries, followed by the existing value.",
+            name: self.dylib_path_envvar(),
+            value: self.dylib_path_env_value(),
+            append_existing: true,
+        }];
+        if let Some(out_dir) = self.build_script_out_dirs.get(package_id) {
+            vars.push(ScriptVar {
+                comment: "The output directory of the package's build script.",
+                name: "OUT_DIR",
+                value: self.target_directory.join(out_dir).into_string(),
+                append_existing: false,
+            });
+        }
+        vars.push(ScriptVar {
+            comment: "Indicates that tests are being run under nextest.",
+            name: "NEXTEST",
+            value: "1".to_owned(),
+            append_existing: false,
+        });
+        vars.push(ScriptVar {
+            comment: "Indicates that each test is run in its own process.",
+            name: "NEXTEST_EXECUTION_MODE",
+            value: "process-per-test".to_owned(),
+            append_existing: false,
+        });
+
+        let separator = self.build_platforms.path_separator();
+        let mut script = String::new();
+        match kind {
+            ScriptKind::Sh => {
+                script.push_str("#!/bin/sh\n");
+                script.push_str("# Runs a test binary in the environment set up by nextest.\n");
+                for var in &vars {
+                    let mut value = shell_words::quote(&var.value).into_owned();
+                    if var.append_existing {
+                        // Expands to the separator and the existing value if it's set and
+                        // non-empty, and to nothing otherwise.
+                        write!(value, "\"${{{0}:+{separator}${0}}}\"", var.name)
+                            .expect("writing to a String is infallible");
+                    }
+                    writeln!(script, "\n# {}\nexport {}={value}", var.comment, var.name)
+                        .expect("writing to a String is infallible");
+                }
+                writeln!(
+                    script,
+                    "\nexec {} \"$@\"",
+                    shell_words::quote(binary.as_str())
+                )
+                .expect("writing to a String is infallible");
+            }
+            ScriptKind::Batch => {
+                script.push_str("@echo off\n");
+                script.push_str("rem Runs a test binary in the environment set up by nextest.\n");
+                for var in &vars {
+                    let mut value = var.value.replace('%', "%%");
+                    if var.append_existing {
+                        write!(value, "{separator}%{}%", var.name)
+                            .expect("writing to a String is infallible");
+                    }
+                    writeln!(
+                        script,
+                        "\nrem {}\nset \"{}={value}\"",
+                        var.comment, var.name
+                    )
+                    .expect("writing to a String is infallible");
+                }
+                writeln!(script, "\n\"{binary}\" %*").expect("writing to a String is infallible");
+            }
+        }
+        script
+    }
+
+    /// Writes a `.env` file to `path`, for test runners other than nextest that read one.
+    ///
+    /// The file sets the dynamic library path environment variable to
+    /// [`Self::dylib_path_env_value`]. If `binary` is specified and its package has a build
+    /// script, `OUT_DIR` is set as well.
+    ///
+    /// Values are single-quoted, so most `.env` parsers read them literally. Values that contain
+    /// single quotes are double-quoted instead, with backslashes and double quotes escaped.
+    pub fn write_dotenv(
+        &self,
+        path: &Utf8Path,
+        binary: Option<&RustTestBinary>,
+    ) -> Result<(), WriteDotenvError> {
+        std::fs::write(path, self.dotenv_contents(binary)).map_err(|error| WriteDotenvError {
+            path: path.to_owned(),
+            error,
+        })
+    }
+
+    /// Returns the smallest metadata that reproduces the environment `binary` is run in.
+    ///
+    /// The result combines [`Self::slice_for_binary`] with the per-platform split performed by
+    /// [`Self::split_by_target`]: it only has the platform `binary` was built for, the base output
+    /// directory containing it, and the build script output directory, linked paths and non-test
+    /// binaries of its package. Linked paths recorded without a package (as with metadata produced
+    /// by older versions of nextest) are kept as well.
+    ///
+    /// Unlike the full metadata, the result doesn't expose any other packages in the workspace,
+    /// so it can be attached to bug reports.
+    pub fn minimal_repro_for(&self, binary: &RustTestBinary) -> RustBuildMeta<TestListState> {
+        let target = match binary.build_platform {
+            BuildPlatform::Target => self.build_platforms.target.clone(),
+            BuildPlatform::Host => None,
+        };
+        let is_relevant = |requested_by: &BTreeSet<String>| {
+            requested_by.is_empty() || requested_by.contains(&binary.package_id)
+        };
+
+        let mut keep: BTreeSet<Utf8PathBuf> = BTreeSet::new();
+        if let Some(base) = self.base_output_dir_containing(&binary.path) {
+            keep.insert(base.join("deps"));
+            keep.insert(base.clone());
+        }
+        keep.extend(self.build_script_out_dirs.get(&binary.package_id).cloned());
+        keep.extend(
+            self.linked_paths
+                .iter()
+                .filter(|(_, requested_by)| is_relevant(requested_by))
+                .map(|(path, _)| path.clone()),
+        );
+        keep.extend(
+            self.non_test_binaries
+                .get(&binary.package_id)
+                .into_iter()
+                .flatten()
+                .map(|non_test_binary| non_test_binary.path.clone()),
+        );
+
+        let mut meta = self.filtered(target, |path| keep.contains(path));
+        meta.absolute_linked_paths
+            .retain(|_, requested_by| is_relevant(requested_by));
+        meta
+    }
+
+    fn dotenv_contents(&self, binary: Option<&RustTestBinary>) -> String {
+        let mut vars = vec![(self.dylib_path_envvar(), self.dylib_path_env_value())];
+        if let Some(out_dir) =
+            binary.and_then(|binary| self.build_script_out_dirs.get(&binary.package_id))
+        {
+            vars.push(("OUT_DIR", self.target_directory.join(out_dir).into_string()));
+        }
+
+        let mut contents = String::new();
+        for (name, value) in vars {
+            writeln!(contents, "{name}={}", dotenv_quote(&value))
+                .expect("writing to a String is infallible");
+        }
+        contents
+    }
+}
+
+/// Quotes `value` for a `.env` file.
+fn dotenv_quote(value: &str) -> String {
+    if value.contains('\'') {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        format!("'{value}'")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cargo_config::TargetTriple,
+        platform::{BuildPlatforms, HostPlatform, PlatformLibdir, TargetPlatform},
+    };
+    use indoc::formatdoc;
+    use nextest_metadata::{
+        RustBinaryId, RustNonTestBinaryKind, RustNonTestBinarySummary, RustTestBinaryKind,
+    };
+
+    #[test]
+    fn test_repro_script() {
+        let meta = RustBuildMeta::<TestListState> {
+            target_directory: "/fake/target".into(),
+            base_output_directories: [Utf8PathBuf::from("debug")].into(),
+            build_script_out_dirs: [(
+                "foo-id".to_owned(),
+                Utf8PathBuf::from("debug/build/foo-1234/out"),
+            )]
+            .into(),
+            ..RustBuildMeta::empty()
+        };
+        let separator = meta.build_platforms.path_separator();
+        let dylib_value = meta.dylib_path_env_value();
+        let envvar = meta.dylib_path_envvar();
+
+        assert_eq!(
+            meta.repro_script_for(
+                ScriptKind::Sh,
+                Utf8Path::new("/fake/target/debug/deps/foo-5678"),
+                "foo-id"
+            ),
+            formatdoc! {r#"
+                #!/bin/sh
+                # Runs a test binary in the environment set up by nextest.
+
+                # Directories searched for dynamic libraries, followed by the existing value.
+                export {envvar}={}"${{{envvar}:+{separator}${envvar}}}"
+
+                # The output directory of the package's build script.
+                export OUT_DIR=/fake/target/debug/build/foo-1234/out
+
+                # Indicates that tests are being run under nextest.
+                export NEXTEST=1
+
+                # Indicates that each test is run in its own process.
+                export NEXTEST_EXECUTION_MODE=process-per-test
+
+                exec /fake/target/debug/deps/foo-5678 "$@"
+            "#, shell_words::quote(&dylib_value)},
+        );
+
+        assert_eq!(
+            meta.repro_script_for(
+                ScriptKind::Batch,
+                Utf8Path::new("/fake/target/debug/deps/foo-5678"),
+                "bar-id"
+            ),
+            formatdoc! {r#"
+                @echo off
+                rem Runs a test binary in the environment set up by nextest.
+
+                rem Directories searched for dynamic libraries, followed by the existing value.
+                set "{envvar}={dylib_value}{separator}%{envvar}%"
+
+                rem Indicates that tests are being run under nextest.
+                set "NEXTEST=1"
+
+                rem Indicates that each test is run in its own process.
+                set "NEXTEST_EXECUTION_MODE=process-per-test"
+
+                "/fake/target/debug/deps/foo-5678" %*
+            "#},
+            "packages without build scripts don't set OUT_DIR",
+        );
+    }
+
+    #[test]
+    fn test_write_dotenv() {
+        let tmpdir = camino_tempfile::tempdir().expect("should create temp dir successfully");
+        let meta = RustBuildMeta::<TestListState> {
+            target_directory: "/fake/target".into(),
+            base_output_directories: [Utf8PathBuf::from("debug")].into(),
+            build_script_out_dirs: [(
+                "foo-id".to_owned(),
+                Utf8PathBuf::from("debug/build/foo's-1234/out"),
+            )]
+            .into(),
+            ..RustBuildMeta::empty()
+        };
+        let envvar = meta.dylib_path_envvar();
+        let dylib_value = meta.dylib_path_env_value();
+        let binary = RustTestBinary {
+            id: RustBinaryId::new("foo"),
+            path: "/fake/target/debug/deps/foo-5678".into(),
+            package_id: "foo-id".to_owned(),
+            kind: RustTestBinaryKind::LIB,
+            name: "foo".to_owned(),
+            build_platform: BuildPlatform::Target,
+        };
+
+        let path = tmpdir.path().join("global.env");
+        meta.write_dotenv(&path, None)
+            .expect("writing .env file should succeed");
+        assert_eq!(
+            std::fs::read_to_string(&path).expect("should read .env file"),
+            format!("{envvar}='{dylib_value}'\n"),
+        );
+
+        let path = tmpdir.path().join("foo.env");
+        meta.write_dotenv(&path, Some(&binary))
+            .expect("writing .env file should succeed");
+        assert_eq!(
+            std::fs::read_to_string(&path).expect("should read .env file"),
+            format!(
+                "{envvar}='{dylib_value}'\n\
+                 OUT_DIR=\"/fake/target/debug/build/foo's-1234/out\"\n"
+            ),
+            "values with single quotes are double-quoted",
+        );
+    }
+
+    #[test]
+    fn test_minimal_repro_for() {
+        let target_triple =
+            TargetTriple::deserialize_str(Some("aarch64-unknown-linux-gnu".to_owned()))
+                .expect("creating TargetTriple should succeed")
+                .expect("the output of deserialize_str shouldn't be None");
+        let host = HostPlatform {
+            platform: TargetTriple::x86_64_unknown_linux_gnu().platform,
+            libdir: PlatformLibdir::Available("/fake/host/libdir".into()),
+        };
+        let foo_exe = RustNonTestBinarySummary {
+            name: "foo-exe".to_owned(),
+            kind: RustNonTestBinaryKind::BIN_EXE,
+            path: "aarch64-unknown-linux-gnu/debug/foo-exe".into(),
+        };
+        let bar_exe = RustNonTestBinarySummary {
+            name: "bar-exe".to_owned(),
+            kind: RustNonTestBinaryKind::BIN_EXE,
+            path: "aarch64-unknown-linux-gnu/debug/bar-exe".into(),
+        };
+        let meta = RustBuildMeta::<TestListState> {
+            target_directory: "/fake/target".into(),
+            base_output_directories: [
+                Utf8PathBuf::from("debug"),
+                Utf8PathBuf::from("aarch64-unknown-linux-gnu/debug"),
+            ]
+            .into(),
+            non_test_binaries: [
+                ("foo-id".to_owned(), [foo_exe.clone()].into()),
+                ("bar-id".to_owned(), [bar_exe].into()),
+            ]
+            .into(),
+            build_script_out_dirs: [
+                (
+                    "foo-id".to_owned(),
+                    Utf8PathBuf::from("aarch64-unknown-linux-gnu/debug/build/foo-1234/out"),
+                ),
+                (
+                    "bar-id".to_owned(),
+                    Utf8PathBuf::from("aarch64-unknown-linux-gnu/debug/build/bar-1234/out"),
+                ),
+            ]
+            .into(),
+            linked_paths: [
+                (
+                    Utf8PathBuf::from("aarch64-unknown-linux-gnu/debug/build/foo-1234/out/lib"),
+                    ["foo-id".to_owned()].into(),
+                ),
+                (
+                    Utf8PathBuf::from("aarch64-unknown-linux-gnu/debug/build/bar-1234/out/lib"),
+                    ["bar-id".to_owned()].into(),
+                ),
+                (Utf8PathBuf::from("debug/unknown"), BTreeSet::new()),
+            ]
+            .into(),
+            absolute_linked_paths: [
+                (
+                    Utf8PathBuf::from("/fake/foo/lib"),
+                    ["foo-id".to_owned()].into(),
+                ),
+                (
+                    Utf8PathBuf::from("/fake/bar/lib"),
+                    ["bar-id".to_owned()].into(),
+                ),
+            ]
+            .into(),
+            build_platforms: BuildPlatforms {
+                host: host.clone(),
+                target: Some(TargetPlatform::new(
+                    target_triple,
+                    PlatformLibdir::Available("/fake/target/libdir".into()),
+                )),
+            },
+            ..RustBuildMeta::empty()
+        };
+
+        let binary = RustTestBinary {
+            id: RustBinaryId::new("foo"),
+            path: "/fake/target/aarch64-unknown-linux-gnu/debug/deps/foo-5678".into(),
+            package_id: "foo-id".to_owned(),
+            kind: RustTestBinaryKind::LIB,
+            name: "foo".to_owned(),
+            build_platform: BuildPlatform::Target,
+        };
+        let repro = meta.minimal_repro_for(&binary);
+        assert_eq!(
+            repro,
+            RustBuildMeta::<TestListState> {
+                target_directory: "/fake/target".into(),
+                base_output_directories: [Utf8PathBuf::from("aarch64-unknown-linux-gnu/debug")]
+                    .into(),
+                non_test_binaries: [("foo-id".to_owned(), [foo_exe].into())].into(),
+                build_script_out_dirs: [(
+                    "foo-id".to_owned(),
+                    Utf8PathBuf::from("aarch64-unknown-linux-gnu/debug/build/foo-1234/out"),
+                )]
+                .into(),
+                linked_paths: [
+                    (
+                        Utf8PathBuf::from("aarch64-unknown-linux-gnu/debug/build/foo-1234/out/lib"),
+                        ["foo-id".to_owned()].into(),
+                    ),
+                    (Utf8PathBuf::from("debug/unknown"), BTreeSet::new()),
+                ]
+                .into(),
+                absolute_linked_paths: [(
+                    Utf8PathBuf::from("/fake/foo/lib"),
+                    ["foo-id".to_owned()].into(),
+                )]
+                .into(),
+                build_platforms: meta.build_platforms.clone(),
+                ..RustBuildMeta::empty()
+            },
+        );
+
+        let host_binary = RustTestBinary {
+            id: RustBinaryId::new("proc-macro"),
+            path: "/fake/target/debug/deps/proc_macro-5678".into(),
+            package_id: "proc-macro-id".to_owned(),
+            kind: RustTestBinaryKind::PROC_MACRO,
+            name: "proc-macro".to_owned(),
+            build_platform: BuildPlatform::Host,
+        };
+        let repro = meta.minimal_repro_for(&host_binary);
+        assert_eq!(
+            repro.base_output_directories,
+            [Utf8PathBuf::from("debug")].into()
+        );
+        assert!(repro.non_test_binaries.is_empty());
+        assert!(repro.build_script_out_dirs.is_empty());
+        assert_eq!(
+            repro.linked_paths,
+            [(Utf8PathBuf::from("debug/unknown"), BTreeSet::new())].into(),
+            "only linked paths without a package are kept",
+        );
+        assert!(repro.absolute_linked_paths.is_empty());
+        assert_eq!(repro.build_platforms, BuildPlatforms { host, target: None });
+    }
+}
@@ -0,0 +1,281 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Per-binary slices of [`RustBuildMeta`].
+
+use super::RustBuildMeta;
+use crate::{
+    helpers::{convert_rel_path_to_main_sep, normalize_path_lexically},
+    list::{RustTestBinary, TestListState},
+};
+use camino::{Utf8Path, Utf8PathBuf};
+use nextest_metadata::{BuildPlatform, RustBinaryId};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// The subset of [`RustBuildMeta`] needed to run a single test binary.
+///
+/// Returned by [`RustBuildMeta::slice_for_binary`]. A slice is much smaller than the full
+/// metadata, and can be cached and shipped alongside the binary it was created for.
+///
+/// All paths are absolute.
+#[derive(Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RustBuildMetaSlice {
+    /// The binary this slice was created for.
+    pub binary_id: RustBinaryId,
+
+    /// The platform the binary was built for.
+    pub build_platform: BuildPlatform,
+
+    /// The rustc libdir for the binary's platform, if available.
+    pub libdir: Option<Utf8PathBuf>,
+
+    /// The base output directory containing the binary, and its `deps` subdirectory.
+    ///
+    /// Empty if the binary isn't within any of the base output directories.
+    pub base_output_dirs: Vec<Utf8PathBuf>,
+
+    /// Linked paths requested by the binary's package, or by unknown packages (as with metadata
+    /// produced by older versions of nextest).
+    pub linked_paths: Vec<Utf8PathBuf>,
+
+    /// The build script output directory for the binary's package, if any.
+    pub out_dir: Option<Utf8PathBuf>,
+}
+
+impl RustBuildMeta<TestListState> {
+    /// Returns the subset of this metadata needed to run `binary`.
+    ///
+    /// Unlike [`Self::dylib_paths`], linked paths are included whether or not they exist on disk,
+    /// since slices are meant to be shipped to other machines. Linked paths requested by the
+    /// package's dependencies aren't included; use [`Self::native_search_set`] for those.
+    pub fn slice_for_binary(&self, binary: &RustTestBinary) -> RustBuildMetaSlice {
+        let libdir = match binary.build_platform {
+            BuildPlatform::Target => match &self.build_platforms.target {
+                Some(target) => target.libdir.as_path(),
+                None => self.build_platforms.host.libdir.as_path(),
+            },
+            BuildPlatform::Host => self.build_platforms.host.libdir.as_path(),
+        };
+
+        let base_output_dirs = self
+            .base_output_dir_containing(&binary.path)
+            .map(|dir| {
+                let dir = self
+                    .target_directory
+                    .join(convert_rel_path_to_main_sep(dir));
+                vec![dir.join("deps"), dir]
+            })
+            .unwrap_or_default();
+
+        let is_relevant = |requested_by: &BTreeSet<String>| {
+            requested_by.is_empty() || requested_by.contains(&binary.package_id)
+        };
+        let linked_paths = self
+            .linked_paths
+            .iter()
+            .filter(|(_, requested_by)| is_relevant(requested_by))
+            .map(|(rel_path, _)| {
+                self.target_directory
+                    .join(convert_rel_path_to_main_sep(rel_path))
+            })
+            .chain(
+                self.absolute_linked_paths
+                    .iter()
+                    .filter(|(_, requested_by)| is_relevant(requested_by))
+                    .map(|(abs_path, _)| abs_path.clone()),
+            )
+            .collect();
+
+        RustBuildMetaSlice {
+            binary_id: binary.id.clone(),
+            build_platform: binary.build_platform,
+            libdir: libdir.map(|libdir| libdir.to_path_buf()),
+            base_output_dirs,
+            linked_paths,
+            out_dir: self
+                .build_script_out_dirs
+                .get(&binary.package_id)
+                .map(|out_dir| {
+                    self.target_directory
+                        .join(convert_rel_path_to_main_sep(out_dir))
+                }),
+        }
+    }
+
+    /// Returns the IDs of the binaries in `binaries` that depend on `dir`, in the order they're
+    /// provided in.
+    ///
+    /// A binary depends on `dir` if any of the linked paths or base output directories in its
+    /// [slice](Self::slice_for_binary) are within `dir`. Linked paths are searched recursively
+    /// by some native libraries, so a binary also depends on `dir` if `dir` is within one of its
+    /// linked paths. Base output directories are only searched directly, so they must be `dir`
+    /// itself.
+    ///
+    /// Paths are lexically normalized before being compared, and relative paths are interpreted
+    /// relative to the target directory. Symlinks aren't resolved.
+    pub fn binaries_depending_on_dir(
+        &self,
+        dir: &Utf8Path,
+        binaries: &[RustTestBinary],
+    ) -> Vec<RustBinaryId> {
+        let dir = normalize_path_lexically(&self.target_directory.join(dir));
+        binaries
+            .iter()
+            .filter(|binary| {
+                let slice = self.slice_for_binary(binary);
+                let linked_match = slice.linked_paths.iter().any(|path| {
+                    let path = normalize_path_lexically(path);
+                    path.starts_with(&dir) || dir.starts_with(&path)
+                });
+                let base_match = slice
+                    .base_output_dirs
+                    .iter()
+                    .any(|path| normalize_path_lexically(path).starts_with(&dir));
+                linked_match || base_match
+            })
+            .map(|binary| binary.id.clone())
+            .collect()
+    }
+
+    /// Returns the innermost base output directory containing `path`, relative to the target
+    /// directory.
+    pub(super) fn base_output_dir_containing(&self, path: &Utf8Path) -> Option<&Utf8PathBuf> {
+        self.base_output_directories
+            .iter()
+            .filter(|dir| {
+                path.starts_with(
+                    self.target_directory
+                        .join(convert_rel_path_to_main_sep(dir)),
+                )
+            })
+            .max_by_key(|dir| dir.components().count())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::{BuildPlatforms, HostPlatform, PlatformLibdir};
+    use nextest_metadata::RustTestBinaryKind;
+
+    #[test]
+    fn test_slice_for_binary() {
+        let meta = RustBuildMeta::<TestListState> {
+            target_directory: "/fake/target".into(),
+            base_output_directories: [Utf8PathBuf::from("debug"), Utf8PathBuf::from("release")]
+                .into(),
+            build_script_out_dirs: [(
+                "foo-id".to_owned(),
+                Utf8PathBuf::from("debug/build/foo-1234/out"),
+            )]
+            .into(),
+            linked_paths: [
+                (
+                    Utf8PathBuf::from("debug/build/foo-1234/out/lib"),
+                    ["foo-id".to_owned()].into(),
+                ),
+                (
+                    Utf8PathBuf::from("debug/build/bar-5678/out/lib"),
+                    ["bar-id".to_owned()].into(),
+                ),
+                (Utf8PathBuf::from("debug/unknown"), Default::default()),
+            ]
+            .into(),
+            build_platforms: BuildPlatforms {
+                host: HostPlatform::current(PlatformLibdir::Available("/fake/libdir".into()))
+                    .expect("should detect the host platform successfully"),
+                target: None,
+            },
+            ..RustBuildMeta::empty()
+        };
+        let binary = RustTestBinary {
+            id: RustBinaryId::new("foo"),
+            path: "/fake/target/debug/deps/foo-abcd".into(),
+            package_id: "foo-id".to_owned(),
+            kind: RustTestBinaryKind::LIB,
+            name: "foo".to_owned(),
+            build_platform: BuildPlatform::Target,
+        };
+
+        let slice = meta.slice_for_binary(&binary);
+        assert_eq!(
+            slice,
+            RustBuildMetaSlice {
+                binary_id: RustBinaryId::new("foo"),
+                build_platform: BuildPlatform::Target,
+                libdir: Some("/fake/libdir".into()),
+                base_output_dirs: vec![
+                    "/fake/target/debug/deps".into(),
+                    "/fake/target/debug".into(),
+                ],
+                linked_paths: vec![
+                    "/fake/target/debug/build/foo-1234/out/lib".into(),
+                    "/fake/target/debug/unknown".into(),
+                ],
+                out_dir: Some("/fake/target/debug/build/foo-1234/out".into()),
+            }
+        );
+
+        let json = serde_json::to_string(&slice).expect("slice should serialize");
+        let roundtrip: RustBuildMetaSlice =
+            serde_json::from_str(&json).expect("slice should deserialize");
+        assert_eq!(roundtrip, slice);
+    }
+
+    #[test]
+    fn test_binaries_depending_on_dir() {
+        let meta = RustBuildMeta::<TestListState> {
+            target_directory: "/fake/target".into(),
+            base_output_directories: [Utf8PathBuf::from("debug")].into(),
+            linked_paths: [
+                (
+                    Utf8PathBuf::from("debug/build/foo-1234/out/lib"),
+                    ["foo-id".to_owned()].into(),
+                ),
+                (
+                    Utf8PathBuf::from("debug/build/bar-5678/out/lib"),
+                    ["bar-id".to_owned()].into(),
+                ),
+            ]
+            .into(),
+            absolute_linked_paths: [("/opt/native".into(), ["bar-id".to_owned()].into())].into(),
+            ..RustBuildMeta::empty()
+        };
+        let binary = |name: &str, package_id: &str| RustTestBinary {
+            id: RustBinaryId::new(name),
+            path: format!("/fake/target/debug/deps/{name}-abcd").into(),
+            package_id: package_id.to_owned(),
+            kind: RustTestBinaryKind::LIB,
+            name: name.to_owned(),
+            build_platform: BuildPlatform::Target,
+        };
+        let binaries = [binary("foo", "foo-id"), binary("bar", "bar-id")];
+
+        let depending = |dir: &str| -> Vec<String> {
+            meta.binaries_depending_on_dir(Utf8Path::new(dir), &binaries)
+                .into_iter()
+                .map(|id| id.as_str().to_owned())
+                .collect()
+        };
+
+        assert_eq!(
+            depending("/fake/target/debug/build/foo-1234/out/lib"),
+            ["foo"]
+        );
+        // Relative paths are relative to the target directory, and are normalized.
+        assert_eq!(depending("debug/build/./bar-5678/out/lib/../lib"), ["bar"]);
+        // A directory within a linked path.
+        assert_eq!(depending("/opt/native/x86_64"), ["bar"]);
+        // A directory containing linked paths and base output directories.
+        assert_eq!(depending("/fake/target"), ["foo", "bar"]);
+        // Directories within base output directories aren't searched.
+        assert_eq!(
+            depending("/fake/target/debug/incremental"),
+            Vec::<String>::new()
+        );
+        assert_eq!(depending("/fake/target/debug/deps"), ["foo", "bar"]);
+        assert_eq!(depending("/unrelated"), Vec::<String>::new());
+    }
+}
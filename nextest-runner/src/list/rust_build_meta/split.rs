@@ -0,0 +1,266 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Splitting [`RustBuildMeta`] into per-target instances.
+
+use super::RustBuildMeta;
+use crate::{
+    cargo_config::{TargetDefinitionLocation, TargetTriple, TargetTripleSource},
+    platform::{BuildPlatforms, TargetPlatform},
+};
+use camino::Utf8Path;
+use std::{collections::BTreeSet, marker::PhantomData};
+
+impl<State> RustBuildMeta<State> {
+    /// Splits this metadata into one instance per target platform, for distribution to runners
+    /// that each handle a single platform.
+    ///
+    /// Cargo places artifacts for an explicitly specified target under a subdirectory of the
+    /// target directory named after the triple, and artifacts for the host (build scripts, proc
+    /// macros) directly under the target directory. Base output directories, non-test binaries,
+    /// build script output directories and linked paths are assigned based on that layout.
+    ///
+    /// If a target platform is specified, the returned list contains the target first, followed
+    /// by the host. Otherwise, it contains just the host, with all the metadata. Every non-test
+    /// binary is present in exactly one of the returned instances.
+    ///
+    /// Absolute paths, linked libraries, enabled features, the label, the host OS version and the
+    /// list command aren't platform-specific and are copied to every instance.
+    pub fn split_by_target(&self) -> Vec<(TargetTriple, RustBuildMeta<State>)> {
+        let host_triple = TargetTriple {
+            platform: self.build_platforms.host.platform.clone(),
+            source: TargetTripleSource::Metadata,
+            location: TargetDefinitionLocation::Builtin,
+        };
+        let Some(target) = &self.build_platforms.target else {
+            return vec![(host_triple, self.filtered(None, |_| true))];
+        };
+
+        let triple_dir = Utf8Path::new(target.triple.platform.triple_str());
+        let target_meta = self.filtered(Some(target.clone()), |path| path.starts_with(triple_dir));
+        let host_meta = self.filtered(None, |path| !path.starts_with(triple_dir));
+
+        debug_assert_eq!(
+            target_meta
+                .non_test_binaries
+                .values()
+                .map(|b| b.len())
+                .sum::<usize>()
+                + host_meta
+                    .non_test_binaries
+                    .values()
+                    .map(|b| b.len())
+                    .sum::<usize>(),
+            self.non_test_binaries
+                .values()
+                .map(|b| b.len())
+                .sum::<usize>(),
+            "every non-test binary is assigned to exactly one platform",
+        );
+
+        vec![
+            (target.triple.clone(), target_meta),
+            (host_triple, host_meta),
+        ]
+    }
+
+    /// Returns a copy of this metadata with the given target platform, retaining only relative
+    /// paths that match `keep`.
+    pub(super) fn filtered(
+        &self,
+        target: Option<TargetPlatform>,
+        keep: impl Fn(&Utf8Path) -> bool,
+    ) -> Self {
+        let non_test_binaries = self
+            .non_test_binaries
+            .iter()
+            .filter_map(|(package_id, binaries)| {
+                let binaries: BTreeSet<_> = binaries
+                    .iter()
+                    .filter(|binary| keep(&binary.path))
+                    .cloned()
+                    .collect();
+                (!binaries.is_empty()).then(|| (package_id.clone(), binaries))
+            })
+            .collect();
+
+        Self {
+            target_directory: self.target_directory.clone(),
+            base_output_directories: self
+                .base_output_directories
+                .iter()
+                .filter(|dir| keep(dir))
+                .cloned()
+                .collect(),
+            base_output_directory_categories: self
+                .base_output_directory_categories
+                .iter()
+                .filter(|(dir, _)| keep(dir))
+                .map(|(dir, category)| (dir.clone(), category.clone()))
+                .collect(),
+            non_test_binaries,
+            build_script_out_dirs: self
+                .build_script_out_dirs
+                .iter()
+                .filter(|(_, out_dir)| keep(out_dir))
+                .map(|(package_id, out_dir)| (package_id.clone(), out_dir.clone()))
+                .collect(),
+            linked_paths: self
+                .linked_paths
+                .iter()
+                .filter(|(path, _)| keep(path))
+                .map(|(path, requested_by)| (path.clone(), requested_by.clone()))
+                .collect(),
+            absolute_linked_paths: self.absolute_linked_paths.clone(),
+            linked_libs: self.linked_libs.clone(),
+            resolved_search_dirs: self
+                .resolved_search_dirs
+                .as_ref()
+                .map(|dirs| dirs.iter().filter(|dir| keep(dir)).cloned().collect()),
+            extra_output_roots: self.extra_output_roots.clone(),
+            label: self.label.clone(),
+            host_os_version: self.host_os_version.clone(),
+            rust_version: self.rust_version.clone(),
+            list_command: self.list_command.clone(),
+            built_at: self.built_at.clone(),
+            debuginfo: self.debuginfo.clone(),
+            incremental: self.incremental,
+            enabled_features: self.enabled_features.clone(),
+            include_linked_path_parents: self.include_linked_path_parents,
+            scan_linked_path_subdirs: self.scan_linked_path_subdirs,
+            use_dyld_library_path: self.use_dyld_library_path,
+            exclude_example_output_dirs: self.exclude_example_output_dirs,
+            build_platforms: BuildPlatforms {
+                host: self.build_platforms.host.clone(),
+                target,
+            },
+            state: PhantomData,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        list::TestListState,
+        platform::{HostPlatform, PlatformLibdir},
+    };
+    use camino::Utf8PathBuf;
+    use nextest_metadata::{RustNonTestBinaryKind, RustNonTestBinarySummary};
+
+    fn binary(name: &str, path: &str) -> RustNonTestBinarySummary {
+        RustNonTestBinarySummary {
+            name: name.to_owned(),
+            kind: RustNonTestBinaryKind::BIN_EXE,
+            path: path.into(),
+        }
+    }
+
+    #[test]
+    fn test_split_by_target() {
+        let host = HostPlatform {
+            platform: TargetTriple::x86_64_unknown_linux_gnu().platform,
+            libdir: PlatformLibdir::Available("/fake/host/libdir".into()),
+        };
+        let target_triple =
+            TargetTriple::deserialize_str(Some("aarch64-unknown-linux-gnu".to_owned()))
+                .expect("creating TargetTriple should succeed")
+                .expect("the output of deserialize_str shouldn't be None");
+        let meta = RustBuildMeta::<TestListState> {
+            base_output_directories: [
+                Utf8PathBuf::from("debug"),
+                Utf8PathBuf::from("aarch64-unknown-linux-gnu/debug"),
+            ]
+            .into(),
+            non_test_binaries: [(
+                "foo-id".to_owned(),
+                [
+                    binary("foo-host", "debug/foo-host"),
+                    binary("foo-target", "aarch64-unknown-linux-gnu/debug/foo-target"),
+                ]
+                .into(),
+            )]
+            .into(),
+            build_script_out_dirs: [(
+                "foo-id".to_owned(),
+                Utf8PathBuf::from("aarch64-unknown-linux-gnu/debug/build/foo-1234/out"),
+            )]
+            .into(),
+            label: Some("label".to_owned()),
+            build_platforms: BuildPlatforms {
+                host: host.clone(),
+                target: Some(TargetPlatform::new(
+                    target_triple,
+                    PlatformLibdir::Available("/fake/target/libdir".into()),
+                )),
+            },
+            ..RustBuildMeta::empty()
+        };
+
+        let split = meta.split_by_target();
+        assert_eq!(split.len(), 2, "target and host");
+
+        let (triple, target_meta) = &split[0];
+        assert_eq!(triple.platform.triple_str(), "aarch64-unknown-linux-gnu");
+        assert_eq!(
+            target_meta.base_output_directories,
+            [Utf8PathBuf::from("aarch64-unknown-linux-gnu/debug")].into(),
+        );
+        assert_eq!(
+            target_meta.non_test_binaries,
+            [(
+                "foo-id".to_owned(),
+                [binary(
+                    "foo-target",
+                    "aarch64-unknown-linux-gnu/debug/foo-target"
+                )]
+                .into(),
+            )]
+            .into(),
+        );
+        assert_eq!(
+            target_meta.build_script_out_dirs,
+            meta.build_script_out_dirs
+        );
+        assert_eq!(target_meta.build_platforms, meta.build_platforms);
+        assert_eq!(target_meta.label.as_deref(), Some("label"));
+
+        let (triple, host_meta) = &split[1];
+        assert_eq!(triple.platform.triple_str(), "x86_64-unknown-linux-gnu");
+        assert_eq!(
+            host_meta.base_output_directories,
+            [Utf8PathBuf::from("debug")].into()
+        );
+        assert_eq!(
+            host_meta.non_test_binaries,
+            [(
+                "foo-id".to_owned(),
+                [binary("foo-host", "debug/foo-host")].into()
+            )]
+            .into(),
+        );
+        assert!(host_meta.build_script_out_dirs.is_empty());
+        assert_eq!(
+            host_meta.build_platforms,
+            BuildPlatforms { host, target: None }
+        );
+    }
+
+    #[test]
+    fn test_split_by_target_host_only() {
+        let meta = RustBuildMeta::<TestListState> {
+            base_output_directories: [Utf8PathBuf::from("debug")].into(),
+            non_test_binaries: [("foo-id".to_owned(), [binary("foo", "debug/foo")].into())].into(),
+            ..RustBuildMeta::empty()
+        };
+
+        let split = meta.split_by_target();
+        assert_eq!(split.len(), 1, "host only");
+        assert_eq!(
+            split[0].0.platform.triple_str(),
+            meta.build_platforms.host.platform.triple_str()
+        );
+        assert_eq!(split[0].1, meta);
+    }
+}
@@ -0,0 +1,25 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Test helpers shared by the tests for [`RustBuildMeta`](super::RustBuildMeta) and its
+//! submodules.
+
+use super::PathChecker;
+use crate::cargo_config::TargetTriple;
+use camino::{Utf8Path, Utf8PathBuf};
+use std::collections::BTreeSet;
+
+pub(super) fn x86_64_pc_windows_msvc_triple() -> TargetTriple {
+    TargetTriple::deserialize_str(Some("x86_64-pc-windows-msvc".to_owned()))
+        .expect("creating TargetTriple should succeed")
+        .expect("the output of deserialize_str shouldn't be None")
+}
+
+/// A [`PathChecker`] backed by a fixed set of paths.
+pub(super) struct VirtualPaths(pub(super) BTreeSet<Utf8PathBuf>);
+
+impl PathChecker for VirtualPaths {
+    fn exists(&self, path: &Utf8Path) -> bool {
+        self.0.contains(path)
+    }
+}
@@ -0,0 +1,1212 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Validation checks for [`RustBuildMeta`].
+
+use super::{PathChecker, RealPathChecker, RustBuildMeta};
+use crate::{
+    errors::RustBuildMetaParseError, helpers::normalize_path_lexically, platform::PlatformLibdir,
+};
+use camino::{Utf8Component, Utf8Path, Utf8PathBuf};
+use itertools::Itertools;
+use nextest_metadata::{
+    BaseOutputDirCategory, BuildPlatform, PlatformLibdirUnavailable, RustBuildMetaSummary,
+    RustNonTestBinaryKind,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+};
+use target_spec::{summaries::PlatformSummary, Platform, TargetFeatures};
+
+/// The kind of dynamic library search directory a [`RustBuildMetaValidationIssue`] refers to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SearchDirKind {
+    /// A linked path requested by a build script.
+    LinkedPath,
+
+    /// A base output directory.
+    BaseOutputDirectory,
+}
+
+impl fmt::Display for SearchDirKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::LinkedPath => write!(f, "linked path"),
+            Self::BaseOutputDirectory => write!(f, "base output directory"),
+        }
+    }
+}
+
+/// An issue found by [`RustBuildMeta::validate`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum RustBuildMetaValidationIssue {
+    /// A search directory resolves to the target directory or one of its ancestors.
+    ///
+    /// This adds everything under the target directory to the dynamic library search path, which
+    /// is almost always caused by a misconfigured build script.
+    SearchDirCoversTargetDir {
+        /// The kind of search directory.
+        kind: SearchDirKind,
+
+        /// The path as stored in the metadata, relative to the target directory.
+        path: Utf8PathBuf,
+
+        /// The path after being joined to the target directory and normalized.
+        resolved: Utf8PathBuf,
+
+        /// The package IDs that requested this path, if known.
+        requested_by: BTreeSet<String>,
+    },
+
+    /// A linked path uses `..` to reach outside the target directory.
+    ///
+    /// Only the target directory is included in reuse archives, so such a path won't be
+    /// relocated correctly when the archive is extracted. Paths that resolve to the target
+    /// directory or one of its ancestors are reported as
+    /// [`SearchDirCoversTargetDir`](Self::SearchDirCoversTargetDir) instead.
+    LinkedPathEscapesTargetDir {
+        /// The path as stored in the metadata, relative to the target directory.
+        path: Utf8PathBuf,
+
+        /// The path after being joined to the target directory and normalized.
+        resolved: Utf8PathBuf,
+
+        /// The package IDs that requested this path.
+        requested_by: BTreeSet<String>,
+    },
+
+    /// The host and target platforms have the same triple, but different libdirs.
+    ///
+    /// This happens with e.g. `--target` set to the host triple. The libdirs should be identical
+    /// in that case, so a difference indicates a libdir detection bug.
+    HostTargetLibdirMismatch {
+        /// The triple shared by the host and target platforms.
+        triple: String,
+
+        /// The host libdir.
+        host_libdir: PlatformLibdir,
+
+        /// The target libdir.
+        target_libdir: PlatformLibdir,
+    },
+
+    /// A target platform is specified and the workspace builds dynamic libraries, but the target
+    /// libdir wasn't detected.
+    ///
+    /// The dynamic libraries might fail to resolve their own dependencies on the Rust standard
+    /// library. This usually means that the toolchain wasn't fully detected.
+    TargetLibdirMissingForDylibs {
+        /// The reason the target libdir is unavailable.
+        reason: PlatformLibdirUnavailable,
+
+        /// The affected dynamic libraries, as (package ID, binary name) pairs.
+        dylibs: BTreeSet<(String, String)>,
+    },
+
+    /// The host and target triples differ, and the libdir for one of them wasn't detected.
+    ///
+    /// Only reported by [`RustBuildMeta::validate_require_libdirs`]. Nextest can't tell which
+    /// binaries dynamically link against the standard library, so any of the platform's binaries
+    /// (or proc macros, for the host) might fail to load.
+    LibdirMissingWhenCrossCompiling {
+        /// The platform whose libdir is missing.
+        build_platform: BuildPlatform,
+
+        /// The platform's triple.
+        triple: String,
+
+        /// The reason the libdir is unavailable.
+        reason: PlatformLibdirUnavailable,
+    },
+
+    /// Base output directories belong to more than one Cargo profile, e.g. both `debug` and
+    /// `release`.
+    ///
+    /// Only one profile is built at a time, so this indicates corrupted metadata. Tests might load
+    /// libraries built with a different profile.
+    MultipleProfiles {
+        /// The base output directories, relative to the target directory, keyed by profile.
+        profiles: BTreeMap<String, BTreeSet<Utf8PathBuf>>,
+    },
+
+    /// A base output directory exists, but doesn't have a `deps` subdirectory.
+    ///
+    /// Test binaries and their dependencies are built into `deps`, so this usually means that the
+    /// target directory was partially built or partially cleaned. Tests might fail to run or to
+    /// load their dynamic libraries.
+    BaseOutputDirMissingDeps {
+        /// The base output directory, relative to the target directory.
+        path: Utf8PathBuf,
+
+        /// The absolute path to the missing `deps` subdirectory.
+        deps_dir: Utf8PathBuf,
+    },
+
+    /// A package ID isn't well-formed.
+    ///
+    /// Package IDs are produced by Cargo, so this usually indicates a corrupted archive. Lookups
+    /// by package ID, such as for build script output directories, will fail for this package.
+    MalformedPackageId {
+        /// The field the package ID was found in, as named in the summary.
+        field: &'static str,
+
+        /// The malformed package ID.
+        package_id: String,
+    },
+}
+
+impl fmt::Display for RustBuildMetaValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::SearchDirCoversTargetDir {
+                kind,
+                path,
+                resolved,
+                requested_by,
+            } => {
+                write!(
+                    f,
+                    "{kind} `{path}` resolves to `{resolved}`, \
+                     which is the target directory or one of its ancestors"
+                )?;
+                write_requested_by(f, requested_by)
+            }
+            Self::LinkedPathEscapesTargetDir {
+                path,
+                resolved,
+                requested_by,
+            } => {
+                write!(
+                    f,
+                    "linked path `{path}` resolves to `{resolved}`, \
+                     which is outside the target directory"
+                )?;
+                write_requested_by(f, requested_by)
+            }
+            Self::HostTargetLibdirMismatch {
+                triple,
+                host_libdir,
+                target_libdir,
+            } => {
+                write!(
+                    f,
+                    "host and target are both `{triple}`, but the host libdir is "
+                )?;
+                write_libdir(f, host_libdir)?;
+                write!(f, " and the target libdir is ")?;
+                write_libdir(f, target_libdir)
+            }
+            Self::TargetLibdirMissingForDylibs { reason, dylibs } => {
+                write!(
+                    f,
+                    "target libdir is unavailable ({}), but dynamic libraries are built \
+                     for the target: {}",
+                    reason.as_str(),
+                    dylibs
+                        .iter()
+                        .map(|(package_id, name)| format!("{name} (in {package_id})"))
+                        .join(", "),
+                )
+            }
+            Self::LibdirMissingWhenCrossCompiling {
+                build_platform,
+                triple,
+                reason,
+            } => {
+                write!(
+                    f,
+                    "{build_platform} libdir for `{triple}` is unavailable ({}), \
+                     but is required when cross-compiling",
+                    reason.as_str(),
+                )
+            }
+            Self::MultipleProfiles { profiles } => {
+                write!(
+                    f,
+                    "base output directories belong to multiple profiles: {}",
+                    profiles
+                        .iter()
+                        .map(|(profile, dirs)| {
+                            let dirs = dirs.iter().map(|dir| format!("`{dir}`")).join(", ");
+                            format!("{profile} ({dirs})")
+                        })
+                        .join(", "),
+                )
+            }
+            Self::BaseOutputDirMissingDeps { path, deps_dir } => {
+                write!(
+                    f,
+                    "base output directory `{path}` exists, but `{deps_dir}` doesn't \
+                     (is the target directory partially built or cleaned?)"
+                )
+            }
+            Self::MalformedPackageId { field, package_id } => {
+                write!(f, "in `{field}`, package ID {package_id:?} is malformed")
+            }
+        }
+    }
+}
+
+fn write_libdir(f: &mut fmt::Formatter<'_>, libdir: &PlatformLibdir) -> fmt::Result {
+    match libdir {
+        PlatformLibdir::Available(path) => write!(f, "`{path}`"),
+        PlatformLibdir::Unavailable(reason) => write!(f, "unavailable ({})", reason.as_str()),
+    }
+}
+
+pub(super) fn write_requested_by(
+    f: &mut fmt::Formatter<'_>,
+    requested_by: &BTreeSet<String>,
+) -> fmt::Result {
+    if !requested_by.is_empty() {
+        write!(f, " (requested by {})", requested_by.iter().join(", "))?;
+    }
+    Ok(())
+}
+
+/// An error found by [`validate_summary_bytes`].
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum SummaryValidationError {
+    /// The input couldn't be deserialized as a [`RustBuildMetaSummary`].
+    Deserialize {
+        /// The deserialization error message.
+        message: String,
+    },
+
+    /// A platform couldn't be parsed.
+    InvalidPlatform {
+        /// The summary field the platform was found in.
+        field: &'static str,
+
+        /// The platform's triple.
+        triple: String,
+
+        /// The parse error message.
+        message: String,
+    },
+
+    /// A target platform is specified more than once.
+    DuplicateTarget {
+        /// The summary field the target was found in.
+        field: &'static str,
+
+        /// The duplicated triple.
+        triple: String,
+    },
+
+    /// A path that must be relative to the target directory is absolute.
+    AbsolutePath {
+        /// The summary field the path was found in.
+        field: &'static str,
+
+        /// The absolute path.
+        path: Utf8PathBuf,
+    },
+}
+
+impl fmt::Display for SummaryValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Deserialize { message } => {
+                write!(f, "failed to deserialize summary: {message}")
+            }
+            Self::InvalidPlatform {
+                field,
+                triple,
+                message,
+            } => write!(f, "in `{field}`, invalid platform `{triple}`: {message}"),
+            Self::DuplicateTarget { field, triple } => {
+                write!(
+                    f,
+                    "in `{field}`, target `{triple}` is specified more than once"
+                )
+            }
+            Self::AbsolutePath { field, path } => {
+                write!(
+                    f,
+                    "in `{field}`, path `{path}` is absolute, but must be relative to \
+                     the target directory"
+                )
+            }
+        }
+    }
+}
+
+/// Performs structural checks over a serialized [`RustBuildMetaSummary`], without converting it
+/// to a [`RustBuildMeta`] or accessing the filesystem.
+///
+/// This checks that:
+///
+/// * the bytes deserialize to a summary.
+/// * all platforms can be parsed.
+/// * no target platform is specified more than once.
+/// * paths that are stored relative to the target directory aren't absolute.
+///
+/// All errors found are returned, not just the first one.
+pub fn validate_summary_bytes(bytes: &[u8]) -> Result<(), Vec<SummaryValidationError>> {
+    let summary: RustBuildMetaSummary = serde_json::from_slice(bytes).map_err(|error| {
+        vec![SummaryValidationError::Deserialize {
+            message: error.to_string(),
+        }]
+    })?;
+
+    let mut errors = Vec::new();
+    check_summary_platforms(&summary, &mut errors);
+    check_summary_relative_paths(&summary, &mut errors);
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+fn check_summary_platforms(
+    summary: &RustBuildMetaSummary,
+    errors: &mut Vec<SummaryValidationError>,
+) {
+    let mut check_platform = |field: &'static str, platform: &PlatformSummary| {
+        if let Err(error) = platform.to_platform() {
+            errors.push(SummaryValidationError::InvalidPlatform {
+                field,
+                triple: platform.triple.clone(),
+                message: error.to_string(),
+            });
+        }
+    };
+
+    if let Some(platforms) = &summary.platforms {
+        check_platform("platforms.host", &platforms.host.platform);
+        for target in &platforms.targets {
+            check_platform("platforms.targets", &target.platform);
+        }
+    }
+    for platform in &summary.target_platforms {
+        check_platform("target-platforms", platform);
+    }
+    if let Some(triple) = &summary.target_platform {
+        if let Err(error) = Platform::new(triple.clone(), TargetFeatures::Unknown) {
+            errors.push(SummaryValidationError::InvalidPlatform {
+                field: "target-platform",
+                triple: triple.clone(),
+                message: error.to_string(),
+            });
+        }
+    }
+
+    let target_triples = summary
+        .platforms
+        .iter()
+        .flat_map(|platforms| {
+            platforms
+                .targets
+                .iter()
+                .map(|target| ("platforms.targets", &target.platform.triple))
+        })
+        .chain(
+            summary
+                .target_platforms
+                .iter()
+                .map(|platform| ("target-platforms", &platform.triple)),
+        );
+    let mut seen = BTreeSet::new();
+    for (field, triple) in target_triples {
+        if !seen.insert((field, triple)) {
+            errors.push(SummaryValidationError::DuplicateTarget {
+                field,
+                triple: triple.clone(),
+            });
+        }
+    }
+}
+
+fn check_summary_relative_paths(
+    summary: &RustBuildMetaSummary,
+    errors: &mut Vec<SummaryValidationError>,
+) {
+    let paths = summary
+        .base_output_directories
+        .iter()
+        .map(|path| ("base-output-directories", path.as_path()))
+        .chain(
+            summary
+                .base_output_directory_categories
+                .keys()
+                .map(|path| ("base-output-directory-categories", path.as_path())),
+        )
+        .chain(summary.non_test_binaries.values().flat_map(|binaries| {
+            binaries
+                .iter()
+                .map(|binary| ("non-test-binaries", binary.path.as_path()))
+        }))
+        .chain(
+            summary
+                .build_script_out_dirs
+                .values()
+                .map(|path| ("build-script-out-dirs", path.as_path())),
+        )
+        .chain(
+            summary
+                .linked_paths
+                .iter()
+                .map(|path| ("linked-paths", path.as_path())),
+        )
+        .chain(
+            summary
+                .resolved_search_dirs
+                .iter()
+                .flatten()
+                .map(|path| ("resolved-search-dirs", path.as_path())),
+        );
+
+    for (field, path) in paths {
+        if is_absolute_anywhere(path) {
+            errors.push(SummaryValidationError::AbsolutePath {
+                field,
+                path: path.to_owned(),
+            });
+        }
+    }
+}
+
+/// Returns true if `id` looks like a package ID produced by Cargo.
+///
+/// This accepts both the current (`registry+https://...#name@version`) and the older
+/// (`name version (registry+https://...)`) formats. Opaque IDs without any of the structure of
+/// these formats are accepted as long as they don't contain whitespace, since they're used by
+/// tools that construct metadata by hand.
+fn is_plausible_package_id(id: &str) -> bool {
+    // An empty ID, or one with control or replacement characters, indicates corrupted data.
+    if id.is_empty()
+        || id
+            .chars()
+            .any(|c| c.is_control() || c == char::REPLACEMENT_CHARACTER)
+    {
+        return false;
+    }
+
+    if let Some((source, spec)) = id.split_once('#') {
+        source.contains('+')
+            && source.contains("://")
+            && !source.contains(char::is_whitespace)
+            && !spec.is_empty()
+            && !spec.contains(['#', ' '])
+    } else if let Some(rest) = id.strip_suffix(')') {
+        let Some((name_version, source)) = rest.split_once(" (") else {
+            return false;
+        };
+        let mut parts = name_version.split(' ');
+        let (Some(name), Some(version), None) = (parts.next(), parts.next(), parts.next()) else {
+            return false;
+        };
+        !name.is_empty() && !version.is_empty() && source.contains("://")
+    } else {
+        !id.contains(char::is_whitespace)
+    }
+}
+
+/// Returns true if `path` is absolute on the current platform, or has a root (e.g. an absolute
+/// Unix path on Windows).
+fn is_absolute_anywhere(path: &Utf8Path) -> bool {
+    path.is_absolute() || path.has_root()
+}
+
+/// Checks that base output directories and build script output directories in `summary` stay
+/// within the target directory when joined to it.
+///
+/// Archives might come from untrusted sources, so this is checked when converting a summary to a
+/// [`RustBuildMeta`].
+pub(super) fn check_summary_paths_within_target_dir(
+    summary: &RustBuildMetaSummary,
+) -> Result<(), RustBuildMetaParseError> {
+    let paths = summary
+        .base_output_directories
+        .iter()
+        .map(|path| ("base-output-directories", path))
+        .chain(
+            summary
+                .build_script_out_dirs
+                .values()
+                .map(|path| ("build-script-out-dirs", path)),
+        );
+    for (field, path) in paths {
+        let escapes = path.components().any(|component| {
+            matches!(
+                component,
+                Utf8Component::Prefix(_) | Utf8Component::RootDir | Utf8Component::ParentDir
+            )
+        });
+        if escapes {
+            return Err(RustBuildMetaParseError::PathOutsideTargetDir {
+                field,
+                path: path.clone(),
+            });
+        }
+    }
+    Ok(())
+}
+
+impl<State> RustBuildMeta<State> {
+    /// Runs consistency checks over this metadata, returning all issues found.
+    ///
+    /// An empty list means that no issues were found.
+    pub fn validate(&self) -> Vec<RustBuildMetaValidationIssue> {
+        self.validate_with_checker(&RealPathChecker)
+    }
+
+    /// Runs the checks in [`Self::validate`], using the given [`PathChecker`] rather than the real
+    /// filesystem.
+    pub fn validate_with_checker(
+        &self,
+        checker: &dyn PathChecker,
+    ) -> Vec<RustBuildMetaValidationIssue> {
+        let mut issues = Vec::new();
+        self.check_search_dirs_cover_target_dir(&mut issues);
+        self.check_linked_paths_escape_target_dir(&mut issues);
+        self.check_host_target_libdirs(&mut issues);
+        self.check_target_libdir_for_dylibs(&mut issues);
+        self.check_multiple_profiles(&mut issues);
+        self.check_base_output_dirs_have_deps(checker, &mut issues);
+        self.check_package_ids(&mut issues);
+        issues
+    }
+
+    /// Runs the checks in [`Self::validate`], and additionally requires that the host and target
+    /// libdirs were both detected when cross-compiling.
+    ///
+    /// [`Self::validate`] only reports a missing target libdir if the workspace builds dynamic
+    /// libraries. This stricter mode reports a missing libdir for either platform, so that a
+    /// misdetected toolchain doesn't silently break tests that need it.
+    pub fn validate_require_libdirs(&self) -> Vec<RustBuildMetaValidationIssue> {
+        let mut issues = self.validate();
+        self.check_libdirs_when_cross_compiling(&mut issues);
+        issues
+    }
+
+    fn check_libdirs_when_cross_compiling(&self, issues: &mut Vec<RustBuildMetaValidationIssue>) {
+        let Some(target) = &self.build_platforms.target else {
+            return;
+        };
+        let host = &self.build_platforms.host;
+        if host.platform.triple_str() == target.triple.platform.triple_str() {
+            return;
+        }
+
+        let platforms = [
+            (
+                BuildPlatform::Host,
+                host.platform.triple_str(),
+                &host.libdir,
+            ),
+            (
+                BuildPlatform::Target,
+                target.triple.platform.triple_str(),
+                &target.libdir,
+            ),
+        ];
+        for (build_platform, triple, libdir) in platforms {
+            if let PlatformLibdir::Unavailable(reason) = libdir {
+                issues.push(
+                    RustBuildMetaValidationIssue::LibdirMissingWhenCrossCompiling {
+                        build_platform,
+                        triple: triple.to_owned(),
+                        reason: reason.clone(),
+                    },
+                );
+            }
+        }
+    }
+
+    fn check_base_output_dirs_have_deps(
+        &self,
+        checker: &dyn PathChecker,
+        issues: &mut Vec<RustBuildMetaValidationIssue>,
+    ) {
+        for dir in &self.base_output_directories {
+            // Examples are built directly into their base output directory.
+            if self.base_output_dir_category(dir) == BaseOutputDirCategory::EXAMPLES {
+                continue;
+            }
+            let abs_dir = self.target_directory.join(dir);
+            let deps_dir = abs_dir.join("deps");
+            if checker.is_dir(&abs_dir) && !checker.is_dir(&deps_dir) {
+                issues.push(RustBuildMetaValidationIssue::BaseOutputDirMissingDeps {
+                    path: dir.clone(),
+                    deps_dir,
+                });
+            }
+        }
+    }
+
+    fn check_package_ids(&self, issues: &mut Vec<RustBuildMetaValidationIssue>) {
+        let package_ids = self
+            .non_test_binaries
+            .keys()
+            .map(|id| ("non-test-binaries", id))
+            .chain(
+                self.build_script_out_dirs
+                    .keys()
+                    .map(|id| ("build-script-out-dirs", id)),
+            )
+            .chain(
+                self.linked_paths
+                    .values()
+                    .flatten()
+                    .map(|id| ("linked-paths", id)),
+            )
+            .chain(
+                self.absolute_linked_paths
+                    .values()
+                    .flatten()
+                    .map(|id| ("absolute-linked-paths", id)),
+            );
+
+        // The same package ID can request several linked paths, so deduplicate.
+        let malformed: BTreeSet<_> = package_ids
+            .filter(|(_, id)| !is_plausible_package_id(id))
+            .collect();
+        issues.extend(malformed.into_iter().map(|(field, id)| {
+            RustBuildMetaValidationIssue::MalformedPackageId {
+                field,
+                package_id: id.clone(),
+            }
+        }));
+    }
+
+    fn check_multiple_profiles(&self, issues: &mut Vec<RustBuildMetaValidationIssue>) {
+        let mut profiles: BTreeMap<String, BTreeSet<Utf8PathBuf>> = BTreeMap::new();
+        for dir in &self.base_output_directories {
+            if let Some(profile) = self.output_dir_profile(dir) {
+                profiles
+                    .entry(profile.to_owned())
+                    .or_default()
+                    .insert(dir.clone());
+            }
+        }
+
+        if profiles.len() > 1 {
+            issues.push(RustBuildMetaValidationIssue::MultipleProfiles { profiles });
+        }
+    }
+
+    fn check_target_libdir_for_dylibs(&self, issues: &mut Vec<RustBuildMetaValidationIssue>) {
+        let Some(target) = &self.build_platforms.target else {
+            return;
+        };
+        let PlatformLibdir::Unavailable(reason) = &target.libdir else {
+            return;
+        };
+
+        let dylibs: BTreeSet<_> = self
+            .non_test_binaries
+            .iter()
+            .flat_map(|(package_id, binaries)| {
+                binaries
+                    .iter()
+                    .filter(|binary| binary.kind == RustNonTestBinaryKind::DYLIB)
+                    .map(move |binary| (package_id.clone(), binary.name.clone()))
+            })
+            .collect();
+        if !dylibs.is_empty() {
+            issues.push(RustBuildMetaValidationIssue::TargetLibdirMissingForDylibs {
+                reason: reason.clone(),
+                dylibs,
+            });
+        }
+    }
+
+    fn check_host_target_libdirs(&self, issues: &mut Vec<RustBuildMetaValidationIssue>) {
+        let Some(target) = &self.build_platforms.target else {
+            return;
+        };
+        let host = &self.build_platforms.host;
+        if host.platform.triple_str() == target.triple.platform.triple_str()
+            && host.libdir != target.libdir
+        {
+            issues.push(RustBuildMetaValidationIssue::HostTargetLibdirMismatch {
+                triple: host.platform.triple_str().to_owned(),
+                host_libdir: host.libdir.clone(),
+                target_libdir: target.libdir.clone(),
+            });
+        }
+    }
+
+    /// If `linked_path`, relative to the target directory, uses `..` to reach outside the target
+    /// directory, returns the path it resolves to.
+    pub(crate) fn linked_path_escape_target(&self, linked_path: &Utf8Path) -> Option<Utf8PathBuf> {
+        if !linked_path
+            .components()
+            .any(|component| component == Utf8Component::ParentDir)
+        {
+            return None;
+        }
+        let resolved = normalize_path_lexically(&self.target_directory.join(linked_path));
+        (!resolved.starts_with(normalize_path_lexically(&self.target_directory)))
+            .then_some(resolved)
+    }
+
+    fn check_linked_paths_escape_target_dir(&self, issues: &mut Vec<RustBuildMetaValidationIssue>) {
+        let target_dir = normalize_path_lexically(&self.target_directory);
+        for (path, requested_by) in &self.linked_paths {
+            let Some(resolved) = self.linked_path_escape_target(path) else {
+                continue;
+            };
+            // Reported by check_search_dirs_cover_target_dir.
+            if target_dir.starts_with(&resolved) {
+                continue;
+            }
+            issues.push(RustBuildMetaValidationIssue::LinkedPathEscapesTargetDir {
+                path: path.clone(),
+                resolved,
+                requested_by: requested_by.clone(),
+            });
+        }
+    }
+
+    fn check_search_dirs_cover_target_dir(&self, issues: &mut Vec<RustBuildMetaValidationIssue>) {
+        let target_dir = normalize_path_lexically(&self.target_directory);
+
+        let linked_paths = self
+            .linked_paths
+            .iter()
+            .map(|(path, requested_by)| (SearchDirKind::LinkedPath, path, requested_by.clone()));
+        let base_output_dirs = self
+            .base_output_directories
+            .iter()
+            .map(|path| (SearchDirKind::BaseOutputDirectory, path, BTreeSet::new()));
+
+        for (kind, path, requested_by) in linked_paths.chain(base_output_dirs) {
+            let resolved = normalize_path_lexically(&self.target_directory.join(path));
+            if target_dir.starts_with(&resolved) {
+                issues.push(RustBuildMetaValidationIssue::SearchDirCoversTargetDir {
+                    kind,
+                    path: path.clone(),
+                    resolved,
+                    requested_by,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cargo_config::TargetTriple,
+        list::{
+            rust_build_meta::test_helpers::{x86_64_pc_windows_msvc_triple, VirtualPaths},
+            TestListState,
+        },
+        platform::{BuildPlatforms, HostPlatform, TargetPlatform},
+    };
+    use nextest_metadata::RustNonTestBinarySummary;
+    use test_case::test_case;
+
+    #[test]
+    fn test_validate_summary_bytes() {
+        let valid = r#"{
+            "target-directory": "/fake/target",
+            "base-output-directories": ["debug"],
+            "non-test-binaries": {},
+            "linked-paths": ["debug/build/foo/out"],
+            "target-platforms": [{"triple": "x86_64-pc-windows-msvc", "target-features": "unknown"}]
+        }"#;
+        assert_eq!(validate_summary_bytes(valid.as_bytes()), Ok(()));
+
+        let invalid = r#"{
+            "target-directory": "/fake/target",
+            "base-output-directories": ["/fake/target/debug"],
+            "non-test-binaries": {},
+            "linked-paths": ["debug/build/foo/out", "/usr/lib"],
+            "target-platforms": [
+                {"triple": "x86_64-pc-windows-msvc", "target-features": "unknown"},
+                {"triple": "x86_64-pc-windows-msvc", "target-features": "unknown"}
+            ]
+        }"#;
+        assert_eq!(
+            validate_summary_bytes(invalid.as_bytes()),
+            Err(vec![
+                SummaryValidationError::DuplicateTarget {
+                    field: "target-platforms",
+                    triple: "x86_64-pc-windows-msvc".to_owned(),
+                },
+                SummaryValidationError::AbsolutePath {
+                    field: "base-output-directories",
+                    path: "/fake/target/debug".into(),
+                },
+                SummaryValidationError::AbsolutePath {
+                    field: "linked-paths",
+                    path: "/usr/lib".into(),
+                },
+            ])
+        );
+
+        let errors = validate_summary_bytes(b"{}").expect_err("missing fields are an error");
+        assert!(
+            matches!(
+                errors.as_slice(),
+                [SummaryValidationError::Deserialize { .. }]
+            ),
+            "unexpected errors: {errors:?}"
+        );
+    }
+
+    #[test]
+    fn test_search_dirs_cover_target_dir() {
+        let meta = RustBuildMeta::<TestListState> {
+            target_directory: "/fake/workspace/target".into(),
+            base_output_directories: [Utf8PathBuf::from("debug"), Utf8PathBuf::from(".")].into(),
+            linked_paths: [
+                (
+                    Utf8PathBuf::from("debug/build/foo/out"),
+                    ["foo-id".to_owned()].into(),
+                ),
+                (
+                    Utf8PathBuf::from("debug/../.."),
+                    ["bar-id".to_owned()].into(),
+                ),
+            ]
+            .into(),
+            ..RustBuildMeta::empty()
+        };
+
+        assert_eq!(
+            meta.validate(),
+            vec![
+                RustBuildMetaValidationIssue::SearchDirCoversTargetDir {
+                    kind: SearchDirKind::LinkedPath,
+                    path: "debug/../..".into(),
+                    resolved: "/fake/workspace".into(),
+                    requested_by: ["bar-id".to_owned()].into(),
+                },
+                RustBuildMetaValidationIssue::SearchDirCoversTargetDir {
+                    kind: SearchDirKind::BaseOutputDirectory,
+                    path: ".".into(),
+                    resolved: "/fake/workspace/target".into(),
+                    requested_by: BTreeSet::new(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_linked_paths_escape_target_dir() {
+        let meta = RustBuildMeta::<TestListState> {
+            target_directory: "/fake/workspace/target".into(),
+            linked_paths: [
+                (
+                    Utf8PathBuf::from("debug/build/foo/out/../lib"),
+                    ["foo-id".to_owned()].into(),
+                ),
+                (
+                    Utf8PathBuf::from("debug/../../vendor/lib"),
+                    ["bar-id".to_owned()].into(),
+                ),
+                (
+                    Utf8PathBuf::from("debug/../.."),
+                    ["baz-id".to_owned()].into(),
+                ),
+            ]
+            .into(),
+            ..RustBuildMeta::empty()
+        };
+
+        assert_eq!(
+            meta.linked_path_escape_target(Utf8Path::new("debug/build/foo/out/../lib")),
+            None,
+            "`..` within the target directory is fine"
+        );
+        assert_eq!(
+            meta.linked_path_escape_target(Utf8Path::new("debug/../.."))
+                .as_deref(),
+            Some(Utf8Path::new("/fake/workspace")),
+        );
+        assert_eq!(
+            meta.validate(),
+            vec![
+                RustBuildMetaValidationIssue::SearchDirCoversTargetDir {
+                    kind: SearchDirKind::LinkedPath,
+                    path: "debug/../..".into(),
+                    resolved: "/fake/workspace".into(),
+                    requested_by: ["baz-id".to_owned()].into(),
+                },
+                RustBuildMetaValidationIssue::LinkedPathEscapesTargetDir {
+                    path: "debug/../../vendor/lib".into(),
+                    resolved: "/fake/workspace/vendor/lib".into(),
+                    requested_by: ["bar-id".to_owned()].into(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_multiple_profiles() {
+        let single = RustBuildMeta::<TestListState> {
+            base_output_directories: [
+                Utf8PathBuf::from("debug"),
+                Utf8PathBuf::from("debug/examples"),
+            ]
+            .into(),
+            ..RustBuildMeta::empty()
+        };
+        assert_eq!(single.validate(), vec![]);
+
+        let multiple = RustBuildMeta::<TestListState> {
+            base_output_directories: [
+                Utf8PathBuf::from("debug"),
+                Utf8PathBuf::from("release"),
+                Utf8PathBuf::from("release/examples"),
+            ]
+            .into(),
+            ..RustBuildMeta::empty()
+        };
+        assert_eq!(
+            multiple.validate(),
+            vec![RustBuildMetaValidationIssue::MultipleProfiles {
+                profiles: [
+                    ("debug".to_owned(), [Utf8PathBuf::from("debug")].into()),
+                    (
+                        "release".to_owned(),
+                        [
+                            Utf8PathBuf::from("release"),
+                            Utf8PathBuf::from("release/examples"),
+                        ]
+                        .into(),
+                    ),
+                ]
+                .into(),
+            }]
+        );
+    }
+
+    #[test_case("registry+https://github.com/rust-lang/crates.io-index#serde@1.0.210", true; "current format")]
+    #[test_case("path+file:///fake/workspace/foo#0.1.0", true; "current format without name")]
+    #[test_case("serde 1.0.210 (registry+https://github.com/rust-lang/crates.io-index)", true; "older format")]
+    #[test_case("foo-id", true; "opaque")]
+    #[test_case("", false; "empty")]
+    #[test_case("path+file:///fake/workspace/foo#", false; "truncated current format")]
+    #[test_case("foo#0.1.0", false; "current format without source")]
+    #[test_case("serde (registry+https://github.com/rust-lang/crates.io-index)", false; "older format without version")]
+    #[test_case("foo\u{fffd}bar", false; "replacement character")]
+    #[test_case("foo bar", false; "whitespace")]
+    fn test_is_plausible_package_id(id: &str, expected: bool) {
+        assert_eq!(is_plausible_package_id(id), expected);
+    }
+
+    #[test]
+    fn test_malformed_package_ids() {
+        let meta = RustBuildMeta::<TestListState> {
+            build_script_out_dirs: [
+                (
+                    "foo-id".to_owned(),
+                    Utf8PathBuf::from("debug/build/foo/out"),
+                ),
+                (String::new(), Utf8PathBuf::from("debug/build/bar/out")),
+            ]
+            .into(),
+            linked_paths: [
+                (
+                    Utf8PathBuf::from("debug/build/foo/out/a"),
+                    ["path+file:///fake#".to_owned()].into(),
+                ),
+                (
+                    Utf8PathBuf::from("debug/build/foo/out/b"),
+                    ["path+file:///fake#".to_owned()].into(),
+                ),
+            ]
+            .into(),
+            ..RustBuildMeta::empty()
+        };
+
+        assert_eq!(
+            meta.validate(),
+            vec![
+                RustBuildMetaValidationIssue::MalformedPackageId {
+                    field: "build-script-out-dirs",
+                    package_id: String::new(),
+                },
+                RustBuildMetaValidationIssue::MalformedPackageId {
+                    field: "linked-paths",
+                    package_id: "path+file:///fake#".to_owned(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_base_output_dirs_missing_deps() {
+        let meta = RustBuildMeta::<TestListState> {
+            target_directory: "/fake/target".into(),
+            base_output_directories: [
+                Utf8PathBuf::from("debug"),
+                Utf8PathBuf::from("debug/examples"),
+                Utf8PathBuf::from("release"),
+                Utf8PathBuf::from("x86_64-pc-windows-msvc/debug"),
+            ]
+            .into(),
+            base_output_directory_categories: [
+                (Utf8PathBuf::from("debug"), BaseOutputDirCategory::TESTS),
+                (
+                    Utf8PathBuf::from("debug/examples"),
+                    BaseOutputDirCategory::EXAMPLES,
+                ),
+            ]
+            .into(),
+            ..RustBuildMeta::empty()
+        };
+
+        // release doesn't exist at all, and debug/examples is never expected to have deps.
+        let checker = VirtualPaths(
+            [
+                "debug",
+                "debug/examples",
+                "x86_64-pc-windows-msvc/debug",
+                "x86_64-pc-windows-msvc/debug/deps",
+            ]
+            .into_iter()
+            .map(|path| meta.target_directory.join(path))
+            .collect(),
+        );
+        let issues: Vec<_> = meta
+            .validate_with_checker(&checker)
+            .into_iter()
+            .filter(|issue| {
+                matches!(
+                    issue,
+                    RustBuildMetaValidationIssue::BaseOutputDirMissingDeps { .. }
+                )
+            })
+            .collect();
+        assert_eq!(
+            issues,
+            vec![RustBuildMetaValidationIssue::BaseOutputDirMissingDeps {
+                path: "debug".into(),
+                deps_dir: meta.target_directory.join("debug").join("deps"),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_host_target_libdir_mismatch() {
+        let triple = TargetTriple::x86_64_unknown_linux_gnu();
+        let host = HostPlatform {
+            platform: triple.platform.clone(),
+            libdir: PlatformLibdir::Available("/fake/host/libdir".into()),
+        };
+        let matching = RustBuildMeta::<TestListState> {
+            build_platforms: BuildPlatforms {
+                host: host.clone(),
+                target: Some(TargetPlatform::new(
+                    triple.clone(),
+                    PlatformLibdir::Available("/fake/host/libdir".into()),
+                )),
+            },
+            ..RustBuildMeta::empty()
+        };
+        assert_eq!(matching.validate(), vec![]);
+
+        let mismatched = RustBuildMeta::<TestListState> {
+            build_platforms: BuildPlatforms {
+                host,
+                target: Some(TargetPlatform::new(
+                    triple,
+                    PlatformLibdir::Available("/fake/target/libdir".into()),
+                )),
+            },
+            ..RustBuildMeta::empty()
+        };
+        assert_eq!(
+            mismatched.validate(),
+            vec![RustBuildMetaValidationIssue::HostTargetLibdirMismatch {
+                triple: "x86_64-unknown-linux-gnu".to_owned(),
+                host_libdir: PlatformLibdir::Available("/fake/host/libdir".into()),
+                target_libdir: PlatformLibdir::Available("/fake/target/libdir".into()),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_target_libdir_missing_for_dylibs() {
+        let meta = RustBuildMeta::<TestListState> {
+            non_test_binaries: [(
+                "foo-id".to_owned(),
+                [
+                    RustNonTestBinarySummary {
+                        name: "foo".to_owned(),
+                        kind: RustNonTestBinaryKind::DYLIB,
+                        path: "x86_64-pc-windows-msvc/debug/foo.dll".into(),
+                    },
+                    RustNonTestBinarySummary {
+                        name: "foo-bin".to_owned(),
+                        kind: RustNonTestBinaryKind::BIN_EXE,
+                        path: "x86_64-pc-windows-msvc/debug/foo-bin.exe".into(),
+                    },
+                ]
+                .into(),
+            )]
+            .into(),
+            build_platforms: BuildPlatforms {
+                host: HostPlatform {
+                    platform: TargetTriple::x86_64_unknown_linux_gnu().platform,
+                    libdir: PlatformLibdir::Available("/fake/host/libdir".into()),
+                },
+                target: Some(TargetPlatform::new(
+                    x86_64_pc_windows_msvc_triple(),
+                    PlatformLibdir::Unavailable(PlatformLibdirUnavailable::RUSTC_FAILED),
+                )),
+            },
+            ..RustBuildMeta::empty()
+        };
+
+        assert_eq!(
+            meta.validate(),
+            vec![RustBuildMetaValidationIssue::TargetLibdirMissingForDylibs {
+                reason: PlatformLibdirUnavailable::RUSTC_FAILED,
+                dylibs: [("foo-id".to_owned(), "foo".to_owned())].into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_validate_require_libdirs() {
+        let mut meta = RustBuildMeta::<TestListState> {
+            build_platforms: BuildPlatforms {
+                host: HostPlatform {
+                    platform: TargetTriple::x86_64_unknown_linux_gnu().platform,
+                    libdir: PlatformLibdir::Available("/fake/host/libdir".into()),
+                },
+                target: Some(TargetPlatform::new(
+                    x86_64_pc_windows_msvc_triple(),
+                    PlatformLibdir::Unavailable(PlatformLibdirUnavailable::RUSTC_FAILED),
+                )),
+            },
+            ..RustBuildMeta::empty()
+        };
+
+        assert_eq!(
+            meta.validate(),
+            vec![],
+            "without dynamic libraries, a missing target libdir is allowed"
+        );
+        assert_eq!(
+            meta.validate_require_libdirs(),
+            vec![
+                RustBuildMetaValidationIssue::LibdirMissingWhenCrossCompiling {
+                    build_platform: BuildPlatform::Target,
+                    triple: "x86_64-pc-windows-msvc".to_owned(),
+                    reason: PlatformLibdirUnavailable::RUSTC_FAILED,
+                }
+            ]
+        );
+
+        // Without cross-compilation, libdirs aren't required.
+        meta.build_platforms.target = None;
+        meta.build_platforms.host.libdir =
+            PlatformLibdir::Unavailable(PlatformLibdirUnavailable::RUSTC_FAILED);
+        assert_eq!(meta.validate_require_libdirs(), vec![]);
+    }
+}
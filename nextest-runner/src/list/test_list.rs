@@ -6,9 +6,9 @@ use crate::{
     cargo_config::EnvironmentMap,
     double_spawn::DoubleSpawnInfo,
     errors::{CreateTestListError, FromMessagesError, WriteTestListError},
-    helpers::{convert_build_platform, dylib_path, dylib_path_envvar, write_test_name},
+    helpers::{convert_build_platform, write_test_name},
     indenter::indented,
-    list::{BinaryList, OutputFormat, RustBuildMeta, Styles, TestListState},
+    list::{BinaryList, ListCache, OutputFormat, RustBuildMeta, Styles, TestListState},
     reuse_build::PathMapper,
     target_runner::{PlatformRunner, TargetRunner},
     test_command::{LocalExecuteContext, TestCommand},
@@ -23,16 +23,15 @@ use guppy::{
 };
 use nextest_filtering::{BinaryQuery, EvalContext, TestQuery};
 use nextest_metadata::{
-    BuildPlatform, FilterMatch, MismatchReason, RustBinaryId, RustNonTestBinaryKind,
-    RustTestBinaryKind, RustTestBinarySummary, RustTestCaseSummary, RustTestSuiteStatusSummary,
-    RustTestSuiteSummary, TestListSummary,
+    BuildPlatform, FilterMatch, MismatchReason, RustBinaryId, RustTestBinaryKind,
+    RustTestBinarySummary, RustTestCaseSummary, RustTestSuiteStatusSummary, RustTestSuiteSummary,
+    TestListSummary,
 };
 use owo_colors::OwoColorize;
 use std::{
     collections::{BTreeMap, BTreeSet},
     ffi::{OsStr, OsString},
     io,
-    path::PathBuf,
     sync::{Arc, OnceLock},
 };
 use tokio::runtime::Runtime;
@@ -106,30 +105,10 @@ impl<'g> RustTestArtifact<'g> {
             let binary_path = path_mapper.map_binary(binary.path.clone());
             let cwd = path_mapper.map_cwd(cwd);
 
-            // Non-test binaries are only exposed to integration tests and benchmarks.
-            let non_test_binaries = if binary.kind == RustTestBinaryKind::TEST
-                || binary.kind == RustTestBinaryKind::BENCH
-            {
-                // Note we must use the TestListState rust_build_meta here to ensure we get remapped
-                // paths.
-                match rust_build_meta.non_test_binaries.get(package_id.repr()) {
-                    Some(binaries) => binaries
-                        .iter()
-                        .filter(|binary| {
-                            // Only expose BIN_EXE non-test files.
-                            binary.kind == RustNonTestBinaryKind::BIN_EXE
-                        })
-                        .map(|binary| {
-                            // Convert relative paths to absolute ones by joining with the target directory.
-                            let abs_path = rust_build_meta.target_directory.join(&binary.path);
-                            (binary.name.clone(), abs_path)
-                        })
-                        .collect(),
-                    None => BTreeSet::new(),
-                }
-            } else {
-                BTreeSet::new()
-            };
+            // Note we must use the TestListState rust_build_meta here to ensure we get remapped
+            // paths.
+            let non_test_binaries =
+                rust_build_meta.exposed_non_test_binaries(package_id.repr(), &binary.kind);
 
             binaries.push(RustTestArtifact {
                 binary_id: binary.id.clone(),
@@ -230,15 +209,22 @@ impl<'g> TestList<'g> {
         ecx: &EvalContext<'_>,
         bound: FilterBound,
         list_threads: usize,
+        list_cache: Option<&ListCache>,
     ) -> Result<Self, CreateTestListError>
     where
         I: IntoIterator<Item = RustTestArtifact<'g>>,
         I::IntoIter: Send,
     {
+        if !rust_build_meta.target_directory_exists() {
+            return Err(CreateTestListError::TargetDirectoryMissing {
+                target_directory: rust_build_meta.target_directory.clone(),
+            });
+        }
+
         let updated_dylib_path = Self::create_dylib_path(&rust_build_meta)?;
         log::debug!(
             "updated {}: {}",
-            dylib_path_envvar(),
+            rust_build_meta.dylib_path_envvar(),
             updated_dylib_path.to_string_lossy(),
         );
         let lctx = LocalExecuteContext {
@@ -260,9 +246,26 @@ impl<'g> TestList<'g> {
                             (match result is {binary_match:?}): {}",
                             test_binary.binary_id,
                         );
-                        // Run the binary to obtain the test list.
-                        let (non_ignored, ignored) =
-                            test_binary.exec(&lctx, ctx.target_runner).await?;
+                        let cached =
+                            list_cache.and_then(|cache| cache.get(&test_binary.binary_path));
+                        let (non_ignored, ignored) = match cached {
+                            Some(outputs) => {
+                                log::debug!(
+                                    "using cached test list for unchanged binary: {}",
+                                    test_binary.binary_id,
+                                );
+                                outputs
+                            }
+                            None => {
+                                // Run the binary to obtain the test list.
+                                let (non_ignored, ignored) =
+                                    test_binary.exec(&lctx, ctx.target_runner).await?;
+                                if let Some(cache) = list_cache {
+                                    cache.insert(&test_binary.binary_path, &non_ignored, &ignored);
+                                }
+                                (non_ignored, ignored)
+                            }
+                        };
                         let (bin, info) = Self::process_output(
                             test_binary,
                             filter,
@@ -535,35 +538,8 @@ impl<'g> TestList<'g> {
     pub(crate) fn create_dylib_path(
         rust_build_meta: &RustBuildMeta<TestListState>,
     ) -> Result<OsString, CreateTestListError> {
-        let dylib_path = dylib_path();
-        let dylib_path_is_empty = dylib_path.is_empty();
-        let new_paths = rust_build_meta.dylib_paths();
-
-        let mut updated_dylib_path: Vec<PathBuf> =
-            Vec::with_capacity(dylib_path.len() + new_paths.len());
-        updated_dylib_path.extend(
-            new_paths
-                .iter()
-                .map(|path| path.clone().into_std_path_buf()),
-        );
-        updated_dylib_path.extend(dylib_path);
-
-        // On macOS, these are the defaults when DYLD_FALLBACK_LIBRARY_PATH isn't set or set to an
-        // empty string. (This is relevant if nextest is invoked as its own process and not
-        // a Cargo subcommand.)
-        //
-        // This copies the logic from
-        // https://cs.github.com/rust-lang/cargo/blob/7d289b171183578d45dcabc56db6db44b9accbff/src/cargo/core/compiler/compilation.rs#L292.
-        if cfg!(target_os = "macos") && dylib_path_is_empty {
-            if let Some(home) = home::home_dir() {
-                updated_dylib_path.push(home.join("lib"));
-            }
-            updated_dylib_path.push("/usr/local/lib".into());
-            updated_dylib_path.push("/usr/lib".into());
-        }
-
-        std::env::join_paths(updated_dylib_path)
-            .map_err(move |error| CreateTestListError::dylib_join_paths(new_paths, error))
+        let existing = std::env::var_os(rust_build_meta.dylib_path_envvar());
+        rust_build_meta.dylib_path_with_existing(existing.as_deref())
     }
 
     fn process_output(
@@ -860,6 +836,7 @@ impl<'g> RustTestArtifact<'g> {
             &self.cwd,
             &self.package,
             &self.non_test_binaries,
+            self.build_platform,
         );
 
         let output =
@@ -1047,6 +1024,7 @@ impl<'a> TestInstance<'a> {
             &self.suite_info.cwd,
             &self.suite_info.package,
             &self.suite_info.non_test_binaries,
+            self.suite_info.build_platform,
         )
     }
 }
@@ -1154,6 +1132,8 @@ mod tests {
                 triple: fake_triple,
                 // Test an unavailable libdir.
                 libdir: PlatformLibdir::Unavailable(PlatformLibdirUnavailable::new_const("test")),
+                emulator_env: BTreeMap::new(),
+                sysroot: None,
             }),
         };
 
@@ -1266,9 +1246,23 @@ mod tests {
               "rust-build-meta": {
                 "target-directory": "/fake",
                 "base-output-directories": [],
+                "base-output-directory-categories": {},
                 "non-test-binaries": {},
                 "build-script-out-dirs": {},
                 "linked-paths": [],
+                "absolute-linked-paths": [],
+                "linked-path-requested-by": {},
+                "linked-libs": {},
+                "resolved-search-dirs": null,
+                "extra-output-roots": [],
+                "label": null,
+                "host-os-version": null,
+                "rust-version": null,
+                "list-command": null,
+                "built-at": null,
+                "debuginfo": null,
+                "incremental": null,
+                "enabled-features": {},
                 "platforms": {
                   "host": {
                     "platform": {
@@ -1289,7 +1283,9 @@ mod tests {
                       "libdir": {
                         "status": "unavailable",
                         "reason": "test"
-                      }
+                      },
+                      "emulator-env": {},
+                      "sysroot": null
                     }
                   ]
                 },
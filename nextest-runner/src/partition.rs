@@ -3,12 +3,22 @@
 
 //! Support for partitioning test runs across several machines.
 //!
-//! At the moment this only supports simple hash-based and count-based sharding. In the future it
-//! could potentially be made smarter: e.g. using data to pick different sets of binaries and tests
-//! to run, with an aim to minimize total build and test times.
+//! This supports simple hash-based and count-based sharding, as well as timing-based sharding
+//! that uses a previously recorded timing report to balance the expected time spent in each shard.
+//! In the future it could potentially be made smarter: e.g. using data to pick different sets of
+//! binaries to run, with an aim to minimize total build and test times.
 
-use crate::errors::PartitionerBuilderParseError;
-use std::{fmt, str::FromStr};
+use crate::errors::{PartitionTimingsReadError, PartitionerBuilderParseError};
+use camino::Utf8Path;
+use nextest_metadata::RustBinaryId;
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    fmt,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 use xxhash_rust::xxh64::xxh64;
 
 /// A builder for creating `Partitioner` instances.
@@ -28,6 +38,11 @@ pub enum PartitionerBuilder {
     },
 
     /// Partition based on hashing. Individual partitions are stateless.
+    ///
+    /// A test is assigned to a shard based on a stable hash of its name, so adding or removing
+    /// tests doesn't change the shard that other tests are assigned to. The hash is
+    /// [xxHash64](https://xxhash.com/) of the UTF-8 bytes of the test name, with a seed of 0. This
+    /// is fixed for the entire cargo-nextest 0.9 series.
     Hash {
         /// The shard this is in, counting up from 1.
         shard: u64,
@@ -35,6 +50,25 @@ pub enum PartitionerBuilder {
         /// The total number of shards.
         total_shards: u64,
     },
+
+    /// Partition based on previously recorded test durations.
+    ///
+    /// Tests with a recorded duration are assigned to shards ahead of time, so that each shard is
+    /// expected to take roughly the same amount of time. Tests without a recorded duration are
+    /// assigned round-robin within each test binary.
+    Timing {
+        /// The shard this is in, counting up from 1.
+        shard: u64,
+
+        /// The total number of shards, at most 65536 when parsed from a string.
+        total_shards: u64,
+
+        /// The shard assignments for tests with recorded durations.
+        ///
+        /// When parsed from a string, this doesn't have any recorded durations. Use
+        /// [`PartitionerBuilder::with_timings`] to provide them.
+        plan: Arc<TimingPartitionPlan>,
+    },
 }
 
 /// Represents an individual partitioner, typically scoped to a test binary.
@@ -44,9 +78,8 @@ pub trait Partitioner: fmt::Debug {
 }
 
 impl PartitionerBuilder {
-    /// Creates a new `Partitioner` from this `PartitionerBuilder`.
-    pub fn build(&self) -> Box<dyn Partitioner> {
-        // Note we don't use test_binary at the moment but might in the future.
+    /// Creates a new `Partitioner` for the given test binary from this `PartitionerBuilder`.
+    pub fn build(&self, binary_id: &RustBinaryId) -> Box<dyn Partitioner> {
         match self {
             PartitionerBuilder::Count {
                 shard,
@@ -56,6 +89,49 @@ impl PartitionerBuilder {
                 shard,
                 total_shards,
             } => Box::new(HashPartitioner::new(*shard, *total_shards)),
+            PartitionerBuilder::Timing { shard, plan, .. } => Box::new(TimingPartitioner::new(
+                *shard,
+                plan.clone(),
+                binary_id.clone(),
+            )),
+        }
+    }
+
+    /// For timing-based partitioning, returns a builder that assigns tests to shards using the
+    /// given recorded durations.
+    ///
+    /// Tests without a recorded duration are expected to take `default_duration`, or the average
+    /// recorded duration if that's `None`.
+    ///
+    /// Other kinds of partitioning are returned unchanged.
+    pub fn with_timings(
+        self,
+        timings: &PartitionTimings,
+        default_duration: Option<Duration>,
+    ) -> Self {
+        match self {
+            PartitionerBuilder::Timing {
+                shard,
+                total_shards,
+                ..
+            } => PartitionerBuilder::Timing {
+                shard,
+                total_shards,
+                plan: Arc::new(TimingPartitionPlan::new(
+                    total_shards,
+                    timings,
+                    default_duration,
+                )),
+            },
+            other => other,
+        }
+    }
+
+    /// Returns the timing plan, if this is timing-based partitioning.
+    pub fn timing_plan(&self) -> Option<&TimingPartitionPlan> {
+        match self {
+            PartitionerBuilder::Timing { plan, .. } => Some(plan),
+            PartitionerBuilder::Count { .. } | PartitionerBuilder::Hash { .. } => None,
         }
     }
 }
@@ -65,7 +141,27 @@ impl FromStr for PartitionerBuilder {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         // Parse the string: it looks like "hash:<shard>/<total_shards>".
-        if let Some(input) = s.strip_prefix("hash:") {
+        if let Some(input) = s.strip_prefix("timing:") {
+            let (shard, total_shards) = parse_shards(input, "timing:M/N")?;
+            // Timing-based partitioning tracks durations for every shard, so unlike the other
+            // partitioners the number of shards must be bounded.
+            if total_shards > MAX_TIMING_SHARDS {
+                return Err(PartitionerBuilderParseError::new(
+                    Some("timing:M/N"),
+                    format!("total shards {total_shards} must be at most {MAX_TIMING_SHARDS}"),
+                ));
+            }
+
+            Ok(PartitionerBuilder::Timing {
+                shard,
+                total_shards,
+                plan: Arc::new(TimingPartitionPlan::new(
+                    total_shards,
+                    &PartitionTimings::new(),
+                    None,
+                )),
+            })
+        } else if let Some(input) = s.strip_prefix("hash:") {
             let (shard, total_shards) = parse_shards(input, "hash:M/N")?;
 
             Ok(PartitionerBuilder::Hash {
@@ -82,12 +178,17 @@ impl FromStr for PartitionerBuilder {
         } else {
             Err(PartitionerBuilderParseError::new(
                 None,
-                format!("partition input '{s}' must begin with \"hash:\" or \"count:\""),
+                format!(
+                    "partition input '{s}' must begin with \"hash:\", \"count:\" or \"timing:\""
+                ),
             ))
         }
     }
 }
 
+/// The maximum number of shards supported by timing-based partitioning.
+const MAX_TIMING_SHARDS: u64 = 65536;
+
 fn parse_shards(
     input: &str,
     expected_format: &'static str,
@@ -156,6 +257,10 @@ impl Partitioner for CountPartitioner {
     }
 }
 
+/// The seed used by [`HashPartitioner`]. Changing this changes the shard every test is assigned
+/// to, so it must not be changed within a nextest version series.
+const HASH_PARTITION_SEED: u64 = 0;
+
 #[derive(Clone, Debug)]
 struct HashPartitioner {
     shard_minus_one: u64,
@@ -175,13 +280,271 @@ impl HashPartitioner {
 impl Partitioner for HashPartitioner {
     fn test_matches(&mut self, test_name: &str) -> bool {
         // NOTE: this is fixed to be xxhash64 for the entire cargo-nextest 0.9 series.
-        xxh64(test_name.as_bytes(), 0) % self.total_shards == self.shard_minus_one
+        xxh64(test_name.as_bytes(), HASH_PARTITION_SEED) % self.total_shards == self.shard_minus_one
+    }
+}
+
+/// Recorded test durations, used for timing-based partitioning.
+#[derive(Clone, Debug, Default)]
+pub struct PartitionTimings {
+    durations: BTreeMap<RustBinaryId, BTreeMap<String, Duration>>,
+}
+
+impl PartitionTimings {
+    /// Creates a new, empty set of timings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads timings from a timing report previously written by nextest.
+    ///
+    /// Fields in the report other than the binary ID, test name and duration are ignored.
+    pub fn read_report(path: &Utf8Path) -> Result<Self, PartitionTimingsReadError> {
+        let contents =
+            std::fs::read_to_string(path).map_err(|error| PartitionTimingsReadError::Read {
+                path: path.to_owned(),
+                error,
+            })?;
+        Self::parse_report(&contents).map_err(|error| PartitionTimingsReadError::Deserialize {
+            path: path.to_owned(),
+            error,
+        })
+    }
+
+    fn parse_report(contents: &str) -> Result<Self, serde_json::Error> {
+        let report: TimingReportSummary = serde_json::from_str(contents)?;
+        let mut timings = Self::new();
+        for entry in report.tests {
+            // Skip durations that can't be represented (e.g. negative ones), treating those tests
+            // as if they had no recorded timing.
+            if let Ok(duration) = Duration::try_from_secs_f64(entry.duration_secs) {
+                timings.insert(entry.binary_id, entry.test_name, duration);
+            }
+        }
+        Ok(timings)
+    }
+
+    /// Records the duration of a test, replacing any previously recorded duration.
+    pub fn insert(
+        &mut self,
+        binary_id: RustBinaryId,
+        test_name: impl Into<String>,
+        duration: Duration,
+    ) {
+        self.durations
+            .entry(binary_id)
+            .or_default()
+            .insert(test_name.into(), duration);
+    }
+
+    /// Returns the number of tests with recorded durations.
+    pub fn len(&self) -> usize {
+        self.durations.values().map(|tests| tests.len()).sum()
+    }
+
+    /// Returns true if there are no recorded durations.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// The subset of the timing report read by [`PartitionTimings::read_report`].
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct TimingReportSummary {
+    tests: Vec<TimingReportEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct TimingReportEntry {
+    binary_id: RustBinaryId,
+    test_name: String,
+    duration_secs: f64,
+}
+
+/// Shard assignments for timing-based partitioning, computed from [`PartitionTimings`].
+///
+/// Tests with a recorded duration are assigned greedily: starting with the slowest test, each test
+/// is placed into the shard with the least total predicted time so far. Ties are broken by binary
+/// ID and test name, so every machine computes the same assignments from the same timing report.
+#[derive(Debug)]
+pub struct TimingPartitionPlan {
+    total_shards: u64,
+    assignments: BTreeMap<RustBinaryId, BTreeMap<String, TimedTest>>,
+    recorded_durations: Vec<Duration>,
+    fallback_duration: Duration,
+    // The predicted duration of each shard, accumulated as partitioners see tests. This includes
+    // tests without recorded durations, which the plan doesn't know about ahead of time.
+    predicted_durations: Mutex<Vec<Duration>>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct TimedTest {
+    shard_minus_one: u64,
+    duration: Duration,
+}
+
+impl TimingPartitionPlan {
+    /// Computes shard assignments for `total_shards` shards.
+    ///
+    /// Tests without a recorded duration are expected to take `default_duration`, or the average
+    /// recorded duration if that's `None`.
+    pub fn new(
+        total_shards: u64,
+        timings: &PartitionTimings,
+        default_duration: Option<Duration>,
+    ) -> Self {
+        let mut tests: Vec<_> = timings
+            .durations
+            .iter()
+            .flat_map(|(binary_id, tests)| {
+                tests
+                    .iter()
+                    .map(move |(test_name, duration)| (binary_id, test_name, *duration))
+            })
+            .collect();
+        tests.sort_by(|a, b| {
+            b.2.cmp(&a.2)
+                .then_with(|| a.0.cmp(b.0))
+                .then_with(|| a.1.cmp(b.1))
+        });
+
+        let shard_count = usize::try_from(total_shards).expect("total shards fits in a usize");
+        let mut recorded_durations = vec![Duration::ZERO; shard_count];
+        let mut assignments: BTreeMap<RustBinaryId, BTreeMap<String, TimedTest>> = BTreeMap::new();
+        for &(binary_id, test_name, duration) in &tests {
+            // min_by_key returns the first shard among those with the least time.
+            let (shard_minus_one, shard_duration) = recorded_durations
+                .iter_mut()
+                .enumerate()
+                .min_by_key(|(_, shard_duration)| **shard_duration)
+                .expect("there is at least one shard");
+            *shard_duration += duration;
+            assignments.entry(binary_id.clone()).or_default().insert(
+                test_name.clone(),
+                TimedTest {
+                    shard_minus_one: shard_minus_one as u64,
+                    duration,
+                },
+            );
+        }
+
+        let fallback_duration = default_duration.unwrap_or_else(|| {
+            if tests.is_empty() {
+                Duration::ZERO
+            } else {
+                let total: Duration = tests.iter().map(|(_, _, duration)| *duration).sum();
+                total / u32::try_from(tests.len()).unwrap_or(u32::MAX)
+            }
+        });
+
+        Self {
+            total_shards,
+            assignments,
+            recorded_durations,
+            fallback_duration,
+            predicted_durations: Mutex::new(vec![Duration::ZERO; shard_count]),
+        }
+    }
+
+    /// Returns the total number of shards.
+    pub fn total_shards(&self) -> u64 {
+        self.total_shards
+    }
+
+    /// Returns the duration that tests without a recorded duration are expected to take.
+    pub fn fallback_duration(&self) -> Duration {
+        self.fallback_duration
+    }
+
+    /// Returns the total recorded duration of the tests assigned to each shard, indexed by shard
+    /// number minus one.
+    ///
+    /// This covers every test in the timing report, including ones that are no longer present or
+    /// were filtered out.
+    pub fn recorded_durations(&self) -> &[Duration] {
+        &self.recorded_durations
+    }
+
+    /// Returns the predicted duration of each shard, indexed by shard number minus one.
+    ///
+    /// This covers the tests that have been partitioned so far, so it's most useful after the test
+    /// list has been built. Tests without a recorded duration are predicted to take
+    /// [`Self::fallback_duration`].
+    pub fn predicted_durations(&self) -> Vec<Duration> {
+        self.predicted_durations
+            .lock()
+            .expect("predicted durations lock isn't poisoned")
+            .clone()
+    }
+
+    fn lookup(&self, binary_id: &RustBinaryId, test_name: &str) -> Option<TimedTest> {
+        self.assignments.get(binary_id)?.get(test_name).copied()
+    }
+
+    fn record(&self, shard_minus_one: u64, duration: Duration) {
+        let mut predicted_durations = self
+            .predicted_durations
+            .lock()
+            .expect("predicted durations lock isn't poisoned");
+        predicted_durations[shard_minus_one as usize] += duration;
+    }
+}
+
+// Predicted durations are runtime state, so they're not part of equality.
+impl PartialEq for TimingPartitionPlan {
+    fn eq(&self, other: &Self) -> bool {
+        self.total_shards == other.total_shards
+            && self.assignments == other.assignments
+            && self.fallback_duration == other.fallback_duration
+    }
+}
+
+impl Eq for TimingPartitionPlan {}
+
+#[derive(Debug)]
+struct TimingPartitioner {
+    shard_minus_one: u64,
+    plan: Arc<TimingPartitionPlan>,
+    binary_id: RustBinaryId,
+    curr: u64,
+}
+
+impl TimingPartitioner {
+    fn new(shard: u64, plan: Arc<TimingPartitionPlan>, binary_id: RustBinaryId) -> Self {
+        Self {
+            shard_minus_one: shard - 1,
+            plan,
+            binary_id,
+            curr: 0,
+        }
+    }
+}
+
+impl Partitioner for TimingPartitioner {
+    fn test_matches(&mut self, test_name: &str) -> bool {
+        let (shard_minus_one, duration) = match self.plan.lookup(&self.binary_id, test_name) {
+            Some(test) => (test.shard_minus_one, test.duration),
+            None => {
+                // This test isn't in the timing report, likely because it was added after the
+                // report was recorded. Assign it round-robin, like count-based partitioning.
+                let shard_minus_one = self.curr;
+                self.curr = (self.curr + 1) % self.plan.total_shards;
+                (shard_minus_one, self.plan.fallback_duration)
+            }
+        };
+        self.plan.record(shard_minus_one, duration);
+        shard_minus_one == self.shard_minus_one
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use indoc::indoc;
+    use std::collections::HashMap;
+    use test_case::test_case;
 
     #[test]
     fn partitioner_builder_from_str() {
@@ -207,6 +570,14 @@ mod tests {
                     total_shards: 200,
                 },
             ),
+            (
+                "timing:2/3",
+                PartitionerBuilder::Timing {
+                    shard: 2,
+                    total_shards: 3,
+                    plan: Arc::new(TimingPartitionPlan::new(3, &PartitionTimings::new(), None)),
+                },
+            ),
         ];
 
         let failures = vec![
@@ -220,6 +591,9 @@ mod tests {
             "hash:m/2",
             "hash:1/n",
             "hash:1/2/3",
+            "timing:0/2",
+            "timing:1",
+            "timing:1/1000000000000",
         ];
 
         for (input, output) in successes {
@@ -237,4 +611,233 @@ mod tests {
                 .expect_err(&format!("expected input '{input}' to fail"));
         }
     }
+
+    /// Returns the 1-based shard that `test_name` is assigned to by hash partitioning.
+    fn hash_shard(test_name: &str, total_shards: u64) -> u64 {
+        (1..=total_shards)
+            .find(|&shard| {
+                PartitionerBuilder::Hash {
+                    shard,
+                    total_shards,
+                }
+                .build(&RustBinaryId::new("my-binary"))
+                .test_matches(test_name)
+            })
+            .expect("every test is assigned to exactly one shard")
+    }
+
+    // These values are fixed for the entire cargo-nextest 0.9 series: if this test fails, hash
+    // partitioning has changed in a way that reshuffles tests across shards.
+    #[test_case("tests::test_parse", 1; "short name")]
+    #[test_case("tests::test_run_one", 3; "another short name")]
+    #[test_case("integration::basic::test_output", 2; "nested name")]
+    #[test_case(
+        "a_really_long_test_name_over_thirty_two_bytes::nested",
+        1;
+        "name longer than one xxhash stripe"
+    )]
+    fn hash_partition_stable(test_name: &str, expected_shard: u64) {
+        assert_eq!(hash_shard(test_name, 4), expected_shard);
+    }
+
+    #[test]
+    fn hash_partition_adding_tests() {
+        let existing: Vec<String> = (0..200).map(|i| format!("tests::test_{i}")).collect();
+        let added: Vec<String> = (0..50).map(|i| format!("new_tests::test_{i}")).collect();
+
+        for total_shards in [2, 3, 7] {
+            let before: Vec<u64> = existing
+                .iter()
+                .map(|name| hash_shard(name, total_shards))
+                .collect();
+
+            // Test names are interleaved so that any dependence on neighboring tests would show up.
+            let mut all: Vec<&str> = Vec::new();
+            for (i, name) in existing.iter().enumerate() {
+                all.push(name);
+                if let Some(new_name) = added.get(i) {
+                    all.push(new_name);
+                }
+            }
+
+            for shard in 1..=total_shards {
+                let mut partitioner = PartitionerBuilder::Hash {
+                    shard,
+                    total_shards,
+                }
+                .build(&RustBinaryId::new("my-binary"));
+                let matches: HashMap<&str, bool> = all
+                    .iter()
+                    .map(|&name| (name, partitioner.test_matches(name)))
+                    .collect();
+
+                for (name, &before_shard) in existing.iter().zip(&before) {
+                    assert_eq!(
+                        matches[name.as_str()],
+                        before_shard == shard,
+                        "for {total_shards} shards, adding tests doesn't move {name} \
+                         (previously in shard {before_shard}, checking shard {shard})",
+                    );
+                }
+            }
+        }
+    }
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    /// Returns, for each test in `tests`, whether it's in `shard` of `builder`'s shards.
+    fn timing_matches(
+        builder: &PartitionerBuilder,
+        shard: u64,
+        tests: &[(&str, &str)],
+    ) -> Vec<bool> {
+        let PartitionerBuilder::Timing {
+            total_shards, plan, ..
+        } = builder
+        else {
+            panic!("expected timing partitioner");
+        };
+        let builder = PartitionerBuilder::Timing {
+            shard,
+            total_shards: *total_shards,
+            plan: plan.clone(),
+        };
+        let mut partitioners: BTreeMap<&str, Box<dyn Partitioner>> = BTreeMap::new();
+        tests
+            .iter()
+            .map(|&(binary_id, test_name)| {
+                partitioners
+                    .entry(binary_id)
+                    .or_insert_with(|| builder.build(&RustBinaryId::new(binary_id)))
+                    .test_matches(test_name)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn timing_partition_balances_recorded_durations() {
+        let mut timings = PartitionTimings::new();
+        for (binary_id, test_name, duration) in [
+            ("bin-a", "slow", 10),
+            ("bin-a", "medium", 8),
+            ("bin-b", "slow", 9),
+            ("bin-b", "fast", 1),
+            ("bin-b", "quick", 2),
+            ("bin-c", "short", 3),
+        ] {
+            timings.insert(RustBinaryId::new(binary_id), test_name, secs(duration));
+        }
+        assert_eq!(timings.len(), 6);
+
+        let builder = PartitionerBuilder::from_str("timing:1/2")
+            .expect("valid partition")
+            .with_timings(&timings, None);
+        let plan = builder.timing_plan().expect("timing partitioner");
+
+        // Greedy assignment, slowest first: 10 -> 1, 9 -> 2, 8 -> 2, 3 -> 1, 2 -> 1, 1 -> 1.
+        assert_eq!(plan.recorded_durations(), &[secs(16), secs(17)]);
+        assert_eq!(plan.fallback_duration(), Duration::from_secs(33) / 6);
+
+        let tests = [
+            ("bin-a", "medium"),
+            ("bin-a", "slow"),
+            ("bin-b", "fast"),
+            ("bin-b", "quick"),
+            ("bin-b", "slow"),
+            ("bin-c", "short"),
+        ];
+        assert_eq!(
+            timing_matches(&builder, 1, &tests),
+            [false, true, true, true, false, true],
+        );
+        assert_eq!(
+            timing_matches(&builder, 2, &tests),
+            [true, false, false, false, true, false],
+        );
+    }
+
+    #[test]
+    fn timing_partition_unknown_tests() {
+        let mut timings = PartitionTimings::new();
+        timings.insert(RustBinaryId::new("bin-a"), "known", secs(6));
+        // This test is no longer present, but it still takes up time in shard 1.
+        timings.insert(RustBinaryId::new("bin-a"), "removed", secs(4));
+
+        let builder = PartitionerBuilder::from_str("timing:2/2")
+            .expect("valid partition")
+            .with_timings(&timings, Some(secs(2)));
+        let plan = builder.timing_plan().expect("timing partitioner");
+        assert_eq!(plan.recorded_durations(), &[secs(6), secs(4)]);
+        assert_eq!(plan.fallback_duration(), secs(2));
+
+        // Unknown tests are assigned round-robin within each binary.
+        let tests = [
+            ("bin-a", "new-1"),
+            ("bin-a", "known"),
+            ("bin-a", "new-2"),
+            ("bin-a", "new-3"),
+            ("bin-b", "new-4"),
+        ];
+        assert_eq!(
+            timing_matches(&builder, 2, &tests),
+            [false, false, true, false, false],
+        );
+
+        // Predictions only cover tests that were seen, with the fallback duration used for
+        // unknown tests.
+        assert_eq!(plan.predicted_durations(), [secs(6 + 2 + 2 + 2), secs(2)]);
+    }
+
+    #[test]
+    fn timing_partition_parse_report() {
+        let report = indoc! {r#"
+            {
+              "tests": [
+                {
+                  "binary-id": "my-package::my-test",
+                  "test-name": "tests::slow",
+                  "duration-secs": 12.5,
+                  "slow": true,
+                  "passed": true,
+                  "attempts": 1
+                },
+                {
+                  "binary-id": "my-package",
+                  "test-name": "tests::fast",
+                  "duration-secs": 0.25,
+                  "slow": false,
+                  "passed": false,
+                  "attempts": 2
+                },
+                {
+                  "binary-id": "my-package",
+                  "test-name": "tests::invalid",
+                  "duration-secs": -1.0,
+                  "slow": false,
+                  "passed": true,
+                  "attempts": 1
+                }
+              ]
+            }
+        "#};
+        let timings = PartitionTimings::parse_report(report).expect("valid report");
+
+        let mut expected = PartitionTimings::new();
+        expected.insert(
+            RustBinaryId::new("my-package::my-test"),
+            "tests::slow",
+            Duration::from_millis(12500),
+        );
+        expected.insert(
+            RustBinaryId::new("my-package"),
+            "tests::fast",
+            Duration::from_millis(250),
+        );
+        assert_eq!(timings.durations, expected.durations);
+
+        PartitionTimings::parse_report(r#"{"tests": [{"binary-id": "my-package"}]}"#)
+            .expect_err("missing fields are rejected");
+    }
 }
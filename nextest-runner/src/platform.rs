@@ -5,14 +5,26 @@
 
 use crate::{
     cargo_config::{CargoTargetArg, TargetTriple},
-    errors::{RustBuildMetaParseError, TargetTripleError, UnknownHostPlatform},
+    errors::{
+        BuildPlatformsCacheError, RustBuildMetaParseError, TargetTripleError, UnknownHostPlatform,
+        UnsupportedDylibPlatform,
+    },
     reuse_build::{LibdirMapper, PlatformLibdirMapper},
+    RustcCli,
 };
-use camino::{Utf8Path, Utf8PathBuf};
+use atomicwrites::{AtomicFile, OverwriteBehavior};
+use camino::{Utf8Component, Utf8Path, Utf8PathBuf};
 use nextest_metadata::{
     BuildPlatformsSummary, HostPlatformSummary, PlatformLibdirSummary, PlatformLibdirUnavailable,
     TargetPlatformSummary,
 };
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+    sync::{Mutex, MutexGuard, PoisonError},
+};
 use target_spec::summaries::PlatformSummary;
 pub use target_spec::Platform;
 
@@ -52,6 +64,338 @@ impl BuildPlatforms {
         }
     }
 
+    /// Returns the triple of the host platform, e.g. `x86_64-unknown-linux-gnu`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use nextest_metadata::PlatformLibdirUnavailable;
+    /// use nextest_runner::platform::{BuildPlatforms, HostPlatform, Platform, PlatformLibdir};
+    /// use target_spec::TargetFeatures;
+    ///
+    /// let build_platforms = BuildPlatforms {
+    ///     host: HostPlatform {
+    ///         platform: Platform::new("x86_64-unknown-linux-gnu", TargetFeatures::Unknown)
+    ///             .unwrap(),
+    ///         libdir: PlatformLibdir::Unavailable(PlatformLibdirUnavailable::RUSTC_FAILED),
+    ///     },
+    ///     target: None,
+    /// };
+    /// assert_eq!(build_platforms.host_triple_str(), "x86_64-unknown-linux-gnu");
+    /// ```
+    pub fn host_triple_str(&self) -> &str {
+        self.host.platform.triple_str()
+    }
+
+    /// Returns the triples of the platforms test binaries are built for.
+    ///
+    /// This is the triple of each target platform. If no target platform is specified, binaries
+    /// are built for the host, so this is the host triple. The result is never empty.
+    ///
+    /// At the moment, at most one target platform is supported. In the future, with multiple
+    /// targets, this will return the triple of each of them in order.
+    ///
+    /// # Examples
+    ///
+    /// With no target platform:
+    ///
+    /// ```
+    /// use nextest_metadata::PlatformLibdirUnavailable;
+    /// use nextest_runner::platform::{BuildPlatforms, HostPlatform, Platform, PlatformLibdir};
+    /// use target_spec::TargetFeatures;
+    ///
+    /// let build_platforms = BuildPlatforms {
+    ///     host: HostPlatform {
+    ///         platform: Platform::new("x86_64-unknown-linux-gnu", TargetFeatures::Unknown)
+    ///             .unwrap(),
+    ///         libdir: PlatformLibdir::Unavailable(PlatformLibdirUnavailable::RUSTC_FAILED),
+    ///     },
+    ///     target: None,
+    /// };
+    /// assert_eq!(build_platforms.target_triple_strs(), ["x86_64-unknown-linux-gnu"]);
+    /// ```
+    ///
+    /// With a target platform:
+    ///
+    /// ```
+    /// use nextest_metadata::PlatformLibdirUnavailable;
+    /// use nextest_runner::{
+    ///     cargo_config::TargetTriple,
+    ///     platform::{BuildPlatforms, HostPlatform, Platform, PlatformLibdir, TargetPlatform},
+    /// };
+    /// use target_spec::TargetFeatures;
+    ///
+    /// let target_triple =
+    ///     TargetTriple::deserialize_str(Some("aarch64-unknown-linux-gnu".to_owned()))
+    ///         .unwrap()
+    ///         .unwrap();
+    /// let build_platforms = BuildPlatforms {
+    ///     host: HostPlatform {
+    ///         platform: Platform::new("x86_64-unknown-linux-gnu", TargetFeatures::Unknown)
+    ///             .unwrap(),
+    ///         libdir: PlatformLibdir::Unavailable(PlatformLibdirUnavailable::RUSTC_FAILED),
+    ///     },
+    ///     target: Some(TargetPlatform::new(
+    ///         target_triple,
+    ///         PlatformLibdir::Unavailable(PlatformLibdirUnavailable::RUSTC_FAILED),
+    ///     )),
+    /// };
+    /// assert_eq!(build_platforms.host_triple_str(), "x86_64-unknown-linux-gnu");
+    /// assert_eq!(build_platforms.target_triple_strs(), ["aarch64-unknown-linux-gnu"]);
+    /// ```
+    pub fn target_triple_strs(&self) -> Vec<&str> {
+        match &self.target {
+            Some(target) => vec![target.triple.platform.triple_str()],
+            None => vec![self.host_triple_str()],
+        }
+    }
+
+    /// Returns the separator used to join dynamic library search paths for the platform test
+    /// binaries are built for: the target platform if specified, otherwise the host platform.
+    ///
+    /// This is `;` on Windows and `:` everywhere else. When running target binaries under an
+    /// emulator, the separator is determined by the target, not the machine nextest runs on.
+    pub fn path_separator(&self) -> char {
+        let triple_str = match &self.target {
+            Some(target) => target.triple.platform.triple_str(),
+            None => self.host.platform.triple_str(),
+        };
+        if is_windows_triple(triple_str) {
+            ';'
+        } else {
+            ':'
+        }
+    }
+
+    /// Returns the name of the dynamic library path environment variable for the host platform,
+    /// e.g. `LD_LIBRARY_PATH` on Linux.
+    ///
+    /// Operating systems without an explicit mapping fall back to `LD_LIBRARY_PATH`.
+    pub fn host_dylib_path_envvar(&self) -> &'static str {
+        dylib_path_envvar_for_triple(self.host.platform.triple_str()).unwrap_or("LD_LIBRARY_PATH")
+    }
+
+    /// Returns the name of the dynamic library path environment variable for the target platform,
+    /// or for the host platform if no target is specified.
+    ///
+    /// Operating systems without an explicit mapping fall back to `LD_LIBRARY_PATH`.
+    pub fn target_dylib_path_envvar(&self) -> &'static str {
+        match &self.target {
+            Some(target) => dylib_path_envvar_for_triple(target.triple.platform.triple_str())
+                .unwrap_or("LD_LIBRARY_PATH"),
+            None => self.host_dylib_path_envvar(),
+        }
+    }
+
+    /// Returns true if the host and target platforms use different dynamic library path
+    /// environment variables, e.g. a Windows target run under Wine on a Linux host.
+    ///
+    /// In that case, target binaries run under an emulator need the target's variable to be set.
+    pub fn dylib_path_envvars_differ(&self) -> bool {
+        self.host_dylib_path_envvar() != self.target_dylib_path_envvar()
+    }
+
+    /// Returns the rustc libdirs for the host and all target platforms that exist on disk, with
+    /// duplicates removed.
+    ///
+    /// This is the toolchain subset of
+    /// [`RustBuildMeta::dylib_paths`](crate::list::RustBuildMeta::dylib_paths), for callers that
+    /// handle toolchain libraries separately from build outputs. Unavailable libdirs are skipped.
+    pub fn all_libdirs(&self) -> Vec<Utf8PathBuf> {
+        let mut libdirs = Vec::new();
+        let candidates = std::iter::once(&self.host.libdir)
+            .chain(self.target.iter().map(|target| &target.libdir))
+            .filter_map(|libdir| libdir.as_path());
+        for libdir in candidates {
+            if libdir.is_dir() && !libdirs.iter().any(|existing| existing == libdir) {
+                libdirs.push(libdir.to_path_buf());
+            }
+        }
+        libdirs
+    }
+
+    /// Returns true if the host and target libdirs are different subdirectories of the same
+    /// parent directory, as with some multilib toolchains.
+    ///
+    /// See [`Self::libdir_shared_root`] for more.
+    pub fn targets_share_libdir_root(&self) -> bool {
+        self.libdir_shared_root().is_some()
+    }
+
+    /// Returns the parent directory shared by the host and target libdirs, if they're different
+    /// subdirectories of the same directory.
+    ///
+    /// On multilib toolchains, libraries for different architectures can be placed in sibling
+    /// directories (e.g. `/opt/toolchain/lib/64` and `/opt/toolchain/lib/32`). Adding the shared
+    /// parent to the dynamic library path would mix libraries for both architectures, so
+    /// [`RustBuildMeta::dylib_paths_for_platform`](crate::list::RustBuildMeta::dylib_paths_for_platform)
+    /// leaves it out.
+    ///
+    /// Returns `None` if there's no target platform, or either libdir is unavailable. With
+    /// multiple target platforms, this will consider the libdirs of all of them.
+    pub fn libdir_shared_root(&self) -> Option<&Utf8Path> {
+        let host_libdir = self.host.libdir.as_path()?;
+        let target_libdir = self.target.as_ref()?.libdir.as_path()?;
+        if host_libdir == target_libdir {
+            return None;
+        }
+        let host_parent = host_libdir.parent()?;
+        (Some(host_parent) == target_libdir.parent()).then_some(host_parent)
+    }
+
+    /// Fills in unavailable libdirs using the rustc sysroot.
+    ///
+    /// `rustc --print target-libdir` may fail, for example with some custom toolchains. In that
+    /// case, the libdir is `<sysroot>/lib/rustlib/<triple>/lib` if that directory exists. The
+    /// sysroot is obtained with `rustc --print sysroot` the first time it's needed, and cached
+    /// until [`clear_libdir_cache`] is called.
+    ///
+    /// Libdirs that are already available are left as-is. This should not be called for
+    /// platforms read from build metadata, since the libdirs there were detected at build time.
+    /// If the sysroot can't be determined, libdirs are left unavailable.
+    pub fn fill_libdirs_from_sysroot(&mut self) {
+        if self.host.libdir.as_path().is_none() {
+            if let Some(libdir) = sysroot_libdir(self.host.platform.triple_str()) {
+                self.host.libdir = PlatformLibdir::Available(libdir);
+            }
+        }
+        if let Some(target) = &mut self.target {
+            if target.libdir.as_path().is_none() {
+                if let Some(libdir) = sysroot_libdir(target.triple.platform.triple_str()) {
+                    target.libdir = PlatformLibdir::Available(libdir);
+                }
+            }
+        }
+    }
+
+    /// Returns true if a target platform is specified and its triple is different from the host's.
+    pub fn is_cross_compiling(&self) -> bool {
+        self.target.as_ref().is_some_and(|target| {
+            target.triple.platform.triple_str() != self.host.platform.triple_str()
+        })
+    }
+
+    /// Returns true if binaries built for the target platform can't run natively on the host, so
+    /// a target runner (e.g. an emulator) is required.
+    ///
+    /// This is [`Self::is_cross_compiling`], except for targets the host can run directly:
+    ///
+    /// * targets with the same architecture and operating system, but a different environment
+    ///   (e.g. `x86_64-unknown-linux-musl` on `x86_64-unknown-linux-gnu`)
+    /// * 32-bit x86 targets on 64-bit x86 hosts, other than on macOS
+    /// * `x86_64` macOS targets on `aarch64` macOS hosts, via Rosetta 2
+    /// * x86 Windows targets on `aarch64` Windows hosts, via Windows' built-in emulation
+    pub fn target_needs_runner(&self) -> bool {
+        let Some(target) = &self.target else {
+            return false;
+        };
+        if !self.is_cross_compiling() {
+            return false;
+        }
+
+        let host_triple = self.host.platform.triple_str();
+        let target_triple = target.triple.platform.triple_str();
+        let os = triple_os(host_triple);
+        if os != triple_os(target_triple) {
+            return true;
+        }
+        !arch_runs_natively(triple_arch(host_triple), triple_arch(target_triple), os)
+    }
+
+    /// Checks that nextest has an explicit dynamic library path environment variable mapping for
+    /// the operating systems of both the host and target platforms.
+    ///
+    /// By default, nextest falls back to `LD_LIBRARY_PATH` for operating systems it doesn't know
+    /// about. In strict mode, this is used to reject such platforms before tests are listed.
+    pub fn check_dylib_path_envvar(&self) -> Result<(), UnsupportedDylibPlatform> {
+        let host = ("host", self.host.platform.triple_str());
+        let target = self
+            .target
+            .as_ref()
+            .map(|target| ("target", target.triple.platform.triple_str()));
+
+        for (kind, triple_str) in std::iter::once(host).chain(target) {
+            if dylib_path_envvar_for_triple(triple_str).is_none() {
+                return Err(UnsupportedDylibPlatform {
+                    kind,
+                    triple: triple_str.to_owned(),
+                    os: triple_os(triple_str).to_owned(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Saves these build platforms, including detected libdirs, to a cache file at `path`.
+    ///
+    /// `toolchain_version` identifies the toolchain the platforms were detected with, typically
+    /// the output of [`RustcCli::version_verbose`](crate::RustcCli::version_verbose).
+    /// [`Self::load_cache`] only uses the cache if the toolchain version matches.
+    pub fn save_cache(
+        &self,
+        path: &Utf8Path,
+        toolchain_version: &str,
+    ) -> Result<(), BuildPlatformsCacheError> {
+        let cache = BuildPlatformsCache {
+            toolchain_version: toolchain_version.to_owned(),
+            platforms: self.to_summary(),
+        };
+        let contents =
+            serde_json::to_vec(&cache).expect("serializing BuildPlatformsCache should succeed");
+
+        let file = AtomicFile::new(path, OverwriteBehavior::AllowOverwrite);
+        file.write(|f| f.write_all(&contents))
+            .map_err(|error| BuildPlatformsCacheError::Write {
+                path: path.to_owned(),
+                error: match error {
+                    atomicwrites::Error::Internal(error) => error,
+                    atomicwrites::Error::User(error) => error,
+                },
+            })
+    }
+
+    /// Loads build platforms from a cache file written by [`Self::save_cache`].
+    ///
+    /// Returns `Ok(None)` if the cache file doesn't exist, was written for a different toolchain
+    /// version, or can't be parsed. In that case, the caller should detect the build platforms
+    /// and save them again.
+    pub fn load_cache(
+        path: &Utf8Path,
+        toolchain_version: &str,
+    ) -> Result<Option<Self>, BuildPlatformsCacheError> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(error) => {
+                return Err(BuildPlatformsCacheError::Read {
+                    path: path.to_owned(),
+                    error,
+                })
+            }
+        };
+
+        let cache: BuildPlatformsCache = match serde_json::from_str(&contents) {
+            Ok(cache) => cache,
+            Err(error) => {
+                log::debug!("ignoring unparseable build platforms cache `{path}`: {error}");
+                return Ok(None);
+            }
+        };
+        if cache.toolchain_version != toolchain_version {
+            log::debug!("ignoring build platforms cache `{path}` for a different toolchain");
+            return Ok(None);
+        }
+
+        match Self::from_summary(cache.platforms) {
+            Ok(platforms) => Ok(Some(platforms)),
+            Err(error) => {
+                log::debug!("ignoring invalid build platforms cache `{path}`: {error}");
+                Ok(None)
+            }
+        }
+    }
+
     /// Returns the argument to pass into `cargo metadata --filter-platform <triple>`.
     pub fn to_cargo_target_arg(&self) -> Result<CargoTargetArg, TargetTripleError> {
         match &self.target {
@@ -168,6 +512,230 @@ impl BuildPlatforms {
     }
 }
 
+/// Detects the rustc libdir for `triple`, or for the host platform if `triple` is `None`.
+///
+/// Results are cached for the lifetime of the process, so rustc is invoked at most once per
+/// triple. Call [`clear_libdir_cache`] to detect libdirs again, for example in a long-running
+/// process where the toolchain might change.
+pub fn detect_libdir(triple: Option<&TargetTriple>) -> PlatformLibdir {
+    let key = triple.map(|triple| triple.platform.triple_str().to_owned());
+    if let Some(libdir) = libdir_cache().libdirs.get(&key) {
+        return libdir.clone();
+    }
+
+    // rustc is invoked without holding the lock, so that lookups for other triples aren't
+    // blocked on it.
+    let rustc_output = match triple {
+        Some(triple) => RustcCli::print_target_libdir(triple).read(),
+        None => RustcCli::print_host_libdir().read(),
+    };
+    let libdir = PlatformLibdir::from_rustc_stdout(rustc_output);
+    libdir_cache().libdirs.entry(key).or_insert(libdir).clone()
+}
+
+/// Clears the cache used by [`detect_libdir`] and [`BuildPlatforms::fill_libdirs_from_sysroot`].
+pub fn clear_libdir_cache() {
+    let mut cache = libdir_cache();
+    cache.libdirs.clear();
+    cache.sysroot = None;
+}
+
+/// Cached results of invoking rustc, for [`detect_libdir`] and
+/// [`BuildPlatforms::fill_libdirs_from_sysroot`].
+struct LibdirCache {
+    /// Libdirs keyed by target triple, or `None` for the host platform.
+    libdirs: BTreeMap<Option<String>, PlatformLibdir>,
+
+    /// The rustc sysroot, if it has been looked up.
+    sysroot: Option<Option<Utf8PathBuf>>,
+}
+
+static LIBDIR_CACHE: Mutex<LibdirCache> = Mutex::new(LibdirCache {
+    libdirs: BTreeMap::new(),
+    sysroot: None,
+});
+
+fn libdir_cache() -> MutexGuard<'static, LibdirCache> {
+    // The cache is always left in a consistent state, so a poisoned lock can be ignored.
+    LIBDIR_CACHE.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Returns the libdir for `triple` within the rustc sysroot, if it exists.
+fn sysroot_libdir(triple: &str) -> Option<Utf8PathBuf> {
+    let cached = libdir_cache().sysroot.clone();
+    let sysroot = match cached {
+        Some(sysroot) => sysroot,
+        None => {
+            // The sysroot is printed in the same format as the libdir.
+            let sysroot = PlatformLibdir::from_rustc_stdout(RustcCli::print_host_sysroot().read())
+                .as_path()
+                .map(|sysroot| sysroot.to_path_buf());
+            libdir_cache().sysroot = Some(sysroot.clone());
+            sysroot
+        }
+    };
+    sysroot_libdir_in(sysroot.as_deref()?, triple)
+}
+
+fn sysroot_libdir_in(sysroot: &Utf8Path, triple: &str) -> Option<Utf8PathBuf> {
+    let libdir = sysroot.join("lib").join("rustlib").join(triple).join("lib");
+    if libdir.is_dir() {
+        Some(libdir)
+    } else {
+        log::debug!("libdir `{libdir}` derived from the rustc sysroot doesn't exist");
+        None
+    }
+}
+
+/// Returns the version of the host operating system component relevant to dynamic linking, if it
+/// can be determined.
+///
+/// This is the glibc version on Linux with glibc (e.g. `glibc 2.35`), and the OS version on macOS
+/// (e.g. `macOS 14.5`). Returns `None` on other platforms.
+pub fn detect_host_os_version() -> Option<String> {
+    cfg_if::cfg_if! {
+        if #[cfg(all(target_os = "linux", target_env = "gnu"))] {
+            // SAFETY: gnu_get_libc_version returns a pointer to a static NUL-terminated string.
+            let version = unsafe { std::ffi::CStr::from_ptr(libc::gnu_get_libc_version()) };
+            Some(format!("glibc {}", version.to_str().ok()?))
+        } else if #[cfg(target_os = "macos")] {
+            let output = std::process::Command::new("sw_vers")
+                .arg("-productVersion")
+                .output()
+                .ok()?;
+            if !output.status.success() {
+                return None;
+            }
+            let version = String::from_utf8(output.stdout).ok()?;
+            Some(format!("macOS {}", version.trim()))
+        } else {
+            None
+        }
+    }
+}
+
+/// Returns true if `current` is an older version of the same component as `recorded`.
+///
+/// Both are expected to be in the format returned by [`detect_host_os_version`]. Returns false if
+/// the versions can't be compared.
+pub(crate) fn is_older_os_version(current: &str, recorded: &str) -> bool {
+    let (Some((current_name, current_version)), Some((recorded_name, recorded_version))) =
+        (current.split_once(' '), recorded.split_once(' '))
+    else {
+        return false;
+    };
+    if current_name != recorded_name {
+        return false;
+    }
+
+    let parse = |version: &str| {
+        version
+            .split('.')
+            .map(|component| component.parse::<u64>())
+            .collect::<Result<Vec<_>, _>>()
+            .ok()
+    };
+    match (parse(current_version), parse(recorded_version)) {
+        (Some(current), Some(recorded)) => current < recorded,
+        _ => false,
+    }
+}
+
+/// Returns the version of rustc used for builds, as reported by `rustc -vV` (e.g. `1.80.0` or
+/// `1.81.0-nightly`).
+///
+/// This honors the `RUSTC` environment variable. Returns `None` if rustc couldn't be run or its
+/// output couldn't be parsed.
+pub fn detect_rustc_version() -> Option<String> {
+    let output = RustcCli::version_verbose().read()?;
+    parse_rustc_release(std::str::from_utf8(&output).ok()?)
+}
+
+/// Returns the `release` line from the output of `rustc -vV`.
+fn parse_rustc_release(version_verbose: &str) -> Option<String> {
+    version_verbose
+        .lines()
+        .find_map(|line| line.strip_prefix("release:"))
+        .map(|release| release.trim().to_owned())
+        .filter(|release| !release.is_empty())
+}
+
+/// Returns true if `current` is an older rustc version than `recorded`.
+///
+/// Both are expected to be in the format returned by [`detect_rustc_version`]. Returns false if
+/// the versions can't be compared.
+pub(crate) fn is_older_rustc_version(current: &str, recorded: &str) -> bool {
+    match (Version::parse(current), Version::parse(recorded)) {
+        (Ok(current), Ok(recorded)) => current < recorded,
+        _ => false,
+    }
+}
+
+fn is_windows_triple(triple_str: &str) -> bool {
+    triple_str
+        .split('-')
+        .any(|component| component == "windows")
+}
+
+/// Returns the dynamic library path environment variable for the given triple, or `None` if the
+/// operating system isn't one nextest has an explicit mapping for.
+///
+/// This mirrors `helpers::dylib_path_envvar`, which is based on the platform nextest was compiled
+/// for.
+fn dylib_path_envvar_for_triple(triple_str: &str) -> Option<&'static str> {
+    triple_str.split('-').find_map(|component| match component {
+        "windows" => Some("PATH"),
+        "darwin" | "macos" | "ios" | "tvos" | "watchos" | "visionos" => {
+            Some("DYLD_FALLBACK_LIBRARY_PATH")
+        }
+        "linux" | "android" | "freebsd" | "netbsd" | "openbsd" | "dragonfly" | "solaris"
+        | "illumos" => Some("LD_LIBRARY_PATH"),
+        _ => None,
+    })
+}
+
+/// Returns true if a host with architecture `host_arch` can run binaries for `target_arch`, both
+/// on the operating system `os`.
+fn arch_runs_natively(host_arch: &str, target_arch: &str, os: &str) -> bool {
+    if host_arch == target_arch {
+        return true;
+    }
+    match (host_arch, target_arch) {
+        // macOS dropped support for 32-bit binaries in Catalina.
+        ("x86_64", "i686" | "i586" | "i386") => os != "darwin",
+        // Rosetta 2 on macOS, and built-in emulation on Windows.
+        ("aarch64", "x86_64") => os == "darwin" || os == "windows",
+        ("aarch64", "i686") => os == "windows",
+        _ => false,
+    }
+}
+
+/// Returns the architecture component of a triple.
+fn triple_arch(triple_str: &str) -> &str {
+    triple_str.split('-').next().unwrap_or(triple_str)
+}
+
+/// Returns the operating system component of a triple, on a best-effort basis.
+///
+/// Triples are of the form `arch-vendor-os[-env]`, though the vendor is sometimes omitted (e.g.
+/// `aarch64-linux-android`).
+fn triple_os(triple_str: &str) -> &str {
+    let components: Vec<_> = triple_str.split('-').collect();
+    match components.len() {
+        0 | 1 => triple_str,
+        2 => components[1],
+        _ => components[2],
+    }
+}
+
+/// The on-disk format for [`BuildPlatforms::save_cache`].
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct BuildPlatformsCache {
+    toolchain_version: String,
+    platforms: BuildPlatformsSummary,
+}
+
 /// A representation of a host platform during a build.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct HostPlatform {
@@ -221,12 +789,42 @@ pub struct TargetPlatform {
 
     /// The target libdir.
     pub libdir: PlatformLibdir,
+
+    /// Environment variables required by the emulator used to run binaries for this target, e.g.
+    /// `QEMU_LD_PREFIX`.
+    ///
+    /// These are set for target binaries when running tests. Empty by default.
+    pub emulator_env: BTreeMap<String, String>,
+
+    /// The sysroot for the target platform, if detected.
+    ///
+    /// This is exposed to target binaries as `NEXTEST_TARGET_SYSROOT`, for use by emulators that
+    /// need to be pointed at it. `None` by default.
+    pub sysroot: Option<Utf8PathBuf>,
 }
 
 impl TargetPlatform {
     /// Creates a new [`TargetPlatform`].
     pub fn new(triple: TargetTriple, libdir: PlatformLibdir) -> Self {
-        Self { triple, libdir }
+        Self {
+            triple,
+            libdir,
+            emulator_env: BTreeMap::new(),
+            sysroot: None,
+        }
+    }
+
+    /// Sets the environment variables required by the emulator used to run binaries for this
+    /// target.
+    pub fn with_emulator_env(mut self, emulator_env: BTreeMap<String, String>) -> Self {
+        self.emulator_env = emulator_env;
+        self
+    }
+
+    /// Sets the sysroot for this target.
+    pub fn with_sysroot(mut self, sysroot: Option<Utf8PathBuf>) -> Self {
+        self.sysroot = sysroot;
+        self
     }
 
     /// Converts self to a summary.
@@ -234,6 +832,8 @@ impl TargetPlatform {
         TargetPlatformSummary {
             platform: self.triple.platform.to_summary(),
             libdir: self.libdir.to_summary(),
+            emulator_env: self.emulator_env.clone(),
+            sysroot: self.sysroot.clone(),
         }
     }
 
@@ -244,6 +844,8 @@ impl TargetPlatform {
                 .map_err(RustBuildMetaParseError::PlatformDeserializeError)?
                 .expect("the input is not None, so the output must not be None"),
             libdir: PlatformLibdir::from_summary(summary.libdir),
+            emulator_env: summary.emulator_env,
+            sysroot: summary.sysroot,
         })
     }
 
@@ -251,6 +853,8 @@ impl TargetPlatform {
         Self {
             triple: self.triple.clone(),
             libdir: mapper.map(&self.libdir),
+            emulator_env: self.emulator_env.clone(),
+            sysroot: self.sysroot.clone(),
         }
     }
 }
@@ -279,8 +883,15 @@ impl PlatformLibdir {
             let v = v.ok_or(PlatformLibdirUnavailable::RUSTC_FAILED)?;
 
             let s = String::from_utf8(v).map_err(|e| {
-                log::debug!("failed to convert the output to a string: {e}");
-                PlatformLibdirUnavailable::RUSTC_OUTPUT_ERROR
+                // Paths in metadata must be valid UTF-8, so the libdir can't be used. Report it
+                // with a lossy rendering so that users can tell which path was skipped.
+                log::warn!(
+                    target: "nextest-runner::list",
+                    "rustc libdir isn't valid UTF-8, so it won't be added to the dynamic \
+                     library path: {}",
+                    String::from_utf8_lossy(e.as_bytes()).trim(),
+                );
+                PlatformLibdirUnavailable::NON_UTF8_PATH
             })?;
 
             let mut lines = s.lines();
@@ -325,6 +936,24 @@ impl PlatformLibdir {
         }
     }
 
+    /// Returns the name of the toolchain this libdir belongs to, if it can be determined.
+    ///
+    /// Libdirs in toolchains installed by rustup are of the form
+    /// `<toolchain>/lib/rustlib/<triple>/lib`, where `<toolchain>` is e.g.
+    /// `nightly-2024-01-01-x86_64-unknown-linux-gnu`. This returns `<toolchain>` for such paths,
+    /// and `None` if the libdir is unavailable or doesn't follow this layout.
+    pub fn toolchain_name(&self) -> Option<&str> {
+        let components: Vec<_> = self.as_path()?.components().collect();
+        components.windows(3).find_map(|window| match window {
+            [
+                Utf8Component::Normal(toolchain),
+                Utf8Component::Normal("lib"),
+                Utf8Component::Normal("rustlib"),
+            ] => Some(*toolchain),
+            _ => None,
+        })
+    }
+
     /// Converts self to a summary.
     pub fn to_summary(&self) -> PlatformLibdirSummary {
         match self {
@@ -347,8 +976,295 @@ impl PlatformLibdir {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use target_spec::TargetFeatures;
     use test_case::test_case;
 
+    fn build_platforms(host: &str, target: Option<&str>) -> BuildPlatforms {
+        BuildPlatforms {
+            host: HostPlatform {
+                platform: Platform::new(host.to_owned(), TargetFeatures::Unknown)
+                    .expect("host triple should be valid"),
+                libdir: PlatformLibdir::Unavailable(PlatformLibdirUnavailable::new_const("test")),
+            },
+            target: target.map(|target| {
+                TargetPlatform::new(
+                    TargetTriple::deserialize_str(Some(target.to_owned()))
+                        .expect("target triple should be valid")
+                        .expect("the output of deserialize_str shouldn't be None"),
+                    PlatformLibdir::Unavailable(PlatformLibdirUnavailable::new_const("test")),
+                )
+            }),
+        }
+    }
+
+    #[test_case("x86_64-unknown-linux-gnu", None, ':'; "linux host")]
+    #[test_case("x86_64-pc-windows-msvc", None, ';'; "windows host")]
+    #[test_case("x86_64-unknown-linux-gnu", Some("x86_64-pc-windows-gnu"), ';'; "windows target on linux")]
+    #[test_case("x86_64-pc-windows-msvc", Some("aarch64-unknown-linux-gnu"), ':'; "linux target on windows")]
+    fn test_path_separator(host: &str, target: Option<&str>, expected: char) {
+        assert_eq!(build_platforms(host, target).path_separator(), expected);
+    }
+
+    #[test_case("x86_64-unknown-linux-gnu", None, "LD_LIBRARY_PATH", false; "linux host")]
+    #[test_case(
+        "x86_64-unknown-linux-gnu",
+        Some("aarch64-unknown-linux-gnu"),
+        "LD_LIBRARY_PATH",
+        false;
+        "linux target on linux"
+    )]
+    #[test_case(
+        "x86_64-unknown-linux-gnu",
+        Some("x86_64-pc-windows-gnu"),
+        "PATH",
+        true;
+        "windows target on linux"
+    )]
+    #[test_case(
+        "aarch64-apple-darwin",
+        Some("x86_64-unknown-linux-gnu"),
+        "LD_LIBRARY_PATH",
+        true;
+        "linux target on macos"
+    )]
+    fn test_target_dylib_path_envvar(
+        host: &str,
+        target: Option<&str>,
+        expected: &str,
+        expected_differ: bool,
+    ) {
+        let build_platforms = build_platforms(host, target);
+        assert_eq!(build_platforms.target_dylib_path_envvar(), expected);
+        assert_eq!(build_platforms.dylib_path_envvars_differ(), expected_differ);
+    }
+
+    #[test_case("x86_64-unknown-linux-gnu", Some("LD_LIBRARY_PATH"); "linux")]
+    #[test_case("aarch64-linux-android", Some("LD_LIBRARY_PATH"); "android without vendor")]
+    #[test_case("x86_64-unknown-illumos", Some("LD_LIBRARY_PATH"); "illumos")]
+    #[test_case("aarch64-apple-darwin", Some("DYLD_FALLBACK_LIBRARY_PATH"); "macos")]
+    #[test_case("x86_64-pc-windows-msvc", Some("PATH"); "windows")]
+    #[test_case("wasm32-unknown-unknown", None; "wasm")]
+    #[test_case("x86_64-unknown-haiku", None; "haiku")]
+    fn test_dylib_path_envvar_for_triple(triple_str: &str, expected: Option<&str>) {
+        assert_eq!(dylib_path_envvar_for_triple(triple_str), expected);
+    }
+
+    #[test_case("x86_64-unknown-linux-gnu", None, false; "no target")]
+    #[test_case("x86_64-unknown-linux-gnu", Some("x86_64-unknown-linux-gnu"), false; "same triple")]
+    #[test_case("x86_64-unknown-linux-gnu", Some("x86_64-unknown-linux-musl"), false; "different env")]
+    #[test_case("x86_64-unknown-linux-gnu", Some("i686-unknown-linux-gnu"), false; "32-bit x86 on linux")]
+    #[test_case("x86_64-unknown-linux-gnu", Some("aarch64-unknown-linux-gnu"), true; "different arch")]
+    #[test_case("x86_64-unknown-linux-gnu", Some("x86_64-pc-windows-gnu"), true; "different os")]
+    #[test_case("aarch64-apple-darwin", Some("x86_64-apple-darwin"), false; "rosetta")]
+    #[test_case("x86_64-apple-darwin", Some("aarch64-apple-darwin"), true; "arm on intel mac")]
+    #[test_case("aarch64-pc-windows-msvc", Some("x86_64-pc-windows-msvc"), false; "windows on arm")]
+    fn test_target_needs_runner(host: &str, target: Option<&str>, expected: bool) {
+        assert_eq!(
+            build_platforms(host, target).target_needs_runner(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_all_libdirs() {
+        let tmpdir = camino_tempfile::tempdir().expect("should create temp dir successfully");
+        let libdir = tmpdir.path().to_path_buf();
+
+        let mut platforms =
+            build_platforms("x86_64-unknown-linux-gnu", Some("x86_64-unknown-linux-gnu"));
+        assert_eq!(
+            platforms.all_libdirs(),
+            Vec::<Utf8PathBuf>::new(),
+            "unavailable"
+        );
+
+        platforms.host.libdir = PlatformLibdir::Available(libdir.clone());
+        let target = platforms.target.as_mut().expect("target is specified");
+        target.libdir = PlatformLibdir::Available(libdir.clone());
+        assert_eq!(
+            platforms.all_libdirs(),
+            vec![libdir.clone()],
+            "deduplicated"
+        );
+
+        platforms.host.libdir = PlatformLibdir::Available(libdir.join("missing"));
+        assert_eq!(
+            platforms.all_libdirs(),
+            vec![libdir],
+            "missing libdirs are skipped"
+        );
+    }
+
+    #[test]
+    fn test_detect_libdir_cache() {
+        let libdir = detect_libdir(None);
+        assert!(
+            libdir.as_path().is_some(),
+            "host libdir should be detected: {libdir:?}"
+        );
+        assert!(
+            libdir_cache().libdirs.contains_key(&None),
+            "host libdir should be cached"
+        );
+        assert_eq!(detect_libdir(None), libdir);
+
+        clear_libdir_cache();
+        assert!(
+            !libdir_cache().libdirs.contains_key(&None),
+            "cache should be cleared"
+        );
+        assert_eq!(detect_libdir(None), libdir, "detecting again should agree");
+    }
+
+    #[test]
+    fn test_libdir_shared_root() {
+        let mut platforms =
+            build_platforms("x86_64-unknown-linux-gnu", Some("i686-unknown-linux-gnu"));
+        assert_eq!(platforms.libdir_shared_root(), None, "libdirs unavailable");
+
+        platforms.host.libdir = PlatformLibdir::Available("/opt/toolchain/lib/64".into());
+        let target = platforms.target.as_mut().expect("target is specified");
+        target.libdir = PlatformLibdir::Available("/opt/toolchain/lib/32".into());
+        assert_eq!(
+            platforms.libdir_shared_root(),
+            Some(Utf8Path::new("/opt/toolchain/lib"))
+        );
+        assert!(platforms.targets_share_libdir_root());
+
+        let target = platforms.target.as_mut().expect("target is specified");
+        target.libdir = PlatformLibdir::Available("/opt/toolchain/lib/64".into());
+        assert_eq!(
+            platforms.libdir_shared_root(),
+            None,
+            "identical libdirs don't have a shared root"
+        );
+
+        let target = platforms.target.as_mut().expect("target is specified");
+        target.libdir =
+            PlatformLibdir::Available("/rustup/toolchains/stable/lib/rustlib/i686/lib".into());
+        platforms.host.libdir =
+            PlatformLibdir::Available("/rustup/toolchains/stable/lib/rustlib/x86_64/lib".into());
+        assert_eq!(
+            platforms.libdir_shared_root(),
+            None,
+            "rustup libdirs have different parents"
+        );
+    }
+
+    #[test]
+    fn test_sysroot_libdir_in() {
+        let tmpdir = camino_tempfile::tempdir().expect("should create temp dir successfully");
+        let libdir = tmpdir
+            .path()
+            .join("lib/rustlib/x86_64-unknown-linux-gnu/lib");
+        std::fs::create_dir_all(&libdir).expect("should create libdir successfully");
+
+        assert_eq!(
+            sysroot_libdir_in(tmpdir.path(), "x86_64-unknown-linux-gnu"),
+            Some(libdir)
+        );
+        assert_eq!(
+            sysroot_libdir_in(tmpdir.path(), "aarch64-unknown-linux-gnu"),
+            None,
+            "libdirs for triples not installed in the sysroot are skipped"
+        );
+    }
+
+    #[test]
+    fn test_build_platforms_cache() {
+        let tmpdir = camino_tempfile::tempdir().expect("should create temp dir successfully");
+        let path = tmpdir.path().join("build-platforms.json");
+
+        assert_eq!(
+            BuildPlatforms::load_cache(&path, "rustc 1.81.0")
+                .expect("a missing cache is not an error"),
+            None,
+        );
+
+        let platforms = BuildPlatforms {
+            host: HostPlatform {
+                platform: Platform::new("x86_64-unknown-linux-gnu", TargetFeatures::Unknown)
+                    .expect("host triple should be valid"),
+                libdir: PlatformLibdir::Available("/fake/host/libdir".into()),
+            },
+            target: None,
+        };
+        platforms
+            .save_cache(&path, "rustc 1.81.0")
+            .expect("saving the cache should succeed");
+
+        assert_eq!(
+            BuildPlatforms::load_cache(&path, "rustc 1.81.0").expect("loading should succeed"),
+            Some(platforms),
+        );
+        assert_eq!(
+            BuildPlatforms::load_cache(&path, "rustc 1.82.0").expect("loading should succeed"),
+            None,
+            "a different toolchain version invalidates the cache",
+        );
+    }
+
+    #[test_case("glibc 2.31", "glibc 2.35", true; "older glibc")]
+    #[test_case("glibc 2.35", "glibc 2.35", false; "same glibc")]
+    #[test_case("glibc 2.39", "glibc 2.35", false; "newer glibc")]
+    #[test_case("macOS 13.6.1", "macOS 14.5", true; "older macOS")]
+    #[test_case("macOS 14.5", "glibc 2.35", false; "different components")]
+    #[test_case("glibc unknown", "glibc 2.35", false; "unparseable")]
+    fn test_is_older_os_version(current: &str, recorded: &str, expected: bool) {
+        assert_eq!(is_older_os_version(current, recorded), expected);
+    }
+
+    #[test]
+    fn test_parse_rustc_release() {
+        let output = "rustc 1.80.0 (051478957 2024-07-21)\n\
+                      binary: rustc\n\
+                      commit-hash: 051478957371ee0084a7c0913941d2a8c4757bb9\n\
+                      host: x86_64-unknown-linux-gnu\n\
+                      release: 1.80.0\n\
+                      LLVM version: 18.1.7\n";
+        assert_eq!(parse_rustc_release(output).as_deref(), Some("1.80.0"));
+        assert_eq!(parse_rustc_release("rustc 1.80.0\n"), None);
+        assert_eq!(parse_rustc_release("release: \n"), None);
+    }
+
+    #[test_case("1.79.0", "1.80.0", true; "older stable")]
+    #[test_case("1.80.0", "1.80.0", false; "same stable")]
+    #[test_case("1.81.0", "1.80.0", false; "newer stable")]
+    #[test_case("1.81.0-nightly", "1.81.0", true; "nightly of the same version")]
+    #[test_case("1.80.0", "1.81.0-nightly", true; "older stable than nightly")]
+    #[test_case("unknown", "1.80.0", false; "unparseable")]
+    fn test_is_older_rustc_version(current: &str, recorded: &str, expected: bool) {
+        assert_eq!(is_older_rustc_version(current, recorded), expected);
+    }
+
+    #[test_case(
+        "/home/user/.rustup/toolchains/nightly-2024-01-01-x86_64-unknown-linux-gnu/lib/rustlib/x86_64-unknown-linux-gnu/lib",
+        Some("nightly-2024-01-01-x86_64-unknown-linux-gnu");
+        "rustup"
+    )]
+    #[test_case("/usr/lib/rustlib/x86_64-unknown-linux-gnu/lib", Some("usr"); "system install")]
+    #[test_case("/fake/libdir", None; "non-standard layout")]
+    fn test_libdir_toolchain_name(libdir: &str, expected: Option<&str>) {
+        assert_eq!(
+            PlatformLibdir::Available(libdir.into()).toolchain_name(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_check_dylib_path_envvar() {
+        build_platforms("x86_64-unknown-linux-gnu", Some("aarch64-apple-darwin"))
+            .check_dylib_path_envvar()
+            .expect("linux host and macOS target are supported");
+
+        let error = build_platforms("x86_64-unknown-linux-gnu", Some("x86_64-unknown-haiku"))
+            .check_dylib_path_envvar()
+            .expect_err("haiku target is unsupported");
+        assert_eq!(error.kind, "target");
+        assert_eq!(error.triple, "x86_64-unknown-haiku");
+        assert_eq!(error.os, "haiku");
+    }
+
     #[test]
     fn test_from_rustc_output_invalid() {
         // None.
@@ -374,6 +1290,12 @@ mod tests {
             PlatformLibdir::from_rustc_stdout(Some(b"/fake/libdir/1\n/fake/libdir/2\n".to_vec())),
             PlatformLibdir::Unavailable(PlatformLibdirUnavailable::RUSTC_OUTPUT_ERROR),
         );
+
+        // Non-UTF-8 path.
+        assert_eq!(
+            PlatformLibdir::from_rustc_stdout(Some(b"/fake/libdir/\xff\n".to_vec())),
+            PlatformLibdir::Unavailable(PlatformLibdirUnavailable::NON_UTF8_PATH),
+        );
     }
 
     #[test_case(b"/fake/libdir/22548", "/fake/libdir/22548"; "single line")]
@@ -3,6 +3,9 @@
 
 //! Metadata management.
 
+mod tap;
+mod timing;
+
 use super::TestEvent;
 use crate::{
     config::{NextestJunitConfig, NextestProfile},
@@ -26,6 +29,8 @@ pub(crate) struct EventAggregator<'cfg> {
     // TODO: log information in a JSONable report (converting that to XML later) instead of directly
     // writing it to XML
     junit: Option<MetadataJunit<'cfg>>,
+    tap: Option<tap::MetadataTap>,
+    timing: Option<timing::MetadataTiming>,
 }
 
 impl<'cfg> EventAggregator<'cfg> {
@@ -33,10 +38,18 @@ impl<'cfg> EventAggregator<'cfg> {
         Self {
             store_dir: profile.store_dir().to_owned(),
             junit: profile.junit().map(MetadataJunit::new),
+            tap: profile.tap().map(tap::MetadataTap::new),
+            timing: profile.timing().map(timing::MetadataTiming::new),
         }
     }
 
     pub(crate) fn write_event(&mut self, event: TestEvent<'cfg>) -> Result<(), WriteEventError> {
+        if let Some(tap) = &mut self.tap {
+            tap.write_event(&event)?;
+        }
+        if let Some(timing) = &mut self.timing {
+            timing.write_event(&event)?;
+        }
         if let Some(junit) = &mut self.junit {
             junit.write_event(event)?;
         }
@@ -79,46 +92,6 @@ impl<'cfg> MetadataJunit<'cfg> {
                 junit_store_failure_output,
                 ..
             } => {
-                fn kind_ty(run_status: &ExecuteStatus) -> (NonSuccessKind, Cow<'static, str>) {
-                    match run_status.result {
-                        ExecutionResult::Fail {
-                            abort_status: Some(_),
-                            leaked: true,
-                        } => (
-                            NonSuccessKind::Failure,
-                            "test abort with leaked handles".into(),
-                        ),
-                        ExecutionResult::Fail {
-                            abort_status: Some(_),
-                            leaked: false,
-                        } => (NonSuccessKind::Failure, "test abort".into()),
-                        ExecutionResult::Fail {
-                            abort_status: None,
-                            leaked: true,
-                        } => (
-                            NonSuccessKind::Failure,
-                            "test failure with leaked handles".into(),
-                        ),
-                        ExecutionResult::Fail {
-                            abort_status: None,
-                            leaked: false,
-                        } => (NonSuccessKind::Failure, "test failure".into()),
-                        ExecutionResult::Timeout => {
-                            (NonSuccessKind::Failure, "test timeout".into())
-                        }
-                        ExecutionResult::ExecFail => {
-                            (NonSuccessKind::Error, "execution failure".into())
-                        }
-                        ExecutionResult::Leak => (
-                            NonSuccessKind::Error,
-                            "test passed but leaked handles".into(),
-                        ),
-                        ExecutionResult::Pass => {
-                            unreachable!("this is a failure status")
-                        }
-                    }
-                }
-
                 let testsuite = self.testsuite_for(test_instance);
 
                 let (mut testcase_status, main_status, reruns) = match run_statuses.describe() {
@@ -241,6 +214,42 @@ impl<'cfg> MetadataJunit<'cfg> {
     }
 }
 
+fn kind_ty(run_status: &ExecuteStatus) -> (NonSuccessKind, Cow<'static, str>) {
+    match run_status.result {
+        ExecutionResult::Fail {
+            abort_status: Some(_),
+            leaked: true,
+        } => (
+            NonSuccessKind::Failure,
+            "test abort with leaked handles".into(),
+        ),
+        ExecutionResult::Fail {
+            abort_status: Some(_),
+            leaked: false,
+        } => (NonSuccessKind::Failure, "test abort".into()),
+        ExecutionResult::Fail {
+            abort_status: None,
+            leaked: true,
+        } => (
+            NonSuccessKind::Failure,
+            "test failure with leaked handles".into(),
+        ),
+        ExecutionResult::Fail {
+            abort_status: None,
+            leaked: false,
+        } => (NonSuccessKind::Failure, "test failure".into()),
+        ExecutionResult::Timeout => (NonSuccessKind::Failure, "test timeout".into()),
+        ExecutionResult::ExecFail => (NonSuccessKind::Error, "execution failure".into()),
+        ExecutionResult::Leak => (
+            NonSuccessKind::Error,
+            "test passed but leaked handles".into(),
+        ),
+        ExecutionResult::Pass => {
+            unreachable!("this is a failure status")
+        }
+    }
+}
+
 enum TestcaseOrRerun<'a> {
     Testcase(&'a mut TestCase),
     Rerun(&'a mut TestRerun),
@@ -0,0 +1,323 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! TAP (Test Anything Protocol) output support.
+//!
+//! The report follows [TAP version 13](https://testanything.org/tap-version-13-specification.html).
+//! Each test is a test point named `<binary-id> <test-name>`. Failing tests have a YAML
+//! diagnostic block with their captured output, skipped tests use the `SKIP` directive, and tests
+//! that passed after being retried are followed by a comment.
+//!
+//! Like JUnit, the report is written out once the run finishes, since the plan line (`1..N`) goes
+//! at the top.
+
+use super::kind_ty;
+use crate::{
+    config::NextestTapConfig,
+    errors::WriteEventError,
+    list::TestInstance,
+    reporter::{TestEvent, TestEventKind},
+    runner::{ExecuteStatus, ExecutionDescription, ExecutionResult},
+    test_output::{TestExecutionOutput, TestOutput},
+};
+use std::fmt::Write as _;
+
+#[derive(Clone, Debug)]
+pub(super) struct MetadataTap {
+    config: NextestTapConfig,
+    test_points: Vec<TapTestPoint>,
+}
+
+impl MetadataTap {
+    pub(super) fn new(config: NextestTapConfig) -> Self {
+        Self {
+            config,
+            test_points: Vec::new(),
+        }
+    }
+
+    pub(super) fn write_event(&mut self, event: &TestEvent<'_>) -> Result<(), WriteEventError> {
+        match &event.kind {
+            TestEventKind::TestFinished {
+                test_instance,
+                run_statuses,
+                ..
+            } => {
+                let description = test_description(test_instance);
+                let test_point = match run_statuses.describe() {
+                    ExecutionDescription::Success { single_status } => TapTestPoint {
+                        comment: (single_status.result == ExecutionResult::Leak)
+                            .then(|| "passed but leaked handles".to_owned()),
+                        ..TapTestPoint::new(true, description)
+                    },
+                    ExecutionDescription::Flaky { last_status, .. } => TapTestPoint {
+                        comment: Some(format!(
+                            "flaky: passed on attempt {} of {}",
+                            last_status.retry_data.attempt, last_status.retry_data.total_attempts,
+                        )),
+                        ..TapTestPoint::new(true, description)
+                    },
+                    ExecutionDescription::Failure { last_status, .. } => TapTestPoint {
+                        diagnostics: Some(failure_diagnostics(last_status)),
+                        ..TapTestPoint::new(false, description)
+                    },
+                };
+                self.test_points.push(test_point);
+            }
+            TestEventKind::TestSkipped {
+                test_instance,
+                reason,
+            } => {
+                self.test_points.push(TapTestPoint {
+                    directive: Some(format!("SKIP test {reason}")),
+                    ..TapTestPoint::new(true, test_description(test_instance))
+                });
+            }
+            TestEventKind::RunFinished { .. } => {
+                let tap_path = self.config.path();
+                let tap_dir = tap_path.parent().expect("tap path must have a parent");
+                std::fs::create_dir_all(tap_dir).map_err(|error| WriteEventError::Fs {
+                    file: tap_dir.to_path_buf(),
+                    error,
+                })?;
+
+                let report = render_report(&self.test_points);
+                std::fs::write(tap_path, report).map_err(|error| WriteEventError::Fs {
+                    file: tap_path.to_path_buf(),
+                    error,
+                })?;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+/// A single test point (`ok` or `not ok` line) in the TAP report.
+#[derive(Clone, Debug)]
+struct TapTestPoint {
+    ok: bool,
+    description: String,
+    /// A directive such as `SKIP <reason>`, written after a `#` on the test point line.
+    directive: Option<String>,
+    /// A comment written on its own line after the test point.
+    comment: Option<String>,
+    /// The body of the YAML diagnostic block, with each line indented by two spaces.
+    diagnostics: Option<String>,
+}
+
+impl TapTestPoint {
+    fn new(ok: bool, description: String) -> Self {
+        Self {
+            ok,
+            description,
+            directive: None,
+            comment: None,
+            diagnostics: None,
+        }
+    }
+}
+
+fn render_report(test_points: &[TapTestPoint]) -> String {
+    let mut out = String::new();
+    out.push_str("TAP version 13\n");
+    writeln!(out, "1..{}", test_points.len()).expect("writing to a String is infallible");
+
+    for (idx, test_point) in test_points.iter().enumerate() {
+        let status = if test_point.ok { "ok" } else { "not ok" };
+        write!(out, "{status} {} - {}", idx + 1, test_point.description)
+            .expect("writing to a String is infallible");
+        if let Some(directive) = &test_point.directive {
+            write!(out, " # {directive}").expect("writing to a String is infallible");
+        }
+        out.push('\n');
+        if let Some(comment) = &test_point.comment {
+            writeln!(out, "# {comment}").expect("writing to a String is infallible");
+        }
+        if let Some(diagnostics) = &test_point.diagnostics {
+            out.push_str("  ---\n");
+            out.push_str(diagnostics);
+            out.push_str("  ...\n");
+        }
+    }
+
+    out
+}
+
+/// Returns the description for a test point: the binary ID followed by the test name.
+///
+/// `#` is escaped since it would otherwise start a directive.
+fn test_description(test_instance: &TestInstance<'_>) -> String {
+    format!(
+        "{} {}",
+        test_instance.suite_info.binary_id.as_str(),
+        test_instance.name
+    )
+    .replace('#', "\\#")
+}
+
+fn failure_diagnostics(status: &ExecuteStatus) -> String {
+    let (_, ty) = kind_ty(status);
+    let mut out = String::new();
+    write_yaml_string(&mut out, "message", &ty);
+    out.push_str("  severity: fail\n");
+    writeln!(out, "  duration_ms: {}", status.time_taken.as_millis())
+        .expect("writing to a String is infallible");
+    if status.retry_data.total_attempts > 1 {
+        writeln!(out, "  attempts: {}", status.retry_data.attempt)
+            .expect("writing to a String is infallible");
+    }
+
+    match &status.output {
+        Some(TestExecutionOutput::Output(output)) => {
+            if let Some(description) = output.heuristic_extract_description(status.result) {
+                write_yaml_string(
+                    &mut out,
+                    "description",
+                    &description.display_human().to_string(),
+                );
+            }
+            match output {
+                TestOutput::Split { stdout, stderr } => {
+                    write_yaml_string(&mut out, "stdout", stdout.as_str_lossy());
+                    write_yaml_string(&mut out, "stderr", stderr.as_str_lossy());
+                }
+                TestOutput::Combined { output } => {
+                    write_yaml_string(&mut out, "output", output.as_str_lossy());
+                }
+            }
+        }
+        Some(TestExecutionOutput::ExecFail {
+            message,
+            description,
+        }) => {
+            write_yaml_string(
+                &mut out,
+                "description",
+                &format!("Test execution failed: {message}\n{description}"),
+            );
+        }
+        None => {
+            write_yaml_string(
+                &mut out,
+                "description",
+                "Test failed, but output was not captured",
+            );
+        }
+    }
+
+    out
+}
+
+/// Writes `key: value` to `out` as a YAML mapping entry indented by two spaces.
+///
+/// Single-line values are written as single-quoted scalars, and multi-line values as literal
+/// block scalars. Control characters other than newlines and tabs can't appear in YAML, so they're
+/// replaced with their escaped forms.
+fn write_yaml_string(out: &mut String, key: &str, value: &str) {
+    let value = value.replace("\r\n", "\n");
+    let value: String = value
+        .chars()
+        .map(|c| {
+            if c.is_control() && c != '\n' && c != '\t' {
+                c.escape_unicode().to_string()
+            } else {
+                c.to_string()
+            }
+        })
+        .collect();
+
+    if !value.contains('\n') {
+        writeln!(out, "  {key}: '{}'", value.replace('\'', "''"))
+            .expect("writing to a String is infallible");
+        return;
+    }
+
+    // The indentation indicator is required if the first line starts with a space, since the
+    // indentation would otherwise be inferred from it.
+    let indicator = if value.starts_with(' ') { "2" } else { "" };
+    let chomping = if !value.ends_with('\n') {
+        "-"
+    } else if value.ends_with("\n\n") {
+        "+"
+    } else {
+        ""
+    };
+    writeln!(out, "  {key}: |{indicator}{chomping}").expect("writing to a String is infallible");
+    for line in value.strip_suffix('\n').unwrap_or(&value).split('\n') {
+        if line.is_empty() {
+            out.push('\n');
+        } else {
+            writeln!(out, "    {line}").expect("writing to a String is infallible");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+    use test_case::test_case;
+
+    #[test]
+    fn test_render_report() {
+        let mut diagnostics = String::new();
+        write_yaml_string(&mut diagnostics, "message", "test failure");
+        write_yaml_string(&mut diagnostics, "stdout", "running 1 test\nfailed\n");
+
+        let test_points = vec![
+            TapTestPoint::new(true, "my-crate::tests test_pass".to_owned()),
+            TapTestPoint {
+                comment: Some("flaky: passed on attempt 2 of 3".to_owned()),
+                ..TapTestPoint::new(true, "my-crate::tests test_flaky".to_owned())
+            },
+            TapTestPoint {
+                diagnostics: Some(diagnostics),
+                ..TapTestPoint::new(false, "my-crate::tests test_fail".to_owned())
+            },
+            TapTestPoint {
+                directive: Some("SKIP test does not match the run-ignored option".to_owned()),
+                ..TapTestPoint::new(true, "my-crate::tests test_ignored".to_owned())
+            },
+        ];
+
+        assert_eq!(
+            render_report(&test_points),
+            indoc! {"
+                TAP version 13
+                1..4
+                ok 1 - my-crate::tests test_pass
+                ok 2 - my-crate::tests test_flaky
+                # flaky: passed on attempt 2 of 3
+                not ok 3 - my-crate::tests test_fail
+                  ---
+                  message: 'test failure'
+                  stdout: |
+                    running 1 test
+                    failed
+                  ...
+                ok 4 - my-crate::tests test_ignored # SKIP test does not match the run-ignored option
+            "}
+        );
+    }
+
+    #[test]
+    fn test_render_empty_report() {
+        assert_eq!(render_report(&[]), "TAP version 13\n1..0\n");
+    }
+
+    #[test_case("", "  key: ''\n"; "empty")]
+    #[test_case("it's", "  key: 'it''s'\n"; "single line with quote")]
+    #[test_case("a\x1bb", "  key: 'a\\u{1b}b'\n"; "control character")]
+    #[test_case("a\nb\n", "  key: |\n    a\n    b\n"; "trailing newline")]
+    #[test_case("a\nb", "  key: |-\n    a\n    b\n"; "no trailing newline")]
+    #[test_case("a\n\nb\n\n", "  key: |+\n    a\n\n    b\n\n"; "blank lines")]
+    #[test_case("  a\nb\n", "  key: |2\n      a\n    b\n"; "leading spaces")]
+    #[test_case("a\r\nb\r\n", "  key: |\n    a\n    b\n"; "crlf")]
+    fn test_write_yaml_string(value: &str, expected: &str) {
+        let mut out = String::new();
+        write_yaml_string(&mut out, "key", value);
+        assert_eq!(out, expected);
+    }
+}
@@ -0,0 +1,170 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! Machine-readable timing report support.
+//!
+//! The report is a JSON object with a `tests` array containing every test that was run, sorted by
+//! duration, slowest first. Like JUnit, it's written out once the run finishes.
+
+use crate::{
+    config::NextestTimingConfig,
+    errors::WriteEventError,
+    reporter::{TestEvent, TestEventKind},
+};
+use serde::Serialize;
+use std::time::Duration;
+
+#[derive(Clone, Debug)]
+pub(super) struct MetadataTiming {
+    config: NextestTimingConfig,
+    tests: Vec<TimingEntry>,
+}
+
+impl MetadataTiming {
+    pub(super) fn new(config: NextestTimingConfig) -> Self {
+        Self {
+            config,
+            tests: Vec::new(),
+        }
+    }
+
+    pub(super) fn write_event(&mut self, event: &TestEvent<'_>) -> Result<(), WriteEventError> {
+        match &event.kind {
+            TestEventKind::TestFinished {
+                test_instance,
+                run_statuses,
+                ..
+            } => {
+                let last_status = run_statuses.last_status();
+                self.tests.push(TimingEntry {
+                    binary_id: test_instance.suite_info.binary_id.as_str().to_owned(),
+                    test_name: test_instance.name.to_owned(),
+                    duration: last_status.time_taken,
+                    slow: last_status.is_slow,
+                    passed: last_status.result.is_success(),
+                    attempts: last_status.retry_data.attempt,
+                });
+            }
+            TestEventKind::RunFinished { .. } => {
+                let timing_path = self.config.path();
+                let timing_dir = timing_path
+                    .parent()
+                    .expect("timing path must have a parent");
+                std::fs::create_dir_all(timing_dir).map_err(|error| WriteEventError::Fs {
+                    file: timing_dir.to_path_buf(),
+                    error,
+                })?;
+
+                let report = render_report(std::mem::take(&mut self.tests));
+                std::fs::write(timing_path, report).map_err(|error| WriteEventError::Fs {
+                    file: timing_path.to_path_buf(),
+                    error,
+                })?;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct TimingReport {
+    tests: Vec<TimingEntry>,
+}
+
+/// The timing of a single test.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct TimingEntry {
+    binary_id: String,
+    test_name: String,
+    /// The wall-clock duration of the last attempt, in seconds.
+    #[serde(rename = "duration-secs", serialize_with = "serialize_secs")]
+    duration: Duration,
+    /// Whether the last attempt exceeded the slow timeout.
+    slow: bool,
+    passed: bool,
+    attempts: usize,
+}
+
+fn serialize_secs<S: serde::Serializer>(
+    duration: &Duration,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(duration.as_secs_f64())
+}
+
+fn render_report(mut tests: Vec<TimingEntry>) -> String {
+    tests.sort_by(|a, b| {
+        b.duration
+            .cmp(&a.duration)
+            .then_with(|| a.binary_id.cmp(&b.binary_id))
+            .then_with(|| a.test_name.cmp(&b.test_name))
+    });
+    let mut report = serde_json::to_string_pretty(&TimingReport { tests })
+        .expect("serializing TimingReport should succeed");
+    report.push('\n');
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    fn entry(binary_id: &str, test_name: &str, millis: u64, slow: bool) -> TimingEntry {
+        TimingEntry {
+            binary_id: binary_id.to_owned(),
+            test_name: test_name.to_owned(),
+            duration: Duration::from_millis(millis),
+            slow,
+            passed: true,
+            attempts: 1,
+        }
+    }
+
+    #[test]
+    fn test_render_report() {
+        let tests = vec![
+            entry("my-crate", "tests::fast", 250, false),
+            entry("my-crate::integration", "slow", 75_000, true),
+            entry("my-crate", "tests::also_fast", 250, false),
+        ];
+
+        assert_eq!(
+            render_report(tests),
+            indoc! {r#"
+                {
+                  "tests": [
+                    {
+                      "binary-id": "my-crate::integration",
+                      "test-name": "slow",
+                      "duration-secs": 75.0,
+                      "slow": true,
+                      "passed": true,
+                      "attempts": 1
+                    },
+                    {
+                      "binary-id": "my-crate",
+                      "test-name": "tests::also_fast",
+                      "duration-secs": 0.25,
+                      "slow": false,
+                      "passed": true,
+                      "attempts": 1
+                    },
+                    {
+                      "binary-id": "my-crate",
+                      "test-name": "tests::fast",
+                      "duration-secs": 0.25,
+                      "slow": false,
+                      "passed": true,
+                      "attempts": 1
+                    }
+                  ]
+                }
+            "#}
+        );
+    }
+}
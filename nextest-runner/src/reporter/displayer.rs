@@ -345,6 +345,7 @@ pub struct TestReporterBuilder {
 
     verbose: bool,
     hide_progress_bar: bool,
+    slowest: Option<usize>,
 }
 
 impl TestReporterBuilder {
@@ -393,6 +394,12 @@ impl TestReporterBuilder {
         self.hide_progress_bar = hide_progress_bar;
         self
     }
+
+    /// Sets the number of slowest tests to print out at the end of the run.
+    pub fn set_slowest(&mut self, slowest: Option<usize>) -> &mut Self {
+        self.slowest = slowest;
+        self
+    }
 }
 
 impl TestReporterBuilder {
@@ -447,6 +454,10 @@ impl TestReporterBuilder {
                 // gain.
                 ReporterStderrImpl::TerminalWithoutBar
             }
+            ReporterStderr::Terminal if profile.has_no_capture_tests(test_list) => {
+                // Likewise, tests with output capture disabled through overrides inherit stderr.
+                ReporterStderrImpl::TerminalWithoutBar
+            }
             ReporterStderr::Terminal if is_ci::uncached() => {
                 // Some CI environments appear to pretend to be a terminal. Disable the progress bar
                 // in these environments.
@@ -503,6 +514,8 @@ impl TestReporterBuilder {
                 styles,
                 cancel_status: None,
                 final_outputs: DebugIgnore(vec![]),
+                slowest: self.slowest,
+                test_durations: DebugIgnore(vec![]),
             },
             stderr,
             structured_reporter,
@@ -766,6 +779,9 @@ struct TestReporterImpl<'a> {
     styles: Box<Styles>,
     cancel_status: Option<CancelReason>,
     final_outputs: DebugIgnore<Vec<(TestInstance<'a>, FinalOutput)>>,
+    slowest: Option<usize>,
+    // Only populated if `slowest` is set.
+    test_durations: DebugIgnore<Vec<(TestInstance<'a>, Duration)>>,
 }
 
 impl<'a> TestReporterImpl<'a> {
@@ -1002,6 +1018,10 @@ impl<'a> TestReporterImpl<'a> {
             } => {
                 let describe = run_statuses.describe();
                 let last_status = run_statuses.last_status();
+                if self.slowest.is_some() {
+                    self.test_durations
+                        .push((*test_instance, last_status.time_taken));
+                }
                 let test_output_display = match last_status.result.is_success() {
                     true => self.success_output(*success_output),
                     false => self.failure_output(*failure_output),
@@ -1181,6 +1201,15 @@ impl<'a> TestReporterImpl<'a> {
                 let _ = write_summary_str(run_stats, &self.styles, &mut summary_str);
                 writeln!(writer, " {tests_str} run: {summary_str}")?;
 
+                if !run_stats.retry_delay.is_zero() {
+                    writeln!(
+                        writer,
+                        "{:>12} [{:>8.3?}s] spent waiting before retries",
+                        "Backoff".style(self.styles.skip),
+                        run_stats.retry_delay.as_secs_f64(),
+                    )?;
+                }
+
                 // Don't print out test outputs after Ctrl-C, but *do* print them after SIGTERM or
                 // SIGHUP since those tend to be automated tasks performing kills.
                 if self.cancel_status < Some(CancelReason::Interrupt) {
@@ -1224,6 +1253,10 @@ impl<'a> TestReporterImpl<'a> {
                     }
                 }
 
+                if let Some(slowest) = self.slowest {
+                    self.write_slowest(slowest, writer)?;
+                }
+
                 // Print out warnings at the end, if any.
                 write_final_warnings(stats_summary, self.cancel_status, &self.styles, writer)?;
             }
@@ -1232,6 +1265,34 @@ impl<'a> TestReporterImpl<'a> {
         Ok(())
     }
 
+    fn write_slowest(&mut self, slowest: usize, writer: &mut dyn Write) -> io::Result<()> {
+        if slowest == 0 || self.test_durations.is_empty() {
+            return Ok(());
+        }
+
+        self.test_durations
+            .sort_by(|(a_instance, a_duration), (b_instance, b_duration)| {
+                b_duration
+                    .cmp(a_duration)
+                    .then_with(|| a_instance.sort_key().cmp(&b_instance.sort_key()))
+            });
+        let count = slowest.min(self.test_durations.len());
+        writeln!(
+            writer,
+            "{:>12} {} {}",
+            "Slowest".style(self.styles.skip),
+            count.style(self.styles.count),
+            plural::tests_str(count),
+        )?;
+        for (test_instance, duration) in self.test_durations.iter().take(count) {
+            write!(writer, "{:>12} [{:>8.3?}s] ", "", duration.as_secs_f64())?;
+            self.write_instance(*test_instance, writer)?;
+            writeln!(writer)?;
+        }
+
+        Ok(())
+    }
+
     fn write_skip_line(
         &self,
         test_instance: TestInstance<'a>,
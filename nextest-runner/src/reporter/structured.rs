@@ -1,10 +1,12 @@
 //! Functionality for emitting structured, machine readable output in different
 //! formats
 
+mod json_lines;
 mod libtest;
 
 use super::TestEvent;
 use crate::errors::WriteEventError;
+pub use json_lines::JsonLinesReporter;
 pub use libtest::{EmitNextestObject, LibtestReporter};
 
 /// Error returned when a user-supplied format version fails to be parsed to a
@@ -49,11 +51,43 @@ pub enum FormatVersionErrorInner {
     },
 }
 
+/// Parses a user-supplied format version of the form `<major>.<minor>`.
+fn parse_format_version(version: &str) -> Result<(u8, u8), FormatVersionError> {
+    let Some((major, minor)) = version.split_once('.') else {
+        return Err(FormatVersionError {
+            input: version.into(),
+            err: FormatVersionErrorInner::InvalidFormat {
+                expected: "<major>.<minor>",
+            },
+        });
+    };
+
+    let major: u8 = major.parse().map_err(|err| FormatVersionError {
+        input: version.into(),
+        err: FormatVersionErrorInner::InvalidInteger {
+            which: "major",
+            err,
+        },
+    })?;
+
+    let minor: u8 = minor.parse().map_err(|err| FormatVersionError {
+        input: version.into(),
+        err: FormatVersionErrorInner::InvalidInteger {
+            which: "minor",
+            err,
+        },
+    })?;
+
+    Ok((major, minor))
+}
+
 /// A reporter for structured, machine-readable formats.
 #[derive(Default)]
 pub struct StructuredReporter<'a> {
     /// Libtest-compatible output written to stdout
     libtest: Option<LibtestReporter<'a>>,
+    /// JSON Lines output written to stdout
+    json_lines: Option<JsonLinesReporter>,
     // Internal structured reporter.
     // internal: Option<T>,
 }
@@ -70,11 +104,20 @@ impl<'a> StructuredReporter<'a> {
         self
     }
 
+    /// Sets JSON Lines output for the `StructuredReporter`.
+    pub fn set_json_lines(&mut self, json_lines: JsonLinesReporter) -> &mut Self {
+        self.json_lines = Some(json_lines);
+        self
+    }
+
     #[inline]
     pub(super) fn write_event(&mut self, event: &TestEvent<'a>) -> Result<(), WriteEventError> {
         if let Some(libtest) = &mut self.libtest {
             libtest.write_event(event)?;
         }
+        if let Some(json_lines) = &mut self.json_lines {
+            json_lines.write_event(event)?;
+        }
         Ok(())
     }
 }
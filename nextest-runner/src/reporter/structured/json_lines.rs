@@ -0,0 +1,372 @@
+// Copyright (c) The nextest Contributors
+// SPDX-License-Identifier: MIT OR Apache-2.0
+
+//! JSON Lines output support
+//!
+//! Unlike the libtest-compatible output, which buffers events until each test binary is done,
+//! this format writes one JSON object per line to stdout as soon as each event occurs. This lets
+//! consumers such as dashboards ingest results while the run is still in progress.
+//!
+//! Every line has a `format-version` field, so that consumers can detect the schema they're
+//! reading. The version can be pinned with `--message-format-version`.
+
+use super::{parse_format_version, FormatVersionError, FormatVersionErrorInner, TestEvent};
+use crate::{
+    errors::WriteEventError,
+    list::TestInstance,
+    reporter::TestEventKind,
+    runner::{ExecuteStatus, ExecutionResult, RetryData},
+};
+use serde::Serialize;
+use std::io::Write as _;
+
+/// The versions of the JSON Lines format.
+///
+/// New versions are added as minor versions while the format is experimental.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum FormatVersion {
+    /// The first version of the format.
+    ///
+    /// * `{"event": "test-started", ...}` - A test started running.
+    /// * `{"event": "test-retry", ...}` - A test attempt failed and the test will be retried.
+    /// * `{"event": "test-slow", ...}` - A test was slower than its configured slow timeout.
+    /// * `{"event": "test-finished", ...}` - A test finished running, possibly after retries.
+    /// * `{"event": "run-finished", ...}` - The run finished, with summary statistics.
+    V0_1,
+}
+
+impl FormatVersion {
+    const LATEST: Self = Self::V0_1;
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::V0_1 => "0.1",
+        }
+    }
+}
+
+/// A reporter that writes one JSON object per line to stdout for each test event, as it occurs.
+pub struct JsonLinesReporter {
+    version: FormatVersion,
+}
+
+impl JsonLinesReporter {
+    /// Creates a new JSON Lines reporter.
+    ///
+    /// The version string pins the schema of the output, in the form `<major>.<minor>`. If it is
+    /// not specified, the latest version of the format is produced.
+    pub fn new(version: Option<&str>) -> Result<Self, FormatVersionError> {
+        let Some(version) = version else {
+            return Ok(Self {
+                version: FormatVersion::LATEST,
+            });
+        };
+
+        let version = match parse_format_version(version)? {
+            (0, 1) => FormatVersion::V0_1,
+            (0, minor) => {
+                return Err(FormatVersionError {
+                    input: version.into(),
+                    err: FormatVersionErrorInner::InvalidValue {
+                        which: "minor",
+                        value: minor,
+                        range: 1..2,
+                    },
+                });
+            }
+            (major, _) => {
+                return Err(FormatVersionError {
+                    input: version.into(),
+                    err: FormatVersionErrorInner::InvalidValue {
+                        which: "major",
+                        value: major,
+                        range: 0..1,
+                    },
+                });
+            }
+        };
+
+        Ok(Self { version })
+    }
+
+    pub(crate) fn write_event(&mut self, event: &TestEvent<'_>) -> Result<(), WriteEventError> {
+        let Some(line) = self.to_line(event)? else {
+            return Ok(());
+        };
+
+        let mut stdout = std::io::stdout().lock();
+        stdout
+            .write_all(line.as_bytes())
+            .map_err(WriteEventError::Io)?;
+        stdout.flush().map_err(WriteEventError::Io)
+    }
+
+    /// Returns the line for `event`, including the trailing newline, or `None` if the event isn't
+    /// part of the output.
+    fn to_line(&self, event: &TestEvent<'_>) -> Result<Option<String>, WriteEventError> {
+        let Some(kind) = JsonLineEvent::new(&event.kind) else {
+            return Ok(None);
+        };
+        let line = JsonLine {
+            format_version: self.version.as_str(),
+            timestamp: event.timestamp.to_rfc3339(),
+            kind,
+        };
+
+        let mut out =
+            serde_json::to_string(&line).map_err(|err| WriteEventError::Io(err.into()))?;
+        out.push('\n');
+        Ok(Some(out))
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct JsonLine<'a> {
+    format_version: &'static str,
+    timestamp: String,
+    #[serde(flatten)]
+    kind: JsonLineEvent<'a>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+enum JsonLineEvent<'a> {
+    #[serde(rename_all = "kebab-case")]
+    TestStarted {
+        #[serde(flatten)]
+        test: JsonTest<'a>,
+    },
+    #[serde(rename_all = "kebab-case")]
+    TestRetry {
+        #[serde(flatten)]
+        test: JsonTest<'a>,
+        #[serde(flatten)]
+        attempt: JsonAttempt,
+        delay_before_next_attempt_secs: f64,
+    },
+    #[serde(rename_all = "kebab-case")]
+    TestSlow {
+        #[serde(flatten)]
+        test: JsonTest<'a>,
+        attempt: usize,
+        total_attempts: usize,
+        elapsed_secs: f64,
+        will_terminate: bool,
+    },
+    #[serde(rename_all = "kebab-case")]
+    TestFinished {
+        #[serde(flatten)]
+        test: JsonTest<'a>,
+        #[serde(flatten)]
+        attempt: JsonAttempt,
+        retry_delay_secs: f64,
+    },
+    #[serde(rename_all = "kebab-case")]
+    RunFinished {
+        run_id: String,
+        duration_secs: f64,
+        initial_run_count: usize,
+        finished_count: usize,
+        passed: usize,
+        flaky: usize,
+        failed: usize,
+        timed_out: usize,
+        exec_failed: usize,
+        skipped: usize,
+        retry_delay_secs: f64,
+    },
+}
+
+impl<'a> JsonLineEvent<'a> {
+    fn new(kind: &TestEventKind<'a>) -> Option<Self> {
+        let event = match kind {
+            TestEventKind::TestStarted { test_instance, .. } => Self::TestStarted {
+                test: JsonTest::new(test_instance),
+            },
+            TestEventKind::TestAttemptFailedWillRetry {
+                test_instance,
+                run_status,
+                delay_before_next_attempt,
+                ..
+            } => Self::TestRetry {
+                test: JsonTest::new(test_instance),
+                attempt: JsonAttempt::new(run_status),
+                delay_before_next_attempt_secs: delay_before_next_attempt.as_secs_f64(),
+            },
+            TestEventKind::TestSlow {
+                test_instance,
+                retry_data:
+                    RetryData {
+                        attempt,
+                        total_attempts,
+                    },
+                elapsed,
+                will_terminate,
+            } => Self::TestSlow {
+                test: JsonTest::new(test_instance),
+                attempt: *attempt,
+                total_attempts: *total_attempts,
+                elapsed_secs: elapsed.as_secs_f64(),
+                will_terminate: *will_terminate,
+            },
+            TestEventKind::TestFinished {
+                test_instance,
+                run_statuses,
+                ..
+            } => Self::TestFinished {
+                test: JsonTest::new(test_instance),
+                attempt: JsonAttempt::new(run_statuses.last_status()),
+                retry_delay_secs: run_statuses.total_delay_before_start().as_secs_f64(),
+            },
+            TestEventKind::RunFinished {
+                run_id,
+                elapsed,
+                run_stats,
+                ..
+            } => Self::RunFinished {
+                run_id: run_id.to_string(),
+                duration_secs: elapsed.as_secs_f64(),
+                initial_run_count: run_stats.initial_run_count,
+                finished_count: run_stats.finished_count,
+                passed: run_stats.passed,
+                flaky: run_stats.flaky,
+                failed: run_stats.failed,
+                timed_out: run_stats.timed_out,
+                exec_failed: run_stats.exec_failed,
+                skipped: run_stats.skipped,
+                retry_delay_secs: run_stats.retry_delay.as_secs_f64(),
+            },
+            _ => return None,
+        };
+        Some(event)
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct JsonTest<'a> {
+    binary_id: &'a str,
+    name: &'a str,
+}
+
+impl<'a> JsonTest<'a> {
+    fn new(test_instance: &TestInstance<'a>) -> Self {
+        Self {
+            binary_id: test_instance.suite_info.binary_id.as_str(),
+            name: test_instance.name,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct JsonAttempt {
+    status: &'static str,
+    attempt: usize,
+    total_attempts: usize,
+    duration_secs: f64,
+}
+
+impl JsonAttempt {
+    fn new(status: &ExecuteStatus) -> Self {
+        Self {
+            status: match status.result {
+                ExecutionResult::Pass => "pass",
+                ExecutionResult::Leak => "leak",
+                ExecutionResult::Fail { .. } => "fail",
+                ExecutionResult::ExecFail => "exec-fail",
+                ExecutionResult::Timeout => "timeout",
+            },
+            attempt: status.retry_data.attempt,
+            total_attempts: status.retry_data.total_attempts,
+            duration_secs: status.time_taken.as_secs_f64(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runner::RunStats;
+    use chrono::{FixedOffset, TimeZone};
+    use quick_junit::ReportUuid;
+    use std::time::Duration;
+
+    #[test]
+    fn test_new_version() {
+        assert_eq!(
+            JsonLinesReporter::new(None)
+                .expect("no version is valid")
+                .version,
+            FormatVersion::LATEST
+        );
+        assert_eq!(
+            JsonLinesReporter::new(Some("0.1"))
+                .expect("0.1 is valid")
+                .version,
+            FormatVersion::V0_1
+        );
+        for invalid in ["0.2", "1.0", "1", "a.b"] {
+            assert!(
+                JsonLinesReporter::new(Some(invalid)).is_err(),
+                "{invalid} is invalid"
+            );
+        }
+    }
+
+    #[test]
+    fn test_run_finished_line() {
+        let reporter = JsonLinesReporter::new(None).expect("no version is valid");
+        let run_id = ReportUuid::new_v4();
+        let timestamp = FixedOffset::east_opt(0)
+            .expect("offset is valid")
+            .with_ymd_and_hms(2024, 1, 1, 0, 0, 0)
+            .single()
+            .expect("timestamp is valid");
+        let event = TestEvent {
+            timestamp,
+            elapsed: Duration::from_secs(2),
+            kind: TestEventKind::RunFinished {
+                run_id,
+                start_time: timestamp,
+                elapsed: Duration::from_secs(2),
+                run_stats: RunStats {
+                    initial_run_count: 3,
+                    finished_count: 3,
+                    passed: 2,
+                    failed: 1,
+                    retry_delay: Duration::from_millis(1500),
+                    ..Default::default()
+                },
+            },
+        };
+
+        let line = reporter
+            .to_line(&event)
+            .expect("serializing succeeds")
+            .expect("run-finished is part of the output");
+        assert!(line.ends_with('\n'), "line ends with a newline");
+        let value: serde_json::Value =
+            serde_json::from_str(line.trim_end()).expect("line is valid JSON");
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "format-version": "0.1",
+                "timestamp": "2024-01-01T00:00:00+00:00",
+                "event": "run-finished",
+                "run-id": run_id.to_string(),
+                "duration-secs": 2.0,
+                "initial-run-count": 3,
+                "finished-count": 3,
+                "passed": 2,
+                "flaky": 0,
+                "failed": 1,
+                "timed-out": 0,
+                "exec-failed": 0,
+                "skipped": 0,
+                "retry-delay-secs": 1.5,
+            })
+        );
+    }
+}
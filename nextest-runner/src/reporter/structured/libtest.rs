@@ -22,7 +22,9 @@
 //! users to move to the new format or stick to the format version(s) they were
 //! using before
 
-use super::{FormatVersionError, FormatVersionErrorInner, TestEvent, WriteEventError};
+use super::{
+    parse_format_version, FormatVersionError, FormatVersionErrorInner, TestEvent, WriteEventError,
+};
 use crate::{
     list::RustTestSuite,
     reporter::TestEventKind,
@@ -156,30 +158,7 @@ impl<'cfg> LibtestReporter<'cfg> {
                 emit_nextest_obj,
             });
         };
-        let Some((major, minor)) = version.split_once('.') else {
-            return Err(FormatVersionError {
-                input: version.into(),
-                err: FormatVersionErrorInner::InvalidFormat {
-                    expected: "<major>.<minor>",
-                },
-            });
-        };
-
-        let major: u8 = major.parse().map_err(|err| FormatVersionError {
-            input: version.into(),
-            err: FormatVersionErrorInner::InvalidInteger {
-                which: "major",
-                err,
-            },
-        })?;
-
-        let minor: u8 = minor.parse().map_err(|err| FormatVersionError {
-            input: version.into(),
-            err: FormatVersionErrorInner::InvalidInteger {
-                which: "minor",
-                err,
-            },
-        })?;
+        let (major, minor) = parse_format_version(version)?;
 
         let major = match major {
             0 => FormatMajorVersion::Unstable,
@@ -121,6 +121,22 @@ impl ArchiveReporter {
                     self.redactor.redact_path(path).style(self.styles.bold),
                 )?;
             }
+            ArchiveEvent::LinkedPathEscapesTargetDir {
+                path,
+                resolved,
+                requested_by,
+            } => {
+                write!(writer, "{:>12} ", "Warning".style(self.styles.warning))?;
+                writeln!(
+                    writer,
+                    "not archiving linked path `{}`: it resolves to `{}`, which is outside \
+                     the target directory and wouldn't be relocated on extraction \
+                     (requested by: {})",
+                    path.style(self.styles.bold),
+                    self.redactor.redact_path(resolved).style(self.styles.bold),
+                    requested_by.join(", ").style(self.styles.bold),
+                )?;
+            }
             ArchiveEvent::LinkedPathNotFound { path, requested_by } => {
                 write!(writer, "{:>12} ", "Warning".style(self.styles.warning))?;
                 writeln!(
@@ -357,6 +373,18 @@ pub enum ArchiveEvent<'a> {
         path: &'a Utf8Path,
     },
 
+    /// A linked path uses `..` to reach outside the target directory, so it wasn't archived.
+    LinkedPathEscapesTargetDir {
+        /// The linked path, relative to the target directory.
+        path: &'a Utf8Path,
+
+        /// The path it resolves to.
+        resolved: &'a Utf8Path,
+
+        /// The crates that linked against the path.
+        requested_by: &'a [String],
+    },
+
     /// A crate linked against a non-existent path.
     LinkedPathNotFound {
         /// The path of the linked file.
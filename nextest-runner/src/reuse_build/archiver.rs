@@ -4,14 +4,14 @@
 use super::{ArchiveCounts, ArchiveEvent, BINARIES_METADATA_FILE_NAME, CARGO_METADATA_FILE_NAME};
 use crate::{
     config::{
-        get_num_cpus, ArchiveConfig, ArchiveIncludeOnMissing, FinalConfig, NextestProfile,
-        RecursionDepth,
+        get_num_cpus, ArchiveConfig, ArchiveIncludeOnMissing, ArchiveWorkspaceFiles, FinalConfig,
+        NextestProfile, RecursionDepth,
     },
     errors::{ArchiveCreateError, UnknownArchiveFormat},
-    helpers::{convert_rel_path_to_forward_slash, rel_path_join},
+    helpers::{convert_rel_path_to_forward_slash, convert_rel_path_to_main_sep, rel_path_join},
     list::{BinaryList, OutputFormat, SerializableFormat},
     redact::Redactor,
-    reuse_build::{PathMapper, LIBDIRS_BASE_DIR},
+    reuse_build::{PathMapper, LIBDIRS_BASE_DIR, WORKSPACE_FILES_BASE_DIR},
 };
 use atomicwrites::{AtomicFile, OverwriteBehavior};
 use camino::{Utf8Path, Utf8PathBuf};
@@ -115,6 +115,7 @@ where
                 binary_list,
                 cargo_metadata,
                 graph,
+                graph.workspace().root(),
                 path_mapper,
                 host_stdlib,
                 target_stdlib,
@@ -184,6 +185,7 @@ struct Archiver<'a, W: Write> {
     binary_list: &'a BinaryList,
     cargo_metadata: &'a str,
     graph: &'a PackageGraph,
+    workspace_root: &'a Utf8Path,
     path_mapper: &'a PathMapper,
     host_stdlib: Option<Utf8PathBuf>,
     target_stdlib: Option<Utf8PathBuf>,
@@ -201,6 +203,7 @@ impl<'a, W: Write> Archiver<'a, W> {
         binary_list: &'a BinaryList,
         cargo_metadata: &'a str,
         graph: &'a PackageGraph,
+        workspace_root: &'a Utf8Path,
         path_mapper: &'a PathMapper,
         host_stdlib: Option<Utf8PathBuf>,
         target_stdlib: Option<Utf8PathBuf>,
@@ -233,6 +236,7 @@ impl<'a, W: Write> Archiver<'a, W> {
             binary_list,
             cargo_metadata,
             graph,
+            workspace_root,
             path_mapper,
             host_stdlib,
             target_stdlib,
@@ -397,18 +401,8 @@ impl<'a, W: Write> Archiver<'a, W> {
 
         // Write linked paths to the archive.
         for (linked_path, requested_by) in &self.binary_list.rust_build_meta.linked_paths {
-            // Linked paths are relative, e.g. debug/foo/bar. We need to prepend the target
-            // directory.
-            let src_path = self
-                .binary_list
-                .rust_build_meta
-                .target_directory
-                .join(linked_path);
-            let src_path = self.path_mapper.map_binary(src_path);
-
-            // Some crates produce linked paths that don't exist. This is a bug in those libraries.
-            if !src_path.exists() {
-                // Map each requested_by to its package name and version.
+            // Map each requested_by to its package name and version.
+            let describe_requested_by = || {
                 let mut requested_by: Vec<_> = requested_by
                     .iter()
                     .map(|package_id| {
@@ -425,10 +419,39 @@ impl<'a, W: Write> Archiver<'a, W> {
                     })
                     .collect();
                 requested_by.sort_unstable();
+                requested_by
+            };
+
+            // A linked path that uses `..` to reach outside the target directory can't be
+            // relocated on extraction.
+            if let Some(resolved) = self
+                .binary_list
+                .rust_build_meta
+                .linked_path_escape_target(linked_path)
+            {
+                callback(ArchiveEvent::LinkedPathEscapesTargetDir {
+                    path: linked_path,
+                    resolved: &resolved,
+                    requested_by: &describe_requested_by(),
+                })
+                .map_err(ArchiveCreateError::ReporterIo)?;
+                continue;
+            }
 
+            // Linked paths are relative, e.g. debug/foo/bar. We need to prepend the target
+            // directory.
+            let src_path = self
+                .binary_list
+                .rust_build_meta
+                .target_directory
+                .join(linked_path);
+            let src_path = self.path_mapper.map_binary(src_path);
+
+            // Some crates produce linked paths that don't exist. This is a bug in those libraries.
+            if !src_path.exists() {
                 callback(ArchiveEvent::LinkedPathNotFound {
                     path: &src_path,
-                    requested_by: &requested_by,
+                    requested_by: &describe_requested_by(),
                 })
                 .map_err(ArchiveCreateError::ReporterIo)?;
                 continue;
@@ -466,6 +489,22 @@ impl<'a, W: Write> Archiver<'a, W> {
             }
         }
 
+        // Include files from the workspace that match archive.workspace-files.
+        if !self.config.workspace_files.is_empty() {
+            let workspace_files = collect_workspace_files(
+                self.workspace_root,
+                &self.config.workspace_files,
+                target_dir,
+            )?;
+            for rel_path in workspace_files {
+                let src_path = self
+                    .workspace_root
+                    .join(convert_rel_path_to_main_sep(&rel_path));
+                let dest_path = rel_path_join(Utf8Path::new(WORKSPACE_FILES_BASE_DIR), &rel_path);
+                self.append_file(ArchiveStep::ExtraPaths, &src_path, &dest_path)?;
+            }
+        }
+
         // Add the standard libraries to the archive if available.
         if let Some(host_stdlib) = self.host_stdlib.clone() {
             let rel_path = Utf8Path::new(LIBDIRS_BASE_DIR)
@@ -643,6 +682,80 @@ impl<'a, W: Write> Archiver<'a, W> {
     }
 }
 
+/// Returns the files under `workspace_root` that match `workspace_files`, as sorted paths relative
+/// to the workspace root with forward slashes.
+///
+/// Only the literal prefixes of the include patterns are walked. The target directory is skipped,
+/// since its contents are archived separately. Symlinks to directories aren't followed.
+fn collect_workspace_files(
+    workspace_root: &Utf8Path,
+    workspace_files: &ArchiveWorkspaceFiles,
+    target_dir: &Utf8Path,
+) -> Result<Vec<Utf8PathBuf>, ArchiveCreateError> {
+    let mut matches = Vec::new();
+
+    for root in workspace_files.roots() {
+        let src_path = workspace_root.join(convert_rel_path_to_main_sep(&root));
+        let metadata = match fs::symlink_metadata(&src_path) {
+            Ok(metadata) => metadata,
+            // A pattern that doesn't match anything isn't an error.
+            Err(error) if error.kind() == io::ErrorKind::NotFound => continue,
+            Err(error) => {
+                return Err(ArchiveCreateError::InputFileRead {
+                    step: ArchiveStep::ExtraPaths,
+                    path: src_path,
+                    is_dir: None,
+                    error,
+                });
+            }
+        };
+
+        let mut stack = vec![(src_path, root, metadata)];
+        while let Some((src_path, rel_path, metadata)) = stack.pop() {
+            if metadata.is_dir() {
+                if src_path.as_path() == target_dir {
+                    continue;
+                }
+                let entries = src_path.read_dir_utf8().map_err(|error| {
+                    ArchiveCreateError::InputFileRead {
+                        step: ArchiveStep::ExtraPaths,
+                        path: src_path.clone(),
+                        is_dir: Some(true),
+                        error,
+                    }
+                })?;
+                for entry in entries {
+                    let entry = entry.map_err(|error| ArchiveCreateError::DirEntryRead {
+                        path: src_path.clone(),
+                        error,
+                    })?;
+                    let metadata = entry.path().symlink_metadata().map_err(|error| {
+                        ArchiveCreateError::InputFileRead {
+                            step: ArchiveStep::ExtraPaths,
+                            path: entry.path().to_owned(),
+                            is_dir: None,
+                            error,
+                        }
+                    })?;
+                    let entry_rel_path = if rel_path.as_str().is_empty() {
+                        Utf8PathBuf::from(entry.file_name())
+                    } else {
+                        rel_path_join(&rel_path, entry.file_name().as_ref())
+                    };
+                    stack.push((entry.into_path(), entry_rel_path, metadata));
+                }
+            } else if (metadata.is_file() || metadata.is_symlink())
+                && workspace_files.is_match(&rel_path)
+            {
+                matches.push(rel_path);
+            }
+        }
+    }
+
+    matches.sort_unstable();
+    Ok(matches)
+}
+
 fn find_std(libdir: &Utf8Path) -> io::Result<Utf8PathBuf> {
     for path in libdir.read_dir_utf8()? {
         let path = path?;
@@ -714,6 +827,11 @@ impl fmt::Display for ArchiveStep {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{
+        list::RustBuildMeta,
+        platform::BuildPlatforms,
+        reuse_build::{ExtractDestination, Unarchiver},
+    };
 
     #[test]
     fn test_archive_format_autodetect() {
@@ -728,4 +846,117 @@ mod tests {
         ArchiveFormat::autodetect("foo".as_ref()).unwrap_err();
         ArchiveFormat::autodetect("/".as_ref()).unwrap_err();
     }
+
+    #[test]
+    fn test_archive_workspace_files() {
+        let workspace_dir =
+            camino_tempfile::tempdir().expect("should create temp dir successfully");
+        let workspace_root = workspace_dir.path();
+        let target_dir = workspace_root.join("target");
+        for rel_path in [
+            "fixtures/a.json",
+            "fixtures/nested/b.json",
+            "fixtures/c.txt",
+            "fixtures/private/d.json",
+            "target/debug/e.json",
+        ] {
+            let path = workspace_root.join(rel_path);
+            fs::create_dir_all(path.parent().expect("path has a parent"))
+                .expect("should create parent dir");
+            fs::write(&path, rel_path).expect("should write file");
+        }
+
+        let config: ArchiveConfig = serde_json::from_str(
+            r#"{
+                "include": [],
+                "workspace-files": {
+                    "include": ["fixtures/**/*.json", "**/e.json"],
+                    "exclude": ["fixtures/private/**"]
+                }
+            }"#,
+        )
+        .expect("archive config should deserialize");
+
+        let expected = vec![
+            Utf8PathBuf::from("fixtures/a.json"),
+            Utf8PathBuf::from("fixtures/nested/b.json"),
+        ];
+        assert_eq!(
+            collect_workspace_files(workspace_root, &config.workspace_files, &target_dir)
+                .expect("collecting workspace files should succeed"),
+            expected,
+            "excluded files and files in the target directory aren't collected",
+        );
+
+        // Archive the files, then extract them to a different directory.
+        static FIXTURE_JSON: &str = include_str!("../../../fixtures/cargo-metadata.json");
+        let graph = PackageGraph::from_json(FIXTURE_JSON).expect("fixture is valid JSON");
+        let binary_list = BinaryList {
+            rust_build_meta: RustBuildMeta::new(
+                &target_dir,
+                BuildPlatforms::new_with_no_target().expect("should detect the host platform"),
+            ),
+            rust_binaries: vec![],
+        };
+        let path_mapper = PathMapper::noop();
+        let archiver = Archiver::new(
+            &config,
+            &binary_list,
+            FIXTURE_JSON,
+            &graph,
+            workspace_root,
+            &path_mapper,
+            None,
+            None,
+            ArchiveFormat::TarZst,
+            0,
+            Vec::new(),
+            Redactor::noop(),
+        )
+        .expect("archiver should be created");
+        let (archive, _) = archiver
+            .archive(&mut |_: ArchiveEvent<'_>| Ok(()))
+            .expect("archiving should succeed");
+
+        let archive_dir = camino_tempfile::tempdir().expect("should create temp dir successfully");
+        let archive_path = archive_dir.path().join("archive.tar.zst");
+        fs::write(&archive_path, archive).expect("should write archive");
+        let extract_dir = camino_tempfile::tempdir().expect("should create temp dir successfully");
+        let mut archive_file = fs::File::open(&archive_path).expect("should open archive");
+        let extract_info = Unarchiver::new(&mut archive_file, ArchiveFormat::TarZst)
+            .extract(
+                ExtractDestination::Destination {
+                    dir: extract_dir.path().to_owned(),
+                    overwrite: false,
+                },
+                |_: ArchiveEvent<'_>| Ok(()),
+            )
+            .expect("extracting should succeed");
+
+        // Workspace files are mapped to the new target directory, like other build artifacts.
+        let path_mapper = PathMapper::new(
+            workspace_root,
+            None,
+            &target_dir,
+            Some(&extract_info.dest_dir.join("target")),
+            Default::default(),
+        )
+        .expect("path mapper should be created");
+        for rel_path in &expected {
+            let orig_path = target_dir.join("nextest/workspace-files").join(rel_path);
+            let extracted_path = path_mapper.map_binary(orig_path);
+            assert_eq!(
+                fs::read_to_string(&extracted_path).expect("extracted file should exist"),
+                rel_path.as_str(),
+                "contents of {extracted_path} match",
+            );
+        }
+        assert!(
+            !extract_info
+                .dest_dir
+                .join("target/nextest/workspace-files/fixtures/c.txt")
+                .exists(),
+            "files that don't match aren't archived",
+        );
+    }
 }
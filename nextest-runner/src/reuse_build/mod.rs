@@ -38,6 +38,13 @@ pub const BINARIES_METADATA_FILE_NAME: &str = "target/nextest/binaries-metadata.
 /// The name of the directory in which libdirs are stored.
 pub const LIBDIRS_BASE_DIR: &str = "target/nextest/libdirs";
 
+/// The name of the directory in which files from the workspace, selected by
+/// `archive.workspace-files`, are stored.
+///
+/// Files are stored at their path relative to the workspace root, so after extraction they're
+/// within the remapped target directory at `nextest/workspace-files/<path>`.
+pub const WORKSPACE_FILES_BASE_DIR: &str = "target/nextest/workspace-files";
+
 /// Reuse build information.
 #[derive(Debug, Default)]
 pub struct ReuseBuildInfo {
@@ -340,6 +347,18 @@ impl PathMapper {
         }
     }
 
+    /// Returns true if the workspace root or the target directory is remapped.
+    pub(crate) fn is_remapping(&self) -> bool {
+        self.workspace.is_some() || self.target_dir.is_some()
+    }
+
+    /// If the target directory is remapped and `path` is within the original target directory,
+    /// returns `path` relative to it.
+    pub(crate) fn strip_orig_target_dir<'a>(&self, path: &'a Utf8Path) -> Option<&'a Utf8Path> {
+        let (from, _) = self.target_dir.as_ref()?;
+        path.strip_prefix(from).ok()
+    }
+
     pub(crate) fn map_binary(&self, path: Utf8PathBuf) -> Utf8PathBuf {
         match &self.target_dir {
             Some((from, to)) => match path.strip_prefix(from) {
@@ -363,6 +382,19 @@ pub struct LibdirMapper {
     pub(crate) target: PlatformLibdirMapper,
 }
 
+impl LibdirMapper {
+    /// Returns true if either libdir is remapped, as with libdirs extracted from an archive.
+    pub fn is_remapping(&self) -> bool {
+        !matches!(
+            (&self.host, &self.target),
+            (
+                PlatformLibdirMapper::NotRequested,
+                PlatformLibdirMapper::NotRequested
+            )
+        )
+    }
+}
+
 /// A mapper for an individual platform libdir.
 ///
 /// Part of [`LibdirMapper`].
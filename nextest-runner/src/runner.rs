@@ -36,9 +36,11 @@ use quick_junit::ReportUuid;
 use rand::{distributions::OpenClosed01, thread_rng, Rng};
 use std::{
     convert::Infallible,
-    fmt::Write,
+    ffi::{OsStr, OsString},
+    fmt::{self, Write},
     marker::PhantomData,
     num::NonZeroUsize,
+    path::PathBuf,
     pin::Pin,
     process::{ExitStatus, Stdio},
     sync::{
@@ -250,6 +252,84 @@ impl<'a> TestRunner<'a> {
         self.inner.runtime.shutdown_background();
         run_stats
     }
+
+    /// Returns the commands that would be run for each test that matches the filter, without
+    /// running any of them.
+    ///
+    /// Setup scripts aren't run either, so environment variables they would set aren't included.
+    pub fn command_previews(&self) -> Vec<TestCommandPreview<'a>> {
+        self.inner.command_previews()
+    }
+}
+
+/// The command that would be run for a test, as returned by [`TestRunner::command_previews`].
+///
+/// The [`Display`](fmt::Display) implementation renders this as a shell command line, preceded by
+/// comments with the test's name, retry policy, slow timeout and test group.
+#[derive(Clone, Debug)]
+pub struct TestCommandPreview<'a> {
+    /// The test instance.
+    pub test_instance: TestInstance<'a>,
+
+    /// The program to run: the test binary, or the target runner if one is configured.
+    pub program: OsString,
+
+    /// The arguments to pass to the program.
+    pub args: Vec<OsString>,
+
+    /// The working directory for the command.
+    pub cwd: Option<PathBuf>,
+
+    /// The environment variables set for the command, in addition to those inherited from
+    /// nextest's environment.
+    pub env: Vec<(OsString, OsString)>,
+
+    /// The retry policy for the test.
+    pub retries: RetryPolicy,
+
+    /// The slow timeout for the test.
+    pub slow_timeout: SlowTimeout,
+
+    /// The test group the test is in.
+    pub test_group: TestGroup,
+}
+
+impl fmt::Display for TestCommandPreview<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let quote = |s: &OsStr| shell_words::quote(&s.to_string_lossy()).into_owned();
+
+        writeln!(
+            f,
+            "# {} {}",
+            self.test_instance.suite_info.binary_id, self.test_instance.name
+        )?;
+        write!(
+            f,
+            "# retries: {}, slow-timeout: {:?}",
+            self.retries.count(),
+            self.slow_timeout.period,
+        )?;
+        if let Some(terminate_after) = self.slow_timeout.terminate_after {
+            write!(f, " (terminate after {terminate_after} periods)")?;
+        }
+        writeln!(f, ", test-group: {}", self.test_group)?;
+
+        if let Some(cwd) = &self.cwd {
+            write!(f, "cd {} && ", quote(cwd.as_os_str()))?;
+        }
+        if !self.env.is_empty() {
+            write!(f, "env")?;
+            for (key, value) in &self.env {
+                write!(f, " {}={}", key.to_string_lossy(), quote(value))?;
+            }
+            write!(f, " ")?;
+        }
+        write!(f, "{}", quote(&self.program))?;
+        for arg in &self.args {
+            write!(f, " {}", quote(arg))?;
+        }
+        writeln!(f)
+    }
 }
 
 #[derive(Debug)]
@@ -269,6 +349,52 @@ struct TestRunnerInner<'a> {
 }
 
 impl<'a> TestRunnerInner<'a> {
+    fn command_previews(&self) -> Vec<TestCommandPreview<'a>> {
+        // Commands are shown as they'd be run without double-spawning, since that's how they'd be
+        // run by hand.
+        let double_spawn = DoubleSpawnInfo::disabled();
+        let ctx = TestExecuteContext {
+            double_spawn: &double_spawn,
+            target_runner: &self.target_runner,
+        };
+
+        self.test_list
+            .iter_tests()
+            .filter(|test_instance| test_instance.test_info.filter_match.is_match())
+            .map(|test_instance| {
+                let settings = self.profile.settings_for(&test_instance.to_test_query());
+                let mut cmd = test_instance.make_command(&ctx, self.test_list);
+                let command_mut = cmd.command_mut();
+                self.apply_test_group_env(&settings, command_mut);
+
+                TestCommandPreview {
+                    test_instance,
+                    program: command_mut.get_program().to_owned(),
+                    args: command_mut.get_args().map(ToOwned::to_owned).collect(),
+                    cwd: command_mut.get_current_dir().map(ToOwned::to_owned),
+                    env: command_mut
+                        .get_envs()
+                        .filter_map(|(key, value)| Some((key.to_owned(), value?.to_owned())))
+                        .collect(),
+                    retries: self.force_retries.unwrap_or_else(|| settings.retries()),
+                    slow_timeout: settings.slow_timeout(),
+                    test_group: settings.test_group().clone(),
+                }
+            })
+            .collect()
+    }
+
+    fn apply_test_group_env(&self, settings: &TestSettings, command: &mut std::process::Command) {
+        if let TestGroup::Custom(group) = settings.test_group() {
+            if let Some(group_config) = self.profile.test_group_config().get(group) {
+                group_config.apply_env(
+                    command,
+                    self.test_list.rust_build_meta().dylib_path_envvar(),
+                );
+            }
+        }
+    }
+
     fn try_execute<E, F>(
         &self,
         signal_handler: &mut SignalHandler,
@@ -507,12 +633,15 @@ impl<'a> TestRunnerInner<'a> {
                     }
                 }
 
-                // groups is going to be passed to future_queue_grouped.
+                // groups is going to be passed to future_queue_grouped. Groups without a
+                // declared limit are only limited by the global number of test threads.
                 let groups = self
                     .profile
                     .test_group_config()
                     .iter()
-                    .map(|(group_name, config)| (group_name, config.max_threads.compute()));
+                    .map(|(group_name, config)| {
+                        (group_name, config.compute_max_threads(self.test_threads))
+                    });
 
                 let setup_script_data = Arc::new(setup_script_data);
 
@@ -524,8 +653,14 @@ impl<'a> TestRunnerInner<'a> {
                         let query = test_instance.to_test_query();
                         let settings = self.profile.settings_for(&query);
                         let setup_script_data = setup_script_data.clone();
-                        let threads_required =
-                            settings.threads_required().compute(self.test_threads);
+                        let threads_required = if settings.no_capture() {
+                            // Tests with output capture disabled through overrides write
+                            // directly to the terminal, so run them on their own to avoid
+                            // interleaving their output with other tests.
+                            self.test_threads
+                        } else {
+                            settings.threads_required().compute(self.test_threads)
+                        };
                         let test_group = match settings.test_group() {
                             TestGroup::Global => None,
                             TestGroup::Custom(name) => Some(name.clone()),
@@ -606,6 +741,11 @@ impl<'a> TestRunnerInner<'a> {
                                     delay = backoff_iter
                                         .next()
                                         .expect("backoff delay must be non-empty");
+                                    // Don't wait between attempts for longer than a single attempt
+                                    // is allowed to run.
+                                    if let Some(timeout) = settings.slow_timeout().timeout() {
+                                        delay = delay.min(timeout);
+                                    }
 
                                     let _ = this_run_sender.send(
                                         InternalTestEvent::AttemptFailedWillRetry {
@@ -966,15 +1106,20 @@ impl<'a> TestRunnerInner<'a> {
             &self.profile.filterset_ecx(),
             command_mut,
         );
+        self.apply_test_group_env(settings, command_mut);
         imp::set_process_group(command_mut);
 
         // If creating a job fails, we might be on an old system. Ignore this -- job objects are a
         // best-effort thing.
         let job = imp::Job::create().ok();
 
-        let crate::test_command::Child { mut child, output } = cmd
-            .spawn(self.capture_strategy)
-            .map_err(RunTestError::Spawn)?;
+        let capture_strategy = if settings.no_capture() {
+            CaptureStrategy::None
+        } else {
+            self.capture_strategy
+        };
+        let crate::test_command::Child { mut child, output } =
+            cmd.spawn(capture_strategy).map_err(RunTestError::Spawn)?;
 
         // If assigning the child to the job fails, ignore this. This can happen if the process has
         // exited.
@@ -1290,6 +1435,15 @@ impl ExecutionStatuses {
         self.statuses.len()
     }
 
+    /// Returns the total time spent waiting before retries of this test, as determined by the
+    /// retry policy's backoff.
+    pub fn total_delay_before_start(&self) -> Duration {
+        self.statuses
+            .iter()
+            .map(|status| status.delay_before_start)
+            .sum()
+    }
+
     /// Returns a description of self.
     pub fn describe(&self) -> ExecutionDescription<'_> {
         let last_status = self.last_status();
@@ -1545,6 +1699,11 @@ pub struct RunStats {
 
     /// The number of tests that were skipped.
     pub skipped: usize,
+
+    /// The total time spent waiting before retries, across all tests.
+    ///
+    /// Tests run in parallel, so this can be greater than the wall time of the run.
+    pub retry_delay: Duration,
 }
 
 impl RunStats {
@@ -1607,6 +1766,7 @@ impl RunStats {
 
     fn on_test_finished(&mut self, run_statuses: &ExecutionStatuses) {
         self.finished_count += 1;
+        self.retry_delay += run_statuses.total_delay_before_start();
         // run_statuses is guaranteed to have at least one element.
         // * If the last element is success, treat it as success (and possibly flaky).
         // * If the last element is a failure, use it to determine fail/exec fail.
@@ -30,6 +30,32 @@ impl<'a> RustcCli<'a> {
         cli
     }
 
+    /// Create a rustc CLI call: `rustc --print sysroot`.
+    pub fn print_host_sysroot() -> Self {
+        let mut cli = Self::default();
+        cli.add_arg("--print").add_arg("sysroot");
+        cli
+    }
+
+    /// Create a rustc CLI call: `rustc --print sysroot --target <triple>`.
+    pub fn print_target_sysroot(triple: &'a TargetTriple) -> Self {
+        let mut cli = Self::default();
+        cli.add_arg("--print")
+            .add_arg("sysroot")
+            .add_arg("--target")
+            .add_arg(triple.platform.triple_str());
+        cli
+    }
+
+    /// Create a rustc CLI call: `rustc -vV`.
+    ///
+    /// The output identifies the toolchain, including its version, commit hash, and host triple.
+    pub fn version_verbose() -> Self {
+        let mut cli = Self::default();
+        cli.add_arg("-vV");
+        cli
+    }
+
     fn add_arg(&mut self, arg: impl Into<Cow<'a, str>>) -> &mut Self {
         self.args.push(arg.into());
         self
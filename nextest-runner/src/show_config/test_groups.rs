@@ -142,13 +142,14 @@ impl<'a> ShowTestGroups<'a> {
 
             write!(writer, "group: {}", test_group.style(styles.group))?;
             if let TestGroup::Custom(group) = test_group {
-                write!(
-                    writer,
-                    " (max threads = {})",
-                    self.test_group_config[group]
-                        .max_threads
-                        .style(styles.max_threads)
-                )?;
+                match self.test_group_config[group].max_threads {
+                    Some(max_threads) => write!(
+                        writer,
+                        " (max threads = {})",
+                        max_threads.style(styles.max_threads)
+                    )?,
+                    None => write!(writer, " (no max threads)")?,
+                }
             }
             writeln!(writer)?;
 
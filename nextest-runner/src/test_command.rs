@@ -4,12 +4,12 @@
 use crate::{
     cargo_config::EnvironmentMap,
     double_spawn::{DoubleSpawnContext, DoubleSpawnInfo},
-    helpers::dylib_path_envvar,
     list::{RustBuildMeta, TestListState},
     test_output::CaptureStrategy,
 };
 use camino::{Utf8Path, Utf8PathBuf};
 use guppy::graph::PackageMetadata;
+use nextest_metadata::BuildPlatform;
 use once_cell::sync::Lazy;
 use std::{
     collections::{BTreeSet, HashMap},
@@ -45,6 +45,7 @@ impl TestCommand {
         cwd: &Utf8Path,
         package: &PackageMetadata<'_>,
         non_test_binaries: &BTreeSet<(String, Utf8PathBuf)>,
+        build_platform: BuildPlatform,
     ) -> Self {
         let mut cmd = create_command(program, args, lctx.double_spawn);
 
@@ -52,6 +53,18 @@ impl TestCommand {
         // `CARGO_*` and `NEXTEST_*` variables set directly on `cmd` below.
         lctx.env.apply_env(&mut cmd);
 
+        // Target binaries may be run under an emulator that needs its own environment.
+        if build_platform == BuildPlatform::Target {
+            if let Some(target) = &lctx.rust_build_meta.build_platforms.target {
+                for (key, value) in &target.emulator_env {
+                    cmd.env(key, value);
+                }
+                if let Some(sysroot) = &target.sysroot {
+                    cmd.env("NEXTEST_TARGET_SYSROOT", sysroot);
+                }
+            }
+        }
+
         cmd.current_dir(cwd)
             // This environment variable is set to indicate that tests are being run under nextest.
             .env("NEXTEST", "1")
@@ -65,15 +78,16 @@ impl TestCommand {
 
         apply_package_env(&mut cmd, package);
 
-        apply_ld_dyld_env(&mut cmd, lctx.dylib_path);
+        apply_ld_dyld_env(
+            &mut cmd,
+            lctx.rust_build_meta.dylib_path_envvar(),
+            lctx.dylib_path,
+        );
+        if build_platform == BuildPlatform::Target {
+            apply_target_dylib_env(&mut cmd, lctx);
+        }
 
-        if let Some(out_dir) = lctx
-            .rust_build_meta
-            .build_script_out_dirs
-            .get(package.id().repr())
-        {
-            // Convert the output directory to an absolute path.
-            let out_dir = lctx.rust_build_meta.target_directory.join(out_dir);
+        if let Some(out_dir) = lctx.rust_build_meta.out_dir(package.id().repr()) {
             cmd.env("OUT_DIR", out_dir);
         }
 
@@ -191,6 +205,63 @@ fn apply_package_env(cmd: &mut std::process::Command, package: &PackageMetadata<
     );
 }
 
+/// Sets the target platform's dynamic library path environment variable, if it's different from
+/// the one nextest sets for the platform it's running on.
+///
+/// This is the case when target binaries are run under an emulator, e.g. Windows binaries under
+/// Wine on Linux: the emulated binary looks up libraries through the target's variable (`PATH`),
+/// not the host's (`LD_LIBRARY_PATH`).
+fn apply_target_dylib_env(cmd: &mut std::process::Command, lctx: &LocalExecuteContext<'_>) {
+    let target_envvar = lctx
+        .rust_build_meta
+        .build_platforms
+        .target_dylib_path_envvar();
+    let existing = std::env::var_os(target_envvar);
+    if let Some(value) = target_dylib_env_value(lctx.rust_build_meta, lctx.dylib_path, existing) {
+        cmd.env(target_envvar, value);
+    }
+}
+
+/// Returns the value of the target platform's dynamic library path environment variable, or `None`
+/// if it's the same variable nextest already sets.
+///
+/// The dynamic library paths are joined with the target's separator, and prepended to `existing`.
+/// The existing value is left as-is and separated from the new paths with the host's separator:
+/// the variable may be `PATH`, which the host also uses to look up programs such as the target
+/// runner.
+pub(crate) fn target_dylib_env_value(
+    rust_build_meta: &RustBuildMeta<TestListState>,
+    dylib_path: &OsStr,
+    existing: Option<OsString>,
+) -> Option<OsString> {
+    let build_platforms = &rust_build_meta.build_platforms;
+    if build_platforms.target_dylib_path_envvar() == rust_build_meta.dylib_path_envvar() {
+        return None;
+    }
+
+    let separator = OsString::from(build_platforms.path_separator().to_string());
+    let mut value = OsString::new();
+    for (i, path) in std::env::split_paths(dylib_path).enumerate() {
+        if i > 0 {
+            value.push(&separator);
+        }
+        value.push(path);
+    }
+    if let Some(existing) = existing.filter(|v| !v.is_empty()) {
+        value.push(if cfg!(windows) { ";" } else { ":" });
+        value.push(existing);
+    }
+    Some(value)
+}
+
+/// Returns true if macOS System Integrity Protection removes `var` from the environment of
+/// system-protected processes.
+pub(crate) fn is_sip_sanitized(var: &str) -> bool {
+    // Look for variables starting with LD_ or DYLD_.
+    // https://briandfoy.github.io/macos-s-system-integrity-protection-sanitizes-your-environment/
+    var.starts_with("LD_") || var.starts_with("DYLD_")
+}
+
 /// This is a workaround for a macOS SIP issue:
 /// https://github.com/nextest-rs/nextest/pull/84
 ///
@@ -206,13 +277,11 @@ fn apply_package_env(cmd: &mut std::process::Command, package: &PackageMetadata<
 ///
 /// Nextest never changes these environment variables within its own process, so caching them is
 /// valid.
-pub(crate) fn apply_ld_dyld_env(cmd: &mut std::process::Command, dylib_path: &OsStr) {
-    fn is_sip_sanitized(var: &str) -> bool {
-        // Look for variables starting with LD_ or DYLD_.
-        // https://briandfoy.github.io/macos-s-system-integrity-protection-sanitizes-your-environment/
-        var.starts_with("LD_") || var.starts_with("DYLD_")
-    }
-
+pub(crate) fn apply_ld_dyld_env(
+    cmd: &mut std::process::Command,
+    dylib_path_envvar: &str,
+    dylib_path: &OsStr,
+) {
     static LD_DYLD_ENV_VARS: Lazy<HashMap<String, OsString>> = Lazy::new(|| {
         std::env::vars_os()
             .filter_map(|(k, v)| match k.into_string() {
@@ -222,17 +291,237 @@ pub(crate) fn apply_ld_dyld_env(cmd: &mut std::process::Command, dylib_path: &Os
             .collect()
     });
 
-    cmd.env(dylib_path_envvar(), dylib_path);
+    cmd.env(dylib_path_envvar, dylib_path);
 
     // NB: we will always override user-provided environment variables with the
     // `CARGO_*` and `NEXTEST_*` variables set directly on `cmd` below.
     for (k, v) in &*LD_DYLD_ENV_VARS {
-        if k != dylib_path_envvar() {
+        if k != dylib_path_envvar {
             cmd.env("NEXTEST_".to_owned() + k, v);
         }
     }
     // Also add the dylib path envvar under the NEXTEST_ prefix.
-    if is_sip_sanitized(dylib_path_envvar()) {
-        cmd.env("NEXTEST_".to_owned() + dylib_path_envvar(), dylib_path);
+    if is_sip_sanitized(dylib_path_envvar) {
+        cmd.env("NEXTEST_".to_owned() + dylib_path_envvar, dylib_path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cargo_config::TargetTriple,
+        platform::{PlatformLibdir, TargetPlatform},
+    };
+    use guppy::{graph::PackageGraph, CargoMetadata, PackageId};
+    use nextest_metadata::{
+        PlatformLibdirUnavailable, RustNonTestBinaryKind, RustNonTestBinarySummary,
+        RustTestBinaryKind,
+    };
+
+    static PACKAGE_GRAPH_FIXTURE: Lazy<PackageGraph> = Lazy::new(|| {
+        static FIXTURE_JSON: &str = include_str!("../../fixtures/cargo-metadata.json");
+        CargoMetadata::parse_json(FIXTURE_JSON)
+            .expect("fixture is valid JSON")
+            .build_graph()
+            .expect("fixture is valid PackageGraph")
+    });
+
+    static PACKAGE_METADATA_ID: &str = "metadata-helper 0.1.0 (path+file:///Users/fakeuser/local/testcrates/metadata/metadata-helper)";
+    fn package_metadata() -> PackageMetadata<'static> {
+        PACKAGE_GRAPH_FIXTURE
+            .metadata(&PackageId::new(PACKAGE_METADATA_ID))
+            .expect("package ID is valid")
+    }
+
+    /// Returns the value of `key` set directly on `command`, if any.
+    fn command_env<'a>(command: &'a mut TestCommand, key: &str) -> Option<&'a OsStr> {
+        command
+            .command_mut()
+            .get_envs()
+            .find(|(k, _)| *k == OsStr::new(key))
+            .and_then(|(_, value)| value)
+    }
+
+    #[test]
+    fn test_target_emulator_env() {
+        let mut rust_build_meta = RustBuildMeta::<TestListState>::empty();
+        rust_build_meta.build_platforms.target = Some(
+            TargetPlatform::new(
+                TargetTriple::x86_64_unknown_linux_gnu(),
+                PlatformLibdir::Unavailable(PlatformLibdirUnavailable::RUSTC_FAILED),
+            )
+            .with_emulator_env([("QEMU_LD_PREFIX".to_owned(), "/sysroot".to_owned())].into())
+            .with_sysroot(Some("/sysroot".into())),
+        );
+        let double_spawn = DoubleSpawnInfo::disabled();
+        let env = EnvironmentMap::empty();
+        let lctx = LocalExecuteContext {
+            rust_build_meta: &rust_build_meta,
+            double_spawn: &double_spawn,
+            dylib_path: OsStr::new("/fake/target/debug"),
+            env: &env,
+        };
+        let package = package_metadata();
+        let new_command = |build_platform| {
+            TestCommand::new(
+                &lctx,
+                "/fake/program".to_owned(),
+                &[],
+                Utf8Path::new("/fake/cwd"),
+                &package,
+                &BTreeSet::new(),
+                build_platform,
+            )
+        };
+
+        let mut target_command = new_command(BuildPlatform::Target);
+        assert_eq!(
+            command_env(&mut target_command, "QEMU_LD_PREFIX"),
+            Some(OsStr::new("/sysroot")),
+        );
+        assert_eq!(
+            command_env(&mut target_command, "NEXTEST_TARGET_SYSROOT"),
+            Some(OsStr::new("/sysroot")),
+        );
+
+        // Host binaries aren't run under the target's emulator.
+        let mut host_command = new_command(BuildPlatform::Host);
+        assert_eq!(command_env(&mut host_command, "QEMU_LD_PREFIX"), None);
+        assert_eq!(
+            command_env(&mut host_command, "NEXTEST_TARGET_SYSROOT"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_spawn_env_matches_test_command() {
+        let package_id = PACKAGE_METADATA_ID;
+        let package = package_metadata();
+
+        let rust_build_meta = RustBuildMeta::<TestListState> {
+            target_directory: "/fake/target".into(),
+            base_output_directories: [Utf8PathBuf::from("debug")].into(),
+            build_script_out_dirs: [(package_id.to_owned(), "debug/build/foo/out".into())].into(),
+            non_test_binaries: [(
+                package_id.to_owned(),
+                [RustNonTestBinarySummary {
+                    name: "foo-bin".to_owned(),
+                    kind: RustNonTestBinaryKind::BIN_EXE,
+                    path: "debug/foo-bin".into(),
+                }]
+                .into(),
+            )]
+            .into(),
+            ..RustBuildMeta::empty()
+        };
+
+        let existing: HashMap<String, String> = std::env::vars_os()
+            .filter_map(|(key, value)| Some((key.into_string().ok()?, value.into_string().ok()?)))
+            .collect();
+        let envvar = rust_build_meta.dylib_path_envvar();
+        let dylib_path = rust_build_meta
+            .dylib_path_with_existing(existing.get(envvar).map(OsStr::new))
+            .expect("dylib paths can be joined");
+        let double_spawn = DoubleSpawnInfo::disabled();
+        let env = EnvironmentMap::empty();
+        let lctx = LocalExecuteContext {
+            rust_build_meta: &rust_build_meta,
+            double_spawn: &double_spawn,
+            dylib_path: &dylib_path,
+            env: &env,
+        };
+
+        for kind in [RustTestBinaryKind::LIB, RustTestBinaryKind::TEST] {
+            let mut command = TestCommand::new(
+                &lctx,
+                "/fake/program".to_owned(),
+                &[],
+                Utf8Path::new("/fake/cwd"),
+                &package,
+                &rust_build_meta.exposed_non_test_binaries(package_id, &kind),
+                BuildPlatform::Host,
+            );
+            let spawn_env = rust_build_meta
+                .spawn_env(package_id, &kind, BuildPlatform::Host, &existing)
+                .expect("dylib paths can be joined");
+
+            // Variables that depend on the package graph aren't part of spawn_env.
+            let command_env: HashMap<String, String> = command
+                .command_mut()
+                .get_envs()
+                .filter_map(|(key, value)| {
+                    let key = key.to_str().expect("key is valid UTF-8");
+                    let value = value.expect("variable is set, not removed");
+                    (!key.starts_with("CARGO_") && !key.starts_with("__NEXTEST_ORIGINAL_"))
+                        .then(|| (key.to_owned(), value.to_string_lossy().into_owned()))
+                })
+                .collect();
+
+            for (key, value) in &command_env {
+                assert_eq!(
+                    spawn_env.get(key),
+                    Some(value),
+                    "for {kind}, spawn_env matches TestCommand for {key}",
+                );
+            }
+            for (key, value) in &spawn_env {
+                if existing.get(key) != Some(value) {
+                    assert!(
+                        command_env.contains_key(key),
+                        "for {kind}, TestCommand sets {key}, which spawn_env changes",
+                    );
+                }
+            }
+            assert_eq!(
+                command_env.contains_key("NEXTEST_BIN_EXE_foo-bin"),
+                kind == RustTestBinaryKind::TEST,
+                "for {kind}, non-test binaries are only exposed to integration tests",
+            );
+        }
+    }
+
+    #[test]
+    fn test_target_dylib_env_value() {
+        let mut rust_build_meta = RustBuildMeta::<TestListState>::empty();
+        let target_triple = TargetTriple::deserialize_str(Some("x86_64-pc-windows-gnu".to_owned()))
+            .expect("creating TargetTriple should succeed")
+            .expect("the output of deserialize_str shouldn't be None");
+        rust_build_meta.build_platforms.target = Some(TargetPlatform::new(
+            target_triple,
+            PlatformLibdir::Unavailable(PlatformLibdirUnavailable::RUSTC_FAILED),
+        ));
+
+        let dylib_path =
+            std::env::join_paths(["/target/debug", "/target/debug/deps"]).expect("paths are valid");
+        let value = target_dylib_env_value(
+            &rust_build_meta,
+            &dylib_path,
+            Some("/usr/local/bin:/usr/bin".into()),
+        );
+
+        if cfg!(windows) {
+            // Nextest already sets PATH, so there's nothing more to do.
+            assert_eq!(value, None);
+        } else {
+            // The existing PATH must still be usable by the host to find programs (e.g. `wine`),
+            // so it isn't joined with the target's separator.
+            assert_eq!(
+                value,
+                Some("/target/debug;/target/debug/deps:/usr/local/bin:/usr/bin".into()),
+            );
+            assert_eq!(
+                target_dylib_env_value(&rust_build_meta, &dylib_path, Some("".into())),
+                Some("/target/debug;/target/debug/deps".into()),
+                "empty existing value is skipped",
+            );
+        }
+
+        rust_build_meta.build_platforms.target = None;
+        assert_eq!(
+            target_dylib_env_value(&rust_build_meta, &dylib_path, None),
+            None,
+            "without a target, the host's variable is used",
+        );
     }
 }
@@ -179,15 +179,16 @@ impl TestFilterBuilder {
     ///
     /// This test filter may be stateful.
     pub fn build(&self) -> TestFilter<'_> {
-        let partitioner = self
-            .partitioner_builder
-            .as_ref()
-            .map(|partitioner_builder| partitioner_builder.build());
         TestFilter {
             builder: self,
-            partitioner,
+            partitioner: None,
         }
     }
+
+    /// Returns the partitioner builder, if partitioning is enabled.
+    pub fn partitioner_builder(&self) -> Option<&PartitionerBuilder> {
+        self.partitioner_builder.as_ref()
+    }
 }
 
 /// Whether a binary matched filters and should be run to obtain the list of tests within.
@@ -288,6 +289,7 @@ impl fmt::Display for BinaryMismatchReason {
 #[derive(Debug)]
 pub struct TestFilter<'builder> {
     builder: &'builder TestFilterBuilder,
+    // Built the first time a test reaches partitioning, since partitioners are scoped to a binary.
     partitioner: Option<Box<dyn Partitioner>>,
 }
 
@@ -346,7 +348,7 @@ impl<'filter> TestFilter<'filter> {
             // Note that partition-based filtering MUST come after all other kinds of filtering,
             // so that count-based bucketing applies after ignored, name and expression matching.
             // This also means that mutable count state must be maintained by the partitioner.
-            .or_else(|| self.filter_partition_mismatch(test_name))
+            .or_else(|| self.filter_partition_mismatch(test_binary, test_name))
             .unwrap_or(FilterMatch::Matches)
     }
 
@@ -419,9 +421,16 @@ impl<'filter> TestFilter<'filter> {
         }
     }
 
-    fn filter_partition_mismatch(&mut self, test_name: &str) -> Option<FilterMatch> {
-        let partition_match = match &mut self.partitioner {
-            Some(partitioner) => partitioner.test_matches(test_name),
+    fn filter_partition_mismatch(
+        &mut self,
+        test_binary: &RustTestArtifact<'_>,
+        test_name: &str,
+    ) -> Option<FilterMatch> {
+        let partition_match = match &self.builder.partitioner_builder {
+            Some(partitioner_builder) => self
+                .partitioner
+                .get_or_insert_with(|| partitioner_builder.build(&test_binary.binary_id))
+                .test_matches(test_name),
             None => true,
         };
         if partition_match {
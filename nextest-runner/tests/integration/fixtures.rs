@@ -266,6 +266,7 @@ impl FixtureTargets {
             &ecx,
             FilterBound::All,
             get_num_cpus(),
+            None,
         )
         .context("Failed to make test list")
     }